@@ -0,0 +1,377 @@
+use super::ErrorKind;
+use crate::{
+    instructions::Condition,
+    object::{Expr, PatchKind},
+    Register,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operand {
+    Register(Register),
+    Pair(Pair),
+    Condition(Condition),
+    /// `[BC]`, `[DE]` or `[HL]`
+    Pointer(Pair),
+    /// `[HL+]` or `[HLI]`
+    HLIncrement,
+    /// `[HL-]` or `[HLD]`
+    HLDecrement,
+    /// `[C]` or `[$FF00+C]`
+    HighC,
+    /// `[n16]`
+    Address(Expr),
+    /// `SP+e8`
+    StackOffset(Expr),
+    Immediate(Expr),
+}
+
+/// Register pairs as they appear in source. Unlike [`crate::Pair`], this includes `SP` and `AF`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Pair {
+    BC,
+    DE,
+    HL,
+    SP,
+    AF,
+}
+
+impl Pair {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let pair = match name.to_ascii_uppercase().as_str() {
+            "BC" => Self::BC,
+            "DE" => Self::DE,
+            "HL" => Self::HL,
+            "SP" => Self::SP,
+            "AF" => Self::AF,
+            _ => return None,
+        };
+
+        Some(pair)
+    }
+}
+
+pub fn register_from_name(name: &str) -> Option<Register> {
+    let register = match name.to_ascii_uppercase().as_str() {
+        "A" => Register::A,
+        "B" => Register::B,
+        "C" => Register::C,
+        "D" => Register::D,
+        "E" => Register::E,
+        "H" => Register::H,
+        "L" => Register::L,
+        _ => return None,
+    };
+
+    Some(register)
+}
+
+pub fn condition_from_name(name: &str) -> Option<Condition> {
+    let condition = match name.to_ascii_uppercase().as_str() {
+        "NZ" => Condition::NotZero,
+        "Z" => Condition::Zero,
+        "NC" => Condition::NotCarry,
+        _ => return None,
+    };
+
+    Some(condition)
+}
+
+/// The machine code for a single instruction. If the instruction has an operand, it always
+/// follows the opcode bytes.
+#[derive(Debug, Clone)]
+pub struct Encoded {
+    pub opcode: Vec<u8>,
+    pub operand: Option<(PatchKind, Expr)>,
+}
+
+impl Encoded {
+    fn new(opcode: u8) -> Self {
+        Self {
+            opcode: vec![opcode],
+            operand: None,
+        }
+    }
+
+    fn prefixed(opcode: u8) -> Self {
+        Self {
+            opcode: vec![0xCB, opcode],
+            operand: None,
+        }
+    }
+
+    fn with(opcode: u8, kind: PatchKind, expr: &Expr) -> Self {
+        Self {
+            opcode: vec![opcode],
+            operand: Some((kind, expr.clone())),
+        }
+    }
+}
+
+/// Returns the index used to encode an 8-bit operand in the opcode (with `[HL]` in slot 6).
+fn r8(operand: &Operand) -> Option<u8> {
+    let index = match operand {
+        Operand::Register(r) => match r {
+            Register::B => 0,
+            Register::C => 1,
+            Register::D => 2,
+            Register::E => 3,
+            Register::H => 4,
+            Register::L => 5,
+            Register::A => 7,
+        },
+        Operand::Pointer(Pair::HL) => 6,
+        _ => return None,
+    };
+
+    Some(index)
+}
+
+/// Returns the index of a pair in the `BC, DE, HL, SP` group.
+fn r16(operand: &Operand) -> Option<u8> {
+    match operand {
+        Operand::Pair(Pair::BC) => Some(0),
+        Operand::Pair(Pair::DE) => Some(1),
+        Operand::Pair(Pair::HL) => Some(2),
+        Operand::Pair(Pair::SP) => Some(3),
+        _ => None,
+    }
+}
+
+/// Returns the index of a pair in the `BC, DE, HL, AF` group used by `PUSH` and `POP`.
+fn r16_stack(operand: &Operand) -> Option<u8> {
+    match operand {
+        Operand::Pair(Pair::BC) => Some(0),
+        Operand::Pair(Pair::DE) => Some(1),
+        Operand::Pair(Pair::HL) => Some(2),
+        Operand::Pair(Pair::AF) => Some(3),
+        _ => None,
+    }
+}
+
+fn condition(operand: &Operand) -> Option<u8> {
+    let condition = match operand {
+        Operand::Condition(c) => *c,
+        Operand::Register(Register::C) => Condition::Carry,
+        _ => return None,
+    };
+
+    let index = match condition {
+        Condition::NotZero => 0,
+        Condition::Zero => 1,
+        Condition::NotCarry => 2,
+        Condition::Carry => 3,
+    };
+
+    Some(index)
+}
+
+fn is_a(operand: &Operand) -> bool {
+    matches!(operand, Operand::Register(Register::A))
+}
+
+/// Encodes a single instruction. Mnemonics are case-insensitive.
+pub fn encode(mnemonic: &str, operands: &[Operand]) -> Result<Encoded, ErrorKind> {
+    use Operand::*;
+    use PatchKind::*;
+
+    let mnemonic = mnemonic.to_ascii_lowercase();
+
+    let encoded = match (mnemonic.as_str(), operands) {
+        ("nop", []) => Encoded::new(0x00),
+        ("halt", []) => Encoded::new(0x76),
+        ("stop", []) => Encoded {
+            opcode: vec![0x10, 0x00],
+            operand: None,
+        },
+        ("di", []) => Encoded::new(0xF3),
+        ("ei", []) => Encoded::new(0xFB),
+        ("daa", []) => Encoded::new(0x27),
+        ("cpl", []) => Encoded::new(0x2F),
+        ("cpl", [a]) if is_a(a) => Encoded::new(0x2F),
+        ("ccf", []) => Encoded::new(0x3F),
+        ("scf", []) => Encoded::new(0x37),
+        ("rlca", []) => Encoded::new(0x07),
+        ("rrca", []) => Encoded::new(0x0F),
+        ("rla", []) => Encoded::new(0x17),
+        ("rra", []) => Encoded::new(0x1F),
+        ("reti", []) => Encoded::new(0xD9),
+        ("ret", []) => Encoded::new(0xC9),
+        ("ret", [cc]) if condition(cc).is_some() => {
+            Encoded::new(0xC0 | condition(cc).unwrap() << 3)
+        }
+
+        ("jp", [Immediate(e)]) => Encoded::with(0xC3, Word, e),
+        ("jp", [Pair(self::Pair::HL)]) | ("jp", [Pointer(self::Pair::HL)]) => Encoded::new(0xE9),
+        ("jp", [cc, Immediate(e)]) if condition(cc).is_some() => {
+            Encoded::with(0xC2 | condition(cc).unwrap() << 3, Word, e)
+        }
+        ("jr", [Immediate(e)]) => Encoded::with(0x18, Relative, e),
+        ("jr", [cc, Immediate(e)]) if condition(cc).is_some() => {
+            Encoded::with(0x20 | condition(cc).unwrap() << 3, Relative, e)
+        }
+        ("call", [Immediate(e)]) => Encoded::with(0xCD, Word, e),
+        ("call", [cc, Immediate(e)]) if condition(cc).is_some() => {
+            Encoded::with(0xC4 | condition(cc).unwrap() << 3, Word, e)
+        }
+        ("rst", [Immediate(Expr::Number(n))]) if n % 8 == 0 && (0..=0x38).contains(n) => {
+            Encoded::new(0xC7 | *n as u8)
+        }
+        ("rst", [Immediate(_)]) => {
+            return Err(ErrorKind::Operand(
+                "RST vectors must be constant multiples of 8 between $00 and $38".into(),
+            ))
+        }
+
+        ("push", [rr]) if r16_stack(rr).is_some() => {
+            Encoded::new(0xC5 | r16_stack(rr).unwrap() << 4)
+        }
+        ("pop", [rr]) if r16_stack(rr).is_some() => {
+            Encoded::new(0xC1 | r16_stack(rr).unwrap() << 4)
+        }
+
+        ("inc", [r]) if r8(r).is_some() => Encoded::new(0x04 | r8(r).unwrap() << 3),
+        ("dec", [r]) if r8(r).is_some() => Encoded::new(0x05 | r8(r).unwrap() << 3),
+        ("inc", [rr]) if r16(rr).is_some() => Encoded::new(0x03 | r16(rr).unwrap() << 4),
+        ("dec", [rr]) if r16(rr).is_some() => Encoded::new(0x0B | r16(rr).unwrap() << 4),
+
+        ("add", [Pair(self::Pair::HL), rr]) if r16(rr).is_some() => {
+            Encoded::new(0x09 | r16(rr).unwrap() << 4)
+        }
+        ("add", [Pair(self::Pair::SP), Immediate(e)]) => Encoded::with(0xE8, Byte, e),
+
+        ("add" | "adc" | "sub" | "sbc" | "and" | "xor" | "or" | "cp", _) => {
+            return encode_alu(&mnemonic, operands)
+        }
+
+        ("ld" | "ldi" | "ldd" | "ldh", _) => return encode_load(&mnemonic, operands),
+
+        ("rlc" | "rrc" | "rl" | "rr" | "sla" | "sra" | "swap" | "srl", [r]) if r8(r).is_some() => {
+            let group = match mnemonic.as_str() {
+                "rlc" => 0,
+                "rrc" => 1,
+                "rl" => 2,
+                "rr" => 3,
+                "sla" => 4,
+                "sra" => 5,
+                "swap" => 6,
+                _ => 7,
+            };
+
+            Encoded::prefixed(group << 3 | r8(r).unwrap())
+        }
+        ("bit" | "res" | "set", [Immediate(bit), r]) if r8(r).is_some() => {
+            let Expr::Number(bit @ 0..=7) = bit else {
+                return Err(ErrorKind::Operand(
+                    "bit index must be a constant between 0 and 7".into(),
+                ));
+            };
+
+            let group = match mnemonic.as_str() {
+                "bit" => 0x40,
+                "res" => 0x80,
+                _ => 0xC0,
+            };
+
+            Encoded::prefixed(group | (*bit as u8) << 3 | r8(r).unwrap())
+        }
+
+        _ => return Err(invalid(&mnemonic, operands)),
+    };
+
+    Ok(encoded)
+}
+
+fn encode_alu(mnemonic: &str, operands: &[Operand]) -> Result<Encoded, ErrorKind> {
+    let group = match mnemonic {
+        "add" => 0,
+        "adc" => 1,
+        "sub" => 2,
+        "sbc" => 3,
+        "and" => 4,
+        "xor" => 5,
+        "or" => 6,
+        _ => 7,
+    };
+
+    // The accumulator may be written explicitly (`ADD A, B`) or left out (`ADD B`).
+    let source = match operands {
+        [a, source] if is_a(a) => source,
+        [source] => source,
+        _ => return Err(invalid(mnemonic, operands)),
+    };
+
+    let encoded = match source {
+        Operand::Immediate(e) => Encoded::with(0xC6 | group << 3, PatchKind::Byte, e),
+        r => match r8(r) {
+            Some(index) => Encoded::new(0x80 | group << 3 | index),
+            None => return Err(invalid(mnemonic, operands)),
+        },
+    };
+
+    Ok(encoded)
+}
+
+fn encode_load(mnemonic: &str, operands: &[Operand]) -> Result<Encoded, ErrorKind> {
+    use Operand::*;
+    use PatchKind::*;
+
+    let [target, source] = operands else {
+        return Err(invalid(mnemonic, operands));
+    };
+
+    let encoded = match (mnemonic, target, source) {
+        ("ldh", Address(e), a) if is_a(a) => Encoded::with(0xE0, HighByte, e),
+        ("ldh", a, Address(e)) if is_a(a) => Encoded::with(0xF0, HighByte, e),
+        ("ldh" | "ld", HighC, a) if is_a(a) => Encoded::new(0xE2),
+        ("ldh" | "ld", a, HighC) if is_a(a) => Encoded::new(0xF2),
+        ("ldh", ..) => return Err(invalid(mnemonic, operands)),
+
+        ("ld" | "ldi", HLIncrement, a) | ("ldi", Pointer(self::Pair::HL), a) if is_a(a) => {
+            Encoded::new(0x22)
+        }
+        ("ld" | "ldi", a, HLIncrement) | ("ldi", a, Pointer(self::Pair::HL)) if is_a(a) => {
+            Encoded::new(0x2A)
+        }
+        ("ld" | "ldd", HLDecrement, a) | ("ldd", Pointer(self::Pair::HL), a) if is_a(a) => {
+            Encoded::new(0x32)
+        }
+        ("ld" | "ldd", a, HLDecrement) | ("ldd", a, Pointer(self::Pair::HL)) if is_a(a) => {
+            Encoded::new(0x3A)
+        }
+        ("ldi" | "ldd", ..) => return Err(invalid(mnemonic, operands)),
+
+        (_, Pointer(self::Pair::BC), a) if is_a(a) => Encoded::new(0x02),
+        (_, Pointer(self::Pair::DE), a) if is_a(a) => Encoded::new(0x12),
+        (_, a, Pointer(self::Pair::BC)) if is_a(a) => Encoded::new(0x0A),
+        (_, a, Pointer(self::Pair::DE)) if is_a(a) => Encoded::new(0x1A),
+        (_, Address(e), a) if is_a(a) => Encoded::with(0xEA, Word, e),
+        (_, a, Address(e)) if is_a(a) => Encoded::with(0xFA, Word, e),
+        (_, Address(e), Pair(self::Pair::SP)) => Encoded::with(0x08, Word, e),
+        (_, Pair(self::Pair::HL), StackOffset(e)) => Encoded::with(0xF8, Byte, e),
+        (_, Pair(self::Pair::SP), Pair(self::Pair::HL)) => Encoded::new(0xF9),
+        (_, rr, Immediate(e)) if r16(rr).is_some() => {
+            Encoded::with(0x01 | r16(rr).unwrap() << 4, Word, e)
+        }
+        (_, r, Immediate(e)) if r8(r).is_some() => {
+            Encoded::with(0x06 | r8(r).unwrap() << 3, Byte, e)
+        }
+        (_, Pointer(self::Pair::HL), Pointer(self::Pair::HL)) => {
+            return Err(invalid(mnemonic, operands))
+        }
+        (_, target, source) if r8(target).is_some() && r8(source).is_some() => {
+            Encoded::new(0x40 | r8(target).unwrap() << 3 | r8(source).unwrap())
+        }
+
+        _ => return Err(invalid(mnemonic, operands)),
+    };
+
+    Ok(encoded)
+}
+
+fn invalid(mnemonic: &str, operands: &[Operand]) -> ErrorKind {
+    ErrorKind::Operand(format!(
+        "invalid operands for `{}` ({} given)",
+        mnemonic.to_ascii_uppercase(),
+        operands.len()
+    ))
+}
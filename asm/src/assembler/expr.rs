@@ -0,0 +1,227 @@
+use super::{lexer::Token, ErrorKind};
+use crate::object::{BinaryOp, Expr, Function, UnaryOp};
+
+/// Information the expression parser needs from the assembler.
+pub trait Context {
+    /// Expands a (possibly local) symbol name into its fully-qualified form.
+    fn qualify(&self, name: &str) -> Result<String, ErrorKind>;
+
+    /// Returns an expression for the current location (`@`).
+    fn here(&self) -> Result<Expr, ErrorKind>;
+
+    /// Returns `true` if a symbol or macro with the given name has been defined.
+    fn is_defined(&self, name: &str) -> bool;
+
+    /// Returns the value of a built-in symbol such as `_NARG`, if `name` refers to one.
+    fn builtin(&self, name: &str) -> Option<i64>;
+}
+
+/// Parses a complete expression. Returns an error if any tokens are left over.
+pub fn parse_expr<C>(tokens: &[Token], context: &C) -> Result<Expr, ErrorKind>
+where
+    C: Context + ?Sized,
+{
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        context,
+    };
+
+    let expr = parser.parse_binary(0)?;
+
+    if let Some(token) = parser.peek() {
+        return Err(ErrorKind::Syntax(format!(
+            "unexpected {token:?} in expression"
+        )));
+    }
+
+    Ok(expr)
+}
+
+/// Splits a token list on commas that aren't nested inside parentheses or brackets.
+pub fn split_arguments(tokens: &[Token]) -> Vec<&[Token]> {
+    if tokens.is_empty() {
+        return Vec::new();
+    }
+
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::LeftParen | Token::LeftBracket => depth += 1,
+            Token::RightParen | Token::RightBracket => depth = depth.saturating_sub(1),
+            Token::Comma if depth == 0 => {
+                parts.push(&tokens[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+
+    parts.push(&tokens[start..]);
+    parts
+}
+
+struct Parser<'a, C: ?Sized> {
+    tokens: &'a [Token],
+    pos: usize,
+    context: &'a C,
+}
+
+impl<C> Parser<'_, C>
+where
+    C: Context + ?Sized,
+{
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), ErrorKind> {
+        match self.next() {
+            Some(token) if *token == expected => Ok(()),
+            token => Err(ErrorKind::Syntax(format!(
+                "expected {expected:?}, found {token:?}"
+            ))),
+        }
+    }
+
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<Expr, ErrorKind> {
+        let mut lhs = self.parse_unary()?;
+
+        while let Some((op, precedence)) = self.peek().and_then(binary_op) {
+            if precedence < min_precedence {
+                break;
+            }
+
+            self.pos += 1;
+
+            let rhs = self.parse_binary(precedence + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ErrorKind> {
+        let op = match self.peek() {
+            Some(Token::Minus) => UnaryOp::Negate,
+            Some(Token::Tilde) => UnaryOp::Complement,
+            Some(Token::Bang) => UnaryOp::Not,
+            Some(Token::Plus) => {
+                self.pos += 1;
+                return self.parse_unary();
+            }
+            _ => return self.parse_primary(),
+        };
+
+        self.pos += 1;
+
+        Ok(Expr::Unary(op, Box::new(self.parse_unary()?)))
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ErrorKind> {
+        let Some(token) = self.next().cloned() else {
+            return Err(ErrorKind::Syntax("expected an expression".into()));
+        };
+
+        match token {
+            Token::Number(n) => Ok(Expr::Number(n)),
+            Token::At => self.context.here(),
+            Token::LeftParen => {
+                let expr = self.parse_binary(0)?;
+                self.expect(Token::RightParen)?;
+
+                Ok(expr)
+            }
+            Token::Ident(name) if self.peek() == Some(&Token::LeftParen) => self.parse_call(&name),
+            Token::Ident(name) => match self.context.builtin(&name) {
+                Some(value) => Ok(Expr::Number(value)),
+                None => Ok(Expr::Symbol(self.context.qualify(&name)?)),
+            },
+            token => Err(ErrorKind::Syntax(format!(
+                "unexpected {token:?} in expression"
+            ))),
+        }
+    }
+
+    fn parse_call(&mut self, name: &str) -> Result<Expr, ErrorKind> {
+        self.expect(Token::LeftParen)?;
+
+        let function = match name.to_ascii_uppercase().as_str() {
+            "HIGH" => Function::High,
+            "LOW" => Function::Low,
+            "BANK" => Function::Bank,
+            "SIZEOF" => Function::SizeOf,
+            "STARTOF" => Function::StartOf,
+            "DEF" => {
+                let defined = match self.next().cloned() {
+                    Some(Token::Ident(name)) => {
+                        self.context.is_defined(&self.context.qualify(&name)?)
+                    }
+                    token => {
+                        return Err(ErrorKind::Syntax(format!(
+                            "DEF expects a symbol name, found {token:?}"
+                        )))
+                    }
+                };
+
+                self.expect(Token::RightParen)?;
+                return Ok(Expr::Number(defined as i64));
+            }
+            _ => return Err(ErrorKind::Syntax(format!("unknown function `{name}`"))),
+        };
+
+        let arg = match (function, self.peek().cloned()) {
+            (Function::SizeOf | Function::StartOf, Some(Token::Str(section))) => {
+                self.pos += 1;
+                Expr::Symbol(section)
+            }
+            (Function::Bank, Some(Token::At)) => {
+                self.pos += 1;
+                self.context.here()?
+            }
+            _ => self.parse_binary(0)?,
+        };
+
+        self.expect(Token::RightParen)?;
+
+        Ok(Expr::Call(function, vec![arg]))
+    }
+}
+
+/// Returns the operator `token` stands for between two operands, along with its precedence.
+/// Operators with a higher precedence bind more tightly.
+pub fn binary_op(token: &Token) -> Option<(BinaryOp, u8)> {
+    let op = match token {
+        Token::LogicalOr => (BinaryOp::LogicalOr, 0),
+        Token::LogicalAnd => (BinaryOp::LogicalAnd, 1),
+        Token::DoubleEqual => (BinaryOp::Equal, 2),
+        Token::NotEqual => (BinaryOp::NotEqual, 2),
+        Token::Less => (BinaryOp::Less, 2),
+        Token::LessEqual => (BinaryOp::LessEqual, 2),
+        Token::Greater => (BinaryOp::Greater, 2),
+        Token::GreaterEqual => (BinaryOp::GreaterEqual, 2),
+        Token::Pipe => (BinaryOp::Or, 3),
+        Token::Caret => (BinaryOp::Xor, 4),
+        Token::Ampersand => (BinaryOp::And, 5),
+        Token::ShiftLeft => (BinaryOp::ShiftLeft, 6),
+        Token::ShiftRight => (BinaryOp::ShiftRight, 6),
+        Token::Plus => (BinaryOp::Add, 7),
+        Token::Minus => (BinaryOp::Subtract, 7),
+        Token::Star => (BinaryOp::Multiply, 8),
+        Token::Slash => (BinaryOp::Divide, 8),
+        Token::Percent => (BinaryOp::Modulo, 8),
+        _ => return None,
+    };
+
+    Some(op)
+}
@@ -0,0 +1,272 @@
+use super::ErrorKind;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Ident(String),
+    Number(i64),
+    Str(String),
+    /// The `@` symbol, which evaluates to the address of the current instruction or directive.
+    At,
+    Colon,
+    DoubleColon,
+    Comma,
+    LeftParen,
+    RightParen,
+    LeftBracket,
+    RightBracket,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
+    Bang,
+    ShiftLeft,
+    ShiftRight,
+    Equal,
+    DoubleEqual,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    LogicalAnd,
+    LogicalOr,
+}
+
+impl Token {
+    pub fn is_ident(&self, name: &str) -> bool {
+        matches!(self, Self::Ident(ident) if ident.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Splits a single line of source into tokens. Comments (starting with `;`) are discarded.
+pub fn tokenize(line: &str) -> Result<Vec<Token>, ErrorKind> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c == ';' {
+            break;
+        }
+
+        if c == '"' {
+            let (value, end) = read_string(&chars, i + 1)?;
+            tokens.push(Token::Str(value));
+            i = end;
+            continue;
+        }
+
+        if c == '\'' {
+            let (value, end) = read_string_until(&chars, i + 1, '\'')?;
+            let mut value = value.chars();
+
+            let (Some(c), None) = (value.next(), value.next()) else {
+                return Err(ErrorKind::Syntax(
+                    "character literals must be one character".into(),
+                ));
+            };
+
+            tokens.push(Token::Number(c as i64));
+            i = end;
+            continue;
+        }
+
+        if let Some((value, end)) = read_number(&chars, i)? {
+            tokens.push(Token::Number(value));
+            i = end;
+            continue;
+        }
+
+        if is_ident_start(c) {
+            let start = i;
+
+            while i < chars.len() && is_ident_char(chars[i]) {
+                i += 1;
+            }
+
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            continue;
+        }
+
+        let (token, width) = match (c, next) {
+            (':', Some(':')) => (Token::DoubleColon, 2),
+            ('<', Some('<')) => (Token::ShiftLeft, 2),
+            ('>', Some('>')) => (Token::ShiftRight, 2),
+            ('=', Some('=')) => (Token::DoubleEqual, 2),
+            ('!', Some('=')) => (Token::NotEqual, 2),
+            ('<', Some('=')) => (Token::LessEqual, 2),
+            ('>', Some('=')) => (Token::GreaterEqual, 2),
+            ('&', Some('&')) => (Token::LogicalAnd, 2),
+            ('|', Some('|')) => (Token::LogicalOr, 2),
+            ('@', _) => (Token::At, 1),
+            (':', _) => (Token::Colon, 1),
+            (',', _) => (Token::Comma, 1),
+            ('(', _) => (Token::LeftParen, 1),
+            (')', _) => (Token::RightParen, 1),
+            ('[', _) => (Token::LeftBracket, 1),
+            (']', _) => (Token::RightBracket, 1),
+            ('+', _) => (Token::Plus, 1),
+            ('-', _) => (Token::Minus, 1),
+            ('*', _) => (Token::Star, 1),
+            ('/', _) => (Token::Slash, 1),
+            ('%', _) => (Token::Percent, 1),
+            ('&', _) => (Token::Ampersand, 1),
+            ('|', _) => (Token::Pipe, 1),
+            ('^', _) => (Token::Caret, 1),
+            ('~', _) => (Token::Tilde, 1),
+            ('!', _) => (Token::Bang, 1),
+            ('=', _) => (Token::Equal, 1),
+            ('<', _) => (Token::Less, 1),
+            ('>', _) => (Token::Greater, 1),
+            _ => return Err(ErrorKind::Syntax(format!("unexpected character `{c}`"))),
+        };
+
+        tokens.push(token);
+        i += width;
+    }
+
+    Ok(tokens)
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c == '.'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '#')
+}
+
+fn read_string(chars: &[char], start: usize) -> Result<(String, usize), ErrorKind> {
+    read_string_until(chars, start, '"')
+}
+
+fn read_string_until(
+    chars: &[char],
+    start: usize,
+    terminator: char,
+) -> Result<(String, usize), ErrorKind> {
+    let mut value = String::new();
+    let mut i = start;
+
+    while i < chars.len() {
+        match chars[i] {
+            c if c == terminator => return Ok((value, i + 1)),
+            '\\' => {
+                let escaped = match chars.get(i + 1) {
+                    Some('n') => '\n',
+                    Some('r') => '\r',
+                    Some('t') => '\t',
+                    Some('0') => '\0',
+                    Some(&c @ ('\\' | '"' | '\'')) => c,
+                    _ => return Err(ErrorKind::Syntax("invalid escape sequence".into())),
+                };
+
+                value.push(escaped);
+                i += 2;
+            }
+            c => {
+                value.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    Err(ErrorKind::Syntax("unterminated string".into()))
+}
+
+/// Attempts to read a number literal starting at `start`. Supports decimal, `$` and `0x`
+/// hexadecimal, `%` and `0b` binary, and `&` and `0o` octal literals. Underscores may be used as
+/// digit separators.
+///
+/// Because `%` and `&` are also operators, they are only treated as a prefix if they are
+/// immediately followed by a valid digit, and the previous token could not end an operand.
+fn read_number(chars: &[char], start: usize) -> Result<Option<(i64, usize)>, ErrorKind> {
+    let c = chars[start];
+    let next = chars.get(start + 1).copied();
+    let previous = start.checked_sub(1).map(|i| chars[i]);
+    let after_operand = previous.is_some_and(|c| c.is_ascii_alphanumeric() || c == ')');
+
+    let (radix, digits_start) = match (c, next) {
+        ('$', Some(n)) if n.is_ascii_hexdigit() => (16, start + 1),
+        ('%', Some('0' | '1')) if !after_operand => (2, start + 1),
+        ('&', Some('0'..='7')) if !after_operand => (8, start + 1),
+        ('0', Some('x' | 'X')) => (16, start + 2),
+        ('0', Some('b' | 'B')) => (2, start + 2),
+        ('0', Some('o' | 'O')) => (8, start + 2),
+        (c, _) if c.is_ascii_digit() => (10, start),
+        _ => return Ok(None),
+    };
+
+    let mut i = digits_start;
+    let mut value: i64 = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '_' {
+            i += 1;
+            continue;
+        }
+
+        let Some(digit) = c.to_digit(radix) else {
+            break;
+        };
+
+        value = value.wrapping_mul(radix as i64).wrapping_add(digit as i64);
+        i += 1;
+    }
+
+    if i == digits_start {
+        return Err(ErrorKind::Syntax("number literal has no digits".into()));
+    }
+
+    Ok(Some((value, i)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers() {
+        let tokens = tokenize("$FF 0x10 %101 0b11 &17 42 'A' 1_000").unwrap();
+        let values: Vec<_> = tokens
+            .into_iter()
+            .map(|t| match t {
+                Token::Number(n) => n,
+                t => panic!("unexpected token {t:?}"),
+            })
+            .collect();
+
+        assert_eq!(values, [0xFF, 0x10, 0b101, 0b11, 0o17, 42, 65, 1000]);
+    }
+
+    #[test]
+    fn operators_are_not_prefixes() {
+        let tokens = tokenize("x%2 & 1").unwrap();
+
+        assert_eq!(
+            tokens,
+            [
+                Token::Ident("x".into()),
+                Token::Percent,
+                Token::Number(2),
+                Token::Ampersand,
+                Token::Number(1)
+            ]
+        );
+    }
+}
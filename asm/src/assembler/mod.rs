@@ -0,0 +1,1450 @@
+//! An assembler for RGBDS-flavored SM83 source.
+//!
+//! The supported dialect covers the parts of RGBDS most homebrew projects rely on:
+//!
+//! - `SECTION "name", TYPE[addr], BANK[n], ALIGN[n]`
+//! - global (`Label:`, exported with `Label::`) and local (`.local`, `Label.local`) labels
+//! - constants (`DEF X EQU 1`, `X EQU 1`) and variables (`DEF X = 1`, `X = 1`)
+//! - `DB`, `DW`, `DL` and `DS`
+//! - `INCLUDE` and `INCBIN`
+//! - macros (`MACRO name` ... `ENDM`, with `\1`-`\9`, `\<n>`, `\#`, `\@`, `_NARG` and `SHIFT`)
+//! - conditional assembly (`IF`, `ELIF`, `ELSE`, `ENDC`) and `REPT` ... `ENDR`
+//! - `EXPORT`, `ASSERT` and `FAIL`
+//!
+//! Assembling a file produces an [`Object`], which must be passed to the
+//! [linker](crate::linker) in order to produce a ROM image.
+
+use crate::object::{
+    Expr, ExprError, Object, Patch, PatchKind, Resolve, Section, SectionType, Symbol, SymbolValue,
+};
+use encode::{condition_from_name, register_from_name, Operand, Pair};
+use expr::{parse_expr, split_arguments, Context};
+use lexer::{tokenize, Token};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+pub mod encode;
+pub mod expr;
+pub mod lexer;
+
+/// Maximum depth of nested includes, macro invocations and repeat blocks. Mostly a guard against
+/// files that include themselves or macros that never stop recursing.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, Default)]
+pub struct Assembler {
+    include_paths: Vec<PathBuf>,
+}
+
+impl Assembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a directory that is searched for files passed to `INCLUDE` and `INCBIN`, after the
+    /// directory of the file containing the directive.
+    pub fn include_path<P>(&mut self, path: P) -> &mut Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.include_paths.push(path.into());
+        self
+    }
+
+    pub fn assemble_file(&self, path: &Path) -> Result<Object, Error> {
+        let source = fs::read_to_string(path).map_err(|e| Error {
+            file: path.display().to_string(),
+            line: 0,
+            kind: ErrorKind::Io(e),
+        })?;
+
+        let name = path.display().to_string();
+        let dir = path.parent().map(Path::to_path_buf);
+
+        Session::new(self, &name).run(&name, dir, &source)
+    }
+
+    /// Assembles source code held in memory. `name` is used in error messages and as the name of
+    /// the resulting object. Relative `INCLUDE` paths are resolved against the include paths and
+    /// the working directory.
+    pub fn assemble_source(&self, name: &str, source: &str) -> Result<Object, Error> {
+        Session::new(self, name).run(name, None, source)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("{file}:{line}: {kind}")]
+pub struct Error {
+    pub file: String,
+    pub line: usize,
+    pub kind: ErrorKind,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ErrorKind {
+    #[error("syntax error: {0}")]
+    Syntax(String),
+    #[error("{0}")]
+    Operand(String),
+    #[error("{0}")]
+    Expr(#[from] ExprError),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Symbol(String),
+    #[error("{0}")]
+    Section(String),
+    #[error("{0}")]
+    Directive(String),
+    #[error("unterminated {0}")]
+    Unterminated(&'static str),
+    #[error("nesting too deep (more than {MAX_DEPTH} levels of includes, macros or repeats)")]
+    TooDeep,
+    #[error("{0}")]
+    Fail(String),
+}
+
+#[derive(Debug, Clone)]
+struct Line {
+    text: String,
+    file: Rc<str>,
+    number: usize,
+}
+
+#[derive(Debug)]
+enum FrameKind {
+    File { dir: Option<PathBuf> },
+    Macro { args: Vec<String>, shift: usize },
+    Repeat { remaining: usize },
+}
+
+#[derive(Debug)]
+struct Frame {
+    kind: FrameKind,
+    lines: Rc<[Line]>,
+    next: usize,
+    unique: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Conditional {
+    /// Whether lines in the current branch are assembled.
+    active: bool,
+    /// Whether any branch of this block has been taken yet.
+    taken: bool,
+    /// Whether the enclosing block was active when this one started.
+    parent_active: bool,
+    seen_else: bool,
+}
+
+#[derive(Debug)]
+enum Capture {
+    Macro {
+        name: String,
+        depth: usize,
+        body: Vec<Line>,
+    },
+    Repeat {
+        count: usize,
+        depth: usize,
+        body: Vec<Line>,
+    },
+}
+
+struct Session<'a> {
+    assembler: &'a Assembler,
+    object: Object,
+    frames: Vec<Frame>,
+    conditionals: Vec<Conditional>,
+    capture: Option<Capture>,
+    macros: HashMap<String, Rc<[Line]>>,
+    variables: HashSet<String>,
+    exports: HashSet<String>,
+    section: Option<usize>,
+    scope: Option<String>,
+    /// The offset of the instruction or directive currently being assembled, used for `@`.
+    here: usize,
+    unique: usize,
+    current: Option<(Rc<str>, usize)>,
+}
+
+impl<'a> Session<'a> {
+    fn new(assembler: &'a Assembler, name: &str) -> Self {
+        Self {
+            assembler,
+            object: Object::new(name),
+            frames: Vec::new(),
+            conditionals: Vec::new(),
+            capture: None,
+            macros: HashMap::new(),
+            variables: HashSet::new(),
+            exports: HashSet::new(),
+            section: None,
+            scope: None,
+            here: 0,
+            unique: 0,
+            current: None,
+        }
+    }
+
+    fn run(mut self, name: &str, dir: Option<PathBuf>, source: &str) -> Result<Object, Error> {
+        self.push_file(name, dir, source)
+            .map_err(|kind| self.error(kind))?;
+
+        while let Some(line) = self.next_line() {
+            self.current = Some((line.file.clone(), line.number));
+            self.process(&line).map_err(|kind| self.error(kind))?;
+        }
+
+        let current = self.current.clone();
+
+        self.finish().map_err(|kind| {
+            let (file, line) = current.map_or((name.to_owned(), 0), |(f, l)| (f.to_string(), l));
+            Error { file, line, kind }
+        })
+    }
+
+    fn error(&self, kind: ErrorKind) -> Error {
+        let (file, line) = match &self.current {
+            Some((file, line)) => (file.to_string(), *line),
+            None => (self.object.name.clone(), 0),
+        };
+
+        Error { file, line, kind }
+    }
+
+    fn push_frame(&mut self, kind: FrameKind, lines: Rc<[Line]>) -> Result<(), ErrorKind> {
+        if self.frames.len() >= MAX_DEPTH {
+            return Err(ErrorKind::TooDeep);
+        }
+
+        self.unique += 1;
+        self.frames.push(Frame {
+            kind,
+            lines,
+            next: 0,
+            unique: self.unique,
+        });
+
+        Ok(())
+    }
+
+    fn push_file(
+        &mut self,
+        name: &str,
+        dir: Option<PathBuf>,
+        source: &str,
+    ) -> Result<(), ErrorKind> {
+        let file: Rc<str> = Rc::from(name);
+        let lines: Rc<[Line]> = source
+            .lines()
+            .enumerate()
+            .map(|(i, text)| Line {
+                text: text.to_owned(),
+                file: file.clone(),
+                number: i + 1,
+            })
+            .collect();
+
+        self.push_frame(FrameKind::File { dir }, lines)
+    }
+
+    fn next_line(&mut self) -> Option<Line> {
+        loop {
+            let frame = self.frames.last_mut()?;
+
+            if let Some(line) = frame.lines.get(frame.next) {
+                frame.next += 1;
+                return Some(line.clone());
+            }
+
+            match &mut frame.kind {
+                FrameKind::Repeat { remaining } if *remaining > 1 => {
+                    *remaining -= 1;
+                    frame.next = 0;
+
+                    self.unique += 1;
+                    frame.unique = self.unique;
+                }
+                _ => {
+                    self.frames.pop();
+                }
+            }
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.conditionals.last().is_none_or(|c| c.active)
+    }
+
+    fn process(&mut self, line: &Line) -> Result<(), ErrorKind> {
+        let keyword = first_word(&line.text);
+
+        if self.capture.is_some() {
+            return self.capture_line(line, &keyword);
+        }
+
+        if matches!(keyword.as_str(), "IF" | "ELIF" | "ELSE" | "ENDC") {
+            let text = self.substitute(&line.text)?;
+            return self.conditional(&keyword, &text);
+        }
+
+        if !self.is_active() {
+            return Ok(());
+        }
+
+        let text = self.substitute(&line.text)?;
+        self.statement(line, &text)
+    }
+
+    fn capture_line(&mut self, line: &Line, keyword: &str) -> Result<(), ErrorKind> {
+        let Some(capture) = &mut self.capture else {
+            return Ok(());
+        };
+
+        let (depth, body, opens, closes) = match capture {
+            Capture::Macro { depth, body, .. } => (depth, body, "MACRO", "ENDM"),
+            Capture::Repeat { depth, body, .. } => (depth, body, "REPT", "ENDR"),
+        };
+
+        // Old-style macro definitions put the keyword after the label, so the first word alone
+        // isn't enough to detect them.
+        let opens_block = keyword == opens
+            || (opens == "MACRO" && statement_text(&line.text).1.eq_ignore_ascii_case("MACRO"));
+
+        if opens_block {
+            *depth += 1;
+        } else if keyword == closes {
+            if *depth == 0 {
+                return self.end_capture();
+            }
+
+            *depth -= 1;
+        }
+
+        body.push(line.clone());
+
+        Ok(())
+    }
+
+    fn end_capture(&mut self) -> Result<(), ErrorKind> {
+        match self.capture.take() {
+            Some(Capture::Macro { name, body, .. }) => {
+                self.macros.insert(name, body.into());
+            }
+            Some(Capture::Repeat { count, body, .. }) if count > 0 => {
+                self.push_frame(FrameKind::Repeat { remaining: count }, body.into())?;
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    fn conditional(&mut self, keyword: &str, text: &str) -> Result<(), ErrorKind> {
+        let (_, rest) = statement_text(text);
+        let argument = rest.trim_start()[keyword.len()..].trim();
+
+        match keyword {
+            "IF" => {
+                let parent_active = self.is_active();
+                let active = parent_active && self.evaluate_condition(argument)?;
+
+                self.conditionals.push(Conditional {
+                    active,
+                    taken: active,
+                    parent_active,
+                    seen_else: false,
+                });
+            }
+            "ELIF" => {
+                let Some(&c) = self.conditionals.last() else {
+                    return Err(ErrorKind::Directive("ELIF outside of an IF block".into()));
+                };
+
+                if c.seen_else {
+                    return Err(ErrorKind::Directive("ELIF after ELSE".into()));
+                }
+
+                let active = c.parent_active && !c.taken && self.evaluate_condition(argument)?;
+                let c = self.conditionals.last_mut().unwrap();
+                c.active = active;
+                c.taken |= active;
+            }
+            "ELSE" => {
+                let Some(c) = self.conditionals.last_mut() else {
+                    return Err(ErrorKind::Directive("ELSE outside of an IF block".into()));
+                };
+
+                if c.seen_else {
+                    return Err(ErrorKind::Directive("multiple ELSE in one IF block".into()));
+                }
+
+                c.active = c.parent_active && !c.taken;
+                c.taken = true;
+                c.seen_else = true;
+            }
+            _ => {
+                if self.conditionals.pop().is_none() {
+                    return Err(ErrorKind::Directive("ENDC outside of an IF block".into()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn evaluate_condition(&self, text: &str) -> Result<bool, ErrorKind> {
+        let tokens = tokenize(text)?;
+        let expr = parse_expr(&tokens, self)?;
+
+        Ok(self.constant(&expr)? != 0)
+    }
+
+    /// Replaces macro arguments (`\1`, `\<10>`, `\#`, `\@`) in a line of source.
+    fn substitute(&self, text: &str) -> Result<String, ErrorKind> {
+        if !text.contains('\\') {
+            return Ok(text.to_owned());
+        }
+
+        let args = self.frames.iter().rev().find_map(|f| match &f.kind {
+            FrameKind::Macro { args, shift } => Some(&args[(*shift).min(args.len())..]),
+            _ => None,
+        });
+
+        let unique = self
+            .frames
+            .iter()
+            .rev()
+            .find(|f| !matches!(f.kind, FrameKind::File { .. }))
+            .map(|f| f.unique);
+
+        let mut output = String::with_capacity(text.len());
+        let mut chars = text.chars().peekable();
+        let mut in_string = false;
+
+        while let Some(c) = chars.next() {
+            if c == '"' {
+                in_string = !in_string;
+            }
+
+            if c != '\\' {
+                output.push(c);
+                continue;
+            }
+
+            match chars.peek().copied() {
+                Some(d @ '1'..='9') if args.is_some() => {
+                    chars.next();
+                    let index = d.to_digit(10).unwrap() as usize - 1;
+                    output.push_str(macro_arg(args.unwrap(), index)?);
+                }
+                Some('<') if args.is_some() => {
+                    chars.next();
+                    let digits: String = chars.by_ref().take_while(|c| *c != '>').collect();
+                    let index: usize = digits
+                        .trim()
+                        .parse()
+                        .map_err(|_| ErrorKind::Syntax("invalid macro argument index".into()))?;
+
+                    if index == 0 {
+                        return Err(ErrorKind::Syntax("macro arguments start at 1".into()));
+                    }
+
+                    output.push_str(macro_arg(args.unwrap(), index - 1)?);
+                }
+                Some('#') if args.is_some() => {
+                    chars.next();
+                    output.push_str(&args.unwrap().join(", "));
+                }
+                Some('@') => {
+                    chars.next();
+
+                    let Some(unique) = unique else {
+                        return Err(ErrorKind::Syntax(
+                            "\\@ used outside of a macro or REPT block".into(),
+                        ));
+                    };
+
+                    output.push_str(&format!("_u{unique}"));
+                }
+                // Escape sequences in strings are handled by the lexer.
+                _ if in_string => output.push(c),
+                _ => {
+                    return Err(ErrorKind::Syntax(
+                        "macro arguments used outside of a macro".into(),
+                    ))
+                }
+            }
+        }
+
+        Ok(output)
+    }
+
+    fn statement(&mut self, line: &Line, text: &str) -> Result<(), ErrorKind> {
+        self.here = self.current_size();
+
+        let (label, rest) = statement_text(strip_comment(text));
+        let word = rest.split_whitespace().next().unwrap_or_default();
+
+        if let Some((label, exported)) = label {
+            // `Name: MACRO` is the old syntax for macro definitions.
+            if word.eq_ignore_ascii_case("MACRO") {
+                return self.begin_macro(label);
+            }
+
+            self.define_label(label, exported)?;
+        }
+
+        if word.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(body) = self.macros.get(word).cloned() {
+            let args = split_macro_args(rest.trim_start()[word.len()..].trim());
+            return self.push_frame(FrameKind::Macro { args, shift: 0 }, body);
+        }
+
+        let tokens = tokenize(rest)?;
+        let [Token::Ident(keyword), args @ ..] = tokens.as_slice() else {
+            return Err(ErrorKind::Syntax(format!("unexpected `{}`", rest.trim())));
+        };
+
+        match args.first() {
+            Some(t) if t.is_ident("EQU") => return self.define_constant(keyword, &args[1..]),
+            Some(Token::Equal) => return self.define_variable(keyword, &args[1..]),
+            _ => (),
+        }
+
+        match keyword.to_ascii_uppercase().as_str() {
+            "DEF" => self.def(args),
+            "SECTION" => self.section(args),
+            "DB" => self.data(args, 1),
+            "DW" => self.data(args, 2),
+            "DL" => self.data(args, 4),
+            "DS" => self.reserve(args),
+            "INCLUDE" => self.include(line, args),
+            "INCBIN" => self.incbin(line, args),
+            "MACRO" => match args {
+                [Token::Ident(name)] => self.begin_macro(name),
+                _ => Err(ErrorKind::Syntax("MACRO expects a name".into())),
+            },
+            "ENDM" => Err(ErrorKind::Directive("ENDM outside of a macro".into())),
+            "REPT" => {
+                let count = self.constant(&parse_expr(args, self)?)?;
+
+                self.capture = Some(Capture::Repeat {
+                    count: count.max(0) as usize,
+                    depth: 0,
+                    body: Vec::new(),
+                });
+
+                Ok(())
+            }
+            "ENDR" => Err(ErrorKind::Directive("ENDR outside of a REPT block".into())),
+            "SHIFT" => self.shift(args),
+            "EXPORT" => {
+                for name in split_arguments(args) {
+                    let [Token::Ident(name)] = name else {
+                        return Err(ErrorKind::Syntax("EXPORT expects symbol names".into()));
+                    };
+
+                    self.exports.insert(self.qualify(name)?);
+                }
+
+                Ok(())
+            }
+            "ASSERT" | "STATIC_ASSERT" => self.assert(args),
+            "FAIL" => match args {
+                [Token::Str(message)] => Err(ErrorKind::Fail(message.clone())),
+                _ => Err(ErrorKind::Syntax("FAIL expects a string".into())),
+            },
+            _ => self.instruction(keyword, args),
+        }
+    }
+
+    fn begin_macro(&mut self, name: &str) -> Result<(), ErrorKind> {
+        if self.macros.contains_key(name) {
+            return Err(ErrorKind::Symbol(format!("macro `{name}` already defined")));
+        }
+
+        self.capture = Some(Capture::Macro {
+            name: name.to_owned(),
+            depth: 0,
+            body: Vec::new(),
+        });
+
+        Ok(())
+    }
+
+    fn shift(&mut self, args: &[Token]) -> Result<(), ErrorKind> {
+        let amount = match args {
+            [] => 1,
+            args => self.constant(&parse_expr(args, self)?)?.max(0) as usize,
+        };
+
+        let shift = self
+            .frames
+            .iter_mut()
+            .rev()
+            .find_map(|f| match &mut f.kind {
+                FrameKind::Macro { shift, .. } => Some(shift),
+                _ => None,
+            });
+
+        match shift {
+            Some(shift) => {
+                *shift += amount;
+                Ok(())
+            }
+            None => Err(ErrorKind::Directive("SHIFT outside of a macro".into())),
+        }
+    }
+
+    fn assert(&mut self, args: &[Token]) -> Result<(), ErrorKind> {
+        let args = split_arguments(args);
+
+        let (condition, message) = match args.as_slice() {
+            [condition] => (*condition, "assertion failed".to_owned()),
+            [condition, [Token::Str(message)]] => (*condition, message.clone()),
+            _ => return Err(ErrorKind::Syntax("ASSERT expects a condition".into())),
+        };
+
+        // Assertions that can't be evaluated yet (e.g. ones that depend on where the linker puts
+        // a section) are not checked.
+        match self.try_constant(&parse_expr(condition, self)?)? {
+            Some(0) => Err(ErrorKind::Fail(message)),
+            _ => Ok(()),
+        }
+    }
+
+    fn def(&mut self, args: &[Token]) -> Result<(), ErrorKind> {
+        match args {
+            [Token::Ident(name), t, rest @ ..] if t.is_ident("EQU") => {
+                self.define_constant(name, rest)
+            }
+            [Token::Ident(name), Token::Equal, rest @ ..] => self.define_variable(name, rest),
+            [Token::Ident(_), t, ..] if t.is_ident("EQUS") => Err(ErrorKind::Directive(
+                "string constants (EQUS) are not supported".into(),
+            )),
+            _ => Err(ErrorKind::Syntax(
+                "expected `DEF name EQU value` or `DEF name = value`".into(),
+            )),
+        }
+    }
+
+    fn define_constant(&mut self, name: &str, value: &[Token]) -> Result<(), ErrorKind> {
+        let name = self.qualify(name)?;
+
+        if self.is_defined(&name) {
+            return Err(ErrorKind::Symbol(format!(
+                "symbol `{name}` already defined"
+            )));
+        }
+
+        let value = self.constant(&parse_expr(value, self)?)?;
+        self.add_symbol(name, SymbolValue::Constant(value), false);
+
+        Ok(())
+    }
+
+    fn define_variable(&mut self, name: &str, value: &[Token]) -> Result<(), ErrorKind> {
+        let name = self.qualify(name)?;
+        let value = self.constant(&parse_expr(value, self)?)?;
+
+        if let Some(symbol) = self.object.symbols.iter_mut().find(|s| s.name == name) {
+            if !self.variables.contains(&name) {
+                return Err(ErrorKind::Symbol(format!(
+                    "symbol `{name}` is not a variable"
+                )));
+            }
+
+            symbol.value = SymbolValue::Constant(value);
+        } else {
+            self.variables.insert(name.clone());
+            self.add_symbol(name, SymbolValue::Constant(value), false);
+        }
+
+        Ok(())
+    }
+
+    fn define_label(&mut self, name: &str, exported: bool) -> Result<(), ErrorKind> {
+        let Some(section) = self.section else {
+            return Err(ErrorKind::Section(format!(
+                "label `{name}` defined outside of a section"
+            )));
+        };
+
+        let qualified = self.qualify(name)?;
+
+        if self.is_defined(&qualified) {
+            return Err(ErrorKind::Symbol(format!(
+                "symbol `{qualified}` already defined"
+            )));
+        }
+
+        if !name.contains('.') {
+            self.scope = Some(qualified.clone());
+        }
+
+        let offset = self.current_size();
+        self.add_symbol(qualified, SymbolValue::Label { section, offset }, exported);
+
+        Ok(())
+    }
+
+    fn add_symbol(&mut self, name: String, value: SymbolValue, exported: bool) {
+        self.object.symbols.push(Symbol {
+            name,
+            value,
+            exported,
+        });
+    }
+
+    fn section(&mut self, args: &[Token]) -> Result<(), ErrorKind> {
+        let args = split_arguments(args);
+
+        let (name, kind, address) = match args.first() {
+            Some([Token::Str(name)]) => match args.get(1) {
+                Some([Token::Ident(kind), rest @ ..]) => (name, kind, rest),
+                _ => return Err(ErrorKind::Syntax("SECTION expects a type".into())),
+            },
+            Some([t, ..]) if t.is_ident("UNION") || t.is_ident("FRAGMENT") => {
+                return Err(ErrorKind::Directive(
+                    "SECTION UNION and SECTION FRAGMENT are not supported".into(),
+                ))
+            }
+            _ => return Err(ErrorKind::Syntax("SECTION expects a quoted name".into())),
+        };
+
+        if self.object.find_section(name).is_some() {
+            return Err(ErrorKind::Section(format!(
+                "section `{name}` already defined"
+            )));
+        }
+
+        let Some(kind) = SectionType::from_name(kind) else {
+            return Err(ErrorKind::Section(format!("unknown section type `{kind}`")));
+        };
+
+        let mut section = Section::new(name.clone(), kind);
+        section.address = self.bracketed(address)?.map(|v| v as u16);
+
+        if let Some(address) = section.address {
+            let (start, end) = kind.address_range();
+
+            if !(start..=end).contains(&address) {
+                return Err(ErrorKind::Section(format!(
+                    "address ${address:04X} is outside of {kind} (${start:04X}-${end:04X})"
+                )));
+            }
+        }
+
+        for option in &args[2..] {
+            match option {
+                [t, rest @ ..] if t.is_ident("BANK") => {
+                    let bank = self.bracketed(rest)?.unwrap_or_default();
+                    let (first, last) = kind.bank_range();
+
+                    if !(first as i64..=last as i64).contains(&bank) || first == last {
+                        return Err(ErrorKind::Section(format!(
+                            "bank {bank} is not valid for {kind} sections"
+                        )));
+                    }
+
+                    section.bank = Some(bank as u32);
+                }
+                [t, rest @ ..] if t.is_ident("ALIGN") => {
+                    let alignment = self.bracketed(rest)?.unwrap_or_default();
+
+                    if !(0..=16).contains(&alignment) {
+                        return Err(ErrorKind::Section("alignment must be 0-16".into()));
+                    }
+
+                    section.alignment = alignment as u8;
+                }
+                _ => return Err(ErrorKind::Syntax("unknown SECTION option".into())),
+            }
+        }
+
+        self.object.sections.push(section);
+        self.section = Some(self.object.sections.len() - 1);
+        self.scope = None;
+
+        Ok(())
+    }
+
+    /// Parses an optional `[expr]` suffix, as used by SECTION options.
+    fn bracketed(&self, tokens: &[Token]) -> Result<Option<i64>, ErrorKind> {
+        match tokens {
+            [] => Ok(None),
+            [Token::LeftBracket, inner @ .., Token::RightBracket] => {
+                Ok(Some(self.constant(&parse_expr(inner, self)?)?))
+            }
+            _ => Err(ErrorKind::Syntax("expected `[value]`".into())),
+        }
+    }
+
+    fn current_section(&mut self) -> Result<&mut Section, ErrorKind> {
+        match self.section {
+            Some(index) => Ok(&mut self.object.sections[index]),
+            None => Err(ErrorKind::Section(
+                "code or data outside of a section".into(),
+            )),
+        }
+    }
+
+    fn current_size(&self) -> usize {
+        self.section
+            .map(|i| self.object.sections[i].size)
+            .unwrap_or_default()
+    }
+
+    fn data_section(&mut self) -> Result<&mut Section, ErrorKind> {
+        let section = self.current_section()?;
+
+        if !section.kind.has_data() {
+            return Err(ErrorKind::Section(format!(
+                "cannot store data in {} section `{}` (only DS is allowed)",
+                section.kind, section.name
+            )));
+        }
+
+        Ok(section)
+    }
+
+    fn emit(&mut self, bytes: &[u8]) -> Result<(), ErrorKind> {
+        let section = self.data_section()?;
+
+        section.data.extend_from_slice(bytes);
+        section.size += bytes.len();
+
+        Ok(())
+    }
+
+    /// Emits a value of the given kind, or a patch if the value can't be calculated until link
+    /// time.
+    fn emit_value(&mut self, kind: PatchKind, expr: Expr) -> Result<(), ErrorKind> {
+        let width = kind.width();
+
+        let value = match kind {
+            PatchKind::Relative => None,
+            _ => self.try_constant(&expr)?,
+        };
+
+        match value {
+            Some(value) => {
+                let bytes = kind.encode(value, 0)?;
+                self.emit(&bytes[..width])
+            }
+            None => {
+                let section = self.data_section()?;
+
+                section.patches.push(Patch {
+                    offset: section.size,
+                    kind,
+                    expr,
+                });
+
+                self.emit(&[0; 2][..width])
+            }
+        }
+    }
+
+    fn data(&mut self, args: &[Token], width: usize) -> Result<(), ErrorKind> {
+        for arg in split_arguments(args) {
+            if let [Token::Str(text)] = arg {
+                for c in text.chars() {
+                    let bytes = (c as u32).to_le_bytes();
+                    self.emit(&bytes[..width])?;
+                }
+
+                continue;
+            }
+
+            let expr = parse_expr(arg, self)?;
+
+            match width {
+                1 => self.emit_value(PatchKind::Byte, expr)?,
+                2 => self.emit_value(PatchKind::Word, expr)?,
+                _ => {
+                    let value = self.constant(&expr)?;
+                    self.emit(&(value as u32).to_le_bytes())?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn reserve(&mut self, args: &[Token]) -> Result<(), ErrorKind> {
+        let args = split_arguments(args);
+
+        let Some((count, fill)) = args.split_first() else {
+            return Err(ErrorKind::Syntax("DS expects a size".into()));
+        };
+
+        let count = self.constant(&parse_expr(count, self)?)?;
+
+        if count < 0 {
+            return Err(ErrorKind::Directive("DS size cannot be negative".into()));
+        }
+
+        let section = self.current_section()?;
+
+        if !section.kind.has_data() {
+            if !fill.is_empty() {
+                return Err(ErrorKind::Section(format!(
+                    "cannot fill {} section `{}`",
+                    section.kind, section.name
+                )));
+            }
+
+            section.size += count as usize;
+            return Ok(());
+        }
+
+        let fill: Vec<Expr> = fill
+            .iter()
+            .map(|f| parse_expr(f, self))
+            .collect::<Result<_, _>>()?;
+
+        for i in 0..count as usize {
+            match fill.get(i % fill.len().max(1)) {
+                Some(expr) => self.emit_value(PatchKind::Byte, expr.clone())?,
+                None => self.emit(&[0])?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn resolve_path(&self, line: &Line, path: &str) -> Result<PathBuf, ErrorKind> {
+        let including_dir = self.frames.iter().rev().find_map(|f| match &f.kind {
+            FrameKind::File { dir } => Some(dir.clone()),
+            _ => None,
+        });
+
+        let candidates = including_dir
+            .flatten()
+            .into_iter()
+            .chain(self.assembler.include_paths.iter().cloned())
+            .map(|dir| dir.join(path))
+            .chain(std::iter::once(PathBuf::from(path)));
+
+        for candidate in candidates {
+            if candidate.is_file() {
+                return Ok(candidate);
+            }
+        }
+
+        Err(ErrorKind::Directive(format!(
+            "unable to find `{path}` (included from {})",
+            line.file
+        )))
+    }
+
+    fn include(&mut self, line: &Line, args: &[Token]) -> Result<(), ErrorKind> {
+        let [Token::Str(path)] = args else {
+            return Err(ErrorKind::Syntax("INCLUDE expects a quoted path".into()));
+        };
+
+        let path = self.resolve_path(line, path)?;
+        let source = fs::read_to_string(&path)?;
+        let dir = path.parent().map(Path::to_path_buf);
+
+        self.push_file(&path.display().to_string(), dir, &source)
+    }
+
+    fn incbin(&mut self, line: &Line, args: &[Token]) -> Result<(), ErrorKind> {
+        let args = split_arguments(args);
+
+        let Some(([Token::Str(path)], range)) = args.split_first() else {
+            return Err(ErrorKind::Syntax("INCBIN expects a quoted path".into()));
+        };
+
+        let path = self.resolve_path(line, path)?;
+        let data = fs::read(&path)?;
+
+        let range: Vec<i64> = range
+            .iter()
+            .map(|r| self.constant(&parse_expr(r, self)?))
+            .collect::<Result<_, _>>()?;
+
+        let negative = |value: i64| {
+            ErrorKind::Directive(format!("INCBIN offsets can't be negative, got {value}"))
+        };
+
+        let start = range.first().copied().unwrap_or(0);
+        let start = usize::try_from(start).map_err(|_| negative(start))?;
+        let length = match range.get(1) {
+            Some(&length) => usize::try_from(length).map_err(|_| negative(length))?,
+            None => data.len().saturating_sub(start),
+        };
+
+        let end = start.checked_add(length);
+
+        let Some(data) = end.and_then(|end| data.get(start..end)) else {
+            return Err(ErrorKind::Directive(format!(
+                "INCBIN range {start}+{length} is outside of `{}` ({} bytes)",
+                path.display(),
+                data.len()
+            )));
+        };
+
+        self.emit(data)
+    }
+
+    fn instruction(&mut self, mnemonic: &str, args: &[Token]) -> Result<(), ErrorKind> {
+        let operands = split_arguments(args)
+            .into_iter()
+            .map(|operand| self.operand(operand))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let encoded = encode::encode(mnemonic, &operands).map_err(|e| match e {
+            ErrorKind::Operand(_) if !is_mnemonic(mnemonic) => {
+                ErrorKind::Syntax(format!("unknown instruction or directive `{mnemonic}`"))
+            }
+            e => e,
+        })?;
+
+        self.emit(&encoded.opcode)?;
+
+        if let Some((kind, expr)) = encoded.operand {
+            self.emit_value(kind, expr)?;
+        }
+
+        Ok(())
+    }
+
+    fn operand(&self, tokens: &[Token]) -> Result<Operand, ErrorKind> {
+        let operand = match tokens {
+            [Token::Ident(name)] => {
+                if let Some(register) = register_from_name(name) {
+                    Operand::Register(register)
+                } else if let Some(pair) = Pair::from_name(name) {
+                    Operand::Pair(pair)
+                } else if let Some(condition) = condition_from_name(name) {
+                    Operand::Condition(condition)
+                } else {
+                    Operand::Immediate(parse_expr(tokens, self)?)
+                }
+            }
+            [Token::Ident(sp), sign @ (Token::Plus | Token::Minus), rest @ ..]
+                if sp.eq_ignore_ascii_case("SP") =>
+            {
+                let expr = parse_expr(rest, self)?;
+
+                Operand::StackOffset(match sign {
+                    Token::Minus => Expr::Unary(crate::object::UnaryOp::Negate, Box::new(expr)),
+                    _ => expr,
+                })
+            }
+            [Token::LeftBracket, inner @ .., Token::RightBracket] => self.pointer(inner)?,
+            tokens => Operand::Immediate(parse_expr(tokens, self)?),
+        };
+
+        Ok(operand)
+    }
+
+    fn pointer(&self, inner: &[Token]) -> Result<Operand, ErrorKind> {
+        let pointer = match inner {
+            [Token::Ident(name)] if name.eq_ignore_ascii_case("HLI") => Operand::HLIncrement,
+            [Token::Ident(name)] if name.eq_ignore_ascii_case("HLD") => Operand::HLDecrement,
+            [Token::Ident(name), Token::Plus] if name.eq_ignore_ascii_case("HL") => {
+                Operand::HLIncrement
+            }
+            [Token::Ident(name), Token::Minus] if name.eq_ignore_ascii_case("HL") => {
+                Operand::HLDecrement
+            }
+            [Token::Ident(name)] if name.eq_ignore_ascii_case("C") => Operand::HighC,
+            [Token::Number(0xFF00), Token::Plus, Token::Ident(name)]
+                if name.eq_ignore_ascii_case("C") =>
+            {
+                Operand::HighC
+            }
+            [Token::Ident(name)] if Pair::from_name(name).is_some() => {
+                match Pair::from_name(name) {
+                    Some(pair @ (Pair::BC | Pair::DE | Pair::HL)) => Operand::Pointer(pair),
+                    _ => return Err(ErrorKind::Operand(format!("[{name}] is not valid"))),
+                }
+            }
+            inner => Operand::Address(parse_expr(inner, self)?),
+        };
+
+        Ok(pointer)
+    }
+
+    /// Evaluates an expression that must be known at assembly time.
+    fn constant(&self, expr: &Expr) -> Result<i64, ErrorKind> {
+        match self.try_constant(expr)? {
+            Some(value) => Ok(value),
+            None => Err(ErrorKind::Expr(expr.evaluate(self).unwrap_err())),
+        }
+    }
+
+    /// Evaluates an expression if every symbol it uses is known. Returns `Ok(None)` if the
+    /// expression must be evaluated by the linker.
+    fn try_constant(&self, expr: &Expr) -> Result<Option<i64>, ErrorKind> {
+        match expr.evaluate(self) {
+            Ok(value) => Ok(Some(value)),
+            Err(ExprError::Unresolved(_) | ExprError::Unplaced) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn finish(mut self) -> Result<Object, ErrorKind> {
+        if self.capture.is_some() {
+            return Err(ErrorKind::Unterminated("MACRO or REPT block"));
+        }
+
+        if !self.conditionals.is_empty() {
+            return Err(ErrorKind::Unterminated("IF block"));
+        }
+
+        for name in &self.exports {
+            match self.object.symbols.iter_mut().find(|s| &s.name == name) {
+                Some(symbol) => symbol.exported = true,
+                None => {
+                    return Err(ErrorKind::Symbol(format!(
+                        "exported symbol `{name}` is never defined"
+                    )))
+                }
+            }
+        }
+
+        Ok(self.object)
+    }
+}
+
+impl Context for Session<'_> {
+    fn qualify(&self, name: &str) -> Result<String, ErrorKind> {
+        if !name.starts_with('.') {
+            return Ok(name.to_owned());
+        }
+
+        match &self.scope {
+            Some(scope) => Ok(format!("{scope}{name}")),
+            None => Err(ErrorKind::Symbol(format!(
+                "local label `{name}` used outside of a global label's scope"
+            ))),
+        }
+    }
+
+    fn here(&self) -> Result<Expr, ErrorKind> {
+        match self.section {
+            Some(section) => Ok(Expr::Location {
+                section,
+                offset: self.here,
+            }),
+            None => Err(ErrorKind::Section("`@` used outside of a section".into())),
+        }
+    }
+
+    fn is_defined(&self, name: &str) -> bool {
+        self.object.find_symbol(name).is_some() || self.macros.contains_key(name)
+    }
+
+    fn builtin(&self, name: &str) -> Option<i64> {
+        if name != "_NARG" {
+            return None;
+        }
+
+        self.frames.iter().rev().find_map(|f| match &f.kind {
+            FrameKind::Macro { args, shift } => Some(args.len().saturating_sub(*shift) as i64),
+            _ => None,
+        })
+    }
+}
+
+/// Resolves symbols at assembly time. Only constants, and labels in sections with a fixed
+/// address, can be resolved before linking.
+impl Resolve for Session<'_> {
+    fn symbol(&self, name: &str) -> Option<i64> {
+        match self.object.find_symbol(name)?.value {
+            SymbolValue::Constant(value) => Some(value),
+            SymbolValue::Label { section, offset } => self.location(section, offset),
+        }
+    }
+
+    fn location(&self, section: usize, offset: usize) -> Option<i64> {
+        let address = self.object.sections.get(section)?.address?;
+        Some(address as i64 + offset as i64)
+    }
+
+    fn symbol_bank(&self, name: &str) -> Option<i64> {
+        match self.object.find_symbol(name)?.value {
+            SymbolValue::Label { section, .. } => self.section_bank(section),
+            SymbolValue::Constant(_) => None,
+        }
+    }
+
+    fn section_bank(&self, section: usize) -> Option<i64> {
+        let section = self.object.sections.get(section)?;
+
+        match section.kind.bank_range() {
+            (first, last) if first == last => Some(first as i64),
+            _ => section.bank.map(|b| b as i64),
+        }
+    }
+
+    fn section_start(&self, name: &str) -> Option<i64> {
+        let section = &self.object.sections[self.object.find_section(name)?];
+        section.address.map(|a| a as i64)
+    }
+
+    fn section_size(&self, _name: &str) -> Option<i64> {
+        // Sections may still grow, so their size is only known once assembly is complete.
+        None
+    }
+}
+
+fn macro_arg(args: &[String], index: usize) -> Result<&str, ErrorKind> {
+    args.get(index).map(String::as_str).ok_or_else(|| {
+        ErrorKind::Directive(format!("macro argument \\{} is not defined", index + 1))
+    })
+}
+
+fn is_mnemonic(name: &str) -> bool {
+    const MNEMONICS: &[&str] = &[
+        "adc", "add", "and", "bit", "call", "ccf", "cp", "cpl", "daa", "dec", "di", "ei", "halt",
+        "inc", "jp", "jr", "ld", "ldd", "ldh", "ldi", "nop", "or", "pop", "push", "res", "ret",
+        "reti", "rl", "rla", "rlc", "rlca", "rr", "rra", "rrc", "rrca", "rst", "sbc", "scf", "set",
+        "sla", "sra", "srl", "stop", "sub", "swap", "xor",
+    ];
+
+    MNEMONICS.contains(&name.to_ascii_lowercase().as_str())
+}
+
+/// Returns the uppercased first word of a line, used to spot directives that must be handled
+/// before the line is otherwise processed (conditionals and the ends of blocks).
+fn first_word(text: &str) -> String {
+    text.split(|c: char| c.is_whitespace() || c == ';')
+        .find(|w| !w.is_empty())
+        .unwrap_or_default()
+        .to_ascii_uppercase()
+}
+
+/// Removes a trailing comment from a line, ignoring semicolons inside strings.
+fn strip_comment(text: &str) -> &str {
+    let mut in_string = false;
+
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            ';' if !in_string => return &text[..i],
+            _ => (),
+        }
+    }
+
+    text
+}
+
+/// Splits a line into its label (if any, along with whether it was exported with `::`) and the
+/// rest of the statement.
+fn statement_text(text: &str) -> (Option<(&str, bool)>, &str) {
+    let trimmed = text.trim_start();
+    let end = trimmed
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '#')))
+        .unwrap_or(trimmed.len());
+
+    let (name, rest) = trimmed.split_at(end);
+    let starts_like_label = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '.');
+
+    if !starts_like_label {
+        return (None, text);
+    }
+
+    if let Some(rest) = rest.strip_prefix("::") {
+        return (Some((name, true)), rest);
+    }
+
+    if let Some(rest) = rest.strip_prefix(':') {
+        return (Some((name, false)), rest);
+    }
+
+    // Local labels may omit the colon.
+    if name.starts_with('.') && name.len() > 1 {
+        return (Some((name, false)), rest);
+    }
+
+    (None, text)
+}
+
+/// Splits the arguments of a macro invocation on commas that aren't inside a string or
+/// parentheses. Arguments are passed to the macro as raw text.
+fn split_macro_args(text: &str) -> Vec<String> {
+    if text.is_empty() {
+        return Vec::new();
+    }
+
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut in_string = false;
+
+    for c in text.chars() {
+        match c {
+            ';' if !in_string => break,
+            '"' => in_string = !in_string,
+            '(' | '[' if !in_string => depth += 1,
+            ')' | ']' if !in_string => depth = depth.saturating_sub(1),
+            ',' if !in_string && depth == 0 => {
+                args.push(current.trim().to_owned());
+                current.clear();
+                continue;
+            }
+            _ => (),
+        }
+
+        current.push(c);
+    }
+
+    args.push(current.trim().to_owned());
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assemble(source: &str) -> Object {
+        Assembler::new()
+            .assemble_source("test.asm", source)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    #[test]
+    fn instructions() {
+        let object = assemble(
+            r#"
+            ; Comments may appear on their own line
+            SECTION "Code", ROM0[$150]
+            Start: ; or after a label
+                nop
+                ld a, [hl+]
+                ld [$FF00+c], a
+                ldh [$FF40], a
+                ld hl, sp+-2
+                add a, $10
+                sub b
+                bit 7, h
+                res 0, [hl]
+                rst $38
+                push af
+                jp Start
+            "#,
+        );
+
+        let section = &object.sections[0];
+        assert_eq!(section.address, Some(0x150));
+        assert_eq!(
+            section.data,
+            [
+                0x00, 0x2A, 0xE2, 0xE0, 0x40, 0xF8, 0xFE, 0xC6, 0x10, 0x90, 0xCB, 0x7C, 0xCB, 0x86,
+                0xFF, 0xF5, 0xC3, 0x50, 0x01
+            ]
+        );
+    }
+
+    #[test]
+    fn forward_references_become_patches() {
+        let object = assemble(
+            r#"
+            SECTION "Code", ROM0
+            Main:
+                jr .loop
+            .loop
+                call Other
+                dw Main.loop
+            "#,
+        );
+
+        let section = &object.sections[0];
+        let kinds: Vec<_> = section.patches.iter().map(|p| (p.offset, p.kind)).collect();
+
+        assert_eq!(
+            kinds,
+            [
+                (1, PatchKind::Relative),
+                (3, PatchKind::Word),
+                (5, PatchKind::Word)
+            ]
+        );
+        assert_eq!(section.patches[2].expr, Expr::Symbol("Main.loop".into()));
+    }
+
+    #[test]
+    fn macros_and_conditionals() {
+        let object = assemble(
+            r#"
+            DEF COUNT EQU 3
+            MACRO fill
+                IF _NARG == 2
+                    REPT \1
+                        db \2
+                    ENDR
+                ELSE
+                    FAIL "fill expects two arguments"
+                ENDC
+            ENDM
+
+            SECTION "Data", ROMX, BANK[2]
+                fill COUNT, $AA
+            IF DEF(MISSING)
+                db 1
+            ELIF COUNT > 2
+                db 2
+            ELSE
+                db 3
+            ENDC
+            "#,
+        );
+
+        assert_eq!(object.sections[0].bank, Some(2));
+        assert_eq!(object.sections[0].data, [0xAA, 0xAA, 0xAA, 2]);
+    }
+
+    #[test]
+    fn errors_report_their_location() {
+        let error = Assembler::new()
+            .assemble_source("bad.asm", "SECTION \"A\", ROM0\n  ld a, [de+]\n")
+            .unwrap_err();
+
+        assert_eq!(error.file, "bad.asm");
+        assert_eq!(error.line, 2);
+    }
+
+    #[test]
+    fn incbin_rejects_negative_ranges() {
+        let path = std::env::temp_dir().join(format!("gb-incbin-{}.bin", std::process::id()));
+        fs::write(&path, [1, 2, 3, 4]).unwrap();
+
+        let include = |range: &str| {
+            let source = format!(
+                "SECTION \"A\", ROM0\nINCBIN \"{}\"{range}\n",
+                path.display()
+            );
+            Assembler::new().assemble_source("test.asm", &source)
+        };
+
+        assert_eq!(include(", 1, 2").unwrap().sections[0].data, [2, 3]);
+
+        for range in [", 1, -1", ", -1", ", 2, 3"] {
+            let error = include(range).unwrap_err();
+            assert!(
+                matches!(error.kind, ErrorKind::Directive(_)),
+                "{range}: {error}"
+            );
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,100 @@
+//! Assembles and links RGBDS-style source files into a ROM image.
+//!
+//! ```text
+//! gbasm [-o game.gb] [-n game.sym] [-i include_dir]... [-p pad] [--fix] main.asm [other.asm]...
+//! ```
+
+use gb_asm::{assembler::Assembler, linker::Linker};
+use std::{path::PathBuf, process::ExitCode};
+
+const USAGE: &str =
+    "usage: gbasm [-o out.gb] [-n out.sym] [-i include_dir]... [-p pad] [--fix] <source>...";
+
+#[derive(Debug, Default)]
+struct Options {
+    output: Option<PathBuf>,
+    symbols: Option<PathBuf>,
+    include_paths: Vec<PathBuf>,
+    pad: u8,
+    fix_header: bool,
+    sources: Vec<PathBuf>,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} expects a value"));
+
+        match arg.as_str() {
+            "-o" | "--output" => options.output = Some(value()?.into()),
+            "-n" | "--sym" => options.symbols = Some(value()?.into()),
+            "-i" | "--include" => options.include_paths.push(value()?.into()),
+            "-p" | "--pad" => {
+                let pad = value()?;
+                let digits = pad.trim_start_matches("0x").trim_start_matches('$');
+
+                options.pad = u8::from_str_radix(digits, 16)
+                    .map_err(|_| format!("invalid pad value `{pad}`"))?;
+            }
+            "--fix" => options.fix_header = true,
+            "-h" | "--help" => return Err(USAGE.into()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{arg}`\n{USAGE}")),
+            _ => options.sources.push(arg.into()),
+        }
+    }
+
+    if options.sources.is_empty() {
+        return Err(USAGE.into());
+    }
+
+    Ok(options)
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let mut assembler = Assembler::new();
+
+    for path in &options.include_paths {
+        assembler.include_path(path);
+    }
+
+    let objects = options
+        .sources
+        .iter()
+        .map(|path| assembler.assemble_file(path))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let linked = Linker::new()
+        .pad(options.pad)
+        .fix_header(options.fix_header)
+        .link(&objects)
+        .map_err(|e| e.to_string())?;
+
+    let output = options
+        .output
+        .unwrap_or_else(|| options.sources[0].with_extension("gb"));
+
+    std::fs::write(&output, &linked.rom)
+        .map_err(|e| format!("unable to write {}: {e}", output.display()))?;
+
+    if let Some(path) = options.symbols {
+        std::fs::write(&path, linked.symbols.to_string())
+            .map_err(|e| format!("unable to write {}: {e}", path.display()))?;
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let result = parse_args().and_then(run);
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
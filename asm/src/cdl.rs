@@ -0,0 +1,152 @@
+//! Code/data logs (`.cdl` files), which record how each byte of a ROM was used while it ran.
+//!
+//! A log holds one byte of flags for each byte of the ROM image, at the same offset, so it doesn't
+//! matter which bank a byte was mapped through when it was used. Logs of several runs of the same
+//! ROM can be merged to combine their coverage.
+
+use std::fmt::Display;
+
+/// The byte was fetched as the first byte of an instruction.
+pub const OPCODE: u8 = 0x01;
+/// The byte was fetched as the rest of an instruction, including the second byte of a `$CB`
+/// prefixed one.
+pub const OPERAND: u8 = 0x02;
+/// The byte was read by an instruction.
+pub const DATA: u8 = 0x04;
+/// The byte was copied to OAM by a DMA transfer.
+pub const DMA: u8 = 0x08;
+
+const BANK_SIZE: usize = 0x4000;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CodeDataLog {
+    flags: Vec<u8>,
+}
+
+impl CodeDataLog {
+    /// Creates an empty log for a ROM of `size` bytes.
+    pub fn new(size: usize) -> Self {
+        Self {
+            flags: vec![0; size],
+        }
+    }
+
+    /// Reads a log from the contents of a `.cdl` file.
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self { flags: bytes }
+    }
+
+    /// Returns the contents of the `.cdl` file.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.flags
+    }
+
+    pub fn len(&self) -> usize {
+        self.flags.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.flags.is_empty()
+    }
+
+    /// Returns the flags of the byte at `offset` in the ROM.
+    pub fn flags(&self, offset: usize) -> u8 {
+        self.flags.get(offset).copied().unwrap_or_default()
+    }
+
+    /// Adds `flags` to the byte at `offset` in the ROM. Offsets past the end of the ROM are
+    /// ignored.
+    pub fn mark(&mut self, offset: usize, flags: u8) {
+        if let Some(byte) = self.flags.get_mut(offset) {
+            *byte |= flags;
+        }
+    }
+
+    /// Adds the flags of `other`, such as a log of another run of the same ROM.
+    pub fn merge(&mut self, other: &Self) {
+        if self.flags.len() < other.flags.len() {
+            self.flags.resize(other.flags.len(), 0);
+        }
+
+        for (byte, flags) in self.flags.iter_mut().zip(&other.flags) {
+            *byte |= flags;
+        }
+    }
+
+    /// Counts how the bytes of each 16 KiB bank were used.
+    pub fn stats(&self) -> Vec<BankStats> {
+        self.flags
+            .chunks(BANK_SIZE)
+            .enumerate()
+            .map(|(bank, flags)| BankStats {
+                bank,
+                size: flags.len(),
+                code: flags
+                    .iter()
+                    .filter(|&f| f & (OPCODE | OPERAND) != 0)
+                    .count(),
+                data: flags.iter().filter(|&f| f & (DATA | DMA) != 0).count(),
+                used: flags.iter().filter(|&&f| f != 0).count(),
+            })
+            .collect()
+    }
+}
+
+/// How the bytes of a ROM bank were used. A byte can count as both code and data.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BankStats {
+    pub bank: usize,
+    pub size: usize,
+    pub code: usize,
+    pub data: usize,
+    pub used: usize,
+}
+
+impl Display for BankStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "bank ${:03X}: {:5.1}% used, {} bytes of code, {} bytes of data",
+            self.bank,
+            self.used as f64 * 100.0 / self.size.max(1) as f64,
+            self.code,
+            self.data,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_coverage_per_bank() {
+        let mut log = CodeDataLog::new(0x8000);
+        log.mark(0x0150, OPCODE);
+        log.mark(0x0151, OPERAND);
+        log.mark(0x0151, DATA);
+        log.mark(0x4000, DMA);
+        log.mark(0x8000, DATA);
+
+        assert_eq!(log.flags(0x0151), OPERAND | DATA);
+        assert_eq!(
+            log.stats()[0],
+            BankStats {
+                bank: 0,
+                size: 0x4000,
+                code: 2,
+                data: 1,
+                used: 2,
+            }
+        );
+        assert_eq!(
+            log.stats()[1].to_string(),
+            "bank $001:   0.0% used, 0 bytes of code, 1 bytes of data"
+        );
+
+        let mut other = CodeDataLog::from_bytes(log.as_bytes().to_vec());
+        other.mark(0x0152, DATA);
+        log.merge(&other);
+        assert_eq!(log.stats()[0].used, 3);
+    }
+}
@@ -1,23 +1,12 @@
-use super::test::Target::{self, *};
-use crate::{cycles, Bit, Cycles, Info};
+use super::test::Target;
+use crate::{info_from_table, Bit};
 use derive_more::derive::Display;
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[display("RES {position}, {target}")]
 pub struct ResetBit {
     pub position: Bit,
     pub target: Target,
 }
 
-impl Info for ResetBit {
-    fn bytes(&self) -> u8 {
-        2
-    }
-
-    fn cycles(&self) -> Cycles {
-        match self.target {
-            Register(_) => cycles!(2),
-            PointerValue => cycles!(4),
-        }
-    }
-}
+info_from_table!(ResetBit);
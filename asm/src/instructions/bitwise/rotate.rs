@@ -1,8 +1,8 @@
-use crate::{cycles, Cycles, Info, Register};
+use crate::{info_from_table, Register};
 use derive_more::derive::Display;
 use std::fmt::Display;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Rotate {
     pub direction: Direction,
     pub target: Target,
@@ -26,39 +26,21 @@ impl Display for Rotate {
     }
 }
 
-impl Info for Rotate {
-    fn bytes(&self) -> u8 {
-        if self.target == Target::Accumulator {
-            1
-        } else {
-            2
-        }
-    }
-
-    fn cycles(&self) -> Cycles {
-        use Target::*;
-
-        match self.target {
-            Accumulator => cycles!(1),
-            Register(_) => cycles!(2),
-            PointerValue => cycles!(4),
-        }
-    }
-}
+info_from_table!(Rotate);
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Direction {
     Left,
     Right,
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Behavior {
     Carrying,
     Cyclic,
 }
 
-#[derive(Debug, Copy, Clone, Display, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 pub enum Target {
     #[display("A")]
     Accumulator,
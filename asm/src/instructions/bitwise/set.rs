@@ -1,25 +1,12 @@
 use super::test::Target;
-use crate::{cycles, Bit, Cycles, Info};
+use crate::{info_from_table, Bit};
 use derive_more::derive::Display;
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[display("SET {position}, {target}")]
 pub struct SetBit {
     pub position: Bit,
     pub target: Target,
 }
 
-impl Info for SetBit {
-    fn bytes(&self) -> u8 {
-        2
-    }
-
-    fn cycles(&self) -> Cycles {
-        use Target::*;
-
-        match self.target {
-            Register(_) => cycles!(2),
-            PointerValue => cycles!(4),
-        }
-    }
-}
+info_from_table!(SetBit);
@@ -1,22 +1,11 @@
-use super::shift_right::Target::{self, *};
-use crate::{cycles, Cycles, Info};
+use super::shift_right::Target;
+use crate::info_from_table;
 use derive_more::derive::Display;
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[display("SLA {target}")]
 pub struct ShiftLeft {
     pub target: Target,
 }
 
-impl Info for ShiftLeft {
-    fn bytes(&self) -> u8 {
-        2
-    }
-
-    fn cycles(&self) -> Cycles {
-        match self.target {
-            Register(_) => cycles!(2),
-            PointerValue => cycles!(4),
-        }
-    }
-}
+info_from_table!(ShiftLeft);
@@ -1,29 +1,16 @@
-use crate::{cycles, Cycles, Info, Register};
+use crate::{info_from_table, Register};
 use derive_more::derive::Display;
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[display("SR{behavior} {target}")]
 pub struct ShiftRight {
     pub target: Target,
     pub behavior: Behavior,
 }
 
-impl Info for ShiftRight {
-    fn bytes(&self) -> u8 {
-        2
-    }
+info_from_table!(ShiftRight);
 
-    fn cycles(&self) -> Cycles {
-        use Target::*;
-
-        match self.target {
-            Register(_) => cycles!(2),
-            PointerValue => cycles!(4),
-        }
-    }
-}
-
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 pub enum Target {
     #[display("{_0}")]
     Register(Register),
@@ -31,7 +18,7 @@ pub enum Target {
     PointerValue,
 }
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 pub enum Behavior {
     #[display("A")]
     Arithmetic,
@@ -1,22 +1,11 @@
-use super::test::Target::{self, *};
-use crate::{cycles, Info};
+use super::test::Target;
+use crate::info_from_table;
 use derive_more::derive::Display;
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[display("SWAP {target}")]
 pub struct Swap {
     pub target: Target,
 }
 
-impl Info for Swap {
-    fn bytes(&self) -> u8 {
-        2
-    }
-
-    fn cycles(&self) -> crate::Cycles {
-        match self.target {
-            Register(_) => cycles!(2),
-            PointerValue => cycles!(4),
-        }
-    }
-}
+info_from_table!(Swap);
@@ -1,29 +1,16 @@
-use crate::{cycles, Bit, Cycles, Info, Register};
+use crate::{info_from_table, Bit, Register};
 use derive_more::derive::Display;
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[display("BIT {position}, {target}")]
 pub struct Test {
     pub position: Bit,
     pub target: Target,
 }
 
-impl Info for Test {
-    fn bytes(&self) -> u8 {
-        2
-    }
+info_from_table!(Test);
 
-    fn cycles(&self) -> Cycles {
-        use Target::*;
-
-        match self.target {
-            PointerValue => cycles!(3),
-            Register(_) => cycles!(2),
-        }
-    }
-}
-
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 pub enum Target {
     #[display("(HL)")]
     PointerValue,
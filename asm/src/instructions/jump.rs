@@ -1,33 +1,18 @@
 use super::Condition;
-use crate::{Cycles, Info};
+use crate::info_from_table;
 use derive_more::derive::Display;
 use std::fmt::Display;
 use Target::*;
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[display("JP {target}")]
 pub struct Jump {
     pub target: Target,
 }
 
-impl Info for Jump {
-    fn bytes(&self) -> u8 {
-        match self.target {
-            Pointer => 1,
-            ConstantAddress(_) => 3,
-        }
-    }
-
-    fn cycles(&self) -> Cycles {
-        match self.target {
-            Pointer => Cycles::Fixed(1),
-            ConstantAddress(cond) if cond.is_some() => Cycles::Variable(3, 4),
-            ConstantAddress(_) => Cycles::Fixed(4),
-        }
-    }
-}
+info_from_table!(Jump);
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Target {
     Pointer,
     ConstantAddress(Option<Condition>),
@@ -57,24 +42,12 @@ impl Display for Target {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct JumpRelative {
     pub condition: Option<Condition>,
 }
 
-impl Info for JumpRelative {
-    fn bytes(&self) -> u8 {
-        2
-    }
-
-    fn cycles(&self) -> Cycles {
-        if self.condition.is_some() {
-            Cycles::Variable(3, 4)
-        } else {
-            Cycles::Fixed(3)
-        }
-    }
-}
+info_from_table!(JumpRelative);
 
 impl Display for JumpRelative {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -1,5 +1,5 @@
 use super::Instruction;
-use crate::{cycles, with_info_trait, with_simple_info, Cycles, Info, Pair, Register};
+use crate::{info_from_table, Pair, Register};
 use derive_more::derive::{Display, From};
 
 pub mod to_register;
@@ -8,54 +8,54 @@ pub use to_register::*;
 pub mod to_accumulator;
 pub use to_accumulator::*;
 
-with_info_trait!(
-    #[derive(Debug, Copy, Clone, Display, From)]
-    #[display("LD {_0}")]
-    pub enum Load {
-        /// LD r8, r8
-        /// LD r8, d8
-        /// LD r8, (HL)
-        ToRegister(ToRegister),
-
-        /// LD A, (r16)
-        /// LD A, (HL+)
-        /// LD A, (HL-)
-        /// LD A, (d16)
-        /// LD A, (C)
-        /// LD A, ($FF00+d8)
-        ToAccumulator(ToAccumulator),
-
-        /// LD r16, d16
-        ToPair(ToPair),
-
-        /// LD (r16), A
-        /// LD (HL+), A
-        /// LD (HL-), A
-        ToPairPointer(ToPairPointer),
-
-        /// LD (HL), d8
-        /// LD (HL), r8
-        ToHLPointer(ToHLPointer),
-
-        /// LD SP, HL
-        /// LD SP, d16
-        ToStackPointer(ToStackPointer),
-
-        /// LD ($FF00+C), A
-        ToHighC(ToHighC),
-
-        /// LD (d16), A
-        ToConstantPointer(ToConstantPointer),
-
-        /// LD ($FF00+d8), A
-        ToHighConstantPointer(ToHighConstantPointer),
-
-        /// LD HL, SP+s8
-        ToHL(ToHL),
-    }
-);
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display, From)]
+#[display("LD {_0}")]
+pub enum Load {
+    /// LD r8, r8
+    /// LD r8, d8
+    /// LD r8, (HL)
+    ToRegister(ToRegister),
+
+    /// LD A, (r16)
+    /// LD A, (HL+)
+    /// LD A, (HL-)
+    /// LD A, (d16)
+    /// LD A, (C)
+    /// LD A, ($FF00+d8)
+    ToAccumulator(ToAccumulator),
+
+    /// LD r16, d16
+    ToPair(ToPair),
+
+    /// LD (r16), A
+    /// LD (HL+), A
+    /// LD (HL-), A
+    ToPairPointer(ToPairPointer),
+
+    /// LD (HL), d8
+    /// LD (HL), r8
+    ToHLPointer(ToHLPointer),
+
+    /// LD SP, HL
+    /// LD SP, d16
+    ToStackPointer(ToStackPointer),
+
+    /// LD ($FF00+C), A
+    ToHighC(ToHighC),
+
+    /// LD (d16), A
+    ToConstantPointer(ToConstantPointer),
+
+    /// LD ($FF00+d8), A
+    ToHighConstantPointer(ToHighConstantPointer),
+
+    /// LD HL, SP+s8
+    ToHL(ToHL),
+}
+
+info_from_table!(Load);
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 pub enum Action {
     #[display("+")]
     Increment,
@@ -72,13 +72,13 @@ impl Action {
     }
 }
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[display("{target}, d16")]
 pub struct ToPair {
     pub target: Pair,
 }
 
-with_simple_info!(ToPair => (3, 3));
+info_from_table!(ToPair);
 
 impl From<ToPair> for Instruction {
     fn from(value: ToPair) -> Self {
@@ -86,31 +86,13 @@ impl From<ToPair> for Instruction {
     }
 }
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[display("(HL), {source}")]
 pub struct ToHLPointer {
     pub source: ToHLPointerSource,
 }
 
-impl Info for ToHLPointer {
-    fn bytes(&self) -> u8 {
-        use ToHLPointerSource::*;
-
-        match self.source {
-            Register(_) => 1,
-            ConstantByte => 2,
-        }
-    }
-
-    fn cycles(&self) -> Cycles {
-        use ToHLPointerSource::*;
-
-        match self.source {
-            Register(_) => cycles!(2),
-            ConstantByte => cycles!(3),
-        }
-    }
-}
+info_from_table!(ToHLPointer);
 
 impl From<ToHLPointer> for Instruction {
     fn from(value: ToHLPointer) -> Self {
@@ -118,7 +100,7 @@ impl From<ToHLPointer> for Instruction {
     }
 }
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 pub enum ToHLPointerSource {
     #[display("{_0}")]
     Register(Register),
@@ -126,13 +108,13 @@ pub enum ToHLPointerSource {
     ConstantByte,
 }
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[display("{target}, A")]
 pub struct ToPairPointer {
     pub target: ToPairPointerTarget,
 }
 
-with_simple_info!(ToPairPointer => (1, 2));
+info_from_table!(ToPairPointer);
 
 impl From<ToPairPointer> for Instruction {
     fn from(value: ToPairPointer) -> Self {
@@ -140,7 +122,7 @@ impl From<ToPairPointer> for Instruction {
     }
 }
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 pub enum ToPairPointerTarget {
     #[display("({_0})")]
     Pair(Pair),
@@ -148,31 +130,13 @@ pub enum ToPairPointerTarget {
     HLX(Action),
 }
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[display("SP, {source}")]
 pub struct ToStackPointer {
     pub source: ToStackPointerSource,
 }
 
-impl Info for ToStackPointer {
-    fn bytes(&self) -> u8 {
-        use ToStackPointerSource::*;
-
-        match self.source {
-            HL => 1,
-            ConstantWord => 3,
-        }
-    }
-
-    fn cycles(&self) -> Cycles {
-        use ToStackPointerSource::*;
-
-        match self.source {
-            HL => cycles!(2),
-            ConstantWord => cycles!(3),
-        }
-    }
-}
+info_from_table!(ToStackPointer);
 
 impl From<ToStackPointer> for Instruction {
     fn from(value: ToStackPointer) -> Self {
@@ -180,7 +144,7 @@ impl From<ToStackPointer> for Instruction {
     }
 }
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 pub enum ToStackPointerSource {
     #[display("HL")]
     HL,
@@ -188,26 +152,13 @@ pub enum ToStackPointerSource {
     ConstantWord,
 }
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[display("(d16), {source}")]
 pub struct ToConstantPointer {
     pub source: ToConstantPointerSource,
 }
 
-impl Info for ToConstantPointer {
-    fn bytes(&self) -> u8 {
-        3
-    }
-
-    fn cycles(&self) -> Cycles {
-        use ToConstantPointerSource::*;
-
-        match self.source {
-            Accumulator => cycles!(4),
-            StackPointer => cycles!(5),
-        }
-    }
-}
+info_from_table!(ToConstantPointer);
 
 impl From<ToConstantPointer> for Instruction {
     fn from(value: ToConstantPointer) -> Self {
@@ -215,7 +166,7 @@ impl From<ToConstantPointer> for Instruction {
     }
 }
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 pub enum ToConstantPointerSource {
     #[display("A")]
     Accumulator,
@@ -223,11 +174,11 @@ pub enum ToConstantPointerSource {
     StackPointer,
 }
 
-with_simple_info! {
-    #[derive(Debug, Copy, Clone, Display)]
-    #[display("($FF00+C), A")]
-    pub struct ToHighC => (1, 2);
-}
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
+#[display("($FF00+C), A")]
+pub struct ToHighC;
+
+info_from_table!(ToHighC);
 
 impl From<ToHighC> for Instruction {
     fn from(value: ToHighC) -> Self {
@@ -235,11 +186,11 @@ impl From<ToHighC> for Instruction {
     }
 }
 
-with_simple_info! {
-    #[derive(Debug, Copy, Clone, Display)]
-    #[display("HL, SP+s8")]
-    pub struct ToHL => (2, 3);
-}
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
+#[display("HL, SP+s8")]
+pub struct ToHL;
+
+info_from_table!(ToHL);
 
 impl From<ToHL> for Instruction {
     fn from(value: ToHL) -> Self {
@@ -247,11 +198,11 @@ impl From<ToHL> for Instruction {
     }
 }
 
-with_simple_info! {
-    #[derive(Debug, Copy, Clone, Display)]
-    #[display("($FF00+d8), A")]
-    pub struct ToHighConstantPointer => (2, 3);
-}
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
+#[display("($FF00+d8), A")]
+pub struct ToHighConstantPointer;
+
+info_from_table!(ToHighConstantPointer);
 
 impl From<ToHighConstantPointer> for Instruction {
     fn from(value: ToHighConstantPointer) -> Self {
@@ -1,34 +1,14 @@
 use super::{Action, Load};
-use crate::{cycles, instructions::Instruction, Cycles, Info, Pair};
+use crate::{info_from_table, instructions::Instruction, Pair};
 use derive_more::derive::Display;
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[display("A, {source}")]
 pub struct ToAccumulator {
     pub source: ToAccumulatorSource,
 }
 
-impl Info for ToAccumulator {
-    fn bytes(&self) -> u8 {
-        use ToAccumulatorSource::*;
-
-        match self.source {
-            PairPointer(_) | HLX(_) | HighC => 1,
-            HighConstantPointer => 2,
-            ConstantPointer => 3,
-        }
-    }
-
-    fn cycles(&self) -> Cycles {
-        use ToAccumulatorSource::*;
-
-        match self.source {
-            PairPointer(_) | HLX(_) | HighC => cycles!(2),
-            HighConstantPointer => cycles!(3),
-            ConstantPointer => cycles!(4),
-        }
-    }
-}
+info_from_table!(ToAccumulator);
 
 impl From<ToAccumulator> for Instruction {
     fn from(value: ToAccumulator) -> Self {
@@ -36,7 +16,7 @@ impl From<ToAccumulator> for Instruction {
     }
 }
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 pub enum ToAccumulatorSource {
     #[display("({_0})")]
     PairPointer(Pair),
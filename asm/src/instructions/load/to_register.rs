@@ -1,33 +1,15 @@
 use super::Load;
-use crate::{cycles, instructions::Instruction, sources::ByteSource, Cycles, Info, Register};
+use crate::{info_from_table, instructions::Instruction, sources::ByteSource, Register};
 use derive_more::derive::Display;
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[display("{target}, {source}")]
 pub struct ToRegister {
     pub target: Register,
     pub source: ByteSource,
 }
 
-impl Info for ToRegister {
-    fn bytes(&self) -> u8 {
-        use ByteSource::*;
-
-        match self.source {
-            Register(_) | PointerValue => 1,
-            ConstantByte => 2,
-        }
-    }
-
-    fn cycles(&self) -> Cycles {
-        use ByteSource::*;
-
-        match self.source {
-            Register(_) => cycles!(1),
-            PointerValue | ConstantByte => cycles!(2),
-        }
-    }
-}
+info_from_table!(ToRegister);
 
 impl From<ToRegister> for Instruction {
     fn from(value: ToRegister) -> Self {
@@ -1,7 +1,7 @@
-use crate::{cycles, instructions::Instruction, sources::ByteSource, Cycles, Info, Pair};
+use crate::{info_from_table, instructions::Instruction, sources::ByteSource, Pair};
 use derive_more::derive::{Display, From};
 
-#[derive(Debug, Copy, Clone, Display, From)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display, From)]
 pub enum Add {
     #[display("ADD {_0}")]
     ToAccumulator(ToAccumulator),
@@ -11,49 +11,15 @@ pub enum Add {
     ToStackPointer,
 }
 
-impl Info for Add {
-    fn bytes(&self) -> u8 {
-        match self {
-            Self::ToAccumulator(v) => v.bytes(),
-            Self::ToHLPair(_) => 1,
-            Self::ToStackPointer => 2,
-        }
-    }
+info_from_table!(Add);
 
-    fn cycles(&self) -> Cycles {
-        match self {
-            Self::ToAccumulator(v) => v.cycles(),
-            Self::ToHLPair(_) => cycles!(2),
-            Self::ToStackPointer => cycles!(4),
-        }
-    }
-}
-
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[display("A, {source}")]
 pub struct ToAccumulator {
     pub source: ByteSource,
 }
 
-impl Info for ToAccumulator {
-    fn bytes(&self) -> u8 {
-        use ByteSource::*;
-
-        match self.source {
-            PointerValue | Register(_) => 1,
-            ConstantByte => 2,
-        }
-    }
-
-    fn cycles(&self) -> Cycles {
-        use ByteSource::*;
-
-        match self.source {
-            Register(_) => Cycles::Fixed(1),
-            ConstantByte | PointerValue => Cycles::Fixed(2),
-        }
-    }
-}
+info_from_table!(ToAccumulator);
 
 impl From<ToAccumulator> for Instruction {
     fn from(value: ToAccumulator) -> Self {
@@ -61,7 +27,7 @@ impl From<ToAccumulator> for Instruction {
     }
 }
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[display("HL, {source}")]
 pub struct ToHLPair {
     pub source: ToHLPairSource,
@@ -73,7 +39,7 @@ impl From<ToHLPair> for Instruction {
     }
 }
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 pub enum ToHLPairSource {
     #[display("{_0}")]
     Pair(Pair),
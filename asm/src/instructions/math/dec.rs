@@ -1,28 +1,15 @@
-use crate::{Cycles, Info, Pair, Register};
+use crate::{info_from_table, Pair, Register};
 use derive_more::derive::Display;
-use Target::*;
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[display("DEC {target}")]
 pub struct Decrement {
     pub target: Target,
 }
 
-impl Info for Decrement {
-    fn bytes(&self) -> u8 {
-        1
-    }
+info_from_table!(Decrement);
 
-    fn cycles(&self) -> Cycles {
-        match self.target {
-            Register(_) => Cycles::Fixed(1),
-            Pair(_) | StackPointer => Cycles::Fixed(2),
-            PointerValue => Cycles::Fixed(3),
-        }
-    }
-}
-
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 pub enum Target {
     #[display("{_0}")]
     Register(Register),
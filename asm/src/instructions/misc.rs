@@ -1,62 +1,69 @@
-use crate::with_simple_info;
+use crate::info_from_table;
 use derive_more::derive::Display;
 
-with_simple_info! {
-    #[derive(Debug, Copy, Clone, Display)]
-    #[display("CCF")]
-    pub struct ComplementCarryFlag => (1, 1);
-}
-
-with_simple_info! {
-    #[derive(Debug, Copy, Clone, Display)]
-    #[display("CPL")]
-    pub struct ComplementAccumulator => (1, 1);
-}
-
-with_simple_info! {
-    #[derive(Debug, Copy, Clone, Display)]
-    #[display("DAA")]
-    pub struct DecimalAdjustAccumulator => (1, 1);
-}
-
-with_simple_info! {
-    #[derive(Debug, Copy, Clone, Display)]
-    #[display("DI")]
-    pub struct DisableInterrupts => (1, 1);
-}
-
-with_simple_info! {
-    #[derive(Debug, Copy, Clone, Display)]
-    #[display("EI")]
-    pub struct EnableInterrupts => (1, 1);
-}
-
-with_simple_info! {
-    #[derive(Debug, Copy, Clone, Display)]
-    #[display("HALT")]
-    pub struct Halt => (1, 1);
-}
-
-with_simple_info! {
-    #[derive(Debug, Copy, Clone, Display)]
-    #[display("NOP")]
-    pub struct Nop => (1, 1);
-}
-
-with_simple_info! {
-    #[derive(Debug, Copy, Clone, Display)]
-    #[display("SCF")]
-    pub struct SetCarryFlag => (1, 1);
-}
-
-with_simple_info! {
-    #[derive(Debug, Copy, Clone, Display)]
-    #[display("STOP")]
-    pub struct Stop => (1, 2);
-}
-
-with_simple_info! {
-    #[derive(Debug, Copy, Clone, Display)]
-    #[display("PREFIX")]
-    pub struct Prefix => (1, 1);
-}
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
+#[display("CCF")]
+pub struct ComplementCarryFlag;
+
+info_from_table!(ComplementCarryFlag);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
+#[display("CPL")]
+pub struct ComplementAccumulator;
+
+info_from_table!(ComplementAccumulator);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
+#[display("DAA")]
+pub struct DecimalAdjustAccumulator;
+
+info_from_table!(DecimalAdjustAccumulator);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
+#[display("DI")]
+pub struct DisableInterrupts;
+
+info_from_table!(DisableInterrupts);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
+#[display("EI")]
+pub struct EnableInterrupts;
+
+info_from_table!(EnableInterrupts);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
+#[display("HALT")]
+pub struct Halt;
+
+info_from_table!(Halt);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
+#[display("NOP")]
+pub struct Nop;
+
+info_from_table!(Nop);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
+#[display("SCF")]
+pub struct SetCarryFlag;
+
+info_from_table!(SetCarryFlag);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
+#[display("STOP")]
+pub struct Stop;
+
+info_from_table!(Stop);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
+#[display("PREFIX")]
+pub struct Prefix;
+
+info_from_table!(Prefix);
+
+/// One of the opcodes the CPU doesn't implement, which locks it up.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
+#[display("DB ${_0:02X}")]
+pub struct Illegal(pub u8);
+
+info_from_table!(Illegal);
@@ -1,15 +1,15 @@
-use crate::{with_simple_info, Pair};
+use crate::{info_from_table, Pair};
 use derive_more::derive::Display;
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[display("POP {target}")]
 pub struct Pop {
     pub target: Source,
 }
 
-with_simple_info!(Pop => (1, 3));
+info_from_table!(Pop);
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 pub enum Source {
     #[display("AF")]
     AccumulatorAndFlags,
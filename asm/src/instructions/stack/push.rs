@@ -1,11 +1,11 @@
 use super::pop::Source;
-use crate::with_simple_info;
+use crate::info_from_table;
 use derive_more::derive::Display;
 
-#[derive(Debug, Copy, Clone, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[display("PUSH {source}")]
 pub struct Push {
     pub source: Source,
 }
 
-with_simple_info!(Push => (1, 4));
+info_from_table!(Push);
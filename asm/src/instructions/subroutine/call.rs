@@ -1,43 +1,23 @@
-use crate::{cycles, instructions::Condition, Cycles, Info};
+use crate::{info_from_table, instructions::Condition};
 use std::fmt::Display;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Call {
     Vector(VectorSlot),
     ConstantAddress(Option<Condition>),
 }
 
-impl Info for Call {
-    fn bytes(&self) -> u8 {
-        match self {
-            Self::Vector(_) => 1,
-            Self::ConstantAddress(_) => 3,
-        }
-    }
-
-    fn cycles(&self) -> Cycles {
-        match self {
-            Self::Vector(_) => cycles!(4),
-            Self::ConstantAddress(cond) => {
-                if cond.is_some() {
-                    cycles!(3, 6)
-                } else {
-                    cycles!(6)
-                }
-            }
-        }
-    }
-}
+info_from_table!(Call);
 
 impl Display for Call {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Vector(v) => write!(f, "RST {}", *v as u8),
+            Self::Vector(v) => write!(f, "RST {:02X}H", *v as u8),
             Self::ConstantAddress(cond) => {
                 write!(f, "CALL ")?;
 
                 if let Some(cond) = cond {
-                    write!(f, "{cond}")?;
+                    write!(f, "{cond}, ")?;
                 }
 
                 write!(f, "d16")
@@ -46,17 +26,17 @@ impl Display for Call {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[repr(u16)]
 /// Represents the "restart vector" slots present at the start of ROM. Can be cast to a `u8` in
 /// order to access the address represented by each slot.
 pub enum VectorSlot {
     Zero = 0,
-    One = 8,
-    Two = 10,
-    Three = 18,
-    Four = 20,
-    Five = 28,
-    Six = 30,
-    Seven = 38,
+    One = 0x08,
+    Two = 0x10,
+    Three = 0x18,
+    Four = 0x20,
+    Five = 0x28,
+    Six = 0x30,
+    Seven = 0x38,
 }
@@ -1,7 +1,7 @@
-use crate::{cycles, instructions::Condition, Cycles, Info};
+use crate::{info_from_table, instructions::Condition};
 use std::fmt::Display;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Return {
     Normal(Option<Condition>),
     EnableInterrupts,
@@ -19,16 +19,4 @@ impl Display for Return {
     }
 }
 
-impl Info for Return {
-    fn bytes(&self) -> u8 {
-        1
-    }
-
-    fn cycles(&self) -> Cycles {
-        match self {
-            Self::Normal(Some(_)) => cycles!(2, 5),
-            Self::Normal(None) => cycles!(4),
-            Self::EnableInterrupts => cycles!(4),
-        }
-    }
-}
+info_from_table!(Return);
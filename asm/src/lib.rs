@@ -1,10 +1,17 @@
 use derive_more::derive::Display;
 use std::fmt::Display;
 
+pub mod assembler;
+pub mod cdl;
 pub mod instructions;
+pub mod linker;
+pub mod metadata;
+pub mod object;
 pub mod sources;
+pub mod symbols;
+pub mod table;
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Register {
     A,
     B,
@@ -31,7 +38,7 @@ impl Display for Register {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Pair {
     BC,
     DE,
@@ -60,7 +67,7 @@ impl Pair {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[repr(u8)]
 pub enum Flag {
     Carry = 0b0001_0000,
@@ -80,7 +87,7 @@ impl Flag {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Cycles {
     Variable(u8, u8),
     Fixed(u8),
@@ -114,106 +121,29 @@ impl From<(u8, u8)> for Cycles {
     }
 }
 
-#[macro_export]
-macro_rules! cycles {
-    ( $base:expr ) => {
-        $crate::Cycles::from($base)
-    };
-
-    ( $base:expr, $max:expr ) => {
-        $crate::Cycles::from(($base, $max))
-    };
-}
-
 pub trait Info {
     fn bytes(&self) -> u8;
     fn cycles(&self) -> Cycles;
 }
 
+/// Implements [`Info`] for an instruction type by looking up the
+/// [`Instruction`](instructions::Instruction) it converts to in the [opcode tables](table).
 #[macro_export]
-macro_rules! with_info_trait {
-    (
-        $( #[$meta:meta] )*
-        $vis:vis enum $type:ident {
-            $(
-                $( #[$variant_meta:meta] )*
-                $variant:ident ($inner:path) $(,)?
-            ),*
-        }
-    ) => {
-        $( #[$meta] )*
-        $vis enum $type {
-            $(
-                $( #[$variant_meta] )*
-                $variant ($inner)
-            ),*
-        }
-
-        impl $crate::Info for $type {
-            fn bytes(&self) -> u8 {
-                match self {
-                    $( Self::$variant(v) => v.bytes() ),*
-                }
-            }
-
-            fn cycles(&self) -> Cycles {
-                match self {
-                    $( Self::$variant(v) => v.cycles() ),*
-                }
-            }
-        }
-    };
-}
-
-#[macro_export]
-macro_rules! with_simple_info {
-    (
-        $( #[$meta:meta] )*
-        $vis:vis struct $type:ident => ( $bytes:expr, $cycles:expr );
-    ) => {
-        $( #[$meta] )*
-        $vis struct $type;
-
+macro_rules! info_from_table {
+    ( $type:ty ) => {
         impl $crate::Info for $type {
             fn bytes(&self) -> u8 {
-                $bytes
+                $crate::table::entry(&(*self).into()).bytes
             }
 
             fn cycles(&self) -> $crate::Cycles {
-                $cycles.into()
-            }
-        }
-    };
-
-    ( $type:ty => ($bytes:expr, $cycles:expr) ) => {
-        impl $crate::Info for $type {
-            fn bytes(&self) -> u8 {
-                $bytes
-            }
-
-            fn cycles(&self) -> $crate::Cycles {
-                $cycles.into()
-            }
-        }
-    };
-}
-
-#[macro_export]
-macro_rules! impl_info {
-    ( $type:ty => ( $bytes:literal, $cycles:expr ) ) => {
-        impl Info for $type {
-            fn bytes(&self) -> u8 {
-                $bytes
-            }
-
-            fn cycles(&self) -> Cycles {
-                $cycles.into()
+                $crate::table::entry(&(*self).into()).cycles
             }
         }
     };
 }
 
-#[derive(Debug, Copy, Clone, Display, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[display("{_0}")]
 pub struct Bit(u8);
 
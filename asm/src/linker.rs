@@ -0,0 +1,515 @@
+//! Combines one or more [`Object`]s into a ROM image.
+//!
+//! Sections are placed in the following order, each group using the first free space that fits:
+//!
+//! 1. sections with a fixed address
+//! 2. sections with a fixed bank
+//! 3. floating sections, largest first
+//!
+//! Once every section has a location, the patches left by the assembler are resolved and written
+//! into the image. Symbols are resolved in the object that refers to them first, and then amongst
+//! the exported symbols of every other object.
+
+use crate::{
+    object::{ExprError, Object, Resolve, SectionType, SymbolValue},
+    symbols::SymbolFile,
+};
+use std::collections::HashMap;
+
+const BANK_SIZE: usize = 0x4000;
+
+/// Identifies a section by the index of its object and its index within that object.
+type SectionId = (usize, usize);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Placement {
+    bank: u32,
+    address: u16,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Linker {
+    pad: u8,
+    fix_header: bool,
+}
+
+impl Linker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the value used to fill unused parts of the ROM.
+    pub fn pad(&mut self, value: u8) -> &mut Self {
+        self.pad = value;
+        self
+    }
+
+    /// Enables updating the ROM size, header checksum and global checksum fields of the cartridge
+    /// header, like `rgbfix -v` does.
+    pub fn fix_header(&mut self, fix: bool) -> &mut Self {
+        self.fix_header = fix;
+        self
+    }
+
+    pub fn link(&self, objects: &[Object]) -> Result<Linked, Error> {
+        let exports = collect_exports(objects)?;
+        let placements = place_sections(objects)?;
+
+        let context = LinkContext {
+            objects,
+            exports: &exports,
+            placements: &placements,
+            current: 0,
+        };
+
+        let rom_banks = placements
+            .iter()
+            .filter(|((o, s), _)| objects[*o].sections[*s].kind.has_data())
+            .map(|(_, p)| p.bank as usize)
+            .max()
+            .unwrap_or(1)
+            .max(1);
+
+        let mut rom = vec![self.pad; (rom_banks + 1) * BANK_SIZE];
+
+        for (object_index, object) in objects.iter().enumerate() {
+            let context = LinkContext {
+                current: object_index,
+                ..context
+            };
+
+            for (section_index, section) in object.sections.iter().enumerate() {
+                if !section.kind.has_data() {
+                    continue;
+                }
+
+                let placement = placements[&(object_index, section_index)];
+                let start = rom_offset(placement);
+
+                let mut data = section.data.clone();
+
+                for patch in &section.patches {
+                    let address = placement.address as i64 + patch.offset as i64;
+
+                    let bytes = patch
+                        .expr
+                        .evaluate(&context)
+                        .and_then(|value| patch.kind.encode(value, address))
+                        .map_err(|source| Error::Patch {
+                            object: object.name.clone(),
+                            section: section.name.clone(),
+                            offset: patch.offset,
+                            source,
+                        })?;
+
+                    let width = patch.kind.width();
+                    data[patch.offset..patch.offset + width].copy_from_slice(&bytes[..width]);
+                }
+
+                rom[start..start + data.len()].copy_from_slice(&data);
+            }
+        }
+
+        if self.fix_header {
+            fix_header(&mut rom);
+        }
+
+        let mut symbols = SymbolFile::new();
+
+        for (object_index, object) in objects.iter().enumerate() {
+            for symbol in &object.symbols {
+                if let SymbolValue::Label { section, offset } = symbol.value {
+                    let placement = placements[&(object_index, section)];
+                    let address = placement.address as usize + offset;
+
+                    symbols.insert(placement.bank, address as u16, symbol.name.clone());
+                }
+            }
+        }
+
+        Ok(Linked { rom, symbols })
+    }
+}
+
+/// The result of linking objects together.
+#[derive(Debug, Clone)]
+pub struct Linked {
+    pub rom: Vec<u8>,
+    pub symbols: SymbolFile,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("section `{0}` is defined more than once")]
+    DuplicateSection(String),
+    #[error("symbol `{name}` is exported by both {first} and {second}")]
+    DuplicateSymbol {
+        name: String,
+        first: String,
+        second: String,
+    },
+    #[error("unable to place section `{section}` ({kind}, {size} bytes): {reason}")]
+    Placement {
+        section: String,
+        kind: SectionType,
+        size: usize,
+        reason: &'static str,
+    },
+    #[error("{object}: section `{section}` + {offset:#06x}: {source}")]
+    Patch {
+        object: String,
+        section: String,
+        offset: usize,
+        source: ExprError,
+    },
+}
+
+fn collect_exports(objects: &[Object]) -> Result<HashMap<&str, usize>, Error> {
+    let mut exports = HashMap::new();
+
+    for (index, object) in objects.iter().enumerate() {
+        for symbol in object.symbols.iter().filter(|s| s.exported) {
+            if let Some(first) = exports.insert(symbol.name.as_str(), index) {
+                return Err(Error::DuplicateSymbol {
+                    name: symbol.name.clone(),
+                    first: objects[first].name.clone(),
+                    second: object.name.clone(),
+                });
+            }
+        }
+    }
+
+    Ok(exports)
+}
+
+/// The free address ranges (inclusive) of every bank of every section type used so far.
+#[derive(Debug, Default)]
+struct FreeSpace {
+    ranges: HashMap<(SectionType, u32), Vec<(usize, usize)>>,
+}
+
+impl FreeSpace {
+    fn bank(&mut self, kind: SectionType, bank: u32) -> &mut Vec<(usize, usize)> {
+        self.ranges.entry((kind, bank)).or_insert_with(|| {
+            let (start, end) = kind.address_range();
+            vec![(start as usize, end as usize)]
+        })
+    }
+
+    /// Finds the first address at or after `start` with the requested alignment where `size`
+    /// bytes are free, and marks them as used.
+    fn allocate(
+        &mut self,
+        kind: SectionType,
+        bank: u32,
+        fixed: Option<u16>,
+        alignment: u8,
+        size: usize,
+    ) -> Option<u16> {
+        let ranges = self.bank(kind, bank);
+        let align = 1usize << alignment;
+
+        let (index, address) = ranges.iter().enumerate().find_map(|(i, &(start, end))| {
+            let address = match fixed {
+                Some(address) => address as usize,
+                None => start.next_multiple_of(align),
+            };
+
+            let fits =
+                address >= start && address.is_multiple_of(align) && address + size <= end + 1;
+            fits.then_some((i, address))
+        })?;
+
+        let (start, end) = ranges.remove(index);
+
+        if address + size <= end {
+            ranges.insert(index, (address + size, end));
+        }
+
+        if start < address {
+            ranges.insert(index, (start, address - 1));
+        }
+
+        Some(address as u16)
+    }
+}
+
+fn place_sections(objects: &[Object]) -> Result<HashMap<SectionId, Placement>, Error> {
+    let mut ids: Vec<SectionId> = Vec::new();
+    let mut names = HashMap::new();
+
+    for (object_index, object) in objects.iter().enumerate() {
+        for (section_index, section) in object.sections.iter().enumerate() {
+            if names.insert(section.name.as_str(), ()).is_some() {
+                return Err(Error::DuplicateSection(section.name.clone()));
+            }
+
+            ids.push((object_index, section_index));
+        }
+    }
+
+    // Most constrained first. The sort is stable, so sections with the same constraints are
+    // placed in the order they were defined.
+    ids.sort_by_key(|&(o, s)| {
+        let section = &objects[o].sections[s];
+
+        let priority = match (section.address, section.bank) {
+            (Some(_), _) => 0,
+            (None, Some(_)) => 1,
+            (None, None) => 2,
+        };
+
+        (priority, std::cmp::Reverse(section.size))
+    });
+
+    let mut free = FreeSpace::default();
+    let mut placements = HashMap::new();
+
+    for id in ids {
+        let section = &objects[id.0].sections[id.1];
+        let (first, last) = section.kind.bank_range();
+
+        let banks = match section.bank {
+            Some(bank) => bank..=bank,
+            None => first..=last,
+        };
+
+        let error = |reason| Error::Placement {
+            section: section.name.clone(),
+            kind: section.kind,
+            size: section.size,
+            reason,
+        };
+
+        if section
+            .address
+            .is_some_and(|a| !(a as usize).is_multiple_of(1 << section.alignment))
+        {
+            return Err(error("its address does not match its alignment"));
+        }
+
+        let placement = banks.into_iter().find_map(|bank| {
+            let address = free.allocate(
+                section.kind,
+                bank,
+                section.address,
+                section.alignment,
+                section.size,
+            )?;
+
+            Some(Placement { bank, address })
+        });
+
+        let Some(placement) = placement else {
+            return Err(error(match section.address {
+                Some(_) => "it overlaps another section or does not fit",
+                None => "there is not enough free space",
+            }));
+        };
+
+        placements.insert(id, placement);
+    }
+
+    Ok(placements)
+}
+
+fn rom_offset(placement: Placement) -> usize {
+    match placement.bank {
+        0 => placement.address as usize,
+        bank => bank as usize * BANK_SIZE + (placement.address as usize - BANK_SIZE),
+    }
+}
+
+/// Writes the ROM size code, header checksum and global checksum into the cartridge header.
+fn fix_header(rom: &mut [u8]) {
+    let banks = rom.len() / BANK_SIZE;
+    rom[0x148] = (banks / 2).trailing_zeros() as u8;
+
+    rom[0x14D] = rom[0x134..=0x14C]
+        .iter()
+        .fold(0u8, |sum, byte| sum.wrapping_sub(*byte).wrapping_sub(1));
+
+    let global = rom
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !matches!(i, 0x14E | 0x14F))
+        .fold(0u16, |sum, (_, byte)| sum.wrapping_add(*byte as u16));
+
+    rom[0x14E..=0x14F].copy_from_slice(&global.to_be_bytes());
+}
+
+#[derive(Clone, Copy)]
+struct LinkContext<'a> {
+    objects: &'a [Object],
+    exports: &'a HashMap<&'a str, usize>,
+    placements: &'a HashMap<SectionId, Placement>,
+    current: usize,
+}
+
+impl LinkContext<'_> {
+    fn find(&self, name: &str) -> Option<(usize, SymbolValue)> {
+        if let Some(symbol) = self.objects[self.current].find_symbol(name) {
+            return Some((self.current, symbol.value));
+        }
+
+        let object = *self.exports.get(name)?;
+        Some((object, self.objects[object].find_symbol(name)?.value))
+    }
+
+    fn find_section(&self, name: &str) -> Option<SectionId> {
+        self.objects
+            .iter()
+            .enumerate()
+            .find_map(|(o, object)| Some((o, object.find_section(name)?)))
+    }
+}
+
+impl Resolve for LinkContext<'_> {
+    fn symbol(&self, name: &str) -> Option<i64> {
+        match self.find(name)? {
+            (_, SymbolValue::Constant(value)) => Some(value),
+            (object, SymbolValue::Label { section, offset }) => {
+                let placement = self.placements.get(&(object, section))?;
+                Some(placement.address as i64 + offset as i64)
+            }
+        }
+    }
+
+    fn location(&self, section: usize, offset: usize) -> Option<i64> {
+        let placement = self.placements.get(&(self.current, section))?;
+        Some(placement.address as i64 + offset as i64)
+    }
+
+    fn symbol_bank(&self, name: &str) -> Option<i64> {
+        match self.find(name)? {
+            (object, SymbolValue::Label { section, .. }) => {
+                Some(self.placements.get(&(object, section))?.bank as i64)
+            }
+            (_, SymbolValue::Constant(_)) => None,
+        }
+    }
+
+    fn section_bank(&self, section: usize) -> Option<i64> {
+        Some(self.placements.get(&(self.current, section))?.bank as i64)
+    }
+
+    fn section_start(&self, name: &str) -> Option<i64> {
+        Some(self.placements.get(&self.find_section(name)?)?.address as i64)
+    }
+
+    fn section_size(&self, name: &str) -> Option<i64> {
+        let (object, section) = self.find_section(name)?;
+        Some(self.objects[object].sections[section].size as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::Assembler;
+
+    fn assemble(name: &str, source: &str) -> Object {
+        Assembler::new()
+            .assemble_source(name, source)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    #[test]
+    fn links_across_objects() {
+        let main = assemble(
+            "main.asm",
+            r#"
+            SECTION "Entry", ROM0[$100]
+                nop
+                jp Main
+
+            SECTION "Main", ROM0
+            Main::
+                ld a, BANK(Graphics)
+                ld hl, Graphics
+            .loop
+                jr .loop
+            "#,
+        );
+
+        let graphics = assemble(
+            "graphics.asm",
+            r#"
+            SECTION "Graphics", ROMX, BANK[3]
+            Graphics::
+                db $12, $34
+            SECTION "Variables", WRAM0
+            wCounter:: ds 2
+            "#,
+        );
+
+        let linked = Linker::new().link(&[main, graphics]).unwrap();
+        let rom = &linked.rom;
+
+        assert_eq!(rom.len(), 4 * BANK_SIZE);
+        assert_eq!(rom[0x100..0x104], [0x00, 0xC3, 0x00, 0x00]);
+        assert_eq!(rom[0..7], [0x3E, 0x03, 0x21, 0x00, 0x40, 0x18, 0xFE]);
+        assert_eq!(rom[3 * BANK_SIZE..3 * BANK_SIZE + 2], [0x12, 0x34]);
+
+        let symbols = linked.symbols.to_string();
+        assert!(symbols.contains("00:0000 Main\n"));
+        assert!(symbols.contains("00:0005 Main.loop\n"));
+        assert!(symbols.contains("03:4000 Graphics\n"));
+        assert!(symbols.contains("00:c000 wCounter\n"));
+    }
+
+    #[test]
+    fn respects_alignment_and_fixed_addresses() {
+        let object = assemble(
+            "align.asm",
+            r#"
+            SECTION "Fixed", ROM0[$0000]
+                ds $10
+            SECTION "Aligned", ROM0, ALIGN[8]
+            Aligned:
+                db 1
+            SECTION "Size", ROM0
+                dw SIZEOF("Fixed"), STARTOF("Aligned")
+            "#,
+        );
+
+        let linked = Linker::new().link(&[object]).unwrap();
+
+        assert_eq!(linked.symbols.find("Aligned").unwrap().address, 0x100);
+        assert_eq!(linked.rom[0x10..0x14], [0x10, 0x00, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn reports_missing_symbols_and_overlaps() {
+        let object = assemble("a.asm", "SECTION \"A\", ROM0\n call Missing\n");
+        let error = Linker::new().link(&[object]).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Patch {
+                source: ExprError::Unresolved(_),
+                ..
+            }
+        ));
+
+        let object = assemble(
+            "b.asm",
+            "SECTION \"A\", ROM0[$10]\n ds 4\nSECTION \"B\", ROM0[$12]\n ds 1\n",
+        );
+        let error = Linker::new().link(&[object]).unwrap_err();
+        assert!(matches!(error, Error::Placement { .. }));
+    }
+
+    #[test]
+    fn fixes_header() {
+        let object = assemble("h.asm", "SECTION \"Header\", ROM0[$134]\n db \"TEST\"\n");
+        let linked = Linker::new().fix_header(true).link(&[object]).unwrap();
+        let rom = &linked.rom;
+
+        let checksum = rom[0x134..=0x14C]
+            .iter()
+            .fold(0u8, |sum, b| sum.wrapping_sub(*b).wrapping_sub(1));
+
+        assert_eq!(rom[0x148], 0);
+        assert_eq!(rom[0x14D], checksum);
+    }
+}
@@ -0,0 +1,649 @@
+//! Static information about what an [`Instruction`] does, for use by analysis tools that need to
+//! reason about code without running it.
+//!
+//! For every instruction this provides:
+//!
+//! - the effect on each flag ([`Instruction::flag_effects()`])
+//! - the registers, flags and memory it reads ([`Instruction::reads()`]) and writes
+//!   ([`Instruction::writes()`])
+//! - how it affects control flow and the interrupt master enable flag
+//!   ([`Instruction::control_flow()`])
+
+use crate::{
+    instructions::{
+        bitwise::{rotate, shift_right, test},
+        jump::Target as JumpTarget,
+        load::{
+            Load, ToAccumulatorSource, ToConstantPointerSource, ToHLPointerSource,
+            ToPairPointerTarget, ToStackPointerSource,
+        },
+        math::{
+            add::{Add, ToHLPairSource},
+            dec::Target as CountTarget,
+        },
+        stack::pop::Source as StackSource,
+        subroutine::{call::Call, ret::Return},
+        Condition, Instruction,
+    },
+    sources::ByteSource,
+    Flag, Pair, Register,
+};
+
+/// How an instruction affects a single flag.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FlagEffect {
+    /// The flag keeps its previous value.
+    Unaffected,
+    /// The flag is always set.
+    Set,
+    /// The flag is always reset.
+    Reset,
+    /// The flag depends on the result of the instruction.
+    Modified,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FlagEffects {
+    pub zero: FlagEffect,
+    pub subtract: FlagEffect,
+    pub half_carry: FlagEffect,
+    pub carry: FlagEffect,
+}
+
+impl FlagEffects {
+    pub const NONE: Self = Self::new(
+        FlagEffect::Unaffected,
+        FlagEffect::Unaffected,
+        FlagEffect::Unaffected,
+        FlagEffect::Unaffected,
+    );
+
+    pub const fn new(
+        zero: FlagEffect,
+        subtract: FlagEffect,
+        half_carry: FlagEffect,
+        carry: FlagEffect,
+    ) -> Self {
+        Self {
+            zero,
+            subtract,
+            half_carry,
+            carry,
+        }
+    }
+
+    pub fn get(&self, flag: Flag) -> FlagEffect {
+        match flag {
+            Flag::Zero => self.zero,
+            Flag::Subtract => self.subtract,
+            Flag::HalfCarry => self.half_carry,
+            Flag::Carry => self.carry,
+        }
+    }
+
+    /// Returns a mask (in the layout of the F register) of every flag the instruction may change.
+    pub fn mask(&self) -> u8 {
+        [Flag::Zero, Flag::Subtract, Flag::HalfCarry, Flag::Carry]
+            .into_iter()
+            .filter(|f| self.get(*f) != FlagEffect::Unaffected)
+            .fold(0, |mask, f| mask | f.mask())
+    }
+}
+
+/// A location in memory accessed by an instruction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Memory {
+    /// The address held in a register pair, e.g. `[HL]` or `[BC]`.
+    Pointer(Pair),
+    /// A constant 16-bit address, e.g. `[$C000]`.
+    Constant,
+    /// A constant address between `$FF00` and `$FFFF`, e.g. `LDH [$FF40], A`.
+    HighConstant,
+    /// `[$FF00+C]`.
+    HighC,
+    /// The stack, at the address held in SP.
+    Stack,
+}
+
+/// The storage read or written by an instruction.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Access {
+    registers: u8,
+    pub stack_pointer: bool,
+    /// A mask (in the layout of the F register) of the flags that are accessed.
+    pub flags: u8,
+    pub memory: Option<Memory>,
+}
+
+impl Access {
+    const REGISTERS: [Register; 7] = [
+        Register::A,
+        Register::B,
+        Register::C,
+        Register::D,
+        Register::E,
+        Register::H,
+        Register::L,
+    ];
+
+    fn index(register: Register) -> u8 {
+        Self::REGISTERS.iter().position(|r| *r == register).unwrap() as u8
+    }
+
+    fn register(mut self, register: Register) -> Self {
+        self.registers |= 1 << Self::index(register);
+        self
+    }
+
+    fn pair(self, pair: Pair) -> Self {
+        let [high, low] = pair.as_registers();
+        self.register(high).register(low)
+    }
+
+    fn sp(mut self) -> Self {
+        self.stack_pointer = true;
+        self
+    }
+
+    fn flags(mut self, mask: u8) -> Self {
+        self.flags |= mask;
+        self
+    }
+
+    fn memory(mut self, memory: Memory) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        self.registers |= other.registers;
+        self.stack_pointer |= other.stack_pointer;
+        self.flags |= other.flags;
+        self.memory = self.memory.or(other.memory);
+        self
+    }
+
+    pub fn has_register(&self, register: Register) -> bool {
+        self.registers & (1 << Self::index(register)) != 0
+    }
+
+    /// Returns `true` if both halves of the pair are accessed.
+    pub fn has_pair(&self, pair: Pair) -> bool {
+        pair.as_registers()
+            .into_iter()
+            .all(|r| self.has_register(r))
+    }
+
+    pub fn has_flag(&self, flag: Flag) -> bool {
+        flag.test(self.flags)
+    }
+
+    pub fn registers(&self) -> impl Iterator<Item = Register> + '_ {
+        Self::REGISTERS
+            .into_iter()
+            .filter(|r| self.has_register(*r))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// How an instruction affects the interrupt master enable flag (IME).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InterruptChange {
+    /// `DI`, which clears IME immediately.
+    Disable,
+    /// `EI`, which sets IME after the following instruction.
+    Enable,
+    /// `RETI`, which sets IME immediately.
+    EnableImmediately,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ControlFlow {
+    /// Execution continues with the next instruction.
+    Sequential,
+    /// A jump. `indirect` is set for `JP HL`, whose target isn't known statically.
+    Branch {
+        conditional: bool,
+        indirect: bool,
+    },
+    /// A subroutine call, including `RST`.
+    Call {
+        conditional: bool,
+    },
+    Return {
+        conditional: bool,
+    },
+    /// `HALT` or `STOP`, which suspend execution until an interrupt or a button press.
+    Suspend,
+    /// An illegal opcode, which stops execution until the system is reset.
+    Lock,
+}
+
+impl Instruction {
+    pub fn flag_effects(&self) -> FlagEffects {
+        use FlagEffect::{Modified as M, Reset as R, Set as S, Unaffected as U};
+
+        let (z, n, h, c) = match self {
+            Self::Add(Add::ToAccumulator(_)) | Self::AddPlusCarry(_) => (M, R, M, M),
+            Self::Add(Add::ToHLPair(_)) => (U, R, M, M),
+            Self::Add(Add::ToStackPointer) | Self::Load(Load::ToHL(_)) => (R, R, M, M),
+            Self::Subtract(_) | Self::Compare(_) => (M, S, M, M),
+            Self::And(_) => (M, R, S, R),
+            Self::Or(_) | Self::Xor(_) => (M, R, R, R),
+            Self::Increment(i) => match i.target {
+                CountTarget::Register(_) | CountTarget::PointerValue => (M, R, M, U),
+                CountTarget::Pair(_) | CountTarget::StackPointer => (U, U, U, U),
+            },
+            Self::Decrement(d) => match d.target {
+                CountTarget::Register(_) | CountTarget::PointerValue => (M, S, M, U),
+                CountTarget::Pair(_) | CountTarget::StackPointer => (U, U, U, U),
+            },
+            Self::Rotate(r) if r.target == rotate::Target::Accumulator => (R, R, R, M),
+            Self::Rotate(_) | Self::ShiftLeft(_) | Self::ShiftRight(_) => (M, R, R, M),
+            Self::Swap(_) => (M, R, R, R),
+            Self::Test(_) => (M, R, S, U),
+            Self::DecimalAdjustAccumulator(_) => (M, U, R, M),
+            Self::ComplementAccumulator(_) => (U, S, S, U),
+            Self::SetCarryFlag(_) => (U, R, R, S),
+            Self::ComplementCarryFlag(_) => (U, R, R, M),
+            Self::Pop(p) if matches!(p.target, StackSource::AccumulatorAndFlags) => (M, M, M, M),
+            _ => (U, U, U, U),
+        };
+
+        FlagEffects::new(z, n, h, c)
+    }
+
+    /// Returns the registers, flags and memory the instruction reads. Immediate operands are not
+    /// included, and neither is PC.
+    pub fn reads(&self) -> Access {
+        let none = Access::default();
+        let a = none.register(Register::A);
+        let carry = Flag::Carry.mask();
+
+        match self {
+            Self::Load(load) => match load {
+                Load::ToRegister(l) => byte_source(l.source),
+                Load::ToAccumulator(l) => match l.source {
+                    ToAccumulatorSource::PairPointer(p) => none.pair(p).memory(Memory::Pointer(p)),
+                    ToAccumulatorSource::ConstantPointer => none.memory(Memory::Constant),
+                    ToAccumulatorSource::HighConstantPointer => none.memory(Memory::HighConstant),
+                    ToAccumulatorSource::HLX(_) => hl_pointer(),
+                    ToAccumulatorSource::HighC => none.register(Register::C).memory(Memory::HighC),
+                },
+                Load::ToPair(_) => none,
+                Load::ToPairPointer(l) => match l.target {
+                    ToPairPointerTarget::Pair(p) => a.pair(p),
+                    ToPairPointerTarget::HLX(_) => a.pair(Pair::HL),
+                },
+                Load::ToHLPointer(l) => match l.source {
+                    ToHLPointerSource::Register(r) => none.register(r).pair(Pair::HL),
+                    ToHLPointerSource::ConstantByte => none.pair(Pair::HL),
+                },
+                Load::ToStackPointer(l) => match l.source {
+                    ToStackPointerSource::HL => none.pair(Pair::HL),
+                    ToStackPointerSource::ConstantWord => none,
+                },
+                Load::ToHighC(_) => a.register(Register::C),
+                Load::ToConstantPointer(l) => match l.source {
+                    ToConstantPointerSource::Accumulator => a,
+                    ToConstantPointerSource::StackPointer => none.sp(),
+                },
+                Load::ToHighConstantPointer(_) => a,
+                Load::ToHL(_) => none.sp(),
+            },
+            Self::Add(add) => match add {
+                Add::ToAccumulator(s) => a.merge(byte_source(s.source)),
+                Add::ToHLPair(s) => match s.source {
+                    ToHLPairSource::Pair(p) => none.pair(Pair::HL).pair(p),
+                    ToHLPairSource::StackPointer => none.pair(Pair::HL).sp(),
+                },
+                Add::ToStackPointer => none.sp(),
+            },
+            Self::AddPlusCarry(s) => a.merge(byte_source(s.source)).flags(carry),
+            Self::Subtract(s) if s.with_carry => a.merge(byte_source(s.source)).flags(carry),
+            Self::Subtract(s) => a.merge(byte_source(s.source)),
+            Self::Compare(s) => a.merge(byte_source(s.source)),
+            Self::And(s) => a.merge(byte_source(s.source)),
+            Self::Or(s) => a.merge(byte_source(s.source)),
+            Self::Xor(s) => a.merge(byte_source(s.source)),
+            Self::Increment(i) => count_target(i.target),
+            Self::Decrement(d) => count_target(d.target),
+            Self::Rotate(r) => {
+                let target = match r.target {
+                    rotate::Target::Accumulator => a,
+                    rotate::Target::Register(reg) => none.register(reg),
+                    rotate::Target::PointerValue => hl_pointer(),
+                };
+
+                match r.behavior {
+                    rotate::Behavior::Carrying => target.flags(carry),
+                    rotate::Behavior::Cyclic => target,
+                }
+            }
+            Self::ShiftLeft(s) => shift_target(s.target),
+            Self::ShiftRight(s) => shift_target(s.target),
+            Self::Swap(s) => bit_target(s.target),
+            Self::Test(t) => bit_target(t.target),
+            Self::SetBit(s) => bit_target(s.target),
+            Self::ResetBit(r) => bit_target(r.target),
+            Self::DecimalAdjustAccumulator(_) => {
+                a.flags(Flag::Subtract.mask() | Flag::HalfCarry.mask() | Flag::Carry.mask())
+            }
+            Self::ComplementAccumulator(_) => a,
+            Self::ComplementCarryFlag(_) => none.flags(carry),
+            Self::Jump(j) => match j.target {
+                JumpTarget::Pointer => none.pair(Pair::HL),
+                JumpTarget::ConstantAddress(c) => condition(c),
+            },
+            Self::JumpRelative(j) => condition(j.condition),
+            Self::Call(call) => match call {
+                Call::Vector(_) => none.sp(),
+                Call::ConstantAddress(c) => condition(*c).sp(),
+            },
+            Self::Return(ret) => {
+                let c = match ret {
+                    Return::Normal(c) => *c,
+                    Return::EnableInterrupts => None,
+                };
+
+                condition(c).sp().memory(Memory::Stack)
+            }
+            Self::Push(p) => match p.source {
+                StackSource::AccumulatorAndFlags => a.flags(0xF0).sp(),
+                StackSource::Pair(pair) => none.pair(pair).sp(),
+            },
+            Self::Pop(_) => none.sp().memory(Memory::Stack),
+            Self::Nop(_)
+            | Self::Halt(_)
+            | Self::Stop(_)
+            | Self::DisableInterrupts(_)
+            | Self::EnableInterrupts(_)
+            | Self::SetCarryFlag(_)
+            | Self::Prefix(_)
+            | Self::Illegal(_) => none,
+        }
+    }
+
+    /// Returns the registers, flags and memory the instruction writes. PC is not included.
+    pub fn writes(&self) -> Access {
+        let none = Access::default();
+        let a = none.register(Register::A);
+        let flags = none.flags(self.flag_effects().mask());
+
+        let access = match self {
+            Self::Load(load) => match load {
+                Load::ToRegister(l) => none.register(l.target),
+                Load::ToAccumulator(l) => match l.source {
+                    ToAccumulatorSource::HLX(_) => a.pair(Pair::HL),
+                    _ => a,
+                },
+                Load::ToPair(l) => none.pair(l.target),
+                Load::ToPairPointer(l) => match l.target {
+                    ToPairPointerTarget::Pair(p) => none.memory(Memory::Pointer(p)),
+                    ToPairPointerTarget::HLX(_) => hl_pointer(),
+                },
+                Load::ToHLPointer(_) => none.memory(Memory::Pointer(Pair::HL)),
+                Load::ToStackPointer(_) => none.sp(),
+                Load::ToHighC(_) => none.memory(Memory::HighC),
+                Load::ToConstantPointer(_) => none.memory(Memory::Constant),
+                Load::ToHighConstantPointer(_) => none.memory(Memory::HighConstant),
+                Load::ToHL(_) => none.pair(Pair::HL),
+            },
+            Self::Add(add) => match add {
+                Add::ToAccumulator(_) => a,
+                Add::ToHLPair(_) => none.pair(Pair::HL),
+                Add::ToStackPointer => none.sp(),
+            },
+            Self::AddPlusCarry(_)
+            | Self::Subtract(_)
+            | Self::And(_)
+            | Self::Or(_)
+            | Self::Xor(_)
+            | Self::DecimalAdjustAccumulator(_)
+            | Self::ComplementAccumulator(_) => a,
+            Self::Increment(i) => count_written(i.target),
+            Self::Decrement(d) => count_written(d.target),
+            Self::Rotate(r) => match r.target {
+                rotate::Target::Accumulator => a,
+                rotate::Target::Register(reg) => none.register(reg),
+                rotate::Target::PointerValue => none.memory(Memory::Pointer(Pair::HL)),
+            },
+            Self::ShiftLeft(s) => shift_written(s.target),
+            Self::ShiftRight(s) => shift_written(s.target),
+            Self::Swap(s) => bit_written(s.target),
+            Self::SetBit(s) => bit_written(s.target),
+            Self::ResetBit(r) => bit_written(r.target),
+            Self::Call(_) | Self::Push(_) => none.sp().memory(Memory::Stack),
+            Self::Return(_) => none.sp(),
+            Self::Pop(p) => match p.target {
+                StackSource::AccumulatorAndFlags => a.sp(),
+                StackSource::Pair(pair) => none.pair(pair).sp(),
+            },
+            Self::Compare(_)
+            | Self::Test(_)
+            | Self::ComplementCarryFlag(_)
+            | Self::SetCarryFlag(_)
+            | Self::Jump(_)
+            | Self::JumpRelative(_)
+            | Self::Nop(_)
+            | Self::Halt(_)
+            | Self::Stop(_)
+            | Self::DisableInterrupts(_)
+            | Self::EnableInterrupts(_)
+            | Self::Prefix(_)
+            | Self::Illegal(_) => none,
+        };
+
+        access.merge(flags)
+    }
+
+    pub fn control_flow(&self) -> ControlFlow {
+        match self {
+            Self::Jump(j) => match j.target {
+                JumpTarget::Pointer => ControlFlow::Branch {
+                    conditional: false,
+                    indirect: true,
+                },
+                JumpTarget::ConstantAddress(c) => ControlFlow::Branch {
+                    conditional: c.is_some(),
+                    indirect: false,
+                },
+            },
+            Self::JumpRelative(j) => ControlFlow::Branch {
+                conditional: j.condition.is_some(),
+                indirect: false,
+            },
+            Self::Call(Call::Vector(_)) => ControlFlow::Call { conditional: false },
+            Self::Call(Call::ConstantAddress(c)) => ControlFlow::Call {
+                conditional: c.is_some(),
+            },
+            Self::Return(Return::Normal(c)) => ControlFlow::Return {
+                conditional: c.is_some(),
+            },
+            Self::Return(Return::EnableInterrupts) => ControlFlow::Return { conditional: false },
+            Self::Halt(_) | Self::Stop(_) => ControlFlow::Suspend,
+            Self::Illegal(_) => ControlFlow::Lock,
+            _ => ControlFlow::Sequential,
+        }
+    }
+
+    pub fn interrupt_change(&self) -> Option<InterruptChange> {
+        match self {
+            Self::DisableInterrupts(_) => Some(InterruptChange::Disable),
+            Self::EnableInterrupts(_) => Some(InterruptChange::Enable),
+            Self::Return(Return::EnableInterrupts) => Some(InterruptChange::EnableImmediately),
+            _ => None,
+        }
+    }
+
+    pub fn is_branch(&self) -> bool {
+        matches!(self.control_flow(), ControlFlow::Branch { .. })
+    }
+
+    pub fn is_call(&self) -> bool {
+        matches!(self.control_flow(), ControlFlow::Call { .. })
+    }
+
+    pub fn is_return(&self) -> bool {
+        matches!(self.control_flow(), ControlFlow::Return { .. })
+    }
+}
+
+fn hl_pointer() -> Access {
+    Access::default()
+        .pair(Pair::HL)
+        .memory(Memory::Pointer(Pair::HL))
+}
+
+fn byte_source(source: ByteSource) -> Access {
+    match source {
+        ByteSource::Register(r) => Access::default().register(r),
+        ByteSource::PointerValue => hl_pointer(),
+        ByteSource::ConstantByte => Access::default(),
+    }
+}
+
+/// What INC and DEC read, which includes HL for `[HL]`.
+fn count_target(target: CountTarget) -> Access {
+    match target {
+        CountTarget::PointerValue => hl_pointer(),
+        target => count_written(target),
+    }
+}
+
+/// What INC and DEC write, which is only the memory for `[HL]`.
+fn count_written(target: CountTarget) -> Access {
+    match target {
+        CountTarget::Register(r) => Access::default().register(r),
+        CountTarget::Pair(p) => Access::default().pair(p),
+        CountTarget::StackPointer => Access::default().sp(),
+        CountTarget::PointerValue => Access::default().memory(Memory::Pointer(Pair::HL)),
+    }
+}
+
+fn shift_target(target: shift_right::Target) -> Access {
+    match target {
+        shift_right::Target::Register(r) => Access::default().register(r),
+        shift_right::Target::PointerValue => hl_pointer(),
+    }
+}
+
+fn shift_written(target: shift_right::Target) -> Access {
+    match target {
+        shift_right::Target::Register(r) => Access::default().register(r),
+        shift_right::Target::PointerValue => Access::default().memory(Memory::Pointer(Pair::HL)),
+    }
+}
+
+fn bit_target(target: test::Target) -> Access {
+    match target {
+        test::Target::Register(r) => Access::default().register(r),
+        test::Target::PointerValue => hl_pointer(),
+    }
+}
+
+fn bit_written(target: test::Target) -> Access {
+    match target {
+        test::Target::Register(r) => Access::default().register(r),
+        test::Target::PointerValue => Access::default().memory(Memory::Pointer(Pair::HL)),
+    }
+}
+
+fn condition(condition: Option<Condition>) -> Access {
+    let flag = match condition {
+        Some(Condition::Zero | Condition::NotZero) => Flag::Zero.mask(),
+        Some(Condition::Carry | Condition::NotCarry) => Flag::Carry.mask(),
+        None => 0,
+    };
+
+    Access::default().flags(flag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::{
+        load::{Action, ToAccumulator, ToRegister},
+        math::{dec::Decrement, inc::Increment, subtract::Subtract},
+        stack::pop::Pop,
+        subroutine::ret::Return,
+    };
+
+    #[test]
+    fn flag_effects() {
+        let sbc: Instruction = Subtract {
+            source: ByteSource::PointerValue,
+            with_carry: true,
+        }
+        .into();
+
+        let effects = sbc.flag_effects();
+        assert_eq!(effects.subtract, FlagEffect::Set);
+        assert_eq!(effects.carry, FlagEffect::Modified);
+        assert!(sbc.reads().has_flag(Flag::Carry));
+        assert!(sbc.reads().has_pair(Pair::HL));
+        assert_eq!(sbc.reads().memory, Some(Memory::Pointer(Pair::HL)));
+        assert!(sbc.writes().has_register(Register::A));
+        assert_eq!(sbc.writes().flags, 0xF0);
+
+        let pop: Instruction = Pop {
+            target: StackSource::AccumulatorAndFlags,
+        }
+        .into();
+
+        assert_eq!(pop.writes().flags, 0xF0);
+        assert!(pop.writes().stack_pointer);
+    }
+
+    #[test]
+    fn counting_memory_only_writes_memory() {
+        // INC [HL] ($34) and DEC [HL] ($35) read HL, but only write the byte it points to.
+        let inc: Instruction = Increment {
+            target: CountTarget::PointerValue,
+        }
+        .into();
+        let dec: Instruction = Decrement {
+            target: CountTarget::PointerValue,
+        }
+        .into();
+
+        for instruction in [inc, dec] {
+            let writes = instruction.writes();
+
+            assert!(instruction.reads().has_pair(Pair::HL));
+            assert!(!writes.has_pair(Pair::HL));
+            assert!(!writes.has_register(Register::H) && !writes.has_register(Register::L));
+            assert_eq!(writes.memory, Some(Memory::Pointer(Pair::HL)));
+        }
+    }
+
+    #[test]
+    fn registers_and_control_flow() {
+        let load: Instruction = ToRegister {
+            target: Register::B,
+            source: ByteSource::Register(Register::C),
+        }
+        .into();
+
+        assert_eq!(load.reads().registers().collect::<Vec<_>>(), [Register::C]);
+        assert_eq!(load.writes().registers().collect::<Vec<_>>(), [Register::B]);
+        assert_eq!(load.control_flow(), ControlFlow::Sequential);
+
+        let ldi: Instruction = ToAccumulator {
+            source: ToAccumulatorSource::HLX(Action::Increment),
+        }
+        .into();
+
+        assert!(ldi.writes().has_pair(Pair::HL));
+
+        let reti: Instruction = Return::EnableInterrupts.into();
+        assert!(reti.is_return());
+        assert_eq!(
+            reti.interrupt_change(),
+            Some(InterruptChange::EnableImmediately)
+        );
+    }
+}
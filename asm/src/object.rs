@@ -0,0 +1,388 @@
+//! The intermediate representation shared by the [assembler](crate::assembler) and the
+//! [linker](crate::linker).
+//!
+//! An [`Object`] is the result of assembling a single source file. It holds a list of sections
+//! whose final location may not be known yet, a list of symbols, and a list of patches for every
+//! value that could not be computed until the sections are placed.
+
+use derive_more::derive::Display;
+
+#[derive(Debug, Clone, Default)]
+pub struct Object {
+    pub name: String,
+    pub sections: Vec<Section>,
+    pub symbols: Vec<Symbol>,
+}
+
+impl Object {
+    pub fn new<N>(name: N) -> Self
+    where
+        N: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn find_symbol(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.iter().find(|s| s.name == name)
+    }
+
+    pub fn find_section(&self, name: &str) -> Option<usize> {
+        self.sections.iter().position(|s| s.name == name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub name: String,
+    pub kind: SectionType,
+    pub address: Option<u16>,
+    pub bank: Option<u32>,
+    /// The section's start address must be a multiple of `2^alignment`.
+    pub alignment: u8,
+    /// The number of bytes reserved by the section. For sections that hold data (ROM sections),
+    /// this is always equal to the length of `data`.
+    pub size: usize,
+    pub data: Vec<u8>,
+    pub patches: Vec<Patch>,
+}
+
+impl Section {
+    pub fn new<N>(name: N, kind: SectionType) -> Self
+    where
+        N: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            kind,
+            address: None,
+            bank: None,
+            alignment: 0,
+            size: 0,
+            data: Vec::new(),
+            patches: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
+pub enum SectionType {
+    #[display("ROM0")]
+    Rom0,
+    #[display("ROMX")]
+    RomX,
+    #[display("VRAM")]
+    Vram,
+    #[display("SRAM")]
+    Sram,
+    #[display("WRAM0")]
+    Wram0,
+    #[display("WRAMX")]
+    WramX,
+    #[display("OAM")]
+    Oam,
+    #[display("HRAM")]
+    Hram,
+}
+
+impl SectionType {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let kind = match name.to_ascii_uppercase().as_str() {
+            "ROM0" => Self::Rom0,
+            "ROMX" => Self::RomX,
+            "VRAM" => Self::Vram,
+            "SRAM" => Self::Sram,
+            "WRAM0" => Self::Wram0,
+            "WRAMX" => Self::WramX,
+            "OAM" => Self::Oam,
+            "HRAM" => Self::Hram,
+            _ => return None,
+        };
+
+        Some(kind)
+    }
+
+    /// Returns the first and last address (inclusive) a section of this type may occupy.
+    pub fn address_range(&self) -> (u16, u16) {
+        match self {
+            Self::Rom0 => (0x0000, 0x3FFF),
+            Self::RomX => (0x4000, 0x7FFF),
+            Self::Vram => (0x8000, 0x9FFF),
+            Self::Sram => (0xA000, 0xBFFF),
+            Self::Wram0 => (0xC000, 0xCFFF),
+            Self::WramX => (0xD000, 0xDFFF),
+            Self::Oam => (0xFE00, 0xFE9F),
+            Self::Hram => (0xFF80, 0xFFFE),
+        }
+    }
+
+    /// Returns the first and last bank number (inclusive) a section of this type may be placed
+    /// in.
+    pub fn bank_range(&self) -> (u32, u32) {
+        match self {
+            Self::RomX => (1, 511),
+            Self::Vram => (0, 1),
+            Self::Sram => (0, 15),
+            Self::WramX => (1, 7),
+            Self::Rom0 | Self::Wram0 | Self::Oam | Self::Hram => (0, 0),
+        }
+    }
+
+    /// Returns `true` if sections of this type end up in the ROM image, and can therefore hold
+    /// data.
+    pub fn has_data(&self) -> bool {
+        matches!(self, Self::Rom0 | Self::RomX)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub value: SymbolValue,
+    pub exported: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SymbolValue {
+    /// A location in one of the object's sections, identified by its index.
+    Label {
+        section: usize,
+        offset: usize,
+    },
+    Constant(i64),
+}
+
+/// A value that must be written into a section once every symbol can be resolved.
+#[derive(Debug, Clone)]
+pub struct Patch {
+    pub offset: usize,
+    pub kind: PatchKind,
+    pub expr: Expr,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PatchKind {
+    Byte,
+    Word,
+    /// A byte written by `LDH`, which must resolve to an address between `$FF00` and `$FFFF`
+    /// (or to the low byte of such an address).
+    HighByte,
+    /// A signed offset written by `JR`, relative to the address following the patched byte.
+    Relative,
+}
+
+impl PatchKind {
+    /// The number of bytes written by a patch of this kind.
+    pub fn width(&self) -> usize {
+        match self {
+            Self::Word => 2,
+            _ => 1,
+        }
+    }
+
+    /// Converts a value into the bytes written by a patch of this kind, checking that it fits.
+    /// `address` is the address of the patched byte, and is only used for relative patches.
+    pub fn encode(&self, value: i64, address: i64) -> Result<[u8; 2], ExprError> {
+        let bytes = match self {
+            Self::Byte if (-128..=255).contains(&value) => [value as u8, 0],
+            Self::Byte => return Err(ExprError::Range(value, "a byte")),
+            Self::Word if (-32768..=65535).contains(&value) => (value as u16).to_le_bytes(),
+            Self::Word => return Err(ExprError::Range(value, "a word")),
+            Self::HighByte if (0xFF00..=0xFFFF).contains(&value) || (0..=0xFF).contains(&value) => {
+                [value as u8, 0]
+            }
+            Self::HighByte => return Err(ExprError::Range(value, "an LDH address ($FF00-$FFFF)")),
+            Self::Relative => {
+                let offset = value - (address + 1);
+
+                if !(-128..=127).contains(&offset) {
+                    return Err(ExprError::Range(offset, "a relative jump (-128 to 127)"));
+                }
+
+                [offset as u8, 0]
+            }
+        };
+
+        Ok(bytes)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Number(i64),
+    Symbol(String),
+    /// A location in one of the object's sections. Used for `@` and for labels that were already
+    /// defined when the expression was parsed.
+    Location {
+        section: usize,
+        offset: usize,
+    },
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum UnaryOp {
+    Negate,
+    Complement,
+    Not,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    ShiftLeft,
+    ShiftRight,
+    And,
+    Or,
+    Xor,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    LogicalAnd,
+    LogicalOr,
+}
+
+impl BinaryOp {
+    pub fn apply(&self, lhs: i64, rhs: i64) -> Result<i64, ExprError> {
+        let value = match self {
+            Self::Add => lhs.wrapping_add(rhs),
+            Self::Subtract => lhs.wrapping_sub(rhs),
+            Self::Multiply => lhs.wrapping_mul(rhs),
+            Self::Divide if rhs == 0 => return Err(ExprError::DivisionByZero),
+            Self::Divide => lhs.wrapping_div(rhs),
+            Self::Modulo if rhs == 0 => return Err(ExprError::DivisionByZero),
+            Self::Modulo => lhs.wrapping_rem(rhs),
+            Self::ShiftLeft => lhs.wrapping_shl(rhs as u32),
+            Self::ShiftRight => lhs.wrapping_shr(rhs as u32),
+            Self::And => lhs & rhs,
+            Self::Or => lhs | rhs,
+            Self::Xor => lhs ^ rhs,
+            Self::Equal => (lhs == rhs) as i64,
+            Self::NotEqual => (lhs != rhs) as i64,
+            Self::Less => (lhs < rhs) as i64,
+            Self::LessEqual => (lhs <= rhs) as i64,
+            Self::Greater => (lhs > rhs) as i64,
+            Self::GreaterEqual => (lhs >= rhs) as i64,
+            Self::LogicalAnd => (lhs != 0 && rhs != 0) as i64,
+            Self::LogicalOr => (lhs != 0 || rhs != 0) as i64,
+        };
+
+        Ok(value)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Function {
+    High,
+    Low,
+    Bank,
+    SizeOf,
+    StartOf,
+}
+
+/// Provides the values of symbols while evaluating an [`Expr`].
+pub trait Resolve {
+    fn symbol(&self, name: &str) -> Option<i64>;
+    fn location(&self, section: usize, offset: usize) -> Option<i64>;
+    fn symbol_bank(&self, name: &str) -> Option<i64>;
+    fn section_bank(&self, section: usize) -> Option<i64>;
+    fn section_start(&self, name: &str) -> Option<i64>;
+    fn section_size(&self, name: &str) -> Option<i64>;
+}
+
+impl Expr {
+    pub fn evaluate<R>(&self, resolver: &R) -> Result<i64, ExprError>
+    where
+        R: Resolve + ?Sized,
+    {
+        let value = match self {
+            Self::Number(n) => *n,
+            Self::Symbol(name) => resolver
+                .symbol(name)
+                .ok_or_else(|| ExprError::Unresolved(name.clone()))?,
+            Self::Location { section, offset } => resolver
+                .location(*section, *offset)
+                .ok_or(ExprError::Unplaced)?,
+            Self::Unary(op, inner) => {
+                let inner = inner.evaluate(resolver)?;
+
+                match op {
+                    UnaryOp::Negate => inner.wrapping_neg(),
+                    UnaryOp::Complement => !inner,
+                    UnaryOp::Not => (inner == 0) as i64,
+                }
+            }
+            Self::Binary(op, lhs, rhs) => {
+                op.apply(lhs.evaluate(resolver)?, rhs.evaluate(resolver)?)?
+            }
+            Self::Call(function, args) => evaluate_call(*function, args, resolver)?,
+        };
+
+        Ok(value)
+    }
+}
+
+fn evaluate_call<R>(function: Function, args: &[Expr], resolver: &R) -> Result<i64, ExprError>
+where
+    R: Resolve + ?Sized,
+{
+    let [arg] = args else {
+        return Err(ExprError::ArgumentCount);
+    };
+
+    let value = match function {
+        Function::High => (arg.evaluate(resolver)? >> 8) & 0xFF,
+        Function::Low => arg.evaluate(resolver)? & 0xFF,
+        Function::Bank => match arg {
+            Expr::Symbol(name) => resolver
+                .symbol_bank(name)
+                .ok_or_else(|| ExprError::Unresolved(name.clone()))?,
+            Expr::Location { section, .. } => {
+                resolver.section_bank(*section).ok_or(ExprError::Unplaced)?
+            }
+            _ => return Err(ExprError::InvalidArgument("BANK")),
+        },
+        Function::SizeOf | Function::StartOf => {
+            let Expr::Symbol(name) = arg else {
+                return Err(ExprError::InvalidArgument("SIZEOF / STARTOF"));
+            };
+
+            let value = if function == Function::SizeOf {
+                resolver.section_size(name)
+            } else {
+                resolver.section_start(name)
+            };
+
+            value.ok_or_else(|| ExprError::Unresolved(name.clone()))?
+        }
+    };
+
+    Ok(value)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ExprError {
+    #[error("unresolved symbol `{0}`")]
+    Unresolved(String),
+    #[error("expression refers to a section that has not been placed yet")]
+    Unplaced,
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("functions take exactly one argument")]
+    ArgumentCount,
+    #[error("invalid argument to {0}")]
+    InvalidArgument(&'static str),
+    #[error("value {0} does not fit in {1}")]
+    Range(i64, &'static str),
+}
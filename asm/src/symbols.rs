@@ -0,0 +1,273 @@
+//! Symbol files in the format written by `rgblink -n` and used by NO$GMB, as understood by most
+//! Game Boy emulators and debuggers.
+//!
+//! Each line holds a bank, an address and a label:
+//!
+//! ```text
+//! ; File generated by gb_asm
+//! 00:0150 Main
+//! 01:4000 Graphics.tiles
+//! ```
+//!
+//! Banks follow `rgblink`: ROM0, WRAM0, HRAM and the other unbanked areas are in bank 0, and
+//! switchable areas use the number of the bank mapped there.
+
+use std::{fmt::Display, str::FromStr};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolEntry {
+    pub bank: u32,
+    pub address: u16,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolFile {
+    entries: Vec<SymbolEntry>,
+}
+
+impl SymbolFile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert<N>(&mut self, bank: u32, address: u16, name: N)
+    where
+        N: Into<String>,
+    {
+        let entry = SymbolEntry {
+            bank,
+            address,
+            name: name.into(),
+        };
+
+        let index = self.entries.partition_point(|e| e.key() <= entry.key());
+        self.entries.insert(index, entry);
+    }
+
+    /// Returns every entry, ordered by bank and address.
+    pub fn entries(&self) -> &[SymbolEntry] {
+        &self.entries
+    }
+
+    pub fn find(&self, name: &str) -> Option<&SymbolEntry> {
+        self.entries.iter().find(|e| e.name == name)
+    }
+
+    /// Returns the label at `address` in `bank`, if there's one exactly there.
+    pub fn name_at(&self, bank: u32, address: u16) -> Option<&str> {
+        self.resolve(bank, address)
+            .filter(|location| location.offset == 0)
+            .map(|location| location.symbol.name.as_str())
+    }
+
+    /// Returns the closest label at or before `address` in `bank`, and how far past it `address`
+    /// is. Labels in a different area of memory, such as WRAM for an address in HRAM, don't count.
+    pub fn resolve(&self, bank: u32, address: u16) -> Option<Location<'_>> {
+        let end = self
+            .entries
+            .partition_point(|e| (e.bank, e.address) <= (bank, address));
+        let last = self.entries[..end].last()?;
+
+        if last.bank != bank || last.address < area_start(address) {
+            return None;
+        }
+
+        // Of several labels at the same address, the first one wins.
+        let first = self.entries[..end].partition_point(|e| e.key() < (bank, last.address, ""));
+        let symbol = &self.entries[first];
+
+        Some(Location {
+            symbol,
+            offset: address - symbol.address,
+        })
+    }
+}
+
+impl SymbolEntry {
+    fn key(&self) -> (u32, u16, &str) {
+        (self.bank, self.address, &self.name)
+    }
+}
+
+/// An address as a label and an offset, such as `Main.loop+3`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Location<'a> {
+    pub symbol: &'a SymbolEntry,
+    pub offset: u16,
+}
+
+impl Display for Location<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.offset {
+            0 => write!(f, "{}", self.symbol.name),
+            offset => write!(f, "{}+{offset}", self.symbol.name),
+        }
+    }
+}
+
+/// The banks mapped to the switchable areas of memory.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Banks {
+    /// The ROM bank mapped to `$4000-$7FFF`.
+    pub rom: usize,
+    /// The cartridge RAM bank mapped to `$A000-$BFFF`.
+    pub sram: usize,
+    /// The WRAM bank mapped to `$D000-$DFFF`.
+    pub wram: usize,
+}
+
+/// Returns the bank a symbol file uses for `address`, given the banks that are mapped.
+pub fn bank_of(address: u16, banks: Banks) -> u32 {
+    let bank = match address {
+        0x4000..=0x7FFF => banks.rom,
+        0xA000..=0xBFFF => banks.sram,
+        0xD000..=0xDFFF => banks.wram,
+        _ => 0,
+    };
+
+    bank as u32
+}
+
+/// Returns `true` if `address` is in an area of memory where banks are switched.
+pub fn is_switched(address: u16) -> bool {
+    matches!(address, 0x4000..=0x7FFF | 0xA000..=0xBFFF | 0xD000..=0xDFFF)
+}
+
+/// Returns the first address of the area of memory that `address` is in.
+fn area_start(address: u16) -> u16 {
+    match address {
+        0x0000..=0x3FFF => 0x0000,
+        0x4000..=0x7FFF => 0x4000,
+        0x8000..=0x9FFF => 0x8000,
+        0xA000..=0xBFFF => 0xA000,
+        0xC000..=0xCFFF => 0xC000,
+        0xD000..=0xDFFF => 0xD000,
+        0xE000..=0xFDFF => 0xE000,
+        0xFE00..=0xFEFF => 0xFE00,
+        0xFF00..=0xFF7F => 0xFF00,
+        0xFF80..=0xFFFE => 0xFF80,
+        0xFFFF => 0xFFFF,
+    }
+}
+
+impl FromStr for SymbolFile {
+    type Err = ParseError;
+
+    /// Parses a symbol file. Comments (starting with `;`), blank lines and section headers such as
+    /// `[labels]` are ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut symbols = Self::new();
+
+        for (index, line) in s.lines().enumerate() {
+            let line = line.split(';').next().unwrap_or_default().trim();
+
+            if line.is_empty() || line.starts_with('[') {
+                continue;
+            }
+
+            let error = |message| ParseError {
+                line: index + 1,
+                message,
+            };
+
+            let (location, name) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| error("expected `bank:address name`"))?;
+
+            let (bank, address) = location
+                .split_once(':')
+                .ok_or_else(|| error("expected `bank:address`"))?;
+
+            let bank = u32::from_str_radix(bank, 16).map_err(|_| error("invalid bank number"))?;
+            let address = u16::from_str_radix(address, 16).map_err(|_| error("invalid address"))?;
+
+            symbols.insert(bank, address, name.trim());
+        }
+
+        Ok(symbols)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("line {line}: {message}")]
+pub struct ParseError {
+    pub line: usize,
+    pub message: &'static str,
+}
+
+impl Display for SymbolFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "; File generated by gb_asm")?;
+
+        for entry in &self.entries {
+            writeln!(f, "{:02x}:{:04x} {}", entry.bank, entry.address, entry.name)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut symbols = SymbolFile::new();
+        symbols.insert(1, 0x4000, "Graphics");
+        symbols.insert(0, 0x0150, "Main");
+
+        let text = symbols.to_string();
+        assert_eq!(
+            text,
+            "; File generated by gb_asm\n00:0150 Main\n01:4000 Graphics\n"
+        );
+        assert_eq!(text.parse(), Ok(symbols));
+
+        let error = "00:0150 Main\nxyz\n".parse::<SymbolFile>().unwrap_err();
+        assert_eq!(error.line, 2);
+    }
+
+    #[test]
+    fn resolves_addresses() {
+        let symbols: SymbolFile = "00:0150 Main\n00:0158 Main.loop\n01:4000 Graphics\n\
+                                   00:c000 wCounter\n00:ff80 hFlags\n00:0150 Start"
+            .parse()
+            .unwrap();
+
+        let name = |bank, address| symbols.resolve(bank, address).map(|l| l.to_string());
+
+        assert_eq!(name(0, 0x0150).as_deref(), Some("Main"));
+        assert_eq!(name(0, 0x015A).as_deref(), Some("Main.loop+2"));
+        assert_eq!(name(1, 0x4010).as_deref(), Some("Graphics+16"));
+        assert_eq!(name(2, 0x4010), None);
+        assert_eq!(name(0, 0x0100), None);
+        assert_eq!(name(0, 0xFF40), None);
+        assert_eq!(symbols.name_at(0, 0xC000), Some("wCounter"));
+        assert_eq!(symbols.name_at(0, 0xC001), None);
+
+        let entry = symbols.find("Main.loop").unwrap();
+        assert_eq!((entry.bank, entry.address), (0, 0x0158));
+    }
+
+    #[test]
+    fn banks_follow_what_is_mapped() {
+        let symbols: SymbolFile = "02:4000 Graphics\n03:a000 sSave\n05:d000 wBuffer"
+            .parse()
+            .unwrap();
+
+        let banks = Banks {
+            rom: 2,
+            sram: 3,
+            wram: 5,
+        };
+        let name = |address| symbols.name_at(bank_of(address, banks), address);
+
+        assert_eq!(name(0x4000), Some("Graphics"));
+        assert_eq!(name(0xA000), Some("sSave"));
+        assert_eq!(name(0xD000), Some("wBuffer"));
+        assert_eq!(bank_of(0xC000, banks), 0);
+        assert!(!is_switched(0xC000));
+    }
+}
@@ -1,4 +1,4 @@
-use gb_asm::{
+use crate::{
     instructions::{math::adc::AddPlusCarry, Instruction},
     sources::ByteSource::*,
     Register,
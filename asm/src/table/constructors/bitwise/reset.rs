@@ -1,4 +1,4 @@
-use gb_asm::{
+use crate::{
     instructions::{bitwise::reset::*, bitwise::test::Target::*, Instruction},
     Bit, Register,
 };
@@ -1,4 +1,4 @@
-use gb_asm::{
+use crate::{
     instructions::{
         bitwise::{shift_left::*, shift_right::Target::*},
         Instruction,
@@ -1,4 +1,4 @@
-use gb_asm::{
+use crate::{
     instructions::{
         bitwise::shift_right::{Behavior::*, Target::*, *},
         Instruction,
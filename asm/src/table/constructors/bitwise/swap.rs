@@ -1,4 +1,4 @@
-use gb_asm::{
+use crate::{
     instructions::{
         bitwise::{swap::*, test::Target::*},
         Instruction,
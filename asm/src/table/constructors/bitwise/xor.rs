@@ -1,4 +1,4 @@
-use gb_asm::{
+use crate::{
     instructions::{bitwise::xor::*, Instruction},
     sources::ByteSource::*,
     Register,
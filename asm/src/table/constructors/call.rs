@@ -1,4 +1,4 @@
-use gb_asm::instructions::{
+use crate::instructions::{
     subroutine::call::{Call, VectorSlot},
     Condition, Instruction,
 };
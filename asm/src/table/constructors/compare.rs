@@ -1,4 +1,4 @@
-use gb_asm::{
+use crate::{
     instructions::{math::cp::*, Instruction},
     sources::ByteSource::*,
     Register,
@@ -1,4 +1,4 @@
-use gb_asm::{
+use crate::{
     instructions::{math::dec::Target::*, math::inc::*, Instruction},
     Pair, Register,
 };
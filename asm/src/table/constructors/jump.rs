@@ -1,4 +1,4 @@
-use gb_asm::instructions::{
+use crate::instructions::{
     jump::{Jump, JumpRelative, Target},
     Condition, Instruction,
 };
@@ -1,4 +1,4 @@
-use gb_asm::instructions::{misc::*, Instruction};
+use crate::instructions::{misc::*, Instruction};
 
 macro_rules! create_fn {
     ($name:ident => $inner:path) => {
@@ -18,3 +18,7 @@ create_fn!(nop => Nop);
 create_fn!(set_carry_flag => SetCarryFlag);
 create_fn!(stop => Stop);
 create_fn!(prefix => Prefix);
+
+pub fn illegal(opcode: u8) -> Instruction {
+    Illegal(opcode).into()
+}
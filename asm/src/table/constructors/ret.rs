@@ -1,4 +1,4 @@
-use gb_asm::instructions::{subroutine::ret::Return, Condition, Instruction};
+use crate::instructions::{subroutine::ret::Return, Condition, Instruction};
 
 pub fn sub_return<C>(condition: C) -> Instruction
 where
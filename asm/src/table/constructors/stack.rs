@@ -1,4 +1,4 @@
-use gb_asm::{
+use crate::{
     instructions::{
         stack::{
             pop::{Source::*, *},
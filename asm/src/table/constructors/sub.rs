@@ -1,4 +1,4 @@
-use gb_asm::{
+use crate::{
     instructions::{math::subtract::*, Instruction},
     sources::ByteSource::{self, *},
     Register,
@@ -0,0 +1,751 @@
+//! The canonical opcode table of the SM83, transcribed from the published opcode matrix.
+//!
+//! Every opcode is described exactly once, here. The disassembler decodes through these tables,
+//! and the [`Info`] of every instruction is looked up in them, so the length and timing of an
+//! instruction aren't written down anywhere else. The tests below check the
+//! [`flag_effects()`](Instruction::flag_effects) of every instruction against the table.
+
+pub mod constructors;
+
+use crate::{
+    instructions::{load::Action::*, subroutine::call::VectorSlot, Condition::*, Instruction},
+    Bit, Cycles, Info,
+    Pair::*,
+    Register::*,
+};
+use constructors::*;
+use std::{collections::HashMap, sync::LazyLock};
+
+/// A row of the opcode matrix.
+#[derive(Debug, Copy, Clone)]
+pub struct Opcode {
+    /// The mnemonic, with operands written as in the matrix: `n8`/`n16` for immediates, `a8`/`a16`
+    /// for addresses and `e8` for signed offsets.
+    pub mnemonic: &'static str,
+    /// The length of the instruction, including the `$CB` prefix for prefixed opcodes.
+    pub bytes: u8,
+    /// The duration in M-cycles. Conditional instructions take the longer time when the branch is
+    /// taken.
+    pub cycles: Cycles,
+    /// The effect on the Z, N, H and C flags, in that order: `-` for unaffected, `0` for reset,
+    /// `1` for set, and the flag's letter when it depends on the result.
+    pub flags: &'static str,
+    build: fn() -> Instruction,
+}
+
+impl Opcode {
+    pub fn instruction(&self) -> Instruction {
+        (self.build)()
+    }
+}
+
+macro_rules! table_cycles {
+    ( ($min:literal, $max:literal) ) => {
+        Cycles::Variable($min, $max)
+    };
+
+    ( $cycles:literal ) => {
+        Cycles::Fixed($cycles)
+    };
+}
+
+macro_rules! opcode_table {
+    (
+        $(
+            $opcode:literal => $mnemonic:literal, $bytes:literal, $cycles:tt, $flags:literal, $build:expr;
+        )*
+    ) => {{
+        // Every slot starts out as a placeholder, and the table only compiles once each opcode has
+        // been written exactly once.
+        let placeholder = Opcode {
+            mnemonic: "",
+            bytes: 0,
+            cycles: Cycles::Fixed(0),
+            flags: "",
+            build: nop,
+        };
+        let mut table = [placeholder; 256];
+        let mut written = [false; 256];
+
+        $(
+            assert!(!written[$opcode], "duplicate opcode");
+            written[$opcode] = true;
+
+            table[$opcode] = Opcode {
+                mnemonic: $mnemonic,
+                bytes: $bytes,
+                cycles: table_cycles!($cycles),
+                flags: $flags,
+                build: || $build,
+            };
+        )*
+
+        let mut opcode = 0;
+
+        while opcode < 256 {
+            assert!(written[opcode], "missing opcode");
+            opcode += 1;
+        }
+
+        table
+    }};
+}
+
+/// The unprefixed opcodes. Opcodes that aren't implemented by the CPU decode to
+/// [`Instruction::Illegal`].
+pub static UNPREFIXED: [Opcode; 256] = opcode_table! {
+    0x00 => "NOP", 1, 1, "----", nop();
+    0x01 => "LD BC, n16", 3, 3, "----", load_into_pair(BC);
+    0x02 => "LD [BC], A", 1, 2, "----", load_into_pair_pointer(BC);
+    0x03 => "INC BC", 1, 2, "----", increment_pair(BC);
+    0x04 => "INC B", 1, 1, "Z0H-", increment_register(B);
+    0x05 => "DEC B", 1, 1, "Z1H-", decrement_register(B);
+    0x06 => "LD B, n8", 2, 2, "----", load_into_register_from_constant(B);
+    0x07 => "RLCA", 1, 1, "000C", cyclic_rotate_left_accumulator();
+    0x08 => "LD [a16], SP", 3, 5, "----", load_into_constant_pointer_from_stack_pointer();
+    0x09 => "ADD HL, BC", 1, 2, "-0HC", add_pair_to_hl(BC);
+    0x0A => "LD A, [BC]", 1, 2, "----", load_into_accumulator_from_pair_pointer(BC);
+    0x0B => "DEC BC", 1, 2, "----", decrement_pair(BC);
+    0x0C => "INC C", 1, 1, "Z0H-", increment_register(C);
+    0x0D => "DEC C", 1, 1, "Z1H-", decrement_register(C);
+    0x0E => "LD C, n8", 2, 2, "----", load_into_register_from_constant(C);
+    0x0F => "RRCA", 1, 1, "000C", cyclic_rotate_right_accumulator();
+
+    0x10 => "STOP n8", 2, 1, "----", stop();
+    0x11 => "LD DE, n16", 3, 3, "----", load_into_pair(DE);
+    0x12 => "LD [DE], A", 1, 2, "----", load_into_pair_pointer(DE);
+    0x13 => "INC DE", 1, 2, "----", increment_pair(DE);
+    0x14 => "INC D", 1, 1, "Z0H-", increment_register(D);
+    0x15 => "DEC D", 1, 1, "Z1H-", decrement_register(D);
+    0x16 => "LD D, n8", 2, 2, "----", load_into_register_from_constant(D);
+    0x17 => "RLA", 1, 1, "000C", carrying_rotate_left_accumulator();
+    0x18 => "JR e8", 2, 3, "----", relative_jump(None);
+    0x19 => "ADD HL, DE", 1, 2, "-0HC", add_pair_to_hl(DE);
+    0x1A => "LD A, [DE]", 1, 2, "----", load_into_accumulator_from_pair_pointer(DE);
+    0x1B => "DEC DE", 1, 2, "----", decrement_pair(DE);
+    0x1C => "INC E", 1, 1, "Z0H-", increment_register(E);
+    0x1D => "DEC E", 1, 1, "Z1H-", decrement_register(E);
+    0x1E => "LD E, n8", 2, 2, "----", load_into_register_from_constant(E);
+    0x1F => "RRA", 1, 1, "000C", carrying_rotate_right_accumulator();
+
+    0x20 => "JR NZ, e8", 2, (2, 3), "----", relative_jump(NotZero);
+    0x21 => "LD HL, n16", 3, 3, "----", load_into_pair(HL);
+    0x22 => "LD [HL+], A", 1, 2, "----", load_into_hl_incdec(Increment);
+    0x23 => "INC HL", 1, 2, "----", increment_pair(HL);
+    0x24 => "INC H", 1, 1, "Z0H-", increment_register(H);
+    0x25 => "DEC H", 1, 1, "Z1H-", decrement_register(H);
+    0x26 => "LD H, n8", 2, 2, "----", load_into_register_from_constant(H);
+    0x27 => "DAA", 1, 1, "Z-0C", decimal_adjust_acumulator();
+    0x28 => "JR Z, e8", 2, (2, 3), "----", relative_jump(Zero);
+    0x29 => "ADD HL, HL", 1, 2, "-0HC", add_pair_to_hl(HL);
+    0x2A => "LD A, [HL+]", 1, 2, "----", load_into_accumulator_from_hlx(Increment);
+    0x2B => "DEC HL", 1, 2, "----", decrement_pair(HL);
+    0x2C => "INC L", 1, 1, "Z0H-", increment_register(L);
+    0x2D => "DEC L", 1, 1, "Z1H-", decrement_register(L);
+    0x2E => "LD L, n8", 2, 2, "----", load_into_register_from_constant(L);
+    0x2F => "CPL", 1, 1, "-11-", complement_accumulator();
+
+    0x30 => "JR NC, e8", 2, (2, 3), "----", relative_jump(NotCarry);
+    0x31 => "LD SP, n16", 3, 3, "----", load_from_constant_into_stack_pointer();
+    0x32 => "LD [HL-], A", 1, 2, "----", load_into_hl_incdec(Decrement);
+    0x33 => "INC SP", 1, 2, "----", increment_stack_pointer();
+    0x34 => "INC [HL]", 1, 3, "Z0H-", increment_pointer_value();
+    0x35 => "DEC [HL]", 1, 3, "Z1H-", decrement_pointer_value();
+    0x36 => "LD [HL], n8", 2, 3, "----", load_into_hl_pointer();
+    0x37 => "SCF", 1, 1, "-001", set_carry_flag();
+    0x38 => "JR C, e8", 2, (2, 3), "----", relative_jump(Carry);
+    0x39 => "ADD HL, SP", 1, 2, "-0HC", add_stack_pointer_to_hl();
+    0x3A => "LD A, [HL-]", 1, 2, "----", load_into_accumulator_from_hlx(Decrement);
+    0x3B => "DEC SP", 1, 2, "----", decrement_stack_pointer();
+    0x3C => "INC A", 1, 1, "Z0H-", increment_register(A);
+    0x3D => "DEC A", 1, 1, "Z1H-", decrement_register(A);
+    0x3E => "LD A, n8", 2, 2, "----", load_into_register_from_constant(A);
+    0x3F => "CCF", 1, 1, "-00C", complement_carry_flag();
+
+    0x40 => "LD B, B", 1, 1, "----", load_into_register_from_register(B, B);
+    0x41 => "LD B, C", 1, 1, "----", load_into_register_from_register(B, C);
+    0x42 => "LD B, D", 1, 1, "----", load_into_register_from_register(B, D);
+    0x43 => "LD B, E", 1, 1, "----", load_into_register_from_register(B, E);
+    0x44 => "LD B, H", 1, 1, "----", load_into_register_from_register(B, H);
+    0x45 => "LD B, L", 1, 1, "----", load_into_register_from_register(B, L);
+    0x46 => "LD B, [HL]", 1, 2, "----", load_into_register_from_pointer_value(B);
+    0x47 => "LD B, A", 1, 1, "----", load_into_register_from_register(B, A);
+    0x48 => "LD C, B", 1, 1, "----", load_into_register_from_register(C, B);
+    0x49 => "LD C, C", 1, 1, "----", load_into_register_from_register(C, C);
+    0x4A => "LD C, D", 1, 1, "----", load_into_register_from_register(C, D);
+    0x4B => "LD C, E", 1, 1, "----", load_into_register_from_register(C, E);
+    0x4C => "LD C, H", 1, 1, "----", load_into_register_from_register(C, H);
+    0x4D => "LD C, L", 1, 1, "----", load_into_register_from_register(C, L);
+    0x4E => "LD C, [HL]", 1, 2, "----", load_into_register_from_pointer_value(C);
+    0x4F => "LD C, A", 1, 1, "----", load_into_register_from_register(C, A);
+
+    0x50 => "LD D, B", 1, 1, "----", load_into_register_from_register(D, B);
+    0x51 => "LD D, C", 1, 1, "----", load_into_register_from_register(D, C);
+    0x52 => "LD D, D", 1, 1, "----", load_into_register_from_register(D, D);
+    0x53 => "LD D, E", 1, 1, "----", load_into_register_from_register(D, E);
+    0x54 => "LD D, H", 1, 1, "----", load_into_register_from_register(D, H);
+    0x55 => "LD D, L", 1, 1, "----", load_into_register_from_register(D, L);
+    0x56 => "LD D, [HL]", 1, 2, "----", load_into_register_from_pointer_value(D);
+    0x57 => "LD D, A", 1, 1, "----", load_into_register_from_register(D, A);
+    0x58 => "LD E, B", 1, 1, "----", load_into_register_from_register(E, B);
+    0x59 => "LD E, C", 1, 1, "----", load_into_register_from_register(E, C);
+    0x5A => "LD E, D", 1, 1, "----", load_into_register_from_register(E, D);
+    0x5B => "LD E, E", 1, 1, "----", load_into_register_from_register(E, E);
+    0x5C => "LD E, H", 1, 1, "----", load_into_register_from_register(E, H);
+    0x5D => "LD E, L", 1, 1, "----", load_into_register_from_register(E, L);
+    0x5E => "LD E, [HL]", 1, 2, "----", load_into_register_from_pointer_value(E);
+    0x5F => "LD E, A", 1, 1, "----", load_into_register_from_register(E, A);
+
+    0x60 => "LD H, B", 1, 1, "----", load_into_register_from_register(H, B);
+    0x61 => "LD H, C", 1, 1, "----", load_into_register_from_register(H, C);
+    0x62 => "LD H, D", 1, 1, "----", load_into_register_from_register(H, D);
+    0x63 => "LD H, E", 1, 1, "----", load_into_register_from_register(H, E);
+    0x64 => "LD H, H", 1, 1, "----", load_into_register_from_register(H, H);
+    0x65 => "LD H, L", 1, 1, "----", load_into_register_from_register(H, L);
+    0x66 => "LD H, [HL]", 1, 2, "----", load_into_register_from_pointer_value(H);
+    0x67 => "LD H, A", 1, 1, "----", load_into_register_from_register(H, A);
+    0x68 => "LD L, B", 1, 1, "----", load_into_register_from_register(L, B);
+    0x69 => "LD L, C", 1, 1, "----", load_into_register_from_register(L, C);
+    0x6A => "LD L, D", 1, 1, "----", load_into_register_from_register(L, D);
+    0x6B => "LD L, E", 1, 1, "----", load_into_register_from_register(L, E);
+    0x6C => "LD L, H", 1, 1, "----", load_into_register_from_register(L, H);
+    0x6D => "LD L, L", 1, 1, "----", load_into_register_from_register(L, L);
+    0x6E => "LD L, [HL]", 1, 2, "----", load_into_register_from_pointer_value(L);
+    0x6F => "LD L, A", 1, 1, "----", load_into_register_from_register(L, A);
+
+    0x70 => "LD [HL], B", 1, 2, "----", load_register_into_hl_pointer(B);
+    0x71 => "LD [HL], C", 1, 2, "----", load_register_into_hl_pointer(C);
+    0x72 => "LD [HL], D", 1, 2, "----", load_register_into_hl_pointer(D);
+    0x73 => "LD [HL], E", 1, 2, "----", load_register_into_hl_pointer(E);
+    0x74 => "LD [HL], H", 1, 2, "----", load_register_into_hl_pointer(H);
+    0x75 => "LD [HL], L", 1, 2, "----", load_register_into_hl_pointer(L);
+    0x76 => "HALT", 1, 1, "----", halt();
+    0x77 => "LD [HL], A", 1, 2, "----", load_register_into_hl_pointer(A);
+    0x78 => "LD A, B", 1, 1, "----", load_into_register_from_register(A, B);
+    0x79 => "LD A, C", 1, 1, "----", load_into_register_from_register(A, C);
+    0x7A => "LD A, D", 1, 1, "----", load_into_register_from_register(A, D);
+    0x7B => "LD A, E", 1, 1, "----", load_into_register_from_register(A, E);
+    0x7C => "LD A, H", 1, 1, "----", load_into_register_from_register(A, H);
+    0x7D => "LD A, L", 1, 1, "----", load_into_register_from_register(A, L);
+    0x7E => "LD A, [HL]", 1, 2, "----", load_into_register_from_pointer_value(A);
+    0x7F => "LD A, A", 1, 1, "----", load_into_register_from_register(A, A);
+
+    0x80 => "ADD A, B", 1, 1, "Z0HC", add_register_to_accumulator(B);
+    0x81 => "ADD A, C", 1, 1, "Z0HC", add_register_to_accumulator(C);
+    0x82 => "ADD A, D", 1, 1, "Z0HC", add_register_to_accumulator(D);
+    0x83 => "ADD A, E", 1, 1, "Z0HC", add_register_to_accumulator(E);
+    0x84 => "ADD A, H", 1, 1, "Z0HC", add_register_to_accumulator(H);
+    0x85 => "ADD A, L", 1, 1, "Z0HC", add_register_to_accumulator(L);
+    0x86 => "ADD A, [HL]", 1, 2, "Z0HC", add_pointer_value_to_accumulator();
+    0x87 => "ADD A, A", 1, 1, "Z0HC", add_register_to_accumulator(A);
+    0x88 => "ADC A, B", 1, 1, "Z0HC", add_register_to_accumulator_plus_carry(B);
+    0x89 => "ADC A, C", 1, 1, "Z0HC", add_register_to_accumulator_plus_carry(C);
+    0x8A => "ADC A, D", 1, 1, "Z0HC", add_register_to_accumulator_plus_carry(D);
+    0x8B => "ADC A, E", 1, 1, "Z0HC", add_register_to_accumulator_plus_carry(E);
+    0x8C => "ADC A, H", 1, 1, "Z0HC", add_register_to_accumulator_plus_carry(H);
+    0x8D => "ADC A, L", 1, 1, "Z0HC", add_register_to_accumulator_plus_carry(L);
+    0x8E => "ADC A, [HL]", 1, 2, "Z0HC", add_pointer_value_to_accumulator_plus_carry();
+    0x8F => "ADC A, A", 1, 1, "Z0HC", add_register_to_accumulator_plus_carry(A);
+
+    0x90 => "SUB A, B", 1, 1, "Z1HC", subtract_register(B);
+    0x91 => "SUB A, C", 1, 1, "Z1HC", subtract_register(C);
+    0x92 => "SUB A, D", 1, 1, "Z1HC", subtract_register(D);
+    0x93 => "SUB A, E", 1, 1, "Z1HC", subtract_register(E);
+    0x94 => "SUB A, H", 1, 1, "Z1HC", subtract_register(H);
+    0x95 => "SUB A, L", 1, 1, "Z1HC", subtract_register(L);
+    0x96 => "SUB A, [HL]", 1, 2, "Z1HC", subtract_pointer_value();
+    0x97 => "SUB A, A", 1, 1, "Z1HC", subtract_register(A);
+    0x98 => "SBC A, B", 1, 1, "Z1HC", subtract_register_with_carry(B);
+    0x99 => "SBC A, C", 1, 1, "Z1HC", subtract_register_with_carry(C);
+    0x9A => "SBC A, D", 1, 1, "Z1HC", subtract_register_with_carry(D);
+    0x9B => "SBC A, E", 1, 1, "Z1HC", subtract_register_with_carry(E);
+    0x9C => "SBC A, H", 1, 1, "Z1HC", subtract_register_with_carry(H);
+    0x9D => "SBC A, L", 1, 1, "Z1HC", subtract_register_with_carry(L);
+    0x9E => "SBC A, [HL]", 1, 2, "Z1HC", subtract_pointer_value_with_carry();
+    0x9F => "SBC A, A", 1, 1, "Z1HC", subtract_register_with_carry(A);
+
+    0xA0 => "AND A, B", 1, 1, "Z010", and_register(B);
+    0xA1 => "AND A, C", 1, 1, "Z010", and_register(C);
+    0xA2 => "AND A, D", 1, 1, "Z010", and_register(D);
+    0xA3 => "AND A, E", 1, 1, "Z010", and_register(E);
+    0xA4 => "AND A, H", 1, 1, "Z010", and_register(H);
+    0xA5 => "AND A, L", 1, 1, "Z010", and_register(L);
+    0xA6 => "AND A, [HL]", 1, 2, "Z010", and_pointer_value();
+    0xA7 => "AND A, A", 1, 1, "Z010", and_register(A);
+    0xA8 => "XOR A, B", 1, 1, "Z000", xor_register(B);
+    0xA9 => "XOR A, C", 1, 1, "Z000", xor_register(C);
+    0xAA => "XOR A, D", 1, 1, "Z000", xor_register(D);
+    0xAB => "XOR A, E", 1, 1, "Z000", xor_register(E);
+    0xAC => "XOR A, H", 1, 1, "Z000", xor_register(H);
+    0xAD => "XOR A, L", 1, 1, "Z000", xor_register(L);
+    0xAE => "XOR A, [HL]", 1, 2, "Z000", xor_pointer_value();
+    0xAF => "XOR A, A", 1, 1, "Z000", xor_register(A);
+
+    0xB0 => "OR A, B", 1, 1, "Z000", or_register(B);
+    0xB1 => "OR A, C", 1, 1, "Z000", or_register(C);
+    0xB2 => "OR A, D", 1, 1, "Z000", or_register(D);
+    0xB3 => "OR A, E", 1, 1, "Z000", or_register(E);
+    0xB4 => "OR A, H", 1, 1, "Z000", or_register(H);
+    0xB5 => "OR A, L", 1, 1, "Z000", or_register(L);
+    0xB6 => "OR A, [HL]", 1, 2, "Z000", or_pointer_value();
+    0xB7 => "OR A, A", 1, 1, "Z000", or_register(A);
+    0xB8 => "CP A, B", 1, 1, "Z1HC", compare_register(B);
+    0xB9 => "CP A, C", 1, 1, "Z1HC", compare_register(C);
+    0xBA => "CP A, D", 1, 1, "Z1HC", compare_register(D);
+    0xBB => "CP A, E", 1, 1, "Z1HC", compare_register(E);
+    0xBC => "CP A, H", 1, 1, "Z1HC", compare_register(H);
+    0xBD => "CP A, L", 1, 1, "Z1HC", compare_register(L);
+    0xBE => "CP A, [HL]", 1, 2, "Z1HC", compare_pointer_value();
+    0xBF => "CP A, A", 1, 1, "Z1HC", compare_register(A);
+
+    0xC0 => "RET NZ", 1, (2, 5), "----", sub_return(NotZero);
+    0xC1 => "POP BC", 1, 3, "----", pop_pair(BC);
+    0xC2 => "JP NZ, a16", 3, (3, 4), "----", jump(NotZero);
+    0xC3 => "JP a16", 3, 4, "----", jump(None);
+    0xC4 => "CALL NZ, a16", 3, (3, 6), "----", call(NotZero);
+    0xC5 => "PUSH BC", 1, 4, "----", push_pair(BC);
+    0xC6 => "ADD A, n8", 2, 2, "Z0HC", add_constant_to_accumulator();
+    0xC7 => "RST $00", 1, 4, "----", call_vector(VectorSlot::Zero);
+    0xC8 => "RET Z", 1, (2, 5), "----", sub_return(Zero);
+    0xC9 => "RET", 1, 4, "----", sub_return(None);
+    0xCA => "JP Z, a16", 3, (3, 4), "----", jump(Zero);
+    0xCB => "PREFIX", 1, 1, "----", prefix();
+    0xCC => "CALL Z, a16", 3, (3, 6), "----", call(Zero);
+    0xCD => "CALL a16", 3, 6, "----", call(None);
+    0xCE => "ADC A, n8", 2, 2, "Z0HC", add_constant_to_accumulator_plus_carry();
+    0xCF => "RST $08", 1, 4, "----", call_vector(VectorSlot::One);
+
+    0xD0 => "RET NC", 1, (2, 5), "----", sub_return(NotCarry);
+    0xD1 => "POP DE", 1, 3, "----", pop_pair(DE);
+    0xD2 => "JP NC, a16", 3, (3, 4), "----", jump(NotCarry);
+    0xD3 => "ILLEGAL_D3", 1, 1, "----", illegal(0xD3);
+    0xD4 => "CALL NC, a16", 3, (3, 6), "----", call(NotCarry);
+    0xD5 => "PUSH DE", 1, 4, "----", push_pair(DE);
+    0xD6 => "SUB A, n8", 2, 2, "Z1HC", subtract_constant();
+    0xD7 => "RST $10", 1, 4, "----", call_vector(VectorSlot::Two);
+    0xD8 => "RET C", 1, (2, 5), "----", sub_return(Carry);
+    0xD9 => "RETI", 1, 4, "----", sub_return_enable_interrupts();
+    0xDA => "JP C, a16", 3, (3, 4), "----", jump(Carry);
+    0xDB => "ILLEGAL_DB", 1, 1, "----", illegal(0xDB);
+    0xDC => "CALL C, a16", 3, (3, 6), "----", call(Carry);
+    0xDD => "ILLEGAL_DD", 1, 1, "----", illegal(0xDD);
+    0xDE => "SBC A, n8", 2, 2, "Z1HC", subtract_constant_with_carry();
+    0xDF => "RST $18", 1, 4, "----", call_vector(VectorSlot::Three);
+
+    0xE0 => "LDH [a8], A", 2, 3, "----", load_into_high_constant_pointer();
+    0xE1 => "POP HL", 1, 3, "----", pop_pair(HL);
+    0xE2 => "LDH [C], A", 1, 2, "----", load_into_highc_pointer();
+    0xE3 => "ILLEGAL_E3", 1, 1, "----", illegal(0xE3);
+    0xE4 => "ILLEGAL_E4", 1, 1, "----", illegal(0xE4);
+    0xE5 => "PUSH HL", 1, 4, "----", push_pair(HL);
+    0xE6 => "AND A, n8", 2, 2, "Z010", and_constant();
+    0xE7 => "RST $20", 1, 4, "----", call_vector(VectorSlot::Four);
+    0xE8 => "ADD SP, e8", 2, 4, "00HC", add_signed_constant_to_stack_pointer();
+    0xE9 => "JP HL", 1, 1, "----", jump_to_pointer();
+    0xEA => "LD [a16], A", 3, 4, "----", load_into_constant_pointer_from_accumulator();
+    0xEB => "ILLEGAL_EB", 1, 1, "----", illegal(0xEB);
+    0xEC => "ILLEGAL_EC", 1, 1, "----", illegal(0xEC);
+    0xED => "ILLEGAL_ED", 1, 1, "----", illegal(0xED);
+    0xEE => "XOR A, n8", 2, 2, "Z000", xor_constant();
+    0xEF => "RST $28", 1, 4, "----", call_vector(VectorSlot::Five);
+
+    0xF0 => "LDH A, [a8]", 2, 3, "----", load_high_constant_pointer_into_accumulator();
+    0xF1 => "POP AF", 1, 3, "ZNHC", pop_accumulator_and_flags();
+    0xF2 => "LDH A, [C]", 1, 2, "----", load_highc_pointer_into_accumulator();
+    0xF3 => "DI", 1, 1, "----", disable_interrupts();
+    0xF4 => "ILLEGAL_F4", 1, 1, "----", illegal(0xF4);
+    0xF5 => "PUSH AF", 1, 4, "----", push_accumulator_and_flags();
+    0xF6 => "OR A, n8", 2, 2, "Z000", or_constant();
+    0xF7 => "RST $30", 1, 4, "----", call_vector(VectorSlot::Six);
+    0xF8 => "LD HL, SP + e8", 2, 3, "00HC", load_stack_pointer_plus_signed_constant_into_hl();
+    0xF9 => "LD SP, HL", 1, 2, "----", load_from_hl_into_stack_pointer();
+    0xFA => "LD A, [a16]", 3, 4, "----", load_constant_pointer_into_accumulator();
+    0xFB => "EI", 1, 1, "----", enable_interrupts();
+    0xFC => "ILLEGAL_FC", 1, 1, "----", illegal(0xFC);
+    0xFD => "ILLEGAL_FD", 1, 1, "----", illegal(0xFD);
+    0xFE => "CP A, n8", 2, 2, "Z1HC", compare_constant();
+    0xFF => "RST $38", 1, 4, "----", call_vector(VectorSlot::Seven);
+};
+
+/// The opcodes following a `$CB` prefix. All of them are implemented.
+pub static PREFIXED: [Opcode; 256] = opcode_table! {
+    0x00 => "RLC B", 2, 2, "Z00C", cyclic_rotate_left_register(B);
+    0x01 => "RLC C", 2, 2, "Z00C", cyclic_rotate_left_register(C);
+    0x02 => "RLC D", 2, 2, "Z00C", cyclic_rotate_left_register(D);
+    0x03 => "RLC E", 2, 2, "Z00C", cyclic_rotate_left_register(E);
+    0x04 => "RLC H", 2, 2, "Z00C", cyclic_rotate_left_register(H);
+    0x05 => "RLC L", 2, 2, "Z00C", cyclic_rotate_left_register(L);
+    0x06 => "RLC [HL]", 2, 4, "Z00C", cyclic_rotate_left_pointer_value();
+    0x07 => "RLC A", 2, 2, "Z00C", cyclic_rotate_left_register(A);
+    0x08 => "RRC B", 2, 2, "Z00C", cyclic_rotate_right_register(B);
+    0x09 => "RRC C", 2, 2, "Z00C", cyclic_rotate_right_register(C);
+    0x0A => "RRC D", 2, 2, "Z00C", cyclic_rotate_right_register(D);
+    0x0B => "RRC E", 2, 2, "Z00C", cyclic_rotate_right_register(E);
+    0x0C => "RRC H", 2, 2, "Z00C", cyclic_rotate_right_register(H);
+    0x0D => "RRC L", 2, 2, "Z00C", cyclic_rotate_right_register(L);
+    0x0E => "RRC [HL]", 2, 4, "Z00C", cyclic_rotate_right_pointer_value();
+    0x0F => "RRC A", 2, 2, "Z00C", cyclic_rotate_right_register(A);
+
+    0x10 => "RL B", 2, 2, "Z00C", carrying_rotate_left_register(B);
+    0x11 => "RL C", 2, 2, "Z00C", carrying_rotate_left_register(C);
+    0x12 => "RL D", 2, 2, "Z00C", carrying_rotate_left_register(D);
+    0x13 => "RL E", 2, 2, "Z00C", carrying_rotate_left_register(E);
+    0x14 => "RL H", 2, 2, "Z00C", carrying_rotate_left_register(H);
+    0x15 => "RL L", 2, 2, "Z00C", carrying_rotate_left_register(L);
+    0x16 => "RL [HL]", 2, 4, "Z00C", carrying_rotate_left_pointer_value();
+    0x17 => "RL A", 2, 2, "Z00C", carrying_rotate_left_register(A);
+    0x18 => "RR B", 2, 2, "Z00C", carrying_rotate_right_register(B);
+    0x19 => "RR C", 2, 2, "Z00C", carrying_rotate_right_register(C);
+    0x1A => "RR D", 2, 2, "Z00C", carrying_rotate_right_register(D);
+    0x1B => "RR E", 2, 2, "Z00C", carrying_rotate_right_register(E);
+    0x1C => "RR H", 2, 2, "Z00C", carrying_rotate_right_register(H);
+    0x1D => "RR L", 2, 2, "Z00C", carrying_rotate_right_register(L);
+    0x1E => "RR [HL]", 2, 4, "Z00C", carrying_rotate_right_pointer_value();
+    0x1F => "RR A", 2, 2, "Z00C", carrying_rotate_right_register(A);
+
+    0x20 => "SLA B", 2, 2, "Z00C", shift_left_register(B);
+    0x21 => "SLA C", 2, 2, "Z00C", shift_left_register(C);
+    0x22 => "SLA D", 2, 2, "Z00C", shift_left_register(D);
+    0x23 => "SLA E", 2, 2, "Z00C", shift_left_register(E);
+    0x24 => "SLA H", 2, 2, "Z00C", shift_left_register(H);
+    0x25 => "SLA L", 2, 2, "Z00C", shift_left_register(L);
+    0x26 => "SLA [HL]", 2, 4, "Z00C", shift_left_pointer_value();
+    0x27 => "SLA A", 2, 2, "Z00C", shift_left_register(A);
+    0x28 => "SRA B", 2, 2, "Z00C", arithmetic_shift_right_register(B);
+    0x29 => "SRA C", 2, 2, "Z00C", arithmetic_shift_right_register(C);
+    0x2A => "SRA D", 2, 2, "Z00C", arithmetic_shift_right_register(D);
+    0x2B => "SRA E", 2, 2, "Z00C", arithmetic_shift_right_register(E);
+    0x2C => "SRA H", 2, 2, "Z00C", arithmetic_shift_right_register(H);
+    0x2D => "SRA L", 2, 2, "Z00C", arithmetic_shift_right_register(L);
+    0x2E => "SRA [HL]", 2, 4, "Z00C", arithmetic_shift_right_pointer_value();
+    0x2F => "SRA A", 2, 2, "Z00C", arithmetic_shift_right_register(A);
+
+    0x30 => "SWAP B", 2, 2, "Z000", swap_register(B);
+    0x31 => "SWAP C", 2, 2, "Z000", swap_register(C);
+    0x32 => "SWAP D", 2, 2, "Z000", swap_register(D);
+    0x33 => "SWAP E", 2, 2, "Z000", swap_register(E);
+    0x34 => "SWAP H", 2, 2, "Z000", swap_register(H);
+    0x35 => "SWAP L", 2, 2, "Z000", swap_register(L);
+    0x36 => "SWAP [HL]", 2, 4, "Z000", swap_pointer_value();
+    0x37 => "SWAP A", 2, 2, "Z000", swap_register(A);
+    0x38 => "SRL B", 2, 2, "Z00C", logical_shift_right_register(B);
+    0x39 => "SRL C", 2, 2, "Z00C", logical_shift_right_register(C);
+    0x3A => "SRL D", 2, 2, "Z00C", logical_shift_right_register(D);
+    0x3B => "SRL E", 2, 2, "Z00C", logical_shift_right_register(E);
+    0x3C => "SRL H", 2, 2, "Z00C", logical_shift_right_register(H);
+    0x3D => "SRL L", 2, 2, "Z00C", logical_shift_right_register(L);
+    0x3E => "SRL [HL]", 2, 4, "Z00C", logical_shift_right_pointer_value();
+    0x3F => "SRL A", 2, 2, "Z00C", logical_shift_right_register(A);
+
+    0x40 => "BIT 0, B", 2, 2, "Z01-", test_register(Bit::zero(), B);
+    0x41 => "BIT 0, C", 2, 2, "Z01-", test_register(Bit::zero(), C);
+    0x42 => "BIT 0, D", 2, 2, "Z01-", test_register(Bit::zero(), D);
+    0x43 => "BIT 0, E", 2, 2, "Z01-", test_register(Bit::zero(), E);
+    0x44 => "BIT 0, H", 2, 2, "Z01-", test_register(Bit::zero(), H);
+    0x45 => "BIT 0, L", 2, 2, "Z01-", test_register(Bit::zero(), L);
+    0x46 => "BIT 0, [HL]", 2, 3, "Z01-", test_pointer_value(Bit::zero());
+    0x47 => "BIT 0, A", 2, 2, "Z01-", test_register(Bit::zero(), A);
+    0x48 => "BIT 1, B", 2, 2, "Z01-", test_register(Bit::one(), B);
+    0x49 => "BIT 1, C", 2, 2, "Z01-", test_register(Bit::one(), C);
+    0x4A => "BIT 1, D", 2, 2, "Z01-", test_register(Bit::one(), D);
+    0x4B => "BIT 1, E", 2, 2, "Z01-", test_register(Bit::one(), E);
+    0x4C => "BIT 1, H", 2, 2, "Z01-", test_register(Bit::one(), H);
+    0x4D => "BIT 1, L", 2, 2, "Z01-", test_register(Bit::one(), L);
+    0x4E => "BIT 1, [HL]", 2, 3, "Z01-", test_pointer_value(Bit::one());
+    0x4F => "BIT 1, A", 2, 2, "Z01-", test_register(Bit::one(), A);
+
+    0x50 => "BIT 2, B", 2, 2, "Z01-", test_register(Bit::two(), B);
+    0x51 => "BIT 2, C", 2, 2, "Z01-", test_register(Bit::two(), C);
+    0x52 => "BIT 2, D", 2, 2, "Z01-", test_register(Bit::two(), D);
+    0x53 => "BIT 2, E", 2, 2, "Z01-", test_register(Bit::two(), E);
+    0x54 => "BIT 2, H", 2, 2, "Z01-", test_register(Bit::two(), H);
+    0x55 => "BIT 2, L", 2, 2, "Z01-", test_register(Bit::two(), L);
+    0x56 => "BIT 2, [HL]", 2, 3, "Z01-", test_pointer_value(Bit::two());
+    0x57 => "BIT 2, A", 2, 2, "Z01-", test_register(Bit::two(), A);
+    0x58 => "BIT 3, B", 2, 2, "Z01-", test_register(Bit::three(), B);
+    0x59 => "BIT 3, C", 2, 2, "Z01-", test_register(Bit::three(), C);
+    0x5A => "BIT 3, D", 2, 2, "Z01-", test_register(Bit::three(), D);
+    0x5B => "BIT 3, E", 2, 2, "Z01-", test_register(Bit::three(), E);
+    0x5C => "BIT 3, H", 2, 2, "Z01-", test_register(Bit::three(), H);
+    0x5D => "BIT 3, L", 2, 2, "Z01-", test_register(Bit::three(), L);
+    0x5E => "BIT 3, [HL]", 2, 3, "Z01-", test_pointer_value(Bit::three());
+    0x5F => "BIT 3, A", 2, 2, "Z01-", test_register(Bit::three(), A);
+
+    0x60 => "BIT 4, B", 2, 2, "Z01-", test_register(Bit::four(), B);
+    0x61 => "BIT 4, C", 2, 2, "Z01-", test_register(Bit::four(), C);
+    0x62 => "BIT 4, D", 2, 2, "Z01-", test_register(Bit::four(), D);
+    0x63 => "BIT 4, E", 2, 2, "Z01-", test_register(Bit::four(), E);
+    0x64 => "BIT 4, H", 2, 2, "Z01-", test_register(Bit::four(), H);
+    0x65 => "BIT 4, L", 2, 2, "Z01-", test_register(Bit::four(), L);
+    0x66 => "BIT 4, [HL]", 2, 3, "Z01-", test_pointer_value(Bit::four());
+    0x67 => "BIT 4, A", 2, 2, "Z01-", test_register(Bit::four(), A);
+    0x68 => "BIT 5, B", 2, 2, "Z01-", test_register(Bit::five(), B);
+    0x69 => "BIT 5, C", 2, 2, "Z01-", test_register(Bit::five(), C);
+    0x6A => "BIT 5, D", 2, 2, "Z01-", test_register(Bit::five(), D);
+    0x6B => "BIT 5, E", 2, 2, "Z01-", test_register(Bit::five(), E);
+    0x6C => "BIT 5, H", 2, 2, "Z01-", test_register(Bit::five(), H);
+    0x6D => "BIT 5, L", 2, 2, "Z01-", test_register(Bit::five(), L);
+    0x6E => "BIT 5, [HL]", 2, 3, "Z01-", test_pointer_value(Bit::five());
+    0x6F => "BIT 5, A", 2, 2, "Z01-", test_register(Bit::five(), A);
+
+    0x70 => "BIT 6, B", 2, 2, "Z01-", test_register(Bit::six(), B);
+    0x71 => "BIT 6, C", 2, 2, "Z01-", test_register(Bit::six(), C);
+    0x72 => "BIT 6, D", 2, 2, "Z01-", test_register(Bit::six(), D);
+    0x73 => "BIT 6, E", 2, 2, "Z01-", test_register(Bit::six(), E);
+    0x74 => "BIT 6, H", 2, 2, "Z01-", test_register(Bit::six(), H);
+    0x75 => "BIT 6, L", 2, 2, "Z01-", test_register(Bit::six(), L);
+    0x76 => "BIT 6, [HL]", 2, 3, "Z01-", test_pointer_value(Bit::six());
+    0x77 => "BIT 6, A", 2, 2, "Z01-", test_register(Bit::six(), A);
+    0x78 => "BIT 7, B", 2, 2, "Z01-", test_register(Bit::seven(), B);
+    0x79 => "BIT 7, C", 2, 2, "Z01-", test_register(Bit::seven(), C);
+    0x7A => "BIT 7, D", 2, 2, "Z01-", test_register(Bit::seven(), D);
+    0x7B => "BIT 7, E", 2, 2, "Z01-", test_register(Bit::seven(), E);
+    0x7C => "BIT 7, H", 2, 2, "Z01-", test_register(Bit::seven(), H);
+    0x7D => "BIT 7, L", 2, 2, "Z01-", test_register(Bit::seven(), L);
+    0x7E => "BIT 7, [HL]", 2, 3, "Z01-", test_pointer_value(Bit::seven());
+    0x7F => "BIT 7, A", 2, 2, "Z01-", test_register(Bit::seven(), A);
+
+    0x80 => "RES 0, B", 2, 2, "----", reset_register(Bit::zero(), B);
+    0x81 => "RES 0, C", 2, 2, "----", reset_register(Bit::zero(), C);
+    0x82 => "RES 0, D", 2, 2, "----", reset_register(Bit::zero(), D);
+    0x83 => "RES 0, E", 2, 2, "----", reset_register(Bit::zero(), E);
+    0x84 => "RES 0, H", 2, 2, "----", reset_register(Bit::zero(), H);
+    0x85 => "RES 0, L", 2, 2, "----", reset_register(Bit::zero(), L);
+    0x86 => "RES 0, [HL]", 2, 4, "----", reset_pointer_value(Bit::zero());
+    0x87 => "RES 0, A", 2, 2, "----", reset_register(Bit::zero(), A);
+    0x88 => "RES 1, B", 2, 2, "----", reset_register(Bit::one(), B);
+    0x89 => "RES 1, C", 2, 2, "----", reset_register(Bit::one(), C);
+    0x8A => "RES 1, D", 2, 2, "----", reset_register(Bit::one(), D);
+    0x8B => "RES 1, E", 2, 2, "----", reset_register(Bit::one(), E);
+    0x8C => "RES 1, H", 2, 2, "----", reset_register(Bit::one(), H);
+    0x8D => "RES 1, L", 2, 2, "----", reset_register(Bit::one(), L);
+    0x8E => "RES 1, [HL]", 2, 4, "----", reset_pointer_value(Bit::one());
+    0x8F => "RES 1, A", 2, 2, "----", reset_register(Bit::one(), A);
+
+    0x90 => "RES 2, B", 2, 2, "----", reset_register(Bit::two(), B);
+    0x91 => "RES 2, C", 2, 2, "----", reset_register(Bit::two(), C);
+    0x92 => "RES 2, D", 2, 2, "----", reset_register(Bit::two(), D);
+    0x93 => "RES 2, E", 2, 2, "----", reset_register(Bit::two(), E);
+    0x94 => "RES 2, H", 2, 2, "----", reset_register(Bit::two(), H);
+    0x95 => "RES 2, L", 2, 2, "----", reset_register(Bit::two(), L);
+    0x96 => "RES 2, [HL]", 2, 4, "----", reset_pointer_value(Bit::two());
+    0x97 => "RES 2, A", 2, 2, "----", reset_register(Bit::two(), A);
+    0x98 => "RES 3, B", 2, 2, "----", reset_register(Bit::three(), B);
+    0x99 => "RES 3, C", 2, 2, "----", reset_register(Bit::three(), C);
+    0x9A => "RES 3, D", 2, 2, "----", reset_register(Bit::three(), D);
+    0x9B => "RES 3, E", 2, 2, "----", reset_register(Bit::three(), E);
+    0x9C => "RES 3, H", 2, 2, "----", reset_register(Bit::three(), H);
+    0x9D => "RES 3, L", 2, 2, "----", reset_register(Bit::three(), L);
+    0x9E => "RES 3, [HL]", 2, 4, "----", reset_pointer_value(Bit::three());
+    0x9F => "RES 3, A", 2, 2, "----", reset_register(Bit::three(), A);
+
+    0xA0 => "RES 4, B", 2, 2, "----", reset_register(Bit::four(), B);
+    0xA1 => "RES 4, C", 2, 2, "----", reset_register(Bit::four(), C);
+    0xA2 => "RES 4, D", 2, 2, "----", reset_register(Bit::four(), D);
+    0xA3 => "RES 4, E", 2, 2, "----", reset_register(Bit::four(), E);
+    0xA4 => "RES 4, H", 2, 2, "----", reset_register(Bit::four(), H);
+    0xA5 => "RES 4, L", 2, 2, "----", reset_register(Bit::four(), L);
+    0xA6 => "RES 4, [HL]", 2, 4, "----", reset_pointer_value(Bit::four());
+    0xA7 => "RES 4, A", 2, 2, "----", reset_register(Bit::four(), A);
+    0xA8 => "RES 5, B", 2, 2, "----", reset_register(Bit::five(), B);
+    0xA9 => "RES 5, C", 2, 2, "----", reset_register(Bit::five(), C);
+    0xAA => "RES 5, D", 2, 2, "----", reset_register(Bit::five(), D);
+    0xAB => "RES 5, E", 2, 2, "----", reset_register(Bit::five(), E);
+    0xAC => "RES 5, H", 2, 2, "----", reset_register(Bit::five(), H);
+    0xAD => "RES 5, L", 2, 2, "----", reset_register(Bit::five(), L);
+    0xAE => "RES 5, [HL]", 2, 4, "----", reset_pointer_value(Bit::five());
+    0xAF => "RES 5, A", 2, 2, "----", reset_register(Bit::five(), A);
+
+    0xB0 => "RES 6, B", 2, 2, "----", reset_register(Bit::six(), B);
+    0xB1 => "RES 6, C", 2, 2, "----", reset_register(Bit::six(), C);
+    0xB2 => "RES 6, D", 2, 2, "----", reset_register(Bit::six(), D);
+    0xB3 => "RES 6, E", 2, 2, "----", reset_register(Bit::six(), E);
+    0xB4 => "RES 6, H", 2, 2, "----", reset_register(Bit::six(), H);
+    0xB5 => "RES 6, L", 2, 2, "----", reset_register(Bit::six(), L);
+    0xB6 => "RES 6, [HL]", 2, 4, "----", reset_pointer_value(Bit::six());
+    0xB7 => "RES 6, A", 2, 2, "----", reset_register(Bit::six(), A);
+    0xB8 => "RES 7, B", 2, 2, "----", reset_register(Bit::seven(), B);
+    0xB9 => "RES 7, C", 2, 2, "----", reset_register(Bit::seven(), C);
+    0xBA => "RES 7, D", 2, 2, "----", reset_register(Bit::seven(), D);
+    0xBB => "RES 7, E", 2, 2, "----", reset_register(Bit::seven(), E);
+    0xBC => "RES 7, H", 2, 2, "----", reset_register(Bit::seven(), H);
+    0xBD => "RES 7, L", 2, 2, "----", reset_register(Bit::seven(), L);
+    0xBE => "RES 7, [HL]", 2, 4, "----", reset_pointer_value(Bit::seven());
+    0xBF => "RES 7, A", 2, 2, "----", reset_register(Bit::seven(), A);
+
+    0xC0 => "SET 0, B", 2, 2, "----", set_register(Bit::zero(), B);
+    0xC1 => "SET 0, C", 2, 2, "----", set_register(Bit::zero(), C);
+    0xC2 => "SET 0, D", 2, 2, "----", set_register(Bit::zero(), D);
+    0xC3 => "SET 0, E", 2, 2, "----", set_register(Bit::zero(), E);
+    0xC4 => "SET 0, H", 2, 2, "----", set_register(Bit::zero(), H);
+    0xC5 => "SET 0, L", 2, 2, "----", set_register(Bit::zero(), L);
+    0xC6 => "SET 0, [HL]", 2, 4, "----", set_pointer_value(Bit::zero());
+    0xC7 => "SET 0, A", 2, 2, "----", set_register(Bit::zero(), A);
+    0xC8 => "SET 1, B", 2, 2, "----", set_register(Bit::one(), B);
+    0xC9 => "SET 1, C", 2, 2, "----", set_register(Bit::one(), C);
+    0xCA => "SET 1, D", 2, 2, "----", set_register(Bit::one(), D);
+    0xCB => "SET 1, E", 2, 2, "----", set_register(Bit::one(), E);
+    0xCC => "SET 1, H", 2, 2, "----", set_register(Bit::one(), H);
+    0xCD => "SET 1, L", 2, 2, "----", set_register(Bit::one(), L);
+    0xCE => "SET 1, [HL]", 2, 4, "----", set_pointer_value(Bit::one());
+    0xCF => "SET 1, A", 2, 2, "----", set_register(Bit::one(), A);
+
+    0xD0 => "SET 2, B", 2, 2, "----", set_register(Bit::two(), B);
+    0xD1 => "SET 2, C", 2, 2, "----", set_register(Bit::two(), C);
+    0xD2 => "SET 2, D", 2, 2, "----", set_register(Bit::two(), D);
+    0xD3 => "SET 2, E", 2, 2, "----", set_register(Bit::two(), E);
+    0xD4 => "SET 2, H", 2, 2, "----", set_register(Bit::two(), H);
+    0xD5 => "SET 2, L", 2, 2, "----", set_register(Bit::two(), L);
+    0xD6 => "SET 2, [HL]", 2, 4, "----", set_pointer_value(Bit::two());
+    0xD7 => "SET 2, A", 2, 2, "----", set_register(Bit::two(), A);
+    0xD8 => "SET 3, B", 2, 2, "----", set_register(Bit::three(), B);
+    0xD9 => "SET 3, C", 2, 2, "----", set_register(Bit::three(), C);
+    0xDA => "SET 3, D", 2, 2, "----", set_register(Bit::three(), D);
+    0xDB => "SET 3, E", 2, 2, "----", set_register(Bit::three(), E);
+    0xDC => "SET 3, H", 2, 2, "----", set_register(Bit::three(), H);
+    0xDD => "SET 3, L", 2, 2, "----", set_register(Bit::three(), L);
+    0xDE => "SET 3, [HL]", 2, 4, "----", set_pointer_value(Bit::three());
+    0xDF => "SET 3, A", 2, 2, "----", set_register(Bit::three(), A);
+
+    0xE0 => "SET 4, B", 2, 2, "----", set_register(Bit::four(), B);
+    0xE1 => "SET 4, C", 2, 2, "----", set_register(Bit::four(), C);
+    0xE2 => "SET 4, D", 2, 2, "----", set_register(Bit::four(), D);
+    0xE3 => "SET 4, E", 2, 2, "----", set_register(Bit::four(), E);
+    0xE4 => "SET 4, H", 2, 2, "----", set_register(Bit::four(), H);
+    0xE5 => "SET 4, L", 2, 2, "----", set_register(Bit::four(), L);
+    0xE6 => "SET 4, [HL]", 2, 4, "----", set_pointer_value(Bit::four());
+    0xE7 => "SET 4, A", 2, 2, "----", set_register(Bit::four(), A);
+    0xE8 => "SET 5, B", 2, 2, "----", set_register(Bit::five(), B);
+    0xE9 => "SET 5, C", 2, 2, "----", set_register(Bit::five(), C);
+    0xEA => "SET 5, D", 2, 2, "----", set_register(Bit::five(), D);
+    0xEB => "SET 5, E", 2, 2, "----", set_register(Bit::five(), E);
+    0xEC => "SET 5, H", 2, 2, "----", set_register(Bit::five(), H);
+    0xED => "SET 5, L", 2, 2, "----", set_register(Bit::five(), L);
+    0xEE => "SET 5, [HL]", 2, 4, "----", set_pointer_value(Bit::five());
+    0xEF => "SET 5, A", 2, 2, "----", set_register(Bit::five(), A);
+
+    0xF0 => "SET 6, B", 2, 2, "----", set_register(Bit::six(), B);
+    0xF1 => "SET 6, C", 2, 2, "----", set_register(Bit::six(), C);
+    0xF2 => "SET 6, D", 2, 2, "----", set_register(Bit::six(), D);
+    0xF3 => "SET 6, E", 2, 2, "----", set_register(Bit::six(), E);
+    0xF4 => "SET 6, H", 2, 2, "----", set_register(Bit::six(), H);
+    0xF5 => "SET 6, L", 2, 2, "----", set_register(Bit::six(), L);
+    0xF6 => "SET 6, [HL]", 2, 4, "----", set_pointer_value(Bit::six());
+    0xF7 => "SET 6, A", 2, 2, "----", set_register(Bit::six(), A);
+    0xF8 => "SET 7, B", 2, 2, "----", set_register(Bit::seven(), B);
+    0xF9 => "SET 7, C", 2, 2, "----", set_register(Bit::seven(), C);
+    0xFA => "SET 7, D", 2, 2, "----", set_register(Bit::seven(), D);
+    0xFB => "SET 7, E", 2, 2, "----", set_register(Bit::seven(), E);
+    0xFC => "SET 7, H", 2, 2, "----", set_register(Bit::seven(), H);
+    0xFD => "SET 7, L", 2, 2, "----", set_register(Bit::seven(), L);
+    0xFE => "SET 7, [HL]", 2, 4, "----", set_pointer_value(Bit::seven());
+    0xFF => "SET 7, A", 2, 2, "----", set_register(Bit::seven(), A);
+};
+
+/// The entry of every instruction in the tables.
+static ENTRIES: LazyLock<HashMap<Instruction, &'static Opcode>> = LazyLock::new(|| {
+    UNPREFIXED
+        .iter()
+        .chain(PREFIXED.iter())
+        .map(|entry| (entry.instruction(), entry))
+        .collect()
+});
+
+/// Finds the entry `instruction` is decoded from.
+pub fn entry(instruction: &Instruction) -> &'static Opcode {
+    ENTRIES
+        .get(instruction)
+        .expect("every instruction has an entry in the opcode tables")
+}
+
+impl Info for Instruction {
+    fn bytes(&self) -> u8 {
+        entry(self).bytes
+    }
+
+    fn cycles(&self) -> Cycles {
+        entry(self).cycles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{metadata::FlagEffect, Flag};
+
+    /// Opcodes without an instruction, from the matrix. They lock up the CPU.
+    const ILLEGAL: [u8; 11] = [
+        0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD,
+    ];
+
+    fn check(prefix: &str, opcode: usize, entry: &'static Opcode) {
+        let name = format!("{prefix}{opcode:02X} {}", entry.mnemonic);
+        let instruction = entry.instruction();
+
+        // `Info` is looked up by instruction, so each one has to come from a single entry.
+        assert!(
+            std::ptr::eq(super::entry(&instruction), entry),
+            "{name} is also decoded from {}",
+            super::entry(&instruction).mnemonic
+        );
+
+        // `Display` writes operand placeholders rather than values, and leaves out the `A` of `SUB`,
+        // `SBC` and the logic instructions, but it lists the same operands.
+        let operands = match entry.mnemonic.split_once(" A, ") {
+            Some(("SUB" | "SBC" | "AND" | "XOR" | "OR" | "CP", operand)) => operand,
+            _ => entry.mnemonic,
+        };
+
+        assert_eq!(
+            instruction.to_string().matches(',').count(),
+            operands.matches(',').count(),
+            "operands of {name} written as {instruction}"
+        );
+
+        let effects = instruction.flag_effects();
+        let flags = [Flag::Zero, Flag::Subtract, Flag::HalfCarry, Flag::Carry];
+
+        for (flag, expected) in flags.into_iter().zip(entry.flags.chars()) {
+            let expected = match expected {
+                '-' => FlagEffect::Unaffected,
+                '0' => FlagEffect::Reset,
+                '1' => FlagEffect::Set,
+                _ => FlagEffect::Modified,
+            };
+
+            assert_eq!(effects.get(flag), expected, "{flag} of {name}");
+        }
+    }
+
+    #[test]
+    fn unprefixed_matches_matrix() {
+        for (opcode, entry) in UNPREFIXED.iter().enumerate() {
+            check("", opcode, entry);
+
+            assert_eq!(
+                matches!(entry.instruction(), Instruction::Illegal(_)),
+                ILLEGAL.contains(&(opcode as u8)),
+                "{opcode:02X} {}",
+                entry.mnemonic
+            );
+        }
+    }
+
+    #[test]
+    fn prefixed_matches_matrix() {
+        for (opcode, entry) in PREFIXED.iter().enumerate() {
+            check("CB ", opcode, entry);
+        }
+    }
+
+    #[test]
+    fn instructions_take_their_info_from_the_table() {
+        let Instruction::Jump(jump) = UNPREFIXED[0xC2].instruction() else {
+            panic!("C2 isn't a jump");
+        };
+
+        assert_eq!(jump.bytes(), 3);
+        assert_eq!(jump.cycles(), Cycles::Variable(3, 4));
+        assert_eq!(PREFIXED[0x46].instruction().bytes(), 2);
+    }
+}
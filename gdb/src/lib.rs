@@ -0,0 +1,195 @@
+//! A server for the GDB remote serial protocol, so GDB and other debuggers can control the
+//! emulator over TCP.
+//!
+//! Registers are exposed as six 16-bit values (`af`, `bc`, `de`, `hl`, `sp` and `pc`), described
+//! to the client through `target.xml`. Memory reads and writes go through the bus like the CPU's,
+//! and breakpoints and watchpoints are kept by the [`Debugger`](gb_interpreter::debugger::Debugger).
+
+pub mod packet;
+pub mod session;
+
+use gb_interpreter::debugger::Stop;
+use packet::{Connection, Packet};
+use session::{Action, Session};
+use std::{io, net::TcpListener};
+
+/// The number of instructions to run between checks for an interrupt from the client.
+const CONTINUE_CHUNK: u64 = 10_000;
+
+/// Accepts one client on `listener` and serves it until it detaches or disconnects.
+pub fn serve(listener: &TcpListener, session: &mut Session) -> io::Result<()> {
+    let (stream, _) = listener.accept()?;
+    stream.set_nodelay(true)?;
+
+    let mut connection = Connection::new(stream)?;
+
+    while let Some(packet) = connection.read_packet()? {
+        let Packet::Command(command) = packet else {
+            // An interrupt while stopped is answered like a query of the stop reason.
+            connection.write_packet("S02")?;
+            continue;
+        };
+
+        match session.handle(&command) {
+            Action::Reply(reply) => {
+                connection.write_packet(&reply)?;
+
+                // The acknowledgement for this packet was the last one sent.
+                if command == "QStartNoAckMode" {
+                    connection.no_ack = true;
+                }
+            }
+            Action::Continue => {
+                let reply = run(&mut connection, session)?;
+                connection.write_packet(&reply)?;
+            }
+            Action::Close(reply) => {
+                if let Some(reply) = reply {
+                    connection.write_packet(&reply)?;
+                }
+
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs until the debugger stops or the client interrupts, and returns the stop reply.
+fn run(connection: &mut Connection, session: &mut Session) -> io::Result<String> {
+    loop {
+        let stop = session.debugger.resume(&mut session.device, CONTINUE_CHUNK);
+
+        if stop != Stop::Limit {
+            return Ok(session.stop_reply(stop));
+        }
+
+        if connection.poll_interrupt()? {
+            return Ok("S02".into());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gb_hardware::{video::Mode, Device};
+    use packet::checksum_of;
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+        thread,
+    };
+
+    struct Client(TcpStream);
+
+    impl Client {
+        /// Sends a command and returns the reply, checking the acknowledgement.
+        fn send(&mut self, command: &str) -> String {
+            let packet = format!("${command}#{:02x}", checksum_of(command.as_bytes()));
+            self.0.write_all(packet.as_bytes()).unwrap();
+
+            let mut ack = [0];
+            self.0.read_exact(&mut ack).unwrap();
+            assert_eq!(ack[0], b'+');
+
+            self.read_reply()
+        }
+
+        fn read_reply(&mut self) -> String {
+            let mut reply = Vec::new();
+            let mut byte = [0];
+
+            loop {
+                self.0.read_exact(&mut byte).unwrap();
+
+                if byte[0] == b'#' {
+                    break;
+                }
+
+                reply.push(byte[0]);
+            }
+
+            let mut checksum = [0; 2];
+            self.0.read_exact(&mut checksum).unwrap();
+            self.0.write_all(b"+").unwrap();
+
+            assert_eq!(reply.first(), Some(&b'$'));
+            String::from_utf8(reply[1..].to_vec()).unwrap()
+        }
+    }
+
+    #[test]
+    fn serves_a_scripted_client() {
+        let mut rom = vec![0; 0x8000];
+
+        // LD A, $34; LD [$C000], A; JR -2
+        rom[0x100..0x107].copy_from_slice(&[0x3E, 0x34, 0xEA, 0x00, 0xC0, 0x18, 0xFE]);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let mut session = Session::new(Device::from_rom(rom).unwrap());
+            serve(&listener, &mut session).unwrap();
+            session.device.cpu.program_counter
+        });
+
+        let mut client = Client(TcpStream::connect(address).unwrap());
+
+        assert!(client
+            .send("qSupported:swbreak+")
+            .contains("qXfer:features:read+"));
+        assert_eq!(client.send("?"), "S05");
+        assert!(client
+            .send("qXfer:features:read:target.xml:0,1000")
+            .starts_with("l<?xml"));
+
+        let registers = client.send("g");
+        assert_eq!(registers.len(), 24);
+        assert_eq!(&registers[20..], "0001");
+
+        assert_eq!(client.send("P1=3412"), "OK");
+        assert_eq!(client.send("p1"), "3412");
+
+        assert_eq!(client.send("Mc100,2:abcd"), "OK");
+        assert_eq!(client.send("mc100,2"), "abcd");
+
+        assert_eq!(client.send("s"), "S05");
+        assert_eq!(client.send("p5"), "0201");
+
+        assert_eq!(client.send("Z2,c000,1"), "OK");
+        assert_eq!(client.send("c"), "T05watch:c000;");
+        assert_eq!(client.send("z2,c000,1"), "OK");
+        assert_eq!(client.send("mc000,1"), "34");
+
+        assert_eq!(client.send("Z0,105,1"), "OK");
+        assert_eq!(client.send("c"), "S05");
+        assert_eq!(client.send("p5"), "0501");
+
+        assert_eq!(client.send("z0,105,1"), "OK");
+        assert_eq!(client.send("vMustReplyEmpty"), "");
+        assert_eq!(client.send("D"), "OK");
+
+        assert_eq!(server.join().unwrap(), 0x105);
+    }
+
+    #[test]
+    fn memory_packets_bypass_the_ppu() {
+        let mut session = Session::new(Device::from_rom(vec![0; 0x8000]).unwrap());
+
+        while session.device.video.mode != Mode::Draw {
+            session.device.process(1);
+        }
+
+        let reply = |session: &mut Session, command| match session.handle(command) {
+            Action::Reply(reply) => reply,
+            action => panic!("unexpected {action:?}"),
+        };
+
+        assert_eq!(reply(&mut session, "M8000,1:12"), "OK");
+        assert_eq!(reply(&mut session, "m8000,1"), "12");
+        assert_eq!(session.device.peek(0x8000), 0x12);
+    }
+}
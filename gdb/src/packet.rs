@@ -0,0 +1,167 @@
+//! Packet framing for the GDB remote serial protocol.
+//!
+//! Packets look like `$data#checksum`, where the checksum is the sum of the data bytes modulo 256
+//! in two hex digits. Each one is acknowledged with `+`, or `-` to ask for it again, until the
+//! client turns acknowledgements off. A lone `$03` byte outside a packet asks the target to stop.
+
+use std::{
+    io::{self, BufRead, BufReader, ErrorKind, Read, Write},
+    net::TcpStream,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Packet {
+    Command(String),
+    /// The client asked the target to stop (Ctrl-C).
+    Interrupt,
+}
+
+pub struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    /// Set once the client sends `QStartNoAckMode`.
+    pub no_ack: bool,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        Ok(Self {
+            writer: stream.try_clone()?,
+            reader: BufReader::new(stream),
+            no_ack: false,
+        })
+    }
+
+    /// Reads the next packet, or returns `None` once the client disconnects.
+    pub fn read_packet(&mut self) -> io::Result<Option<Packet>> {
+        loop {
+            let Some(byte) = self.read_byte()? else {
+                return Ok(None);
+            };
+
+            match byte {
+                0x03 => return Ok(Some(Packet::Interrupt)),
+                b'$' => {}
+                // Acknowledgements, and anything else between packets, are ignored.
+                _ => continue,
+            }
+
+            let mut data = Vec::new();
+
+            if self.reader.read_until(b'#', &mut data)? == 0 || data.pop() != Some(b'#') {
+                return Ok(None);
+            }
+
+            let mut checksum = [0; 2];
+            self.reader.read_exact(&mut checksum)?;
+
+            let expected = std::str::from_utf8(&checksum)
+                .ok()
+                .and_then(|c| u8::from_str_radix(c, 16).ok());
+
+            if expected != Some(checksum_of(&data)) && !self.no_ack {
+                self.writer.write_all(b"-")?;
+                continue;
+            }
+
+            if !self.no_ack {
+                self.writer.write_all(b"+")?;
+            }
+
+            return Ok(Some(Packet::Command(
+                String::from_utf8_lossy(&unescape(&data)).into_owned(),
+            )));
+        }
+    }
+
+    /// Returns `true` if the client has asked the target to stop, without blocking.
+    pub fn poll_interrupt(&mut self) -> io::Result<bool> {
+        if !self.reader.buffer().is_empty() {
+            return Ok(self.reader.buffer().contains(&0x03));
+        }
+
+        self.reader.get_ref().set_nonblocking(true)?;
+        let result = self.reader.fill_buf().map(|buffer| buffer.contains(&0x03));
+        self.reader.get_ref().set_nonblocking(false)?;
+
+        match result {
+            Ok(interrupted) => {
+                if interrupted {
+                    self.reader.consume(self.reader.buffer().len());
+                }
+
+                Ok(interrupted)
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn write_packet(&mut self, data: &str) -> io::Result<()> {
+        let data = escape(data.as_bytes());
+        let mut packet = Vec::with_capacity(data.len() + 4);
+
+        packet.push(b'$');
+        packet.extend(&data);
+        packet.extend(format!("#{:02x}", checksum_of(&data)).as_bytes());
+
+        self.writer.write_all(&packet)?;
+        self.writer.flush()
+    }
+
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        let mut byte = [0];
+
+        match self.reader.read(&mut byte)? {
+            0 => Ok(None),
+            _ => Ok(Some(byte[0])),
+        }
+    }
+}
+
+pub fn checksum_of(data: &[u8]) -> u8 {
+    data.iter().fold(0, |sum, &b| sum.wrapping_add(b))
+}
+
+/// Escapes the bytes that have a meaning in packets, as `}` followed by the byte XOR `$20`.
+fn escape(data: &[u8]) -> Vec<u8> {
+    let mut escaped = Vec::with_capacity(data.len());
+
+    for &byte in data {
+        if matches!(byte, b'#' | b'$' | b'}' | b'*') {
+            escaped.extend([b'}', byte ^ 0x20]);
+        } else {
+            escaped.push(byte);
+        }
+    }
+
+    escaped
+}
+
+fn unescape(data: &[u8]) -> Vec<u8> {
+    let mut unescaped = Vec::with_capacity(data.len());
+    let mut bytes = data.iter();
+
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            b'}' => unescaped.extend(bytes.next().map(|b| b ^ 0x20)),
+            _ => unescaped.push(byte),
+        }
+    }
+
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaping_round_trips() {
+        let data = b"a#b$c}d*e";
+
+        assert_eq!(escape(data), b"a}\x03b}\x04c}]d}\x0ae");
+        assert_eq!(unescape(&escape(data)), data);
+        assert_eq!(checksum_of(b"OK"), 0x9A);
+    }
+}
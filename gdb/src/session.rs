@@ -0,0 +1,323 @@
+//! The commands of the remote protocol, applied to a device and its debugger.
+
+use gb_asm::Pair;
+use gb_hardware::Device;
+use gb_interpreter::debugger::{Debugger, Stop, WatchKind};
+use std::collections::HashMap;
+
+/// The registers in the order GDB numbers them. Each one is 16 bits, sent little-endian.
+pub const REGISTERS: [&str; 6] = ["af", "bc", "de", "hl", "sp", "pc"];
+
+/// The description of the registers, sent to clients that ask for `target.xml`.
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.gb.sm83.core">
+    <reg name="af" bitsize="16" type="int" regnum="0"/>
+    <reg name="bc" bitsize="16" type="int"/>
+    <reg name="de" bitsize="16" type="int"/>
+    <reg name="hl" bitsize="16" type="int"/>
+    <reg name="sp" bitsize="16" type="data_ptr"/>
+    <reg name="pc" bitsize="16" type="code_ptr"/>
+  </feature>
+</target>
+"#;
+
+/// What the server should do after a command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Reply(String),
+    /// Run until something stops execution, then reply with the stop reason.
+    Continue,
+    /// Reply if there's something to say, then close the connection.
+    Close(Option<String>),
+}
+
+/// The kinds of breakpoints and watchpoints in `Z` and `z` packets.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum PointKind {
+    Software,
+    Hardware,
+    Write,
+    Read,
+    Access,
+}
+
+impl PointKind {
+    fn from_packet(kind: &str) -> Option<Self> {
+        let kind = match kind {
+            "0" => Self::Software,
+            "1" => Self::Hardware,
+            "2" => Self::Write,
+            "3" => Self::Read,
+            "4" => Self::Access,
+            _ => return None,
+        };
+
+        Some(kind)
+    }
+}
+
+pub struct Session {
+    pub device: Device,
+    pub debugger: Debugger,
+    /// The debugger IDs of the breakpoints and watchpoints the client has set.
+    points: HashMap<(PointKind, u16), u32>,
+}
+
+impl Session {
+    pub fn new(device: Device) -> Self {
+        Self {
+            device,
+            debugger: Debugger::new(),
+            points: HashMap::new(),
+        }
+    }
+
+    /// Handles one command. Commands that aren't supported are answered with an empty packet, as
+    /// the protocol expects.
+    pub fn handle(&mut self, command: &str) -> Action {
+        self.try_handle(command)
+            .unwrap_or_else(|| Action::Reply(String::new()))
+    }
+
+    /// Describes why execution stopped, as a stop reply packet.
+    pub fn stop_reply(&self, stop: Stop) -> String {
+        let hit = match stop {
+            Stop::Watchpoint(hit) => hit,
+            // SIGILL
+            Stop::Fault(_) | Stop::Locked => return "S04".into(),
+            _ => return "S05".into(),
+        };
+
+        let kind = self
+            .points
+            .iter()
+            .find_map(|(&(kind, _), &id)| (id == hit.id).then_some(kind));
+
+        let name = match kind {
+            Some(PointKind::Read) => "rwatch",
+            Some(PointKind::Access) => "awatch",
+            _ => "watch",
+        };
+
+        format!("T05{name}:{:04x};", hit.address)
+    }
+
+    fn try_handle(&mut self, command: &str) -> Option<Action> {
+        let ok = || Some(Action::Reply("OK".into()));
+
+        let (name, arguments) = command.split_at(command.len().min(1));
+
+        match name {
+            "?" => Some(Action::Reply("S05".into())),
+            "g" => Some(Action::Reply(
+                (0..REGISTERS.len())
+                    .map(|n| encode_word(self.register(n)))
+                    .collect(),
+            )),
+            "G" => {
+                let values = decode_hex(arguments)?;
+
+                for (n, value) in values.chunks_exact(2).take(REGISTERS.len()).enumerate() {
+                    self.set_register(n, u16::from_le_bytes([value[0], value[1]]));
+                }
+
+                ok()
+            }
+            "p" => {
+                let n = usize::from_str_radix(arguments, 16).ok()?;
+                let reply = if n < REGISTERS.len() {
+                    encode_word(self.register(n))
+                } else {
+                    "E00".into()
+                };
+
+                Some(Action::Reply(reply))
+            }
+            "P" => {
+                let (n, value) = arguments.split_once('=')?;
+                let n = usize::from_str_radix(n, 16).ok()?;
+                let value = decode_hex(value)?;
+
+                if n >= REGISTERS.len() || value.len() != 2 {
+                    return Some(Action::Reply("E00".into()));
+                }
+
+                self.set_register(n, u16::from_le_bytes([value[0], value[1]]));
+                ok()
+            }
+            "m" => {
+                let (address, length) = parse_range(arguments)?;
+                let bytes: String = (0..length)
+                    .map(|offset| format!("{:02x}", self.device.peek(address.wrapping_add(offset))))
+                    .collect();
+
+                Some(Action::Reply(bytes))
+            }
+            "M" => {
+                let (range, data) = arguments.split_once(':')?;
+                let (address, length) = parse_range(range)?;
+                let data = decode_hex(data)?;
+
+                if data.len() != length as usize {
+                    return Some(Action::Reply("E01".into()));
+                }
+
+                for (offset, byte) in data.into_iter().enumerate() {
+                    self.device.poke(address.wrapping_add(offset as u16), byte);
+                }
+
+                ok()
+            }
+            "Z" | "z" => self.update_point(name == "Z", arguments),
+            "s" => {
+                // Stepping from another address isn't supported, like continuing from one.
+                let stop = self.debugger.step_into(&mut self.device);
+                Some(Action::Reply(self.stop_reply(stop)))
+            }
+            "c" => Some(Action::Continue),
+            "H" => ok(),
+            "T" => ok(),
+            "D" => Some(Action::Close(Some("OK".into()))),
+            "k" => Some(Action::Close(None)),
+            "q" | "Q" => self.query(command),
+            _ => None,
+        }
+    }
+
+    fn query(&mut self, command: &str) -> Option<Action> {
+        let reply = match command {
+            "QStartNoAckMode" => "OK".into(),
+            "qAttached" => "1".into(),
+            "qC" => "QC1".into(),
+            "qfThreadInfo" => "m1".into(),
+            "qsThreadInfo" => "l".into(),
+            _ if command.starts_with("qSupported") => {
+                "PacketSize=4000;QStartNoAckMode+;qXfer:features:read+;swbreak+;hwbreak+".into()
+            }
+            _ => {
+                let request = command.strip_prefix("qXfer:features:read:target.xml:")?;
+                let (offset, length) = request.split_once(',')?;
+                let offset = usize::from_str_radix(offset, 16).ok()?;
+                let length = usize::from_str_radix(length, 16).ok()?;
+
+                let chunk = TARGET_XML.get(offset.min(TARGET_XML.len())..)?;
+                let chunk = &chunk[..length.min(chunk.len())];
+                let more = offset + chunk.len() < TARGET_XML.len();
+
+                format!("{}{chunk}", if more { 'm' } else { 'l' })
+            }
+        };
+
+        Some(Action::Reply(reply))
+    }
+
+    /// Handles `Z` (insert) and `z` (remove) packets, in the form `kind,address,length`.
+    fn update_point(&mut self, insert: bool, arguments: &str) -> Option<Action> {
+        let mut parts = arguments.splitn(3, ',');
+        let kind = PointKind::from_packet(parts.next()?)?;
+        let address = u16::from_str_radix(parts.next()?, 16).ok()?;
+        let length = u16::from_str_radix(parts.next()?.split(';').next()?, 16).ok()?;
+
+        let key = (kind, address);
+
+        if !insert {
+            if let Some(id) = self.points.remove(&key) {
+                match kind {
+                    PointKind::Software | PointKind::Hardware => {
+                        self.debugger.remove_breakpoint(id);
+                    }
+                    _ => {
+                        self.debugger.remove_watchpoint(id);
+                    }
+                }
+            }
+
+            return Some(Action::Reply("OK".into()));
+        }
+
+        if self.points.contains_key(&key) {
+            return Some(Action::Reply("OK".into()));
+        }
+
+        let last = address.saturating_add(length.max(1) - 1);
+
+        let id = match kind {
+            // Breakpoints are kept by the debugger rather than patched into memory, so both kinds
+            // work the same way.
+            PointKind::Software | PointKind::Hardware => {
+                self.debugger.add_breakpoint(address, None)
+            }
+            PointKind::Write => self
+                .debugger
+                .add_watchpoint(address..=last, WatchKind::Write),
+            PointKind::Read => self
+                .debugger
+                .add_watchpoint(address..=last, WatchKind::Read),
+            PointKind::Access => self
+                .debugger
+                .add_watchpoint(address..=last, WatchKind::Access),
+        };
+
+        self.points.insert(key, id);
+        Some(Action::Reply("OK".into()))
+    }
+
+    fn register(&self, n: usize) -> u16 {
+        let cpu = &self.device.cpu;
+
+        match n {
+            0 => u16::from_le_bytes([cpu.flags, cpu.a]),
+            1 => cpu.get(Pair::BC),
+            2 => cpu.get(Pair::DE),
+            3 => cpu.get(Pair::HL),
+            4 => cpu.stack_pointer,
+            _ => cpu.program_counter,
+        }
+    }
+
+    fn set_register(&mut self, n: usize, value: u16) {
+        let cpu = &mut self.device.cpu;
+
+        match n {
+            0 => {
+                let [flags, a] = value.to_le_bytes();
+                cpu.a = a;
+                // The lower four bits of F don't exist.
+                cpu.flags = flags & 0xF0;
+            }
+            1 => cpu.set(Pair::BC, value),
+            2 => cpu.set(Pair::DE, value),
+            3 => cpu.set(Pair::HL, value),
+            4 => cpu.stack_pointer = value,
+            _ => cpu.program_counter = value,
+        }
+    }
+}
+
+fn encode_word(value: u16) -> String {
+    let [low, high] = value.to_le_bytes();
+    format!("{low:02x}{high:02x}")
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Parses `address,length`.
+fn parse_range(range: &str) -> Option<(u16, u16)> {
+    let (address, length) = range.split_once(',')?;
+
+    Some((
+        u16::from_str_radix(address, 16).ok()?,
+        u16::from_str_radix(length, 16).ok()?,
+    ))
+}
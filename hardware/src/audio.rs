@@ -0,0 +1,38 @@
+//! The audio registers. No sound is produced, so they only hold what was written to them.
+
+const REGISTERS_START: usize = 0xFF10;
+const REGISTERS_END: usize = 0xFF3F;
+
+#[derive(Debug, Clone)]
+pub struct Audio {
+    /// NR10 to NR52 and wave RAM, from `$FF10` to `$FF3F`.
+    registers: Vec<u8>,
+}
+
+impl Default for Audio {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Audio {
+    pub fn new() -> Self {
+        Self {
+            registers: vec![0; REGISTERS_END - REGISTERS_START + 1],
+        }
+    }
+
+    pub fn read(&self, address: usize) -> u8 {
+        match address {
+            REGISTERS_START..=REGISTERS_END => self.registers[address - REGISTERS_START],
+            // PCM12 and PCM34 report the output of each channel, which is always silent.
+            _ => 0x00,
+        }
+    }
+
+    pub fn write(&mut self, address: usize, value: u8) {
+        if let REGISTERS_START..=REGISTERS_END = address {
+            self.registers[address - REGISTERS_START] = value;
+        }
+    }
+}
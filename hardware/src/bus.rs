@@ -0,0 +1,299 @@
+//! The interface between the CPU and the rest of a system.
+//!
+//! The interpreter only ever talks to memory and peripherals through [`Bus`], and to the CPU
+//! registers through [`CpuState`], so it can run against a [`Device`](crate::Device), a
+//! [`FlatBus`], or any other implementation.
+
+use crate::{
+    cpu::Cpu,
+    util::{bytes_to_word, word_to_bytes},
+    DeviceMode, InterruptController,
+};
+use gb_asm::symbols::Banks;
+use std::cell::RefCell;
+
+/// What the CPU did with a 16-bit register in the M-cycle its increment/decrement unit updated
+/// it. The register's value is on the address bus during that cycle.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RegisterAccess {
+    /// The register was only incremented or decremented, as with `INC rr`.
+    Idle,
+    Read,
+    Write,
+}
+
+/// Memory and peripherals, as seen by the CPU.
+pub trait Bus {
+    fn read_byte(&self, address: u16) -> u8;
+    fn write_byte(&mut self, address: u16, value: u8);
+
+    /// Advances everything other than the CPU by `cycles` M-cycles.
+    fn tick(&mut self, cycles: u8);
+
+    /// The interrupt controller, which also holds the CPU's IME flag.
+    fn interrupts(&self) -> &InterruptController;
+    fn interrupts_mut(&mut self) -> &mut InterruptController;
+
+    /// Called when the CPU increments or decrements a 16-bit register holding `address`, which
+    /// some hardware glitches depend on. `cycle` is the M-cycle of the instruction it happens in,
+    /// counting the opcode fetch as 0, since the bus is only ticked once the instruction is done.
+    /// Does nothing by default.
+    fn register_update(&mut self, _address: u16, _access: RegisterAccess, _cycle: u8) {}
+
+    /// Reads `address` without side effects or access restrictions, for debugging tools. This is
+    /// the same as [`Bus::read_byte`] by default.
+    fn peek_byte(&self, address: u16) -> u8 {
+        self.read_byte(address)
+    }
+
+    /// The banks mapped to the switchable areas of memory, if there's a cartridge behind the bus.
+    fn banks(&self) -> Option<Banks> {
+        None
+    }
+
+    /// Whether the bus keeps a code/data log of how the CPU uses ROM. Nothing is logged by
+    /// default.
+    fn logs_coverage(&self) -> bool {
+        false
+    }
+
+    /// The offset into ROM of the byte currently mapped at `address`, if it's in ROM.
+    fn rom_offset(&self, _address: u16) -> Option<usize> {
+        None
+    }
+
+    /// Adds `flags` from [`gb_asm::cdl`] to the log entry of the ROM byte at `offset`.
+    fn log_coverage(&mut self, _offset: usize, _flags: u8) {}
+
+    fn read_word(&self, address: u16) -> u16 {
+        let low = self.read_byte(address);
+        let high = self.read_byte(address.wrapping_add(1));
+
+        bytes_to_word(high, low)
+    }
+
+    fn write_word(&mut self, address: u16, value: u16) {
+        let [low, high] = word_to_bytes(value);
+
+        self.write_byte(address, low);
+        self.write_byte(address.wrapping_add(1), high)
+    }
+}
+
+/// Access to the registers of the CPU.
+pub trait CpuState {
+    fn cpu(&self) -> &Cpu;
+    fn cpu_mut(&mut self) -> &mut Cpu;
+}
+
+/// A CPU attached to a bus, which is everything the interpreter needs to run.
+pub trait System: Bus + CpuState {
+    /// Pushes `value`, starting with SP being decremented in M-cycle `cycle` of the instruction.
+    fn stack_push(&mut self, value: u16, cycle: u8) {
+        let [low, high] = word_to_bytes(value);
+
+        // SP is decremented once on its own, and once more while the high byte is written.
+        self.register_update(self.cpu().stack_pointer, RegisterAccess::Idle, cycle);
+        let stack_pointer = self.cpu().stack_pointer.wrapping_sub(1);
+
+        self.register_update(stack_pointer, RegisterAccess::Write, cycle + 1);
+        self.write_byte(stack_pointer, high);
+
+        let stack_pointer = stack_pointer.wrapping_sub(1);
+        self.write_byte(stack_pointer, low);
+
+        self.cpu_mut().stack_pointer = stack_pointer;
+    }
+
+    /// Pops a value, reading its low byte in M-cycle `cycle` of the instruction.
+    fn stack_pop(&mut self, cycle: u8) -> u16 {
+        let stack_pointer = self.cpu().stack_pointer;
+        self.register_update(stack_pointer, RegisterAccess::Read, cycle);
+        let low = self.read_byte(stack_pointer);
+
+        let stack_pointer = stack_pointer.wrapping_add(1);
+        self.register_update(stack_pointer, RegisterAccess::Read, cycle + 1);
+        let high = self.read_byte(stack_pointer);
+
+        self.cpu_mut().stack_pointer = stack_pointer.wrapping_add(1);
+
+        bytes_to_word(high, low)
+    }
+}
+
+impl<T> System for T where T: Bus + CpuState + ?Sized {}
+
+/// A read or write made through a [`FlatBus`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MemoryAccess {
+    Read { address: u16, value: u8 },
+    Write { address: u16, value: u8 },
+}
+
+/// A CPU attached to a flat, writable 64 KiB address space with no peripherals behind it, which is
+/// the environment CPU test vectors expect.
+#[derive(Debug, Clone)]
+pub struct FlatBus {
+    pub cpu: Cpu,
+    pub memory: Vec<u8>,
+    /// Only reachable through [`Bus::interrupts`], since IF and IE are plain memory on this bus.
+    pub interrupts: InterruptController,
+    /// The total number of M-cycles ticked.
+    pub cycles: u64,
+    /// The reads and writes since the last [`FlatBus::take_accesses()`], in order, once
+    /// [`FlatBus::record_accesses()`] has been called. Each takes an M-cycle on hardware. Reads only
+    /// borrow the bus, so this needs to be a `RefCell`.
+    accesses: Option<RefCell<Vec<MemoryAccess>>>,
+}
+
+impl Default for FlatBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlatBus {
+    pub fn new() -> Self {
+        Self {
+            cpu: Cpu::new(DeviceMode::Classic),
+            memory: vec![0; 0x10000],
+            interrupts: InterruptController::new(),
+            cycles: 0,
+            accesses: None,
+        }
+    }
+
+    /// Starts recording every read and write made through [`Bus`]. Nothing is recorded otherwise,
+    /// so a bus that runs for long doesn't keep growing.
+    pub fn record_accesses(&mut self) {
+        self.accesses.get_or_insert_default();
+    }
+
+    /// Returns the accesses recorded since the last call, and starts over.
+    pub fn take_accesses(&self) -> Vec<MemoryAccess> {
+        self.accesses
+            .as_ref()
+            .map(RefCell::take)
+            .unwrap_or_default()
+    }
+}
+
+impl Bus for FlatBus {
+    fn read_byte(&self, address: u16) -> u8 {
+        let value = self.memory[address as usize];
+
+        if let Some(accesses) = &self.accesses {
+            accesses
+                .borrow_mut()
+                .push(MemoryAccess::Read { address, value });
+        }
+
+        value
+    }
+
+    fn write_byte(&mut self, address: u16, value: u8) {
+        self.memory[address as usize] = value;
+
+        if let Some(accesses) = &mut self.accesses {
+            accesses
+                .get_mut()
+                .push(MemoryAccess::Write { address, value });
+        }
+    }
+
+    fn tick(&mut self, cycles: u8) {
+        self.cycles += cycles as u64;
+    }
+
+    fn interrupts(&self) -> &InterruptController {
+        &self.interrupts
+    }
+
+    fn interrupts_mut(&mut self) -> &mut InterruptController {
+        &mut self.interrupts
+    }
+
+    fn peek_byte(&self, address: u16) -> u8 {
+        self.memory[address as usize]
+    }
+}
+
+impl CpuState for FlatBus {
+    fn cpu(&self) -> &Cpu {
+        &self.cpu
+    }
+
+    fn cpu_mut(&mut self) -> &mut Cpu {
+        &mut self.cpu
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stack_round_trip() {
+        let mut bus = FlatBus::new();
+        bus.record_accesses();
+        bus.cpu.stack_pointer = 0xD000;
+
+        bus.stack_push(0x1234, 1);
+
+        assert_eq!(bus.cpu.stack_pointer, 0xCFFE);
+        assert_eq!(bus.read_word(0xCFFE), 0x1234);
+        assert_eq!(bus.stack_pop(1), 0x1234);
+        assert_eq!(bus.cpu.stack_pointer, 0xD000);
+
+        assert_eq!(
+            bus.take_accesses(),
+            [
+                MemoryAccess::Write {
+                    address: 0xCFFF,
+                    value: 0x12
+                },
+                MemoryAccess::Write {
+                    address: 0xCFFE,
+                    value: 0x34
+                },
+                MemoryAccess::Read {
+                    address: 0xCFFE,
+                    value: 0x34
+                },
+                MemoryAccess::Read {
+                    address: 0xCFFF,
+                    value: 0x12
+                },
+                MemoryAccess::Read {
+                    address: 0xCFFE,
+                    value: 0x34
+                },
+                MemoryAccess::Read {
+                    address: 0xCFFF,
+                    value: 0x12
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn records_accesses_only_when_asked() {
+        let mut bus = FlatBus::new();
+        bus.write_byte(0xC000, 0x12);
+        bus.read_byte(0xC000);
+
+        assert!(bus.take_accesses().is_empty());
+
+        bus.record_accesses();
+        bus.read_byte(0xC000);
+
+        assert_eq!(
+            bus.take_accesses(),
+            [MemoryAccess::Read {
+                address: 0xC000,
+                value: 0x12
+            }]
+        );
+        assert!(bus.take_accesses().is_empty());
+    }
+}
@@ -4,6 +4,19 @@ use crate::{
 };
 use gb_asm::{Flag, Pair, Register};
 
+/// Whether the CPU is executing instructions.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum State {
+    #[default]
+    Running,
+    /// The CPU executed `HALT`, and waits for an enabled interrupt to be requested before it
+    /// fetches the next instruction.
+    Halted,
+    /// The CPU executed this illegal opcode and hung. It no longer fetches instructions or
+    /// handles interrupts until it's reset, though the rest of the system keeps running.
+    Locked(u8),
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct Cpu {
     pub a: u8,
@@ -17,7 +30,7 @@ pub struct Cpu {
     pub stack_pointer: u16,
     pub program_counter: u16,
     pub cycle_counter: u16,
-    pub interrupts_enabled: bool,
+    pub state: State,
 }
 
 impl Cpu {
@@ -27,7 +40,6 @@ impl Cpu {
     {
         let mut cpu = Self {
             a: 0x11,
-            interrupts_enabled: true,
             stack_pointer: 0xFFFE,
             program_counter: 0x100,
             ..Default::default()
@@ -0,0 +1,171 @@
+//! The interrupt controller, which owns the IF and IE registers and the CPU's interrupt master
+//! enable flag (IME).
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Interrupt {
+    VerticalBlank,
+    Stat,
+    Timer,
+    Serial,
+    Joypad,
+}
+
+impl Interrupt {
+    /// Every interrupt, from the highest priority to the lowest.
+    pub const ALL: [Self; 5] = [
+        Self::VerticalBlank,
+        Self::Stat,
+        Self::Timer,
+        Self::Serial,
+        Self::Joypad,
+    ];
+
+    pub fn get_mask(&self) -> u8 {
+        match self {
+            Self::VerticalBlank => 0b0000_0001,
+            Self::Stat => 0b0000_0010,
+            Self::Timer => 0b0000_0100,
+            Self::Serial => 0b0000_1000,
+            Self::Joypad => 0b0001_0000,
+        }
+    }
+
+    pub fn get_address(&self) -> u16 {
+        match self {
+            Self::VerticalBlank => 0x40,
+            Self::Stat => 0x48,
+            Self::Timer => 0x50,
+            Self::Serial => 0x58,
+            Self::Joypad => 0x60,
+        }
+    }
+}
+
+/// Only the lower five bits of IF are backed by anything. The rest always read as 1.
+const FLAGS_MASK: u8 = 0b0001_1111;
+
+#[derive(Debug, Clone, Default)]
+pub struct InterruptController {
+    /// The IF register (`$FF0F`), holding an interrupt's bit for as long as it's requested.
+    pub requested: u8,
+    /// The IE register (`$FFFF`). All eight bits can be written and read back, but only the
+    /// lower five select interrupts.
+    pub enabled: u8,
+    /// IME, which decides whether an enabled and requested interrupt is dispatched.
+    pub master_enable: bool,
+    /// The number of instructions left to execute, including the current one, before IME is set
+    /// by an earlier `EI`.
+    enable_delay: u8,
+}
+
+impl InterruptController {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn request(&mut self, interrupt: Interrupt) {
+        self.requested |= interrupt.get_mask();
+    }
+
+    /// Clears the request for `interrupt`, as happens when it's dispatched.
+    pub fn acknowledge(&mut self, interrupt: Interrupt) {
+        self.requested &= !interrupt.get_mask();
+    }
+
+    /// Returns the highest priority interrupt that is both requested and enabled, regardless of
+    /// IME.
+    pub fn pending(&self) -> Option<Interrupt> {
+        let pending = self.requested & self.enabled;
+
+        Interrupt::ALL
+            .into_iter()
+            .find(|interrupt| pending & interrupt.get_mask() != 0)
+    }
+
+    /// Returns `true` if an interrupt should be dispatched before the next instruction.
+    pub fn should_dispatch(&self) -> bool {
+        self.master_enable && self.pending().is_some()
+    }
+
+    pub fn read_flags(&self) -> u8 {
+        self.requested | !FLAGS_MASK
+    }
+
+    pub fn write_flags(&mut self, value: u8) {
+        self.requested = value & FLAGS_MASK;
+    }
+
+    /// Sets IME once the instruction after the current one has executed, as `EI` does.
+    pub fn schedule_enable(&mut self) {
+        if !self.master_enable && self.enable_delay == 0 {
+            self.enable_delay = 2;
+        }
+    }
+
+    /// Sets IME immediately, as `RETI` does.
+    pub fn enable(&mut self) {
+        self.master_enable = true;
+        self.enable_delay = 0;
+    }
+
+    /// Clears IME, cancelling an enable scheduled by `EI`.
+    pub fn disable(&mut self) {
+        self.master_enable = false;
+        self.enable_delay = 0;
+    }
+
+    /// Applies a scheduled enable. This is called once at the end of every instruction.
+    pub fn end_instruction(&mut self) {
+        if self.enable_delay > 0 {
+            self.enable_delay -= 1;
+            self.master_enable = self.enable_delay == 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn priority_and_flags() {
+        let mut interrupts = InterruptController::new();
+        interrupts.request(Interrupt::Joypad);
+        interrupts.request(Interrupt::Timer);
+
+        assert_eq!(interrupts.pending(), None);
+        assert_eq!(interrupts.read_flags(), 0b1111_0100);
+
+        interrupts.enabled = 0xFF;
+        assert_eq!(interrupts.pending(), Some(Interrupt::Timer));
+
+        interrupts.acknowledge(Interrupt::Timer);
+        assert_eq!(interrupts.pending(), Some(Interrupt::Joypad));
+
+        interrupts.write_flags(0xFF);
+        assert_eq!(interrupts.pending(), Some(Interrupt::VerticalBlank));
+        assert_eq!(interrupts.requested, 0x1F);
+    }
+
+    #[test]
+    fn enable_is_delayed_by_one_instruction() {
+        let mut interrupts = InterruptController::new();
+
+        // EI
+        interrupts.schedule_enable();
+        interrupts.end_instruction();
+        assert!(!interrupts.master_enable);
+
+        // The instruction after EI.
+        interrupts.end_instruction();
+        assert!(interrupts.master_enable);
+
+        // DI right after EI cancels it.
+        interrupts.disable();
+        interrupts.schedule_enable();
+        interrupts.end_instruction();
+        interrupts.disable();
+        interrupts.end_instruction();
+        assert!(!interrupts.master_enable);
+    }
+}
@@ -0,0 +1,245 @@
+//! The map of I/O registers between `$FF00` and `$FF7F`.
+//!
+//! Every register declares the component that owns it, which bits can be read and written, and the
+//! models it exists on. [`Device`](crate::Device) uses the map to route accesses: bits outside the
+//! read mask read as 1, bits outside the write mask keep their value, and registers that don't
+//! exist on the current model (or at all) read as `$FF` and ignore writes.
+
+use crate::{memory::map::IO_START, DeviceMode};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Component {
+    Joypad,
+    Serial,
+    Timer,
+    Interrupts,
+    Audio,
+    Video,
+    Dma,
+    Memory,
+    /// Registers that control the system as a whole, such as the CGB speed switch.
+    System,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Availability {
+    All,
+    ColorOnly,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct IoRegister {
+    pub address: usize,
+    pub name: &'static str,
+    pub component: Component,
+    pub read_mask: u8,
+    pub write_mask: u8,
+    pub availability: Availability,
+}
+
+impl IoRegister {
+    pub fn is_available(&self, mode: DeviceMode) -> bool {
+        match self.availability {
+            Availability::All => true,
+            Availability::ColorOnly => mode == DeviceMode::Color,
+        }
+    }
+}
+
+macro_rules! io_registers {
+    ($($address:literal $name:literal $component:ident $read:literal $write:literal $availability:ident,)*) => {
+        /// Every I/O register, in address order.
+        pub static REGISTERS: &[IoRegister] = &[
+            $(IoRegister {
+                address: $address,
+                name: $name,
+                component: Component::$component,
+                read_mask: $read,
+                write_mask: $write,
+                availability: Availability::$availability,
+            },)*
+        ];
+    };
+}
+
+io_registers! {
+    0xFF00 "P1"    Joypad     0x3F 0x30 All,
+    0xFF01 "SB"    Serial     0xFF 0xFF All,
+    0xFF02 "SC"    Serial     0x81 0x81 All,
+    0xFF04 "DIV"   Timer      0xFF 0xFF All,
+    0xFF05 "TIMA"  Timer      0xFF 0xFF All,
+    0xFF06 "TMA"   Timer      0xFF 0xFF All,
+    0xFF07 "TAC"   Timer      0x07 0x07 All,
+    0xFF0F "IF"    Interrupts 0x1F 0x1F All,
+    0xFF10 "NR10"  Audio      0x7F 0x7F All,
+    0xFF11 "NR11"  Audio      0xC0 0xFF All,
+    0xFF12 "NR12"  Audio      0xFF 0xFF All,
+    0xFF13 "NR13"  Audio      0x00 0xFF All,
+    0xFF14 "NR14"  Audio      0x40 0xC7 All,
+    0xFF16 "NR21"  Audio      0xC0 0xFF All,
+    0xFF17 "NR22"  Audio      0xFF 0xFF All,
+    0xFF18 "NR23"  Audio      0x00 0xFF All,
+    0xFF19 "NR24"  Audio      0x40 0xC7 All,
+    0xFF1A "NR30"  Audio      0x80 0x80 All,
+    0xFF1B "NR31"  Audio      0x00 0xFF All,
+    0xFF1C "NR32"  Audio      0x60 0x60 All,
+    0xFF1D "NR33"  Audio      0x00 0xFF All,
+    0xFF1E "NR34"  Audio      0x40 0xC7 All,
+    0xFF20 "NR41"  Audio      0x00 0x3F All,
+    0xFF21 "NR42"  Audio      0xFF 0xFF All,
+    0xFF22 "NR43"  Audio      0xFF 0xFF All,
+    0xFF23 "NR44"  Audio      0x40 0xC0 All,
+    0xFF24 "NR50"  Audio      0xFF 0xFF All,
+    0xFF25 "NR51"  Audio      0xFF 0xFF All,
+    0xFF26 "NR52"  Audio      0x8F 0x80 All,
+    0xFF30 "WAVE0" Audio      0xFF 0xFF All,
+    0xFF31 "WAVE1" Audio      0xFF 0xFF All,
+    0xFF32 "WAVE2" Audio      0xFF 0xFF All,
+    0xFF33 "WAVE3" Audio      0xFF 0xFF All,
+    0xFF34 "WAVE4" Audio      0xFF 0xFF All,
+    0xFF35 "WAVE5" Audio      0xFF 0xFF All,
+    0xFF36 "WAVE6" Audio      0xFF 0xFF All,
+    0xFF37 "WAVE7" Audio      0xFF 0xFF All,
+    0xFF38 "WAVE8" Audio      0xFF 0xFF All,
+    0xFF39 "WAVE9" Audio      0xFF 0xFF All,
+    0xFF3A "WAVEA" Audio      0xFF 0xFF All,
+    0xFF3B "WAVEB" Audio      0xFF 0xFF All,
+    0xFF3C "WAVEC" Audio      0xFF 0xFF All,
+    0xFF3D "WAVED" Audio      0xFF 0xFF All,
+    0xFF3E "WAVEE" Audio      0xFF 0xFF All,
+    0xFF3F "WAVEF" Audio      0xFF 0xFF All,
+    0xFF40 "LCDC"  Video      0xFF 0xFF All,
+    0xFF41 "STAT"  Video      0x7F 0x78 All,
+    0xFF42 "SCY"   Video      0xFF 0xFF All,
+    0xFF43 "SCX"   Video      0xFF 0xFF All,
+    0xFF44 "LY"    Video      0xFF 0x00 All,
+    0xFF45 "LYC"   Video      0xFF 0xFF All,
+    0xFF46 "DMA"   Dma        0xFF 0xFF All,
+    0xFF47 "BGP"   Video      0xFF 0xFF All,
+    0xFF48 "OBP0"  Video      0xFF 0xFF All,
+    0xFF49 "OBP1"  Video      0xFF 0xFF All,
+    0xFF4A "WY"    Video      0xFF 0xFF All,
+    0xFF4B "WX"    Video      0xFF 0xFF All,
+    0xFF4D "KEY1"  System     0x81 0x01 ColorOnly,
+    0xFF4F "VBK"   Video      0x01 0x01 ColorOnly,
+    0xFF50 "BANK"  System     0x00 0x01 All,
+    0xFF51 "HDMA1" Video      0x00 0xFF ColorOnly,
+    0xFF52 "HDMA2" Video      0x00 0xF0 ColorOnly,
+    0xFF53 "HDMA3" Video      0x00 0x1F ColorOnly,
+    0xFF54 "HDMA4" Video      0x00 0xF0 ColorOnly,
+    0xFF55 "HDMA5" Video      0xFF 0xFF ColorOnly,
+    0xFF56 "RP"    System     0xC3 0xC1 ColorOnly,
+    0xFF68 "BCPS"  Video      0xBF 0xBF ColorOnly,
+    0xFF69 "BCPD"  Video      0xFF 0xFF ColorOnly,
+    0xFF6A "OCPS"  Video      0xBF 0xBF ColorOnly,
+    0xFF6B "OCPD"  Video      0xFF 0xFF ColorOnly,
+    0xFF6C "OPRI"  Video      0x01 0x01 ColorOnly,
+    0xFF70 "SVBK"  Memory     0x07 0x07 ColorOnly,
+    0xFF72 "FF72"  System     0xFF 0xFF ColorOnly,
+    0xFF73 "FF73"  System     0xFF 0xFF ColorOnly,
+    0xFF74 "FF74"  System     0xFF 0xFF ColorOnly,
+    0xFF75 "FF75"  System     0x70 0x70 ColorOnly,
+    0xFF76 "PCM12" Audio      0xFF 0x00 ColorOnly,
+    0xFF77 "PCM34" Audio      0xFF 0x00 ColorOnly,
+}
+
+/// The registers indexed by their offset from `$FF00`, so that lookups don't need a search.
+static MAP: [Option<IoRegister>; 0x80] = {
+    let mut map = [None; 0x80];
+    let mut i = 0;
+
+    while i < REGISTERS.len() {
+        map[REGISTERS[i].address - IO_START] = Some(REGISTERS[i]);
+        i += 1;
+    }
+
+    map
+};
+
+/// Returns the register at `address`, if there is one.
+pub fn lookup(address: usize) -> Option<&'static IoRegister> {
+    MAP.get(address.checked_sub(IO_START)?)?.as_ref()
+}
+
+/// The registers of the [`Component::System`] component.
+#[derive(Debug, Clone, Default)]
+pub struct SystemRegisters {
+    /// KEY1, which prepares a CPU speed switch. Speed switching isn't emulated, so the current
+    /// speed bit always reads as normal speed.
+    pub speed_switch: u8,
+    /// RP, the infrared port. Nothing is ever received.
+    pub infrared: u8,
+    /// `$FF72` to `$FF75`, which have no known purpose but can be read and written on the CGB.
+    pub undocumented: [u8; 4],
+}
+
+impl SystemRegisters {
+    pub fn read(&self, address: usize) -> u8 {
+        match address {
+            0xFF4D => self.speed_switch,
+            0xFF56 => self.infrared,
+            0xFF72..=0xFF75 => self.undocumented[address - 0xFF72],
+            // The boot ROM is never mapped, so BANK is write-only as far as anyone can tell.
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, address: usize, value: u8) {
+        match address {
+            0xFF4D => self.speed_switch = value,
+            0xFF56 => self.infrared = value,
+            0xFF72..=0xFF75 => self.undocumented[address - 0xFF72] = value,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bus::Bus, Device};
+
+    #[test]
+    fn map_is_consistent() {
+        for pair in REGISTERS.windows(2) {
+            assert!(
+                pair[0].address < pair[1].address,
+                "{} is out of order",
+                pair[1].name
+            );
+        }
+
+        for register in REGISTERS {
+            assert_eq!(lookup(register.address).unwrap().name, register.name);
+        }
+
+        assert!(lookup(0xFF03).is_none());
+        assert!(lookup(0xFF80).is_none());
+    }
+
+    #[test]
+    fn device_applies_masks_and_availability() {
+        // A DMG cartridge, so CGB registers don't exist.
+        let mut device = Device::from_rom(vec![0; 0x8000]).unwrap();
+
+        // Unmapped registers, and CGB registers on the DMG, are open bus.
+        assert_eq!(device.read_byte(0xFF03), 0xFF);
+        device.write_byte(0xFF4F, 0x01);
+        assert_eq!(device.read_byte(0xFF4F), 0xFF);
+        assert_eq!(device.video.vram.bank(), 0);
+
+        // Unused bits read as 1.
+        device.write_byte(0xFF07, 0x00);
+        assert_eq!(device.read_byte(0xFF07), 0xF8);
+
+        // Write-only registers don't read back.
+        device.write_byte(0xFF13, 0x12);
+        assert_eq!(device.read_byte(0xFF13), 0xFF);
+        assert_eq!(device.audio.read(0xFF13), 0x12);
+
+        // Read-only bits keep their value.
+        let status = device.read_byte(0xFF41);
+        device.write_byte(0xFF41, 0x00);
+        assert_eq!(device.read_byte(0xFF41), 0x80 | (status & 0x07));
+    }
+}
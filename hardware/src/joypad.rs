@@ -0,0 +1,131 @@
+//! The joypad, read through P1 (`$FF00`).
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Button {
+    Right,
+    Left,
+    Up,
+    Down,
+    A,
+    B,
+    Select,
+    Start,
+}
+
+impl Button {
+    pub const ALL: [Self; 8] = [
+        Self::Right,
+        Self::Left,
+        Self::Up,
+        Self::Down,
+        Self::A,
+        Self::B,
+        Self::Select,
+        Self::Start,
+    ];
+
+    /// Returns the button's bit in [`Joypad::pressed`].
+    fn mask(&self) -> u8 {
+        match self {
+            Self::Right => 0x01,
+            Self::Left => 0x02,
+            Self::Up => 0x04,
+            Self::Down => 0x08,
+            Self::A => 0x10,
+            Self::B => 0x20,
+            Self::Select => 0x40,
+            Self::Start => 0x80,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Joypad {
+    /// Bits 4 and 5 of P1, which select the direction keys and the buttons respectively when
+    /// they're low.
+    select: u8,
+    /// The keys held down, with the direction keys in the lower nibble and the buttons in the
+    /// upper one.
+    pressed: u8,
+}
+
+impl Joypad {
+    pub fn new() -> Self {
+        Self {
+            select: 0x30,
+            pressed: 0,
+        }
+    }
+
+    /// Reads P1. The lower nibble has a low bit for each pressed key in the selected groups.
+    pub fn read(&self) -> u8 {
+        self.select | (!self.selected_keys() & 0x0F)
+    }
+
+    pub fn write(&mut self, value: u8) {
+        self.select = value & 0x30;
+    }
+
+    pub fn is_pressed(&self, button: Button) -> bool {
+        self.pressed & button.mask() != 0
+    }
+
+    /// Presses or releases `button`, returning `true` if this pulled one of P1's input lines low,
+    /// which requests the joypad interrupt.
+    pub fn set_pressed(&mut self, button: Button, pressed: bool) -> bool {
+        let before = self.selected_keys();
+
+        if pressed {
+            self.pressed |= button.mask();
+        } else {
+            self.pressed &= !button.mask();
+        }
+
+        self.selected_keys() & !before != 0
+    }
+
+    /// Returns the pressed keys of the selected groups as the bits of P1's lower nibble, set for
+    /// pressed keys.
+    fn selected_keys(&self) -> u8 {
+        let mut keys = 0;
+
+        if self.select & 0x10 == 0 {
+            keys |= self.pressed & 0x0F;
+        }
+
+        if self.select & 0x20 == 0 {
+            keys |= self.pressed >> 4;
+        }
+
+        keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_selected_groups() {
+        let mut joypad = Joypad::new();
+
+        // Nothing is selected, so pressing a key doesn't pull a line low.
+        assert!(!joypad.set_pressed(Button::A, true));
+        assert_eq!(joypad.read(), 0x3F);
+
+        joypad.write(0x10);
+        assert_eq!(joypad.read(), 0x1E);
+
+        assert!(joypad.set_pressed(Button::Start, true));
+        assert_eq!(joypad.read(), 0x16);
+
+        joypad.write(0x20);
+        assert_eq!(joypad.read(), 0x2F);
+        assert!(joypad.set_pressed(Button::Down, true));
+        assert_eq!(joypad.read(), 0x27);
+
+        joypad.set_pressed(Button::Down, false);
+        assert!(!joypad.is_pressed(Button::Down));
+        assert!(joypad.is_pressed(Button::A));
+    }
+}
@@ -1,56 +1,70 @@
+use audio::Audio;
+use bus::{Bus, CpuState, RegisterAccess};
 use cpu::Cpu;
-use memory::{map::*, Memory, MemoryError};
-use std::{collections::HashSet, fs::File, io::Read, path::Path};
-use util::{bytes_to_word, word_to_bytes};
-use video::{Video, REGISTER_LCD_STATUS, REGISTER_LCD_Y_COMPARE, REGISTER_LCD_Y_COORD};
-
+use gb_asm::{
+    cdl::{self, CodeDataLog},
+    symbols::Banks,
+};
+pub use interrupt::{Interrupt, InterruptController};
+use io::{Component, IoRegister, SystemRegisters};
+use joypad::{Button, Joypad};
+use memory::{
+    map::*,
+    oam_bug::{self, Corruption},
+    Memory, MemoryError,
+};
+use serial::Serial;
+use sgb::SuperGameBoy;
+use std::{fs::File, io::Read, path::Path};
+use timer::Timer;
+use video::Video;
+
+pub mod audio;
+pub mod bus;
 pub mod cpu;
+pub mod interrupt;
+pub mod io;
+pub mod joypad;
 pub mod memory;
+pub mod serial;
+pub mod sgb;
+pub mod timer;
 pub mod util;
 pub mod video;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum DeviceMode {
     Classic,
     Color,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum Interrupt {
-    VerticalBlank,
-    Stat,
-    Timer,
-    Serial,
-    Joypad,
-}
-
-impl Interrupt {
-    pub fn get_mask(&self) -> u8 {
-        match self {
-            Self::VerticalBlank => 0b0000_0001,
-            Self::Stat => 0b0000_0010,
-            Self::Timer => 0b0000_0100,
-            Self::Serial => 0b0000_1000,
-            Self::Joypad => 0b0001_0000,
-        }
-    }
-
-    pub fn get_address(&self) -> u16 {
-        match self {
-            Self::VerticalBlank => 0x40,
-            Self::Stat => 0x48,
-            Self::Timer => 0x50,
-            Self::Serial => 0x58,
-            Self::Joypad => 0x60,
-        }
-    }
+/// Who is accessing memory.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Access {
+    Cpu,
+    /// DMA and debuggers, which can reach VRAM and OAM at any time.
+    Privileged,
 }
 
+/// A whole Game Boy. Cloning it takes a snapshot of its state, as a save state.
+#[derive(Clone)]
 pub struct Device {
+    pub mode: DeviceMode,
     pub cpu: Cpu,
     pub memory: Memory,
     pub video: Video,
-    pub interrupts_pending: HashSet<Interrupt>,
+    pub interrupts: InterruptController,
+    pub joypad: Joypad,
+    pub serial: Serial,
+    pub timer: Timer,
+    pub audio: Audio,
+    pub system: SystemRegisters,
+    /// The Super Game Boy the device is running on, if any.
+    pub sgb: Option<SuperGameBoy>,
+    /// The last value written to DMA.
+    pub dma_source: u8,
+    /// How ROM has been used, once [`Device::enable_coverage`] is called.
+    pub coverage: Option<CodeDataLog>,
     previous_stat_value: bool,
 }
 
@@ -63,30 +77,59 @@ impl Device {
         let mut rom: Vec<u8> = Vec::with_capacity(len);
         file.read_to_end(&mut rom)?;
 
+        Self::from_rom(rom)
+    }
+
+    pub fn from_rom(rom: Vec<u8>) -> Result<Self, Error> {
         let memory = Memory::new(rom)?;
         let device_mode = DeviceMode::from(memory.cartridge.device_mode);
 
         Ok(Self {
+            mode: device_mode,
             cpu: Cpu::new(device_mode),
             video: Video::new(device_mode),
-            interrupts_pending: HashSet::new(),
+            interrupts: InterruptController::new(),
+            joypad: Joypad::new(),
+            serial: Serial::new(),
+            timer: Timer::new(),
+            audio: Audio::new(),
+            system: SystemRegisters::default(),
+            sgb: None,
+            dma_source: 0,
+            coverage: None,
             previous_stat_value: false,
             memory,
         })
     }
 
-    pub fn is_interrupt_enabled(&self, interrupt: Interrupt) -> bool {
-        self.cpu.interrupts_enabled && (self.memory.interrupts_enabled & interrupt.get_mask() > 0)
+    /// Runs the device on a Super Game Boy. This is only possible in classic mode, so `false` is
+    /// returned, and nothing changes, in colour mode.
+    pub fn enable_super_game_boy(&mut self) -> bool {
+        if self.mode != DeviceMode::Classic {
+            return false;
+        }
+
+        self.sgb = Some(SuperGameBoy::new(self.memory.cartridge.sgb_support));
+        true
     }
 
     pub fn process(&mut self, delta: u8) {
+        let was_drawing = matches!(self.video.mode, video::Mode::Draw);
+
         // PPU cycles (also referred to as "dots") are actually t-cycles (m_cycle * 4)
-        self.video.process(delta * 4);
+        self.video.process(delta * 4, &self.memory.oam);
+
+        if was_drawing && matches!(self.video.mode, video::Mode::HorizontalBlank) {
+            let line = self.video.current_line;
+            self.video.render_line(line, &self.memory.oam);
+        }
 
         if self.video.has_vblank_interrupt {
-            self.interrupts_pending.insert(Interrupt::VerticalBlank);
-        } else {
-            self.interrupts_pending.remove(&Interrupt::VerticalBlank);
+            self.interrupts.request(Interrupt::VerticalBlank);
+
+            if let Some(sgb) = &mut self.sgb {
+                sgb.finish_frame(&self.video.framebuffer);
+            }
         }
 
         // STAT interrupts only trigger on the rising edge, meaning that two sequential STAT
@@ -95,79 +138,76 @@ impl Device {
         // the video device, and only queue a STAT interrupt if we've gone from `false` (low
         // signal) to `true` (high signal).
         if self.video.has_stat_interrupt && !self.previous_stat_value {
-            self.interrupts_pending.insert(Interrupt::Stat);
-        } else {
-            self.interrupts_pending.remove(&Interrupt::Stat);
+            self.interrupts.request(Interrupt::Stat);
         }
 
         self.previous_stat_value = self.video.has_stat_interrupt;
     }
 
-    pub fn get_next_interrupt(&mut self) -> Option<Interrupt> {
-        let int = self.interrupts_pending.take(&Interrupt::VerticalBlank);
-
-        if int.is_some() {
-            return int;
-        }
-
-        let int = self.interrupts_pending.take(&Interrupt::Stat);
-
-        if int.is_some() {
-            return int;
+    /// Presses or releases a key, requesting the joypad interrupt if the game is reading it.
+    pub fn set_button(&mut self, button: Button, pressed: bool) {
+        if self.joypad.set_pressed(button, pressed) {
+            self.interrupts.request(Interrupt::Joypad);
         }
+    }
 
-        let int = self.interrupts_pending.take(&Interrupt::Timer);
+    /// Starts logging which bytes of ROM are executed, read or copied by DMA, in
+    /// [`Device::coverage`].
+    pub fn enable_coverage(&mut self) {
+        let size = self.memory.cartridge.rom_size();
+        self.coverage.get_or_insert_with(|| CodeDataLog::new(size));
+    }
 
-        if int.is_some() {
-            return int;
+    /// Returns the ROM, cartridge RAM and WRAM banks that are mapped.
+    pub fn banks(&self) -> Banks {
+        Banks {
+            rom: self.memory.cartridge.rom_bank(),
+            sram: self.memory.cartridge.ram_bank(),
+            wram: self.memory.wram.bank(),
         }
+    }
 
-        let int = self.interrupts_pending.take(&Interrupt::Joypad);
-
-        if int.is_some() {
-            return int;
-        }
+    /// Reads `address` the way DMA and debuggers do, without the restrictions the PPU places on
+    /// the CPU.
+    pub fn peek(&self, address: u16) -> u8 {
+        self.read(address, Access::Privileged)
+    }
 
-        None
+    /// Writes `address` the way DMA and debuggers do, without the restrictions the PPU places on
+    /// the CPU.
+    pub fn poke(&mut self, address: u16, value: u8) {
+        self.write(address, value, Access::Privileged)
     }
 
-    pub fn read_byte(&self, address: u16) -> u8 {
+    fn read(&self, address: u16, access: Access) -> u8 {
         let address = address as usize;
 
         let slot = match address {
             ROM0_START..=ROM0_END | ROM_BANK_START..=ROM_BANK_END => {
                 return self.memory.cartridge.rom_read(address)
             }
+            VRAM_START..=VRAM_END if !self.is_accessible(address, access) => return 0xFF,
             VRAM_START..=VRAM_END => self.video.vram.get(address - VRAM_START),
             EXTERNAL_RAM_START..=EXTERNAL_RAM_END => {
                 return self.memory.cartridge.ram_read(address)
             }
             RAM0_START..=RAM0_END => self.memory.wram.get_from(0, address - RAM0_START),
             RAM_BANK_START..=RAM_BANK_END => self.memory.wram.get(address - RAM_BANK_START),
-            ECHO_START..=ECHO_END => self.memory.wram.get(address - ECHO_START),
+            // Echo RAM mirrors $C000-$DDFF, including the bank selected by SVBK.
+            ECHO_START..=ECHO_END => return self.read((address - ECHO_OFFSET) as u16, access),
+            OAM_START..=OAM_END if !self.is_accessible(address, access) => return 0xFF,
             OAM_START..=OAM_END => self.memory.oam.get(address - OAM_START),
             UNUSED_START..=UNUSED_END => Some(&0),
-            INTERRUPT_FLAGS => Some(&self.memory.interrupt_flags),
-            REGISTER_LCD_STATUS => Some(&self.video.status_register),
-            REGISTER_LCD_Y_COORD => Some(&self.video.current_line),
-            REGISTER_LCD_Y_COMPARE => Some(&self.video.current_line_compare),
-            IO_START..=IO_END => self.memory.io.get(address - IO_START),
+            IO_START..=IO_END => return self.read_io(address),
             HRAM_START..=HRAM_END => self.memory.hram.get(address - HRAM_START),
-            INTERRUPT_ENABLED => Some(&self.memory.interrupts_enabled),
-            _ => unreachable!(),
+            // Only `INTERRUPT_ENABLED` is left, as the last address a `u16` can hold.
+            _ => Some(&self.interrupts.enabled),
         };
 
         *slot.unwrap_or(&0xFF)
     }
 
-    pub fn read_word(&self, address: u16) -> u16 {
-        let low = self.read_byte(address);
-        let high = self.read_byte(address.wrapping_add(1));
-
-        bytes_to_word(high, low)
-    }
-
-    pub fn write_byte(&mut self, address: u16, value: u8) {
+    fn write(&mut self, address: u16, value: u8, access: Access) {
         let address = address as usize;
 
         let slot = match address {
@@ -175,6 +215,7 @@ impl Device {
                 self.memory.cartridge.rom_write(address, value);
                 return;
             }
+            VRAM_START..=VRAM_END if !self.is_accessible(address, access) => return,
             VRAM_START..=VRAM_END => self.video.vram.get_mut(address - VRAM_START),
             EXTERNAL_RAM_START..=EXTERNAL_RAM_END => {
                 self.memory.cartridge.ram_write(address, value);
@@ -182,20 +223,20 @@ impl Device {
             }
             RAM0_START..=RAM0_END => self.memory.wram.get_from_mut(0, address - RAM0_START),
             RAM_BANK_START..=RAM_BANK_END => self.memory.wram.get_mut(address - RAM_BANK_START),
-            ECHO_START..=ECHO_END => self.memory.wram.get_mut(address - ECHO_START),
+            ECHO_START..=ECHO_END => {
+                self.write((address - ECHO_OFFSET) as u16, value, access);
+                return;
+            }
+            OAM_START..=OAM_END if !self.is_accessible(address, access) => return,
             OAM_START..=OAM_END => self.memory.oam.get_mut(address - OAM_START),
             UNUSED_START..=UNUSED_END => return,
-            INTERRUPT_FLAGS => Some(&mut self.memory.interrupt_flags),
-            REGISTER_LCD_STATUS => {
-                self.video.write_status_register(value);
+            IO_START..=IO_END => {
+                self.write_io(address, value);
                 return;
             }
-            REGISTER_LCD_Y_COORD => return,
-            REGISTER_LCD_Y_COMPARE => Some(&mut self.video.current_line_compare),
-            IO_START..=IO_END => self.memory.io.get_mut(address - IO_START),
             HRAM_START..=HRAM_END => self.memory.hram.get_mut(address - HRAM_START),
-            INTERRUPT_ENABLED => Some(&mut self.memory.interrupts_enabled),
-            _ => unreachable!(),
+            // INTERRUPT_ENABLED
+            _ => Some(&mut self.interrupts.enabled),
         };
 
         if let Some(slot) = slot {
@@ -203,34 +244,181 @@ impl Device {
         }
     }
 
-    pub fn write_word(&mut self, address: u16, value: u16) {
-        let [low, high] = word_to_bytes(value);
+    /// Returns `true` if VRAM or OAM at `address` can be reached. The CPU is locked out of VRAM
+    /// while the PPU draws a line, and out of OAM while it scans or draws one.
+    fn is_accessible(&self, address: usize, access: Access) -> bool {
+        if access == Access::Privileged {
+            return true;
+        }
 
-        self.write_byte(address, low);
-        self.write_byte(address.wrapping_add(1), high)
+        match address {
+            VRAM_START..=VRAM_END => !matches!(self.video.mode, video::Mode::Draw),
+            OAM_START..=OAM_END => {
+                !matches!(self.video.mode, video::Mode::OamScan | video::Mode::Draw)
+            }
+            _ => true,
+        }
     }
 
-    pub fn stack_push(&mut self, value: u16) {
-        let [low, high] = word_to_bytes(value);
-        let stack_pointer = self.cpu.stack_pointer;
+    /// Copies 160 bytes from `source` * `$100` into OAM. On hardware this takes 160 M-cycles, during
+    /// which the CPU can only reach HRAM, but here the transfer completes immediately.
+    fn start_dma(&mut self, source: u8) {
+        self.dma_source = source;
 
-        self.write_byte(stack_pointer, high);
-        let stack_pointer = stack_pointer.wrapping_sub(1);
+        let base = (source as u16) << 8;
 
-        self.write_byte(stack_pointer, low);
-        self.cpu.stack_pointer = stack_pointer.wrapping_sub(1);
+        for offset in 0..OAM_SIZE {
+            let address = base + offset as u16;
+            self.memory.oam[offset] = self.peek(address);
+
+            if let Some(offset) = self.rom_offset(address) {
+                self.log_coverage(offset, cdl::DMA);
+            }
+        }
+    }
+
+    fn read_io(&self, address: usize) -> u8 {
+        match self.io_register(address) {
+            Some(register) => self.read_component(register) | !register.read_mask,
+            None => 0xFF,
+        }
     }
 
-    pub fn stack_pop(&mut self) -> u16 {
-        let stack_pointer = self.cpu.stack_pointer.wrapping_add(1);
-        let low = self.read_byte(stack_pointer);
+    fn write_io(&mut self, address: usize, value: u8) {
+        let Some(register) = self.io_register(address) else {
+            return;
+        };
+
+        if register.write_mask == 0 {
+            return;
+        }
+
+        // Components always see a whole register, with the bits that can't be written unchanged.
+        let current = self.read_component(register);
+        let value = (current & !register.write_mask) | (value & register.write_mask);
+
+        match register.component {
+            Component::Joypad => {
+                self.joypad.write(value);
+
+                if let Some(sgb) = &mut self.sgb {
+                    sgb.write_p1(value);
+                }
+            }
+            Component::Serial => {
+                if self.serial.write(address, value) {
+                    self.interrupts.request(Interrupt::Serial);
+                }
+            }
+            Component::Timer => self.timer.write(address, value),
+            Component::Interrupts => self.interrupts.write_flags(value),
+            Component::Audio => self.audio.write(address, value),
+            Component::Video => self.video.write_register(address, value),
+            Component::Dma => self.start_dma(value),
+            Component::Memory => self.memory.write_register(address, value),
+            Component::System => self.system.write(address, value),
+        }
+    }
 
-        let stack_pointer = stack_pointer.wrapping_add(1);
-        let high = self.read_byte(stack_pointer);
+    /// Returns the I/O register at `address`, if it exists on this model.
+    fn io_register(&self, address: usize) -> Option<&'static IoRegister> {
+        io::lookup(address).filter(|register| register.is_available(self.mode))
+    }
 
-        self.cpu.stack_pointer = stack_pointer;
+    /// Reads a register from the component that owns it, without applying the read mask.
+    fn read_component(&self, register: &IoRegister) -> u8 {
+        let address = register.address;
 
-        bytes_to_word(high, low)
+        match register.component {
+            Component::Joypad => {
+                let value = self.joypad.read();
+                self.sgb.as_ref().map_or(value, |sgb| sgb.read_p1(value))
+            }
+            Component::Serial => self.serial.read(address),
+            Component::Timer => self.timer.read(address),
+            Component::Interrupts => self.interrupts.requested,
+            Component::Audio => self.audio.read(address),
+            Component::Video => self.video.read_register(address),
+            Component::Dma => self.dma_source,
+            Component::Memory => self.memory.read_register(address),
+            Component::System => self.system.read(address),
+        }
+    }
+}
+
+impl Bus for Device {
+    fn read_byte(&self, address: u16) -> u8 {
+        self.read(address, Access::Cpu)
+    }
+
+    fn write_byte(&mut self, address: u16, value: u8) {
+        self.write(address, value, Access::Cpu)
+    }
+
+    fn tick(&mut self, cycles: u8) {
+        self.process(cycles);
+    }
+
+    fn register_update(&mut self, address: u16, access: RegisterAccess, cycle: u8) {
+        // Only the DMG is affected, and only while the PPU scans OAM.
+        if self.mode != DeviceMode::Classic || !(0xFE00..=0xFEFF).contains(&address) {
+            return;
+        }
+
+        let Some(row) = self.video.oam_scan_row(cycle) else {
+            return;
+        };
+
+        let corruption = match access {
+            RegisterAccess::Idle | RegisterAccess::Write => Corruption::Write,
+            RegisterAccess::Read => Corruption::ReadDuringIncrement,
+        };
+
+        oam_bug::corrupt(&mut self.memory.oam, row, corruption);
+    }
+
+    fn peek_byte(&self, address: u16) -> u8 {
+        self.peek(address)
+    }
+
+    fn banks(&self) -> Option<Banks> {
+        Some(Device::banks(self))
+    }
+
+    fn logs_coverage(&self) -> bool {
+        self.coverage.is_some()
+    }
+
+    fn rom_offset(&self, address: u16) -> Option<usize> {
+        if address as usize <= ROM_BANK_END {
+            self.memory.cartridge.rom_offset(address as usize)
+        } else {
+            None
+        }
+    }
+
+    fn log_coverage(&mut self, offset: usize, flags: u8) {
+        if let Some(coverage) = &mut self.coverage {
+            coverage.mark(offset, flags);
+        }
+    }
+
+    fn interrupts(&self) -> &InterruptController {
+        &self.interrupts
+    }
+
+    fn interrupts_mut(&mut self) -> &mut InterruptController {
+        &mut self.interrupts
+    }
+}
+
+impl CpuState for Device {
+    fn cpu(&self) -> &Cpu {
+        &self.cpu
+    }
+
+    fn cpu_mut(&mut self) -> &mut Cpu {
+        &mut self.cpu
     }
 }
 
@@ -243,3 +431,153 @@ pub enum Error {
     #[error("cart file size too big")]
     FileTooBig,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_until_mode(device: &mut Device, mode: video::Mode) {
+        while device.video.mode != mode {
+            device.process(1);
+        }
+    }
+
+    #[test]
+    fn ppu_blocks_vram_and_oam() {
+        let mut device = Device::from_rom(vec![0; 0x8000]).unwrap();
+        device.poke(0x8000, 0x12);
+        device.poke(0xFE00, 0x34);
+
+        run_until_mode(&mut device, video::Mode::OamScan);
+        assert_eq!(device.read_byte(0x8000), 0x12);
+        assert_eq!(device.read_byte(0xFE00), 0xFF);
+
+        device.write_byte(0xFE00, 0x56);
+        assert_eq!(device.peek(0xFE00), 0x34);
+
+        run_until_mode(&mut device, video::Mode::Draw);
+        assert_eq!(device.read_byte(0x8000), 0xFF);
+        assert_eq!(device.peek(0x8000), 0x12);
+
+        device.write_byte(0x8000, 0x56);
+        assert_eq!(device.peek(0x8000), 0x12);
+
+        run_until_mode(&mut device, video::Mode::HorizontalBlank);
+        assert_eq!(device.read_byte(0x8000), 0x12);
+        assert_eq!(device.read_byte(0xFE00), 0x34);
+    }
+
+    #[test]
+    fn register_updates_corrupt_oam_during_scan() {
+        let mut device = Device::from_rom(vec![0; 0x8000]).unwrap();
+
+        for offset in 0..OAM_SIZE as u16 {
+            device.poke(0xFE00 + offset, offset as u8);
+        }
+
+        let oam = device.memory.oam.clone();
+
+        // Outside of OAM, or outside of mode 2, nothing happens.
+        run_until_mode(&mut device, video::Mode::HorizontalBlank);
+        device.register_update(0xFE10, RegisterAccess::Idle, 0);
+        run_until_mode(&mut device, video::Mode::OamScan);
+        device.register_update(0xC000, RegisterAccess::Idle, 0);
+        assert_eq!(device.memory.oam, oam);
+
+        // Two M-cycles into the scan, the PPU is reading the third row.
+        device.process(2);
+        device.register_update(0xFE10, RegisterAccess::Idle, 0);
+        assert_ne!(&device.memory.oam[16..24], &oam[16..24]);
+        assert_eq!(&device.memory.oam[18..24], &oam[10..16]);
+        assert_eq!(&device.memory.oam[24..], &oam[24..]);
+    }
+
+    #[test]
+    fn super_game_boy_reads_several_joypads() {
+        let mut rom = vec![0; 0x8000];
+        rom[0x146] = 0x03;
+
+        let mut device = Device::from_rom(rom).unwrap();
+        assert!(device.enable_super_game_boy());
+
+        // MLT_REQ for two players.
+        let mut packet = [0; sgb::packet::PACKET_SIZE];
+        packet[0] = sgb::COMMAND_MLT_REQ << 3 | 1;
+        packet[1] = 1;
+
+        for value in sgb::packet::pulses(&packet) {
+            device.write_byte(0xFF00, value);
+        }
+
+        assert_eq!(device.read_byte(0xFF00), 0xFF);
+
+        device.write_byte(0xFF00, 0x10);
+        device.write_byte(0xFF00, 0x30);
+        assert_eq!(device.read_byte(0xFF00), 0xFE);
+
+        device.write_byte(0xFF00, 0x10);
+        device.write_byte(0xFF00, 0x30);
+        assert_eq!(device.read_byte(0xFF00), 0xFF);
+    }
+
+    #[test]
+    fn buttons_request_joypad_interrupt() {
+        let mut device = Device::from_rom(vec![0; 0x8000]).unwrap();
+        device.write_byte(0xFF00, 0x10);
+
+        let snapshot = device.clone();
+
+        device.set_button(Button::Start, true);
+        assert_eq!(device.read_byte(0xFF00), 0xD7);
+        assert_eq!(device.interrupts.requested & 0x10, 0x10);
+
+        // Snapshots don't share any state with the device they were taken from.
+        assert_eq!(snapshot.read_byte(0xFF00), 0xDF);
+    }
+
+    #[test]
+    fn dma_copies_into_oam() {
+        let mut device = Device::from_rom(vec![0; 0x8000]).unwrap();
+
+        for offset in 0..OAM_SIZE as u16 {
+            device.write_byte(0xC100 + offset, offset as u8);
+        }
+
+        // DMA isn't held back by the PPU, even while it's scanning OAM.
+        run_until_mode(&mut device, video::Mode::OamScan);
+        device.write_byte(0xFF46, 0xC1);
+
+        assert_eq!(device.read_byte(0xFF46), 0xC1);
+        assert_eq!(device.peek(0xFE00), 0x00);
+        assert_eq!(device.peek(0xFE9F), 0x9F);
+    }
+
+    #[test]
+    fn rejects_bad_roms() {
+        use memory::cartridge::{mbc::CreateError, CartridgeError};
+
+        let error = |rom: Vec<u8>| match Device::from_rom(rom) {
+            Err(Error::Memory(MemoryError::CartridgeError(error))) => error,
+            result => panic!("expected a cartridge error, got {:?}", result.map(|_| ())),
+        };
+
+        assert!(matches!(
+            error(vec![0; 0x100]),
+            CartridgeError::Truncated(_)
+        ));
+
+        let mut rom = vec![0; 0x8000];
+        rom[0x147] = 0x01;
+        rom[0x149] = 0x09;
+        assert!(matches!(
+            error(rom.clone()),
+            CartridgeError::UnsupportedRamSize(0x09)
+        ));
+
+        rom[0x147] = 0x13;
+        assert!(matches!(
+            error(rom),
+            CartridgeError::ControllerError(CreateError::UnimplementedController(_))
+        ));
+    }
+}
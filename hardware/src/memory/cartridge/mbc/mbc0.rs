@@ -1,5 +1,6 @@
 use super::{Controller, ControllerAccess};
 
+#[derive(Clone)]
 pub struct Mbc0 {
     rom: Vec<u8>,
 }
@@ -15,6 +16,14 @@ impl ControllerAccess for Mbc0 {
         *self.rom.get(address).unwrap_or(&0xFF)
     }
 
+    fn rom_offset(&self, address: usize) -> Option<usize> {
+        (address < self.rom.len()).then_some(address)
+    }
+
+    fn rom_size(&self) -> usize {
+        self.rom.len()
+    }
+
     fn rom_write(&mut self, _address: usize, _value: u8) {}
 
     fn ram_read(&self, _address: usize) -> u8 {
@@ -23,7 +32,19 @@ impl ControllerAccess for Mbc0 {
 
     fn ram_write(&mut self, _address: usize, _value: u8) {}
 
+    fn rom_bank(&self) -> usize {
+        1
+    }
+
+    fn ram_bank(&self) -> usize {
+        0
+    }
+
     fn get_controller_type(&self) -> Controller {
         Controller::Mbc0
     }
+
+    fn clone_box(&self) -> Box<dyn ControllerAccess> {
+        Box::new(self.clone())
+    }
 }
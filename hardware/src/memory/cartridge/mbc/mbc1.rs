@@ -1,71 +1,81 @@
-use core::panic;
-
 use crate::memory::{
-    cartridge::read_ram_size,
-    map::{EXTERNAL_RAM_SIZE, ROM0_END, ROM0_START, ROM_BANK_END, ROM_BANK_START},
+    cartridge::{read_ram_size, CartridgeError},
+    map::{EXTERNAL_RAM_SIZE, ROM0_END, ROM0_START, ROM_BANK_END, ROM_BANK_SIZE, ROM_BANK_START},
 };
 
 use super::{map_ram_address, map_rom_address, Controller, ControllerAccess};
 
+#[derive(Clone)]
 pub struct Mbc1 {
     rom: Vec<u8>,
     ram: Vec<u8>,
-    rom_bank: usize,
-    ram_bank: usize,
+    /// The lower 5 bits of the ROM bank, written to `$2000-$3FFF`.
+    low_bank: usize,
+    /// The 2-bit register written to `$4000-$5FFF`. It selects bits 5-6 of the ROM bank, and the
+    /// RAM bank in advanced mode, though carts only have the ROM or the RAM to use it on.
+    high_bank: usize,
     ram_enabled: bool,
     advanced_bank_mode: bool,
 }
 
 impl Mbc1 {
-    pub fn new(rom: Vec<u8>) -> Self {
-        let ram_size = read_ram_size(&rom).expect("Unsupported RAM size");
+    pub fn new(rom: Vec<u8>) -> Result<Self, CartridgeError> {
+        let ram_size = read_ram_size(&rom)?;
         let ram = vec![0; ram_size];
 
-        Self {
+        Ok(Self {
             rom,
             ram,
-            rom_bank: 1,
-            ram_bank: 0,
+            low_bank: 1,
+            high_bank: 0,
             ram_enabled: false,
             advanced_bank_mode: false,
+        })
+    }
+
+    /// The bank mapped to `$0000-$3FFF`. Carts with more than 32 ROM banks can map banks $20,
+    /// $40 and $60 there in advanced mode.
+    fn rom0_bank(&self) -> usize {
+        if self.advanced_bank_mode {
+            self.wrap_rom_bank(self.high_bank << 5)
+        } else {
+            0
         }
     }
+
+    /// Drops the bank bits that aren't wired to the ROM chip.
+    fn wrap_rom_bank(&self, bank: usize) -> usize {
+        bank % (self.rom.len() / ROM_BANK_SIZE).max(1)
+    }
 }
 
 impl ControllerAccess for Mbc1 {
     fn rom_read(&self, address: usize) -> u8 {
-        match address {
-            ROM0_START..=ROM0_END => *self.rom.get(address).unwrap_or(&0xFF),
-            ROM_BANK_START..=ROM_BANK_END => {
-                let address = map_rom_address(self.rom_bank, address);
-                *self.rom.get(address).unwrap_or(&0xFF)
-            }
-            _ => panic!("ROM read out of range for MBC1: {address:#X}"),
-        }
+        self.rom_offset(address)
+            .map_or(0xFF, |offset| self.rom[offset])
+    }
+
+    fn rom_offset(&self, address: usize) -> Option<usize> {
+        let offset = match address {
+            ROM0_START..=ROM0_END => self.rom0_bank() * ROM_BANK_SIZE + address,
+            ROM_BANK_START..=ROM_BANK_END => map_rom_address(self.rom_bank(), address),
+            _ => return None,
+        };
+
+        (offset < self.rom.len()).then_some(offset)
+    }
+
+    fn rom_size(&self) -> usize {
+        self.rom.len()
     }
 
     fn rom_write(&mut self, address: usize, value: u8) {
         match address {
             0x0000..0x2000 => self.ram_enabled = value & 0x0A != 0,
-            0x2000..0x4000 => self.rom_bank = (value & 0b1_1111).max(1) as usize,
-            0x4000..0x6000 => {
-                let bits = (value & 0b11) as usize;
-
-                if self.advanced_bank_mode {
-                    // "Large RAM" carts allow RAM bank swapping in advanced mode
-                    if self.ram.len() >= EXTERNAL_RAM_SIZE {
-                        self.ram_bank = bits;
-                    } else {
-                        // "Large ROM" carts allow special case ROM banking in advanced mode that I
-                        // really do not understand at the moment...
-                        unimplemented!("Support for 'large ROM' carts is not yet complete");
-                    }
-                } else {
-                    self.rom_bank = self.rom_bank & 0b1_1111 | (bits << 5);
-                }
-            }
+            0x2000..0x4000 => self.low_bank = (value & 0b1_1111).max(1) as usize,
+            0x4000..0x6000 => self.high_bank = (value & 0b11) as usize,
             0x6000..0x8000 => self.advanced_bank_mode = value & 0b1 != 0,
-            _ => panic!("ROM write out of range for MBC1: {address:#X}"),
+            _ => (),
         }
     }
 
@@ -74,7 +84,7 @@ impl ControllerAccess for Mbc1 {
             return 0xFF;
         }
 
-        let address = map_ram_address(self.ram_bank, address);
+        let address = map_ram_address(self.ram_bank(), address);
         *self.ram.get(address).unwrap_or(&0xFF)
     }
 
@@ -83,7 +93,7 @@ impl ControllerAccess for Mbc1 {
             return;
         }
 
-        let address = map_ram_address(self.ram_bank, address);
+        let address = map_ram_address(self.ram_bank(), address);
         let slot = self.ram.get_mut(address);
 
         if let Some(slot) = slot {
@@ -91,7 +101,68 @@ impl ControllerAccess for Mbc1 {
         }
     }
 
+    fn rom_bank(&self) -> usize {
+        self.wrap_rom_bank(self.high_bank << 5 | self.low_bank)
+    }
+
+    fn ram_bank(&self) -> usize {
+        let banks = (self.ram.len() / EXTERNAL_RAM_SIZE).max(1);
+
+        if self.advanced_bank_mode {
+            self.high_bank % banks
+        } else {
+            0
+        }
+    }
+
     fn get_controller_type(&self) -> Controller {
         Controller::Mbc1
     }
+
+    fn clone_box(&self) -> Box<dyn ControllerAccess> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mbc1(banks: usize, ram_size: u8) -> Mbc1 {
+        let mut rom: Vec<u8> = (0..banks * ROM_BANK_SIZE)
+            .map(|address| (address / ROM_BANK_SIZE) as u8)
+            .collect();
+        rom[0x149] = ram_size;
+
+        Mbc1::new(rom).unwrap()
+    }
+
+    #[test]
+    fn banks_large_roms() {
+        let mut mbc = mbc1(128, 0);
+
+        mbc.rom_write(0x2000, 0x03);
+        mbc.rom_write(0x4000, 0x02);
+        assert_eq!(mbc.rom_read(0x4000), 0x43);
+        assert_eq!(mbc.rom_read(0x0000), 0x00);
+
+        mbc.rom_write(0x6000, 0x01);
+        assert_eq!(mbc.rom_read(0x0000), 0x40);
+        assert_eq!(mbc.ram_bank(), 0);
+    }
+
+    #[test]
+    fn banks_large_ram() {
+        let mut mbc = mbc1(4, 3);
+        mbc.rom_write(0x0000, 0x0A);
+        mbc.rom_write(0x4000, 0x02);
+
+        mbc.ram_write(0xA000, 0x12);
+        assert_eq!(mbc.ram_bank(), 0);
+
+        mbc.rom_write(0x6000, 0x01);
+        assert_eq!(mbc.ram_bank(), 2);
+        assert_eq!(mbc.ram_read(0xA000), 0x00);
+        assert_eq!(mbc.rom_bank(), 1);
+    }
 }
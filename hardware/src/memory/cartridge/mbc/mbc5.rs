@@ -1,6 +1,10 @@
 use super::{map_ram_address, map_rom_address, Controller, ControllerAccess};
-use crate::memory::cartridge::read_ram_size;
+use crate::memory::{
+    cartridge::{read_ram_size, CartridgeError},
+    map::{ROM0_END, ROM0_START},
+};
 
+#[derive(Clone)]
 pub struct Mbc5 {
     rom: Vec<u8>,
     ram: Vec<u8>,
@@ -10,8 +14,8 @@ pub struct Mbc5 {
 }
 
 impl Mbc5 {
-    pub fn new(rom: Vec<u8>) -> Self {
-        let ram_size = read_ram_size(&rom).expect("Unsupported RAM size");
+    pub fn new(rom: Vec<u8>) -> Result<Self, CartridgeError> {
+        let ram_size = read_ram_size(&rom)?;
         let ram = vec![0; ram_size];
 
         // TODO MBC5s also include rumble pack support. I'm not sure that's really relevant for
@@ -19,20 +23,33 @@ impl Mbc5 {
         // I might need to tweak things to account for writes to the registers it uses so it
         // doesn't affect the current RAM bank.
 
-        Self {
+        Ok(Self {
             rom,
             ram,
             rom_bank: 1,
             ram_bank: 0,
             ram_enabled: false,
-        }
+        })
     }
 }
 
 impl ControllerAccess for Mbc5 {
     fn rom_read(&self, address: usize) -> u8 {
-        let address = map_rom_address(self.rom_bank, address);
-        *self.rom.get(address).unwrap_or(&0xFF)
+        self.rom_offset(address)
+            .map_or(0xFF, |offset| self.rom[offset])
+    }
+
+    fn rom_offset(&self, address: usize) -> Option<usize> {
+        let offset = match address {
+            ROM0_START..=ROM0_END => address,
+            _ => map_rom_address(self.rom_bank, address),
+        };
+
+        (offset < self.rom.len()).then_some(offset)
+    }
+
+    fn rom_size(&self) -> usize {
+        self.rom.len()
     }
 
     fn rom_write(&mut self, address: usize, value: u8) {
@@ -67,7 +84,19 @@ impl ControllerAccess for Mbc5 {
         }
     }
 
+    fn rom_bank(&self) -> usize {
+        self.rom_bank
+    }
+
+    fn ram_bank(&self) -> usize {
+        self.ram_bank
+    }
+
     fn get_controller_type(&self) -> Controller {
         Controller::Mbc5
     }
+
+    fn clone_box(&self) -> Box<dyn ControllerAccess> {
+        Box::new(self.clone())
+    }
 }
@@ -1,4 +1,4 @@
-use super::OFFSET_CONTROLLER_TYPE;
+use super::{CartridgeError, OFFSET_CONTROLLER_TYPE};
 use crate::memory::map::{EXTERNAL_RAM_SIZE, EXTERNAL_RAM_START, ROM_BANK_SIZE};
 use derive_more::derive::Display;
 use mbc0::Mbc0;
@@ -19,6 +19,13 @@ pub trait ControllerAccess {
     /// Reads outside the supported range should return `0xFF`.
     fn rom_read(&self, address: usize) -> u8;
 
+    /// Returns the offset in the ROM image of the byte mapped at `address`, which is in
+    /// `$0000-$7FFF`, or `None` if it's past the end of the ROM.
+    fn rom_offset(&self, address: usize) -> Option<usize>;
+
+    /// Returns the size of the ROM image in bytes.
+    fn rom_size(&self) -> usize;
+
     /// "Writes" a value to the MBC registers mapped to ROM memory segments (if supported by the
     /// MBC).
     ///
@@ -40,9 +47,18 @@ pub trait ControllerAccess {
     /// documentation for information on how this is handled.
     fn ram_write(&mut self, address: usize, value: u8);
 
+    /// Returns the ROM bank currently mapped to `$4000-$7FFF`.
+    fn rom_bank(&self) -> usize;
+
+    /// Returns the external RAM bank currently mapped to `$A000-$BFFF`.
+    fn ram_bank(&self) -> usize;
+
     /// Returns the `Controller` variant that this implementation supports. Mostly used for
     /// debugging.
     fn get_controller_type(&self) -> Controller;
+
+    /// Returns a copy of the controller and the memory it holds, for save states.
+    fn clone_box(&self) -> Box<dyn ControllerAccess>;
 }
 
 #[derive(Debug, Copy, Clone, Display)]
@@ -58,25 +74,29 @@ pub enum Controller {
 }
 
 impl Controller {
-    pub fn create(&self, rom: Vec<u8>) -> Box<dyn ControllerAccess> {
-        match self {
+    pub fn create(&self, rom: Vec<u8>) -> Result<Box<dyn ControllerAccess>, CartridgeError> {
+        Ok(match self {
             Self::Mbc0 => Box::new(Mbc0::new(rom)),
-            Self::Mbc1 => Box::new(Mbc1::new(rom)),
-            Self::Mbc5 => Box::new(Mbc5::new(rom)),
-            _ => todo!(),
-        }
+            Self::Mbc1 => Box::new(Mbc1::new(rom)?),
+            Self::Mbc5 => Box::new(Mbc5::new(rom)?),
+            Self::Mbc3 => return Err(CreateError::UnimplementedController(*self).into()),
+        })
     }
 
-    pub fn create_for_rom(rom: Vec<u8>) -> Result<Box<dyn ControllerAccess>, CreateError> {
-        let variant = match rom[OFFSET_CONTROLLER_TYPE] {
+    pub fn create_for_rom(rom: Vec<u8>) -> Result<Box<dyn ControllerAccess>, CartridgeError> {
+        let Some(&controller_type) = rom.get(OFFSET_CONTROLLER_TYPE) else {
+            return Err(CartridgeError::Truncated(OFFSET_CONTROLLER_TYPE));
+        };
+
+        let variant = match controller_type {
             0x00 => Self::Mbc0,
             0x01..=0x03 => Self::Mbc1,
             0x0F..=0x13 => Self::Mbc3,
             0x19..=0x1E => Self::Mbc5,
-            x => return Err(CreateError::UnsupportedControllerType(x)),
+            x => return Err(CreateError::UnsupportedControllerType(x).into()),
         };
 
-        Ok(variant.create(rom))
+        variant.create(rom)
     }
 }
 
@@ -84,6 +104,8 @@ impl Controller {
 pub enum CreateError {
     #[error("unsupported controller type id {0}")]
     UnsupportedControllerType(u8),
+    #[error("{0} cartridges are not supported yet")]
+    UnimplementedController(Controller),
 }
 
 /// Maps a normal ROM address to an absolute banked address.
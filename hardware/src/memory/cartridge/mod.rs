@@ -36,11 +36,11 @@ pub struct Cartridge {
 impl Cartridge {
     pub fn new(rom: Vec<u8>) -> Result<Self, CartridgeError> {
         Ok(Self {
-            title: read_title(&rom),
-            device_mode: read_supported_mode(&rom),
-            licensee_id: read_licensee_id(&rom),
-            sgb_support: read_sgb_support_flag(&rom),
-            version: read_version(&rom),
+            title: read_title(&rom)?,
+            device_mode: read_supported_mode(&rom)?,
+            licensee_id: read_licensee_id(&rom)?,
+            sgb_support: read_sgb_support_flag(&rom)?,
+            version: read_version(&rom)?,
             controller: Controller::create_for_rom(rom)?,
         })
     }
@@ -49,6 +49,15 @@ impl Cartridge {
         self.controller.rom_read(address)
     }
 
+    /// Returns the offset in the ROM image of the byte the CPU sees at `address`, as banked now.
+    pub fn rom_offset(&self, address: usize) -> Option<usize> {
+        self.controller.rom_offset(address)
+    }
+
+    pub fn rom_size(&self) -> usize {
+        self.controller.rom_size()
+    }
+
     pub fn rom_write(&mut self, address: usize, value: u8) {
         self.controller.rom_write(address, value)
     }
@@ -60,6 +69,27 @@ impl Cartridge {
     pub fn ram_write(&mut self, address: usize, value: u8) {
         self.controller.ram_write(address, value)
     }
+
+    pub fn rom_bank(&self) -> usize {
+        self.controller.rom_bank()
+    }
+
+    pub fn ram_bank(&self) -> usize {
+        self.controller.ram_bank()
+    }
+}
+
+impl Clone for Cartridge {
+    fn clone(&self) -> Self {
+        Self {
+            title: self.title.clone(),
+            device_mode: self.device_mode,
+            licensee_id: self.licensee_id,
+            sgb_support: self.sgb_support,
+            version: self.version,
+            controller: self.controller.clone_box(),
+        }
+    }
 }
 
 impl Debug for Cartridge {
@@ -79,15 +109,19 @@ impl Debug for Cartridge {
 pub enum CartridgeError {
     #[error("controller error: {0}")]
     ControllerError(#[from] mbc::CreateError),
+    #[error("invalid ROM: cannot read to offset {0:#04X}")]
+    Truncated(usize),
+    #[error("unsupported RAM size id {0}")]
+    UnsupportedRamSize(u8),
 }
 
+/// Returns [`CartridgeError::Truncated`] from the enclosing function if `offset` is past the end
+/// of the ROM.
 macro_rules! check_offset {
     ($rom:ident [ $offset:expr ]) => {
-        assert!(
-            $rom.len() > $offset,
-            "Invalid ROM: cannot read to offset {:#04X}",
-            $offset
-        );
+        if $rom.len() <= $offset {
+            return Err(CartridgeError::Truncated($offset));
+        }
     };
 }
 
@@ -99,8 +133,8 @@ macro_rules! check_offset {
 /// Titles are padded with null bytes if the length of the title is less than the maximum length.
 /// The return value of this function _does not_ include the null padding, and ignores bytes
 /// starting with the first null byte encountered.
-pub fn read_title(rom: &[u8]) -> String {
-    let title_len = read_title_max_length(rom);
+pub fn read_title(rom: &[u8]) -> Result<String, CartridgeError> {
+    let title_len = read_title_max_length(rom)?;
     check_offset!(rom[OFFSET_TITLE_START + title_len]);
 
     let mut title = String::with_capacity(title_len);
@@ -112,7 +146,7 @@ pub fn read_title(rom: &[u8]) -> String {
         };
     }
 
-    title
+    Ok(title)
 }
 
 /// Returns the maximum possible title length, based on the value of [`OFFSET_GBC_SUPPORT_TYPE`].
@@ -120,13 +154,13 @@ pub fn read_title(rom: &[u8]) -> String {
 /// Pre-GBC ROMs could include titles up to 16 characters long. For the Gameboy Color, the title
 /// length was reduced to 11 characters to make room for two new header fields: manufacturer code
 /// and [support type](OFFSET_GBC_SUPPORT_TYPE).
-pub fn read_title_max_length(rom: &[u8]) -> usize {
+pub fn read_title_max_length(rom: &[u8]) -> Result<usize, CartridgeError> {
     check_offset!(rom[OFFSET_GBC_SUPPORT_TYPE]);
 
     if rom[OFFSET_GBC_SUPPORT_TYPE] & 0x80 != 0 {
-        11
+        Ok(11)
     } else {
-        16
+        Ok(16)
     }
 }
 
@@ -141,14 +175,16 @@ pub fn read_title_max_length(rom: &[u8]) -> usize {
 /// Emphases is placed on "intended" because the Color is both capable of and happy to run Classic
 /// games without issue. There are some differences in how the contents of the ROM are interpreted
 /// (e.g. title length, color palettes, etc.), but that appears to be the only difference.
-pub fn read_supported_mode(rom: &[u8]) -> SupportedDeviceMode {
+pub fn read_supported_mode(rom: &[u8]) -> Result<SupportedDeviceMode, CartridgeError> {
     check_offset!(rom[OFFSET_GBC_SUPPORT_TYPE]);
 
-    match rom[OFFSET_GBC_SUPPORT_TYPE] {
+    let mode = match rom[OFFSET_GBC_SUPPORT_TYPE] {
         0x80 => SupportedDeviceMode::Any,
         0xC0 => SupportedDeviceMode::Color,
         _ => SupportedDeviceMode::Classic,
-    }
+    };
+
+    Ok(mode)
 }
 
 /// Retrieves the licensee ID.
@@ -158,39 +194,41 @@ pub fn read_supported_mode(rom: &[u8]) -> SupportedDeviceMode {
 /// company names can be found
 /// [here](https://gbdev.io/pandocs/The_Cartridge_Header.html#0144-0145---new-licensee-code) and
 /// [here](https://raw.githubusercontent.com/gb-archive/salvage/master/txt-files/gbrom.txt).
-pub fn read_licensee_id(rom: &[u8]) -> u16 {
+pub fn read_licensee_id(rom: &[u8]) -> Result<u16, CartridgeError> {
     check_offset!(rom[OFFSET_OLD_LICENSEE]);
 
-    match rom[OFFSET_OLD_LICENSEE] {
+    let id = match rom[OFFSET_OLD_LICENSEE] {
         0x33 => {
             check_offset!(rom[OFFSET_NEW_LICENSEE_LOW]);
             bytes_to_word(rom[OFFSET_NEW_LICENSEE_HIGH], rom[OFFSET_NEW_LICENSEE_LOW])
         }
         x => x as u16,
-    }
+    };
+
+    Ok(id)
 }
 
 /// Retrieves the status of Super Gameboy Support.
 ///
 /// If set to any value other than `0x03`, the SGB will ignore any command packets.
-pub fn read_sgb_support_flag(rom: &[u8]) -> bool {
+pub fn read_sgb_support_flag(rom: &[u8]) -> Result<bool, CartridgeError> {
     check_offset!(rom[OFFSET_SGB_SUPPORT_FLAG]);
-    rom[OFFSET_SGB_SUPPORT_FLAG] == 0x03
+    Ok(rom[OFFSET_SGB_SUPPORT_FLAG] == 0x03)
 }
 
 /// Retrieves the ROM's version.
 ///
 /// For most ROMs, this seems to be set to `0x00`.
-pub fn read_version(rom: &[u8]) -> u8 {
+pub fn read_version(rom: &[u8]) -> Result<u8, CartridgeError> {
     check_offset!(rom[OFFSET_VERSION]);
-    rom[OFFSET_VERSION]
+    Ok(rom[OFFSET_VERSION])
 }
 
 /// Retrieves the size of the cartridge RAM.
 ///
 /// A map of RAM sizes can be found
 /// [here](https://gbdev.io/pandocs/The_Cartridge_Header.html#0149--ram-size).
-pub fn read_ram_size(rom: &[u8]) -> Result<usize, u8> {
+pub fn read_ram_size(rom: &[u8]) -> Result<usize, CartridgeError> {
     check_offset!(rom[OFFSET_RAM_SIZE]);
 
     let val = match rom[OFFSET_RAM_SIZE] {
@@ -211,7 +249,7 @@ pub fn read_ram_size(rom: &[u8]) -> Result<usize, u8> {
         // 64KB (8 banks); TCAGBD: Used by "Pokemon Crystal (J)"
         5 => 8 * EXTERNAL_RAM_SIZE,
 
-        x => return Err(x),
+        x => return Err(CartridgeError::UnsupportedRamSize(x)),
     };
 
     Ok(val)
@@ -22,6 +22,8 @@ pub const RAM_BANK_SIZE: usize = RAM_BANK_END - RAM_BANK_START + 1;
 
 pub const ECHO_START: usize = 0xE000;
 pub const ECHO_END: usize = 0xFDFF;
+/// The distance between echo RAM and the work RAM it mirrors.
+pub const ECHO_OFFSET: usize = ECHO_START - RAM0_START;
 
 pub const OAM_START: usize = 0xFE00;
 pub const OAM_END: usize = 0xFE9F;
@@ -41,3 +43,7 @@ pub const HRAM_END: usize = 0xFFFE;
 pub const HRAM_SIZE: usize = HRAM_END - HRAM_START + 1;
 
 pub const INTERRUPT_ENABLED: usize = 0xFFFF;
+
+pub const REGISTER_SERIAL_DATA: usize = 0xFF01;
+pub const REGISTER_SERIAL_CONTROL: usize = 0xFF02;
+pub const REGISTER_WRAM_BANK: usize = 0xFF70;
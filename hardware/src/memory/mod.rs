@@ -4,15 +4,16 @@ use map::*;
 
 pub mod cartridge;
 pub mod map;
+pub mod oam_bug;
 
+#[derive(Clone)]
 pub struct Memory {
     pub cartridge: Cartridge,
     pub wram: Bank,
     pub oam: Vec<u8>,
-    pub io: Vec<u8>,
     pub hram: Vec<u8>,
-    pub interrupt_flags: u8,
-    pub interrupts_enabled: u8,
+    /// SVBK on the CGB, as written. The bank it selects is mapped in [`Memory::wram`].
+    pub wram_bank: u8,
 }
 
 impl Memory {
@@ -24,18 +25,37 @@ impl Memory {
             DeviceMode::Classic => 2,
         };
 
+        // $D000-$DFFF maps bank 1 until SVBK selects another, which only the CGB can do.
+        let mut wram = Bank::new(wram_banks, RAM_BANK_SIZE);
+        wram.select(1);
+
         Ok(Self {
             cartridge,
-            wram: Bank::new(wram_banks, RAM_BANK_SIZE),
+            wram,
             oam: vec![0; OAM_SIZE],
-            io: vec![0; IO_SIZE],
             hram: vec![0; HRAM_SIZE],
-            interrupt_flags: 0,
-            interrupts_enabled: 0,
+            wram_bank: 0,
         })
     }
+
+    pub fn read_register(&self, address: usize) -> u8 {
+        match address {
+            REGISTER_WRAM_BANK => self.wram_bank,
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write_register(&mut self, address: usize, value: u8) {
+        if address == REGISTER_WRAM_BANK {
+            self.wram_bank = value;
+
+            // Bank 0 is always mapped at $C000, so selecting it maps bank 1 instead.
+            self.wram.select((value as usize & 0x07).max(1));
+        }
+    }
 }
 
+#[derive(Clone)]
 pub struct Bank {
     data: Vec<u8>,
     bank_size: usize,
@@ -69,6 +89,15 @@ impl Bank {
         self.data.get_mut(address)
     }
 
+    /// Returns the bank that's currently mapped.
+    pub fn bank(&self) -> usize {
+        self.current_bank
+    }
+
+    pub fn select(&mut self, bank: usize) {
+        self.current_bank = bank;
+    }
+
     pub fn set(&mut self, address: usize, value: u8) {
         let slot = self.get_mut(address);
 
@@ -87,3 +116,36 @@ pub enum MemoryError {
     #[error("cartridge error: {0}")]
     CartridgeError(#[from] CartridgeError),
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{bus::Bus, Device};
+
+    #[test]
+    fn svbk_selects_wram_banks() {
+        let mut rom = vec![0; 0x8000];
+        rom[0x143] = 0xC0;
+        let mut device = Device::from_rom(rom).unwrap();
+
+        for bank in 1..8 {
+            device.write_byte(0xFF70, bank);
+            device.write_byte(0xD000, bank * 0x11);
+        }
+
+        device.write_byte(0xFF70, 2);
+        assert_eq!(device.read_byte(0xD000), 0x22);
+        assert_eq!(device.read_byte(0xF000), 0x22);
+        assert_eq!(device.read_byte(0xFF70), 0xFA);
+        assert_eq!(device.banks().wram, 2);
+
+        // Bank 0 is never mapped at $D000.
+        device.write_byte(0xFF70, 0);
+        assert_eq!(device.read_byte(0xD000), 0x11);
+        assert_eq!(device.banks().wram, 1);
+        assert_eq!(device.read_byte(0xFF70), 0xF8);
+
+        device.write_byte(0xC000, 0x99);
+        assert_ne!(device.read_byte(0xD000), 0x99);
+        assert_eq!(device.read_byte(0xE000), 0x99);
+    }
+}
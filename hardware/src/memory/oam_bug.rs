@@ -0,0 +1,126 @@
+//! The DMG's OAM corruption bug.
+//!
+//! While the PPU scans OAM in mode 2, it reads one 8-byte row every M-cycle. If the CPU puts an
+//! address between `$FE00` and `$FEFF` on the bus at the same time, through a 16-bit register it's
+//! incrementing or decrementing, the row the PPU is reading gets mixed with the row before it. The
+//! patterns applied here are the ones documented in the Pan Docs.
+
+pub const ROW_SIZE: usize = 8;
+pub const ROW_COUNT: usize = 20;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Corruption {
+    Write,
+    Read,
+    /// A read in the same M-cycle as the register holding the address is incremented or
+    /// decremented, as with `LD A, [HL+]` or `POP`.
+    ReadDuringIncrement,
+}
+
+/// Corrupts OAM as the bug would while the PPU is reading `row`.
+pub fn corrupt(oam: &mut [u8], row: usize, corruption: Corruption) {
+    // The first row is never affected.
+    if row == 0 || row >= ROW_COUNT {
+        return;
+    }
+
+    match corruption {
+        Corruption::Write => {
+            let a = word(oam, row, 0);
+            let b = word(oam, row - 1, 0);
+            let c = word(oam, row - 1, 2);
+
+            copy_row(oam, row - 1, row);
+            set_word(oam, row, 0, ((a ^ c) & (b ^ c)) ^ c);
+        }
+        Corruption::Read => {
+            let a = word(oam, row, 0);
+            let b = word(oam, row - 1, 0);
+            let c = word(oam, row - 1, 2);
+
+            copy_row(oam, row - 1, row);
+            set_word(oam, row, 0, b | (a & c));
+        }
+        Corruption::ReadDuringIncrement => {
+            // This only happens when the current row is neither one of the first four nor the last.
+            if (4..ROW_COUNT - 1).contains(&row) {
+                let a = word(oam, row - 2, 0);
+                let b = word(oam, row - 1, 0);
+                let c = word(oam, row, 0);
+                let d = word(oam, row - 1, 2);
+
+                // The preceding row is corrupted, then copied over its neighbours.
+                set_word(oam, row - 1, 0, (b & (a | c | d)) | (a & c & d));
+                copy_row(oam, row - 1, row);
+                copy_row(oam, row - 1, row - 2);
+            }
+
+            // Either way, it's followed by a regular read corruption.
+            corrupt(oam, row, Corruption::Read);
+        }
+    }
+}
+
+fn word(oam: &[u8], row: usize, index: usize) -> u16 {
+    let offset = row * ROW_SIZE + index * 2;
+    u16::from_le_bytes([oam[offset], oam[offset + 1]])
+}
+
+fn set_word(oam: &mut [u8], row: usize, index: usize, value: u16) {
+    let offset = row * ROW_SIZE + index * 2;
+    oam[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+fn copy_row(oam: &mut [u8], from: usize, to: usize) {
+    oam.copy_within(from * ROW_SIZE..(from + 1) * ROW_SIZE, to * ROW_SIZE);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn oam() -> Vec<u8> {
+        (0..160).map(|i| i as u8).collect()
+    }
+
+    #[test]
+    fn write_corruption() {
+        let mut oam = oam();
+        corrupt(&mut oam, 1, Corruption::Write);
+
+        // a = $0908, b = $0100, c = $0504
+        assert_eq!(
+            word(&oam, 1, 0),
+            ((0x0908 ^ 0x0504) & (0x0100 ^ 0x0504)) ^ 0x0504
+        );
+        assert_eq!(&oam[10..16], &oam[2..8]);
+        assert_eq!(&oam[16..], &self::oam()[16..]);
+    }
+
+    #[test]
+    fn first_row_is_never_corrupted() {
+        let mut oam = oam();
+
+        for corruption in [
+            Corruption::Write,
+            Corruption::Read,
+            Corruption::ReadDuringIncrement,
+        ] {
+            corrupt(&mut oam, 0, corruption);
+        }
+
+        assert_eq!(oam, self::oam());
+    }
+
+    #[test]
+    fn read_during_increment_touches_three_rows() {
+        let mut oam = oam();
+        corrupt(&mut oam, 5, Corruption::ReadDuringIncrement);
+
+        // Rows 3 and 4 end up identical, and row 5 only differs in its first word.
+        assert_ne!(&oam[24..32], &self::oam()[24..32]);
+        assert_eq!(&oam[24..32], &oam[32..40]);
+        assert_eq!(&oam[34..40], &oam[42..48]);
+        assert_eq!(&oam[48..], &self::oam()[48..]);
+    }
+}
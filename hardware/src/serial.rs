@@ -0,0 +1,44 @@
+//! The serial port. There's never anything on the other end of the link cable, so transfers using
+//! the internal clock complete immediately and shift in `$FF`.
+
+use crate::memory::map::{REGISTER_SERIAL_CONTROL, REGISTER_SERIAL_DATA};
+
+#[derive(Debug, Clone, Default)]
+pub struct Serial {
+    pub data: u8,
+    pub control: u8,
+    /// Every byte sent over the serial port.
+    pub output: Vec<u8>,
+}
+
+impl Serial {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn read(&self, address: usize) -> u8 {
+        match address {
+            REGISTER_SERIAL_DATA => self.data,
+            REGISTER_SERIAL_CONTROL => self.control,
+            _ => 0xFF,
+        }
+    }
+
+    /// Writes a serial register, returning `true` if this completed a transfer.
+    pub fn write(&mut self, address: usize, value: u8) -> bool {
+        match address {
+            REGISTER_SERIAL_DATA => self.data = value,
+            // A transfer starts when both the transfer enable and internal clock bits are set.
+            REGISTER_SERIAL_CONTROL if value & 0x81 == 0x81 => {
+                self.output.push(self.data);
+                self.data = 0xFF;
+                self.control = value & 0x7F;
+                return true;
+            }
+            REGISTER_SERIAL_CONTROL => self.control = value,
+            _ => {}
+        }
+
+        false
+    }
+}
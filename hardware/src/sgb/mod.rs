@@ -0,0 +1,461 @@
+//! The Super Game Boy, which runs DMG games on a SNES.
+//!
+//! Games talk to it by sending [command packets](packet) through P1. It colourises the game screen
+//! with four palettes assigned per 8x8 cell, surrounds it with a border, and can read up to four
+//! joypads. Data too large for packets, such as the border, is transferred by displaying it on the
+//! game screen.
+
+use crate::video::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use packet::{PacketReceiver, PACKET_SIZE};
+pub use render::{FRAME_HEIGHT, FRAME_WIDTH};
+
+pub mod packet;
+mod render;
+
+pub const COMMAND_PAL01: u8 = 0x00;
+pub const COMMAND_PAL23: u8 = 0x01;
+pub const COMMAND_PAL03: u8 = 0x02;
+pub const COMMAND_PAL12: u8 = 0x03;
+pub const COMMAND_ATTR_BLK: u8 = 0x04;
+pub const COMMAND_ATTR_LIN: u8 = 0x05;
+pub const COMMAND_ATTR_DIV: u8 = 0x06;
+pub const COMMAND_ATTR_CHR: u8 = 0x07;
+pub const COMMAND_MLT_REQ: u8 = 0x11;
+pub const COMMAND_CHR_TRN: u8 = 0x13;
+pub const COMMAND_PCT_TRN: u8 = 0x14;
+pub const COMMAND_MASK_EN: u8 = 0x17;
+
+/// The game screen is colourised in cells of 8x8 pixels.
+pub const CELLS_WIDE: usize = SCREEN_WIDTH / 8;
+pub const CELLS_HIGH: usize = SCREEN_HEIGHT / 8;
+
+/// The size of the data sent by a VRAM transfer.
+const TRANSFER_SIZE: usize = 0x1000;
+
+/// The number of border tiles, each of which is an 8x8 SNES tile with 4 bits per pixel.
+const BORDER_TILE_COUNT: usize = 256;
+const BORDER_TILE_SIZE: usize = 32;
+/// The border map covers 32x32 tiles, although only the first 28 rows are visible.
+const BORDER_MAP_SIZE: usize = 32 * 32;
+
+/// A colour in the SNES's 15-bit format, with red in the lowest five bits and blue in the
+/// highest.
+pub type Color = u16;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Mask {
+    /// The game screen is displayed normally.
+    None,
+    /// The game screen keeps showing the last frame from before it was masked.
+    Freeze,
+    Black,
+    /// The game screen is filled with colour 0.
+    Color0,
+}
+
+/// The VRAM transfers, which take the data to send from the next frame the game displays.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Transfer {
+    /// CHR_TRN, which sends half of the border's tiles.
+    BorderTiles { upper: bool },
+    /// PCT_TRN, which sends the border's map and palettes.
+    BorderMap,
+}
+
+#[derive(Debug, Clone)]
+pub struct SuperGameBoy {
+    /// Whether the cartridge header declares SGB support. Packets are ignored if it doesn't.
+    accepts_packets: bool,
+    receiver: PacketReceiver,
+    /// The packets received so far for a command that spans several.
+    command: Vec<u8>,
+    /// A transfer waiting for a frame, and whether that frame has started.
+    transfer: Option<(Transfer, bool)>,
+    /// The palettes for the game screen. Colour 0 is shared by all of them.
+    pub palettes: [[Color; 4]; 4],
+    /// The palette of every cell of the game screen, row by row.
+    pub attributes: [u8; CELLS_WIDE * CELLS_HIGH],
+    pub mask: Mask,
+    pub border_tiles: Vec<u8>,
+    /// The border's map. Each entry holds a tile number in its lower 8 bits, a palette number
+    /// (from 4 to 7) in bits 10 to 12, and X and Y flips in bits 14 and 15.
+    pub border_map: Vec<u16>,
+    /// The palettes for the border, numbered 4 to 7. Colour 0 is transparent.
+    pub border_palettes: [[Color; 16]; 4],
+    /// The number of joypads enabled by MLT_REQ.
+    pub players: u8,
+    /// The joypad that P1 currently reads.
+    pub player: u8,
+    /// The last frame, including the border, in `Color`s row by row.
+    pub frame: Vec<Color>,
+}
+
+impl SuperGameBoy {
+    pub fn new(accepts_packets: bool) -> Self {
+        Self {
+            accepts_packets,
+            receiver: PacketReceiver::new(),
+            command: Vec::new(),
+            transfer: None,
+            palettes: [[0x7FFF, 0x56B5, 0x294A, 0x0000]; 4],
+            attributes: [0; CELLS_WIDE * CELLS_HIGH],
+            mask: Mask::None,
+            border_tiles: vec![0; BORDER_TILE_COUNT * BORDER_TILE_SIZE],
+            border_map: vec![0; BORDER_MAP_SIZE],
+            border_palettes: [[0; 16]; 4],
+            players: 1,
+            player: 0,
+            frame: vec![0; FRAME_WIDTH * FRAME_HEIGHT],
+        }
+    }
+
+    /// Handles a write to P1, which can send packets and select the next joypad.
+    pub fn write_p1(&mut self, value: u8) {
+        let select = value & 0x30;
+
+        // Reading P1 with both lines high gives the current joypad's ID, and raising P15 again
+        // moves on to the next one.
+        if self.players > 1
+            && select == 0x30
+            && self.receiver.previous() & 0x20 == 0
+            && !self.receiver.is_receiving()
+        {
+            self.player = (self.player + 1) % self.players;
+        }
+
+        if let Some(packet) = self.receiver.write(select) {
+            if self.accepts_packets {
+                self.receive(packet);
+            }
+        }
+    }
+
+    /// Applies the SGB's changes to `value`, a read of P1.
+    pub fn read_p1(&self, value: u8) -> u8 {
+        if self.players > 1 && value & 0x30 == 0x30 {
+            value & 0xF0 | (0x0F - self.player)
+        } else {
+            value
+        }
+    }
+
+    /// Called at the start of VBlank, once the game has finished drawing `screen`.
+    pub fn finish_frame(&mut self, screen: &[u8]) {
+        match self.transfer {
+            Some((transfer, true)) => {
+                self.transfer = None;
+                self.apply_transfer(transfer, &screen_data(screen));
+            }
+            Some((transfer, false)) => self.transfer = Some((transfer, true)),
+            None => {}
+        }
+
+        self.compose(screen);
+    }
+
+    fn receive(&mut self, packet: [u8; PACKET_SIZE]) {
+        // The first packet of a command holds the number of packets in its lower 3 bits.
+        if self.command.is_empty() && packet[0] & 0x07 == 0 {
+            return;
+        }
+
+        self.command.extend(packet);
+
+        if self.command.len() == (self.command[0] & 0x07) as usize * PACKET_SIZE {
+            let command = std::mem::take(&mut self.command);
+            self.execute(&command);
+        }
+    }
+
+    fn execute(&mut self, data: &[u8]) {
+        match data[0] >> 3 {
+            COMMAND_PAL01 => self.set_palettes(0, 1, data),
+            COMMAND_PAL23 => self.set_palettes(2, 3, data),
+            COMMAND_PAL03 => self.set_palettes(0, 3, data),
+            COMMAND_PAL12 => self.set_palettes(1, 2, data),
+            COMMAND_ATTR_BLK => self.attribute_blocks(data),
+            COMMAND_ATTR_LIN => self.attribute_lines(data),
+            COMMAND_ATTR_DIV => self.attribute_division(data),
+            COMMAND_ATTR_CHR => self.attribute_cells(data),
+            COMMAND_MLT_REQ => {
+                self.players = match data[1] & 0x03 {
+                    1 => 2,
+                    3 => 4,
+                    _ => 1,
+                };
+                self.player = 0;
+            }
+            COMMAND_CHR_TRN => {
+                let upper = data[1] & 0x01 != 0;
+                self.transfer = Some((Transfer::BorderTiles { upper }, false));
+            }
+            COMMAND_PCT_TRN => self.transfer = Some((Transfer::BorderMap, false)),
+            COMMAND_MASK_EN => {
+                self.mask = match data[1] & 0x03 {
+                    1 => Mask::Freeze,
+                    2 => Mask::Black,
+                    3 => Mask::Color0,
+                    _ => Mask::None,
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// PAL01, PAL23, PAL03 and PAL12, which set colour 0 and colours 1 to 3 of two palettes.
+    fn set_palettes(&mut self, first: usize, second: usize, data: &[u8]) {
+        let colors: Vec<Color> = data[1..15]
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+
+        for palette in &mut self.palettes {
+            palette[0] = colors[0];
+        }
+
+        self.palettes[first][1..].copy_from_slice(&colors[1..4]);
+        self.palettes[second][1..].copy_from_slice(&colors[4..7]);
+    }
+
+    /// ATTR_BLK, which sets the palettes inside, on the edge of and outside rectangles.
+    fn attribute_blocks(&mut self, data: &[u8]) {
+        let count = data[1] as usize;
+
+        for block in data[2..].chunks_exact(6).take(count) {
+            let mut control = block[0] & 0x07;
+            let inside = block[1] & 0x03;
+            let mut edge = block[1] >> 2 & 0x03;
+            let outside = block[1] >> 4 & 0x03;
+            let [left, top, right, bottom] =
+                [block[2], block[3], block[4], block[5]].map(usize::from);
+
+            // When only the inside or the outside is set, the edge goes along with it.
+            match control {
+                0b001 => (control, edge) = (0b011, inside),
+                0b100 => (control, edge) = (0b110, outside),
+                _ => {}
+            }
+
+            for (y, x) in cells() {
+                let within = (left..=right).contains(&x) && (top..=bottom).contains(&y);
+                let on_edge = within && (x == left || x == right || y == top || y == bottom);
+
+                let (bit, palette) = match (within, on_edge) {
+                    (true, false) => (0b001, inside),
+                    (true, true) => (0b010, edge),
+                    (false, _) => (0b100, outside),
+                };
+
+                if control & bit != 0 {
+                    self.attributes[y * CELLS_WIDE + x] = palette;
+                }
+            }
+        }
+    }
+
+    /// ATTR_LIN, which sets the palettes of whole rows and columns.
+    fn attribute_lines(&mut self, data: &[u8]) {
+        let count = data[1] as usize;
+
+        for &line in data[2..].iter().take(count) {
+            let index = (line & 0x1F) as usize;
+            let palette = line >> 5 & 0x03;
+            let horizontal = line & 0x80 != 0;
+
+            for (y, x) in cells() {
+                if (horizontal && y == index) || (!horizontal && x == index) {
+                    self.attributes[y * CELLS_WIDE + x] = palette;
+                }
+            }
+        }
+    }
+
+    /// ATTR_DIV, which splits the screen in two with a line, giving each part and the line
+    /// their own palette.
+    fn attribute_division(&mut self, data: &[u8]) {
+        let after = data[1] & 0x03;
+        let before = data[1] >> 2 & 0x03;
+        let on_line = data[1] >> 4 & 0x03;
+        let horizontal = data[1] & 0x40 != 0;
+        let line = data[2] as usize;
+
+        for (y, x) in cells() {
+            let position = if horizontal { y } else { x };
+
+            self.attributes[y * CELLS_WIDE + x] = match position.cmp(&line) {
+                std::cmp::Ordering::Less => before,
+                std::cmp::Ordering::Equal => on_line,
+                std::cmp::Ordering::Greater => after,
+            };
+        }
+    }
+
+    /// ATTR_CHR, which sets the palettes of consecutive cells, 2 bits each.
+    fn attribute_cells(&mut self, data: &[u8]) {
+        let (mut x, mut y) = (data[1] as usize, data[2] as usize);
+        let count = u16::from_le_bytes([data[3], data[4]]) as usize;
+        let vertical = data[5] & 0x01 != 0;
+
+        for index in 0..count.min((data.len() - 6) * 4) {
+            if x >= CELLS_WIDE || y >= CELLS_HIGH {
+                break;
+            }
+
+            let palette = data[6 + index / 4] >> (6 - index % 4 * 2) & 0x03;
+            self.attributes[y * CELLS_WIDE + x] = palette;
+
+            if vertical {
+                y += 1;
+
+                if y == CELLS_HIGH {
+                    (x, y) = (x + 1, 0);
+                }
+            } else {
+                x += 1;
+
+                if x == CELLS_WIDE {
+                    (x, y) = (0, y + 1);
+                }
+            }
+        }
+    }
+
+    fn apply_transfer(&mut self, transfer: Transfer, data: &[u8]) {
+        match transfer {
+            Transfer::BorderTiles { upper } => {
+                let start = if upper { TRANSFER_SIZE } else { 0 };
+                self.border_tiles[start..start + TRANSFER_SIZE].copy_from_slice(data);
+            }
+            Transfer::BorderMap => {
+                let words: Vec<u16> = data
+                    .chunks_exact(2)
+                    .map(|w| u16::from_le_bytes([w[0], w[1]]))
+                    .collect();
+
+                self.border_map.copy_from_slice(&words[..BORDER_MAP_SIZE]);
+
+                for (palette, colors) in self
+                    .border_palettes
+                    .iter_mut()
+                    .zip(words[BORDER_MAP_SIZE..].chunks_exact(16))
+                {
+                    palette.copy_from_slice(colors);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the position of every cell as `(y, x)`.
+fn cells() -> impl Iterator<Item = (usize, usize)> {
+    (0..CELLS_HIGH).flat_map(|y| (0..CELLS_WIDE).map(move |x| (y, x)))
+}
+
+/// Returns the data a VRAM transfer sends. The SGB reads it from the screen as tiles, left to
+/// right and top to bottom, so the shades have already gone through BGP.
+fn screen_data(screen: &[u8]) -> Vec<u8> {
+    let mut data = vec![0; TRANSFER_SIZE];
+
+    for (tile, bytes) in data.chunks_exact_mut(16).enumerate() {
+        let (column, row) = (tile % CELLS_WIDE, tile / CELLS_WIDE);
+
+        for y in 0..8 {
+            for x in 0..8 {
+                let shade = screen[(row * 8 + y) * SCREEN_WIDTH + column * 8 + x];
+                bytes[y * 2] |= (shade & 1) << (7 - x);
+                bytes[y * 2 + 1] |= (shade >> 1 & 1) << (7 - x);
+            }
+        }
+    }
+
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{packet::pulses, *};
+
+    fn send(sgb: &mut SuperGameBoy, packets: &[[u8; PACKET_SIZE]]) {
+        for packet in packets {
+            for value in pulses(packet) {
+                sgb.write_p1(value);
+            }
+        }
+    }
+
+    fn packet(command: u8, length: u8, data: &[u8]) -> [u8; PACKET_SIZE] {
+        let mut packet = [0; PACKET_SIZE];
+        packet[0] = command << 3 | length;
+        packet[1..=data.len()].copy_from_slice(data);
+        packet
+    }
+
+    #[test]
+    fn palettes() {
+        let mut sgb = SuperGameBoy::new(true);
+        let data: Vec<u8> = (1..=7u16).flat_map(|c| c.to_le_bytes()).collect();
+        send(&mut sgb, &[packet(COMMAND_PAL12, 1, &data)]);
+
+        assert_eq!(sgb.palettes[0], [1, 0x56B5, 0x294A, 0x0000]);
+        assert_eq!(sgb.palettes[1], [1, 2, 3, 4]);
+        assert_eq!(sgb.palettes[2], [1, 5, 6, 7]);
+
+        // Without SGB support in the header, packets are ignored.
+        let mut sgb = SuperGameBoy::new(false);
+        send(&mut sgb, &[packet(COMMAND_PAL12, 1, &data)]);
+        assert_eq!(sgb.palettes[1][1], 0x56B5);
+    }
+
+    #[test]
+    fn attribute_blocks_span_packets() {
+        let mut sgb = SuperGameBoy::new(true);
+
+        // The third block starts in the first packet and ends in the second.
+        let first = packet(
+            COMMAND_ATTR_BLK,
+            2,
+            &[
+                3, 0b111, 0b10_11_00, 5, 5, 6, 6, 0b001, 0b01, 1, 1, 3, 3, 0b001, 0b11,
+            ],
+        );
+        let second = [10, 10, 12, 12, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        send(&mut sgb, &[first, second]);
+
+        let attribute = |x: usize, y: usize| sgb.attributes[y * CELLS_WIDE + x];
+
+        // The first block set everything.
+        assert_eq!(attribute(0, 0), 2);
+        assert_eq!(attribute(5, 6), 3);
+        assert_eq!(attribute(13, 13), 2);
+
+        // The others only set their inside, so their edges followed.
+        assert_eq!(attribute(2, 2), 1);
+        assert_eq!(attribute(1, 3), 1);
+        assert_eq!(attribute(11, 11), 3);
+        assert_eq!(attribute(10, 12), 3);
+    }
+
+    #[test]
+    fn attribute_cells_and_division() {
+        let mut sgb = SuperGameBoy::new(true);
+        send(&mut sgb, &[packet(COMMAND_ATTR_DIV, 1, &[0b0110_0100, 9])]);
+
+        assert_eq!(sgb.attributes[8 * CELLS_WIDE], 1);
+        assert_eq!(sgb.attributes[9 * CELLS_WIDE], 2);
+        assert_eq!(sgb.attributes[10 * CELLS_WIDE], 0);
+
+        // Three cells top to bottom, wrapping to the next column.
+        send(
+            &mut sgb,
+            &[packet(
+                COMMAND_ATTR_CHR,
+                1,
+                &[4, 16, 3, 0, 1, 0b11_10_01_00],
+            )],
+        );
+
+        assert_eq!(sgb.attributes[16 * CELLS_WIDE + 4], 3);
+        assert_eq!(sgb.attributes[17 * CELLS_WIDE + 4], 2);
+        assert_eq!(sgb.attributes[5], 1);
+    }
+}
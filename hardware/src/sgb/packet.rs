@@ -0,0 +1,114 @@
+//! Command packets, which games send to the SGB one bit at a time by pulsing P14 and P15.
+//!
+//! A packet starts with a reset pulse, where both lines are pulled low. Each of its 128 bits
+//! follows, least significant first: a 0 pulls P15 low, a 1 pulls P14 low, and both lines go back
+//! high between bits. A final 0 bit ends the packet.
+
+pub const PACKET_SIZE: usize = 16;
+
+const RESET: u8 = 0x00;
+const ZERO: u8 = 0x20;
+const ONE: u8 = 0x10;
+const IDLE: u8 = 0x30;
+
+#[derive(Debug, Clone)]
+pub struct PacketReceiver {
+    /// The packet being received, or `None` when there's no transfer in progress.
+    packet: Option<([u8; PACKET_SIZE], usize)>,
+    /// Bits 4 and 5 of the last value written to P1.
+    previous: u8,
+}
+
+impl PacketReceiver {
+    pub fn new() -> Self {
+        Self {
+            packet: None,
+            previous: IDLE,
+        }
+    }
+
+    /// Returns `true` while a packet is being received.
+    pub fn is_receiving(&self) -> bool {
+        self.packet.is_some()
+    }
+
+    /// Returns bits 4 and 5 of the last value written to P1.
+    pub fn previous(&self) -> u8 {
+        self.previous
+    }
+
+    /// Handles a write of `select` (bits 4 and 5 of P1), returning a packet once it's complete.
+    pub fn write(&mut self, select: u8) -> Option<[u8; PACKET_SIZE]> {
+        let select = select & IDLE;
+        let previous = std::mem::replace(&mut self.previous, select);
+
+        match select {
+            RESET => {
+                self.packet = Some(([0; PACKET_SIZE], 0));
+                None
+            }
+            // A bit is only sent by a pulse, so holding a line low doesn't send it twice.
+            ZERO | ONE if previous == IDLE => {
+                let (bytes, bits) = self.packet.as_mut()?;
+                let bit = select == ONE;
+
+                if *bits == PACKET_SIZE * 8 {
+                    let (bytes, _) = self.packet.take()?;
+
+                    // A packet without a valid stop bit is dropped.
+                    return (!bit).then_some(bytes);
+                }
+
+                bytes[*bits / 8] |= (bit as u8) << (*bits % 8);
+                *bits += 1;
+
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Default for PacketReceiver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the values to write to P1 to send `packet`.
+#[cfg(test)]
+pub fn pulses(packet: &[u8; PACKET_SIZE]) -> Vec<u8> {
+    let mut pulses = vec![RESET, IDLE];
+
+    for bit in 0..PACKET_SIZE * 8 {
+        let value = packet[bit / 8] >> (bit % 8) & 1;
+        pulses.extend([if value == 1 { ONE } else { ZERO }, IDLE]);
+    }
+
+    pulses.extend([ZERO, IDLE]);
+    pulses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_packets() {
+        let packet: [u8; PACKET_SIZE] = std::array::from_fn(|i| (i * 17) as u8);
+        let mut receiver = PacketReceiver::new();
+
+        let received: Vec<_> = pulses(&packet)
+            .into_iter()
+            .filter_map(|value| receiver.write(value))
+            .collect();
+
+        assert_eq!(received, [packet]);
+        assert!(!receiver.is_receiving());
+
+        // Without a reset pulse first, bits are ignored.
+        assert!(pulses(&packet)[2..]
+            .iter()
+            .all(|&value| receiver.write(value).is_none()));
+    }
+}
@@ -0,0 +1,118 @@
+//! Composes the SGB's output: the colourised game screen in the middle of the border.
+
+use super::{Color, Mask, SuperGameBoy, BORDER_TILE_SIZE, CELLS_WIDE};
+use crate::video::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+pub const FRAME_WIDTH: usize = 256;
+pub const FRAME_HEIGHT: usize = 224;
+
+/// The position of the game screen's top left corner in the frame.
+const SCREEN_X: usize = 48;
+const SCREEN_Y: usize = 40;
+
+impl SuperGameBoy {
+    /// Draws `screen`, a frame of DMG shades, into the frame with the border around it.
+    pub(super) fn compose(&mut self, screen: &[u8]) {
+        // Colour 0 of the first palette shows through wherever nothing else is drawn.
+        let backdrop = self.palettes[0][0];
+
+        for y in 0..FRAME_HEIGHT {
+            for x in 0..FRAME_WIDTH {
+                let index = y * FRAME_WIDTH + x;
+
+                if let Some(color) = self.border_pixel(x, y) {
+                    self.frame[index] = color;
+                    continue;
+                }
+
+                let screen_x = x.wrapping_sub(SCREEN_X);
+                let screen_y = y.wrapping_sub(SCREEN_Y);
+
+                if screen_x >= SCREEN_WIDTH || screen_y >= SCREEN_HEIGHT {
+                    self.frame[index] = backdrop;
+                    continue;
+                }
+
+                self.frame[index] = match self.mask {
+                    Mask::None => {
+                        let cell = (screen_y / 8) * CELLS_WIDE + screen_x / 8;
+                        let palette = self.attributes[cell] as usize;
+                        let shade = screen[screen_y * SCREEN_WIDTH + screen_x] as usize;
+
+                        self.palettes[palette][shade]
+                    }
+                    Mask::Freeze => continue,
+                    Mask::Black => 0x0000,
+                    Mask::Color0 => backdrop,
+                };
+            }
+        }
+    }
+
+    /// Returns the colour of the border at `x` and `y` in the frame, or `None` if it's
+    /// transparent there.
+    fn border_pixel(&self, x: usize, y: usize) -> Option<Color> {
+        let entry = self.border_map[(y / 8) * 32 + x / 8];
+
+        let tile = (entry & 0xFF) as usize;
+        let palette = (entry >> 10 & 0x03) as usize;
+        let flip_x = entry & 0x4000 != 0;
+        let flip_y = entry & 0x8000 != 0;
+
+        let row = if flip_y { 7 - y % 8 } else { y % 8 };
+        let bit = if flip_x { x % 8 } else { 7 - x % 8 };
+
+        // SNES tiles store the first two bit planes of every row, followed by the other two.
+        let address = tile * BORDER_TILE_SIZE + row * 2;
+        let planes = [address, address + 1, address + 16, address + 17];
+
+        let color = planes
+            .iter()
+            .enumerate()
+            .fold(0, |color, (plane, &address)| {
+                color | (self.border_tiles[address] >> bit & 1) << plane
+            });
+
+        (color != 0).then(|| self.border_palettes[palette][color as usize])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(sgb: &SuperGameBoy, x: usize, y: usize) -> Color {
+        sgb.frame[y * FRAME_WIDTH + x]
+    }
+
+    #[test]
+    fn border_surrounds_colourised_screen() {
+        let mut sgb = SuperGameBoy::new(true);
+        sgb.palettes[1] = [0x1111, 0x2222, 0x3333, 0x4444];
+        sgb.attributes[1] = 1;
+
+        // Tile 1 has colour 15 in its first pixel, and the top left corner of the frame uses it
+        // with palette 5.
+        sgb.border_tiles[32] = 0x80;
+        sgb.border_tiles[33] = 0x80;
+        sgb.border_tiles[48] = 0x80;
+        sgb.border_tiles[49] = 0x80;
+        sgb.border_map[0] = 5 << 10 | 0x01;
+        sgb.border_palettes[1][15] = 0x7C00;
+
+        let mut screen = vec![0; SCREEN_WIDTH * SCREEN_HEIGHT];
+        screen[8] = 3;
+        sgb.finish_frame(&screen);
+
+        assert_eq!(pixel(&sgb, 0, 0), 0x7C00);
+        assert_eq!(pixel(&sgb, 1, 0), 0x7FFF);
+        assert_eq!(pixel(&sgb, SCREEN_X, SCREEN_Y), 0x7FFF);
+        assert_eq!(pixel(&sgb, SCREEN_X + 8, SCREEN_Y), 0x4444);
+        assert_eq!(pixel(&sgb, SCREEN_X + 9, SCREEN_Y), 0x1111);
+
+        sgb.mask = Mask::Black;
+        sgb.finish_frame(&screen);
+        assert_eq!(pixel(&sgb, SCREEN_X + 8, SCREEN_Y), 0x0000);
+        assert_eq!(pixel(&sgb, 0, 0), 0x7C00);
+    }
+}
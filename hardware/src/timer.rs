@@ -0,0 +1,41 @@
+//! The timer registers. The timer isn't clocked yet, so they only hold what was written to them.
+
+pub const REGISTER_DIVIDER: usize = 0xFF04;
+pub const REGISTER_COUNTER: usize = 0xFF05;
+pub const REGISTER_MODULO: usize = 0xFF06;
+pub const REGISTER_CONTROL: usize = 0xFF07;
+
+#[derive(Debug, Clone, Default)]
+pub struct Timer {
+    pub divider: u8,
+    pub counter: u8,
+    pub modulo: u8,
+    pub control: u8,
+}
+
+impl Timer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn read(&self, address: usize) -> u8 {
+        match address {
+            REGISTER_DIVIDER => self.divider,
+            REGISTER_COUNTER => self.counter,
+            REGISTER_MODULO => self.modulo,
+            REGISTER_CONTROL => self.control,
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, address: usize, value: u8) {
+        match address {
+            // Any write to DIV resets it.
+            REGISTER_DIVIDER => self.divider = 0,
+            REGISTER_COUNTER => self.counter = value,
+            REGISTER_MODULO => self.modulo = value,
+            REGISTER_CONTROL => self.control = value,
+            _ => {}
+        }
+    }
+}
@@ -1,20 +1,57 @@
 use crate::{memory::Bank, DeviceMode, VRAM_SIZE};
+pub use render::{SCREEN_HEIGHT, SCREEN_WIDTH};
 
+mod render;
+
+pub const REGISTER_LCD_CONTROL: usize = 0xFF40;
+pub const REGISTER_LCD_STATUS: usize = 0xFF41;
+pub const REGISTER_SCROLL_Y: usize = 0xFF42;
+pub const REGISTER_SCROLL_X: usize = 0xFF43;
 pub const REGISTER_LCD_Y_COORD: usize = 0xFF44;
 pub const REGISTER_LCD_Y_COMPARE: usize = 0xFF45;
-pub const REGISTER_LCD_STATUS: usize = 0xFF41;
+pub const REGISTER_BACKGROUND_PALETTE: usize = 0xFF47;
+pub const REGISTER_OBJECT_PALETTE_0: usize = 0xFF48;
+pub const REGISTER_OBJECT_PALETTE_1: usize = 0xFF49;
+pub const REGISTER_WINDOW_Y: usize = 0xFF4A;
+pub const REGISTER_WINDOW_X: usize = 0xFF4B;
+pub const REGISTER_VRAM_BANK: usize = 0xFF4F;
+pub const REGISTER_HDMA_START: usize = 0xFF51;
+pub const REGISTER_HDMA_END: usize = 0xFF55;
+pub const REGISTER_COLOR_PALETTE_START: usize = 0xFF68;
+pub const REGISTER_COLOR_PALETTE_END: usize = 0xFF6B;
+pub const REGISTER_OBJECT_PRIORITY: usize = 0xFF6C;
 
+#[derive(Clone)]
 pub struct Video {
     pub vram: Bank,
+    pub control: u8,
     pub current_line: u8,
     pub current_line_compare: u8,
     pub status_register: u8,
+    pub scroll_y: u8,
+    pub scroll_x: u8,
+    pub background_palette: u8,
+    pub object_palettes: [u8; 2],
+    pub window_y: u8,
+    pub window_x: u8,
+    /// HDMA1 to HDMA5 on the CGB. VRAM DMA isn't emulated, so these only hold what was written.
+    pub hdma: [u8; 5],
+    /// BCPS, BCPD, OCPS and OCPD on the CGB. Colour palettes aren't emulated, so these only hold
+    /// what was written.
+    pub color_palettes: [u8; 4],
+    /// OPRI on the CGB.
+    pub object_priority: u8,
     pub has_vblank_interrupt: bool,
     pub has_stat_interrupt: bool,
     pub mode: Mode,
     pub total_dots: u16,
     pub remaining_dots: u16,
     pub speed_multiplier: u16,
+    /// The shade (0 for white through 3 for black) of every pixel on the screen, row by row.
+    pub framebuffer: Vec<u8>,
+    /// The line of the window to draw next. This only advances on lines where the window is
+    /// visible.
+    window_line: u8,
 }
 
 impl Video {
@@ -23,6 +60,12 @@ impl Video {
     // but the LY register represents this as ten more lines.
     const LINE_COUNT: u8 = 144;
     const VIRTUAL_LINE_COUNT: u8 = Self::LINE_COUNT + 10;
+    const LAST_LINE: u8 = Self::VIRTUAL_LINE_COUNT - 1;
+
+    // Every object on a line stalls drawing by at least this many dots, and by up to 5 more
+    // depending on where it sits relative to the background tiles.
+    const OBJECT_PENALTY_DOTS: u16 = 6;
+    const WINDOW_PENALTY_DOTS: u16 = 6;
 
     pub fn new(device_mode: DeviceMode) -> Self {
         let mode = Mode::OamScan;
@@ -35,13 +78,26 @@ impl Video {
 
         let mut inst = Self {
             vram: Bank::new(vram_banks, VRAM_SIZE),
+            // The boot ROM leaves the LCD on, with the background enabled.
+            control: 0x91,
             current_line: 0,
             current_line_compare: 0,
             status_register: mode as u8,
+            scroll_y: 0,
+            scroll_x: 0,
+            background_palette: 0xFC,
+            object_palettes: [0; 2],
+            window_y: 0,
+            window_x: 0,
+            hdma: [0; 5],
+            color_palettes: [0; 4],
+            object_priority: 0,
             speed_multiplier: 1,
             remaining_dots: total_dots,
             has_stat_interrupt: false,
             has_vblank_interrupt: false,
+            framebuffer: vec![0; SCREEN_WIDTH * SCREEN_HEIGHT],
+            window_line: 0,
             total_dots,
             mode,
         };
@@ -53,52 +109,234 @@ impl Video {
         inst
     }
 
-    pub fn process(&mut self, delta: u8) {
+    /// Advances the PPU by `delta` dots. `oam` is needed to work out how long drawing each line
+    /// takes.
+    pub fn process(&mut self, delta: u8, oam: &[u8]) {
         // A VBlank interrupt is only requested on the tick that we enter vblank, and should be
         // cleared on the next one.
         self.has_vblank_interrupt = false;
 
-        let delta = delta as u16 * self.speed_multiplier;
+        // The PPU is stopped entirely while the LCD is off.
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut delta = delta as u16 * self.speed_multiplier;
 
         // Because we only tick the video device after each instruction, we might end up with a
-        // delta that is greater than the remaining dots for the current mode. In that case,
-        // `overflow` will be non-zero, telling us we need to subtract it from the next mode's
-        // remaining_dots when we switch to it.
-        let overflow = delta.saturating_sub(self.remaining_dots);
-        self.remaining_dots = self.remaining_dots.saturating_sub(delta);
-
-        if self.remaining_dots == 0 {
-            // Advance the line counter by 1 every time we complete a vertical or horizontal blank.
-            if matches!(self.mode, Mode::HorizontalBlank | Mode::VerticalBlank) {
-                self.current_line += 1;
-
-                if self.current_line >= Self::VIRTUAL_LINE_COUNT {
-                    self.current_line = 0;
+        // delta that is greater than the remaining dots for the current mode, in which case we
+        // keep switching modes until all of it has been spent.
+        while delta > 0 {
+            let spent = delta.min(self.remaining_dots);
+            self.remaining_dots -= spent;
+            delta -= spent;
+
+            if self.remaining_dots == 0 {
+                self.next_mode(oam);
+            }
+        }
+
+        // LY only reads 153 for the first M-cycle of the last line, and 0 for the rest of it. This
+        // also means LYC is compared against 0 early.
+        if matches!(self.mode, Mode::VerticalBlank)
+            && self.current_line == Self::LAST_LINE
+            && self.total_dots - self.remaining_dots >= 4
+        {
+            self.current_line = 0;
+        }
+
+        self.update_stat();
+    }
+
+    fn next_mode(&mut self, oam: &[u8]) {
+        let previous = self.mode;
+
+        // Advance the line counter by 1 every time we complete a vertical or horizontal blank.
+        match previous {
+            Mode::HorizontalBlank => self.current_line += 1,
+            // LY has already gone back to 0 by the end of the last line.
+            Mode::VerticalBlank if self.current_line == 0 => {}
+            Mode::VerticalBlank if self.current_line >= Self::LAST_LINE => self.current_line = 0,
+            Mode::VerticalBlank => self.current_line += 1,
+            _ => {}
+        }
+
+        self.set_mode(previous.next(self.current_line));
+
+        self.total_dots = match self.mode {
+            Mode::Draw => self.draw_duration(oam),
+            mode => mode.get_duration(self.total_dots),
+        };
+        self.remaining_dots = self.total_dots;
+
+        if matches!(
+            (previous, self.mode),
+            (Mode::HorizontalBlank, Mode::VerticalBlank)
+        ) {
+            self.has_vblank_interrupt = true;
+            self.window_line = 0;
+        }
+    }
+
+    /// Returns the number of dots it takes to draw the current line. On top of the minimum, the
+    /// PPU discards the pixels scrolled off the first tile, restarts fetching when it reaches the
+    /// window, and stalls to fetch every object on the line.
+    fn draw_duration(&self, oam: &[u8]) -> u16 {
+        let mut dots = Mode::DRAW_MIN_DOTS + (self.scroll_x % 8) as u16;
+
+        if self.is_window_on_line(self.current_line) {
+            dots += Self::WINDOW_PENALTY_DOTS;
+        }
+
+        if self.control & ControlFlag::ObjectsEnabled as u8 != 0 {
+            let mut objects = self.line_objects(self.current_line as usize, oam);
+            objects.sort_by_key(|o| o[1]);
+
+            // The extra stall only applies to the first object fetched within each background
+            // tile, since the tile's data is already there for the rest.
+            let mut previous_tile = None;
+
+            for object in objects {
+                let x = object[1] as u16;
+                let tile = (x + self.scroll_x as u16) / 8;
+                dots += Self::OBJECT_PENALTY_DOTS;
+
+                if x == 0 {
+                    dots += 5;
+                } else if previous_tile != Some(tile) {
+                    dots += 5u16.saturating_sub((x + self.scroll_x as u16) % 8);
                 }
+
+                previous_tile = Some(tile);
+            }
+        }
+
+        dots
+    }
+
+    fn update_stat(&mut self) {
+        let coincidence = self.current_line == self.current_line_compare;
+        self.set_flag(Flag::LycStatus, coincidence);
+
+        // The STAT interrupt line is the logical OR of every enabled source, and the interrupt
+        // itself is only requested on its rising edge.
+        let mode_source = match self.mode {
+            Mode::VerticalBlank => self.get_flag(Flag::VblankInterrupt),
+            Mode::HorizontalBlank => self.get_flag(Flag::HblankInterrupt),
+            Mode::OamScan => self.get_flag(Flag::OamInterrupt),
+            Mode::Draw => false,
+        };
+
+        self.has_stat_interrupt = mode_source || (coincidence && self.get_flag(Flag::LycInterrupt));
+    }
+
+    /// Returns the row of OAM the PPU reads `cycles` M-cycles from now, if it's scanning OAM then.
+    /// The scan of the next line can start within that time.
+    pub fn oam_scan_row(&self, cycles: u8) -> Option<usize> {
+        if !self.is_enabled() {
+            return None;
+        }
+
+        let ahead = cycles as u16 * 4;
+        let dots = match self.mode {
+            Mode::OamScan => self.total_dots - self.remaining_dots + ahead,
+            Mode::HorizontalBlank if self.current_line + 1 < Self::LINE_COUNT => {
+                ahead.checked_sub(self.remaining_dots)?
+            }
+            // LY reads 0 for most of the last line of VBlank, which is followed by the first scan.
+            Mode::VerticalBlank if self.current_line == 0 => {
+                ahead.checked_sub(self.remaining_dots)?
+            }
+            _ => return None,
+        };
+
+        // The PPU reads a new row every M-cycle.
+        (dots < Mode::OAM_DOTS).then_some(dots as usize / 4)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.control & ControlFlag::Enabled as u8 != 0
+    }
+
+    fn write_control(&mut self, value: u8) {
+        let was_enabled = self.is_enabled();
+        self.control = value;
+
+        match (was_enabled, self.is_enabled()) {
+            // Turning the LCD off resets LY and leaves the PPU in mode 0 with a blank screen.
+            (true, false) => {
+                self.current_line = 0;
+                self.window_line = 0;
+                self.set_mode(Mode::HorizontalBlank);
+                self.has_stat_interrupt = false;
+                self.framebuffer.fill(0);
             }
+            // Turning it back on starts a new frame from the top.
+            (false, true) => {
+                self.set_mode(Mode::OamScan);
+                self.total_dots = Mode::OAM_DOTS;
+                self.remaining_dots = self.total_dots;
+                self.update_stat();
+            }
+            _ => {}
+        }
+    }
 
-            self.mode = self.mode.next(self.current_line);
-            self.total_dots = self.mode.get_duration(self.total_dots);
-            self.remaining_dots = self.total_dots - overflow;
+    fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+        self.status_register = (self.status_register & !(Flag::CurrentMode as u8)) | mode as u8;
+    }
 
-            self.has_vblank_interrupt = matches!(self.mode, Mode::VerticalBlank);
-            self.has_stat_interrupt = match self.mode {
-                Mode::VerticalBlank => self.get_flag(Flag::VblankInterrupt),
-                Mode::HorizontalBlank => self.get_flag(Flag::HblankInterrupt),
-                Mode::OamScan => self.get_flag(Flag::OamInterrupt),
-                _ => false,
-            };
+    pub fn read_register(&self, address: usize) -> u8 {
+        match address {
+            REGISTER_LCD_CONTROL => self.control,
+            REGISTER_LCD_STATUS => self.status_register,
+            REGISTER_SCROLL_Y => self.scroll_y,
+            REGISTER_SCROLL_X => self.scroll_x,
+            REGISTER_LCD_Y_COORD => self.current_line,
+            REGISTER_LCD_Y_COMPARE => self.current_line_compare,
+            REGISTER_BACKGROUND_PALETTE => self.background_palette,
+            REGISTER_OBJECT_PALETTE_0 => self.object_palettes[0],
+            REGISTER_OBJECT_PALETTE_1 => self.object_palettes[1],
+            REGISTER_WINDOW_Y => self.window_y,
+            REGISTER_WINDOW_X => self.window_x,
+            REGISTER_VRAM_BANK => self.vram.bank() as u8,
+            REGISTER_HDMA_START..=REGISTER_HDMA_END => self.hdma[address - REGISTER_HDMA_START],
+            REGISTER_COLOR_PALETTE_START..=REGISTER_COLOR_PALETTE_END => {
+                self.color_palettes[address - REGISTER_COLOR_PALETTE_START]
+            }
+            REGISTER_OBJECT_PRIORITY => self.object_priority,
+            _ => 0xFF,
         }
+    }
 
-        self.set_flag(
-            Flag::LycStatus,
-            self.current_line == self.current_line_compare,
-        );
+    pub fn write_register(&mut self, address: usize, value: u8) {
+        match address {
+            REGISTER_LCD_CONTROL => self.write_control(value),
+            REGISTER_LCD_STATUS => self.write_status_register(value),
+            REGISTER_SCROLL_Y => self.scroll_y = value,
+            REGISTER_SCROLL_X => self.scroll_x = value,
+            REGISTER_LCD_Y_COMPARE => self.current_line_compare = value,
+            REGISTER_BACKGROUND_PALETTE => self.background_palette = value,
+            REGISTER_OBJECT_PALETTE_0 => self.object_palettes[0] = value,
+            REGISTER_OBJECT_PALETTE_1 => self.object_palettes[1] = value,
+            REGISTER_WINDOW_Y => self.window_y = value,
+            REGISTER_WINDOW_X => self.window_x = value,
+            REGISTER_VRAM_BANK => self.vram.select(value as usize & 1),
+            REGISTER_HDMA_START..=REGISTER_HDMA_END => {
+                self.hdma[address - REGISTER_HDMA_START] = value
+            }
+            REGISTER_COLOR_PALETTE_START..=REGISTER_COLOR_PALETTE_END => {
+                self.color_palettes[address - REGISTER_COLOR_PALETTE_START] = value
+            }
+            REGISTER_OBJECT_PRIORITY => self.object_priority = value,
+            _ => {}
+        }
     }
 
     pub fn write_status_register(&mut self, value: u8) {
         // The lower 3 bits of the LCD STAT register are not writable, so we need to ignore them.
-        self.status_register = value & 0b1111_1000;
+        self.status_register = (self.status_register & 0b0000_0111) | (value & 0b1111_1000);
     }
 
     fn set_flag(&mut self, flag: Flag, value: bool) {
@@ -125,7 +363,7 @@ pub enum Flag {
     LycInterrupt = 0b0100_0000,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(u8)]
 pub enum Mode {
     HorizontalBlank = 0,
@@ -173,3 +411,114 @@ pub enum ControlFlag {
     WindowTileMapArea = 0b0100_0000,
     Enabled = 0b1000_0000,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME_DOTS: u32 = 70224;
+
+    fn video() -> Video {
+        Video::new(DeviceMode::Classic)
+    }
+
+    /// Runs the PPU one M-cycle at a time until `done` returns `true`, returning the number of dots
+    /// that took.
+    fn run_until(video: &mut Video, oam: &[u8], done: impl Fn(&Video) -> bool) -> u32 {
+        let mut dots = 0;
+
+        while !done(video) {
+            video.process(4, oam);
+            dots += 4;
+            assert!(dots <= 2 * FRAME_DOTS, "condition never met");
+        }
+
+        dots
+    }
+
+    #[test]
+    fn frame_and_line_timing() {
+        let mut video = video();
+        let oam = [0; 160];
+
+        let mut vblanks = 0;
+        for _ in 0..FRAME_DOTS / 4 {
+            video.process(4, &oam);
+            vblanks += video.has_vblank_interrupt as u32;
+        }
+
+        assert_eq!(vblanks, 1);
+        assert_eq!(video.current_line, 0);
+        assert!(matches!(video.mode, Mode::OamScan));
+        assert_eq!(video.status_register & 0b11, Mode::OamScan as u8);
+    }
+
+    #[test]
+    fn draw_length_depends_on_scroll_and_objects() {
+        let mut video = video();
+        let mut oam = [0; 160];
+
+        video.scroll_x = 3;
+        video.control |= ControlFlag::ObjectsEnabled as u8;
+
+        // Two objects on line 0, one at X = 0 and one 3 pixels into a background tile.
+        oam[..8].copy_from_slice(&[16, 0, 0, 0, 16, 40, 0, 0]);
+
+        run_until(&mut video, &oam, |v| matches!(v.mode, Mode::Draw));
+        let draw = video.total_dots;
+        assert_eq!(draw, 172 + 3 + 11 + 8);
+
+        // The rest of the line is made up in HBlank.
+        run_until(&mut video, &oam, |v| {
+            matches!(v.mode, Mode::HorizontalBlank)
+        });
+        assert_eq!(80 + draw + video.total_dots, 456);
+    }
+
+    #[test]
+    fn lyc_raises_stat() {
+        let mut video = video();
+        let oam = [0; 160];
+
+        video.current_line_compare = 5;
+        video.write_status_register(Flag::LycInterrupt as u8);
+
+        run_until(&mut video, &oam, |v| v.has_stat_interrupt);
+        assert_eq!(video.current_line, 5);
+        assert_ne!(video.status_register & Flag::LycStatus as u8, 0);
+    }
+
+    #[test]
+    fn line_153_reads_as_zero() {
+        let mut video = video();
+        let oam = [0; 160];
+
+        run_until(&mut video, &oam, |v| v.current_line == 153);
+        video.process(4, &oam);
+        assert_eq!(video.current_line, 0);
+        assert!(matches!(video.mode, Mode::VerticalBlank));
+
+        let dots = run_until(&mut video, &oam, |v| matches!(v.mode, Mode::OamScan));
+        assert_eq!(dots, 456 - 4);
+        assert_eq!(video.current_line, 0);
+    }
+
+    #[test]
+    fn lcd_off_stops_the_ppu() {
+        let mut video = video();
+        let oam = [0; 160];
+
+        run_until(&mut video, &oam, |v| v.current_line == 10);
+        video.write_register(REGISTER_LCD_CONTROL, 0x11);
+
+        assert_eq!(video.current_line, 0);
+        assert_eq!(video.status_register & 0b11, 0);
+
+        video.process(200, &oam);
+        assert_eq!(video.current_line, 0);
+        assert!(matches!(video.mode, Mode::HorizontalBlank));
+
+        video.write_register(REGISTER_LCD_CONTROL, 0x91);
+        assert!(matches!(video.mode, Mode::OamScan));
+    }
+}
@@ -0,0 +1,189 @@
+//! A scanline renderer for the DMG. Each line is drawn in one go when the PPU finishes drawing it,
+//! using the state of the LCD registers, VRAM and OAM at that moment.
+
+use super::{ControlFlag, Video};
+
+pub const SCREEN_WIDTH: usize = 160;
+pub const SCREEN_HEIGHT: usize = 144;
+
+const OBJECT_LIMIT: usize = 10;
+
+impl Video {
+    /// Draws `line` into the framebuffer, using the objects in `oam`.
+    pub fn render_line(&mut self, line: u8, oam: &[u8]) {
+        let line = line as usize;
+
+        if line >= SCREEN_HEIGHT {
+            return;
+        }
+
+        let control = self.control;
+        let flag = |flag: ControlFlag| control & flag as u8 != 0;
+
+        // The colour indices of the background and window, before the palette is applied. Objects
+        // need these to decide whether they're hidden behind the background.
+        let mut colors = [0u8; SCREEN_WIDTH];
+
+        if !flag(ControlFlag::Enabled) {
+            self.framebuffer[line * SCREEN_WIDTH..][..SCREEN_WIDTH].fill(0);
+            return;
+        }
+
+        if flag(ControlFlag::BackgroundWindowPriority) {
+            let map = if flag(ControlFlag::BackgroundTileMapArea) {
+                0x1C00
+            } else {
+                0x1800
+            };
+
+            let y = (line + self.scroll_y as usize) & 0xFF;
+
+            for (x, color) in colors.iter_mut().enumerate() {
+                let x = (x + self.scroll_x as usize) & 0xFF;
+                *color = self.tile_map_pixel(control, map, x, y);
+            }
+
+            let window_x = self.window_x as usize;
+
+            if self.is_window_on_line(line as u8) {
+                let map = if flag(ControlFlag::WindowTileMapArea) {
+                    0x1C00
+                } else {
+                    0x1800
+                };
+
+                // WX holds the window's position plus 7.
+                let start = window_x.saturating_sub(7);
+                let y = self.window_line as usize;
+
+                for (x, color) in colors.iter_mut().enumerate().skip(start) {
+                    *color = self.tile_map_pixel(control, map, x + 7 - window_x, y);
+                }
+
+                self.window_line = self.window_line.wrapping_add(1);
+            }
+        }
+
+        let background_palette = self.background_palette;
+        let row = &mut self.framebuffer[line * SCREEN_WIDTH..][..SCREEN_WIDTH];
+
+        for (shade, color) in row.iter_mut().zip(colors) {
+            *shade = apply_palette(background_palette, color);
+        }
+
+        if flag(ControlFlag::ObjectsEnabled) {
+            let height = if flag(ControlFlag::ObjectSize) { 16 } else { 8 };
+            self.render_objects(line, height, self.object_palettes, &colors, oam);
+        }
+    }
+
+    fn render_objects(
+        &mut self,
+        line: usize,
+        height: usize,
+        palettes: [u8; 2],
+        colors: &[u8; SCREEN_WIDTH],
+        oam: &[u8],
+    ) {
+        // Among the objects on the line, the one with the smallest X coordinate is drawn on top,
+        // with ties going to the earlier object.
+        let mut objects = self.line_objects(line, oam);
+        objects.sort_by_key(|o| o[1]);
+
+        for object in objects.into_iter().rev() {
+            let [y, x, mut tile, attributes] = [object[0], object[1], object[2], object[3]];
+
+            let behind_background = attributes & 0x80 != 0;
+            let flip_y = attributes & 0x40 != 0;
+            let flip_x = attributes & 0x20 != 0;
+            let palette = palettes[(attributes >> 4 & 1) as usize];
+
+            let mut row = line + 16 - y as usize;
+
+            if flip_y {
+                row = height - 1 - row;
+            }
+
+            if height == 16 {
+                tile &= 0xFE;
+            }
+
+            let address = tile as usize * 16 + row * 2;
+
+            for column in 0..8 {
+                let Some(screen_x) = (x as usize + column).checked_sub(8) else {
+                    continue;
+                };
+
+                if screen_x >= SCREEN_WIDTH {
+                    continue;
+                }
+
+                let bit = if flip_x { column } else { 7 - column };
+                let color = self.tile_pixel(address, bit);
+
+                if color == 0 || (behind_background && colors[screen_x] != 0) {
+                    continue;
+                }
+
+                self.framebuffer[line * SCREEN_WIDTH + screen_x] = apply_palette(palette, color);
+            }
+        }
+    }
+
+    /// Returns the objects the PPU picks for `line`, which are the first ten in OAM order whose
+    /// rows cover it.
+    pub(super) fn line_objects<'a>(&self, line: usize, oam: &'a [u8]) -> Vec<&'a [u8]> {
+        let height = if self.control & ControlFlag::ObjectSize as u8 != 0 {
+            16
+        } else {
+            8
+        };
+
+        oam.chunks_exact(4)
+            .filter(|o| (o[0] as usize..o[0] as usize + height).contains(&(line + 16)))
+            .take(OBJECT_LIMIT)
+            .collect()
+    }
+
+    /// Returns `true` if the window covers part of `line`.
+    pub(super) fn is_window_on_line(&self, line: u8) -> bool {
+        let flag = |flag: ControlFlag| self.control & flag as u8 != 0;
+
+        // On the DMG, clearing the background enable bit hides the window too.
+        flag(ControlFlag::BackgroundWindowPriority)
+            && flag(ControlFlag::WindowEnabled)
+            && line >= self.window_y
+            && self.window_x <= 166
+    }
+
+    /// Returns the colour index of the pixel at `x`, `y` of the 256x256 pixel tile map at `map`.
+    fn tile_map_pixel(&self, control: u8, map: usize, x: usize, y: usize) -> u8 {
+        let index = self.vram_byte(map + (y / 8) * 32 + x / 8);
+
+        // Tiles are either numbered from $8000, or with a signed index from $9000.
+        let tile = if control & ControlFlag::BackgroundWindowDataArea as u8 != 0 {
+            index as usize * 16
+        } else {
+            (0x1000 + index as i8 as isize * 16) as usize
+        };
+
+        self.tile_pixel(tile + (y % 8) * 2, 7 - x % 8)
+    }
+
+    /// Returns the colour index of `bit` in the tile row at `address`.
+    fn tile_pixel(&self, address: usize, bit: usize) -> u8 {
+        let low = self.vram_byte(address) >> bit & 1;
+        let high = self.vram_byte(address + 1) >> bit & 1;
+
+        high << 1 | low
+    }
+
+    fn vram_byte(&self, address: usize) -> u8 {
+        self.vram.get_from(0, address).copied().unwrap_or_default()
+    }
+}
+
+fn apply_palette(palette: u8, color: u8) -> u8 {
+    palette >> (color * 2) & 0b11
+}
@@ -0,0 +1,113 @@
+//! Runs every test ROM in a directory and prints a compatibility matrix.
+//!
+//! ```text
+//! gbtest [-f frames] <directory or ROM>...
+//! ```
+//!
+//! The exit code is non-zero unless every ROM passed.
+
+use gb_interpreter::testrom::{Outcome, Runner};
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+const USAGE: &str = "usage: gbtest [-f frames] <directory or rom>...";
+
+fn collect(path: &Path, roms: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if path.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            collect(&entry?.path(), roms)?;
+        }
+    } else if path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("gb") || e.eq_ignore_ascii_case("gbc"))
+    {
+        roms.push(path.to_path_buf());
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<bool, String> {
+    let mut runner = Runner::new();
+    let mut paths = Vec::new();
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-f" | "--frames" => {
+                let frames = args
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(|| format!("{arg} expects a number of frames"))?;
+
+                runner.frame_limit(frames);
+            }
+            "-h" | "--help" => return Err(USAGE.into()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{arg}`\n{USAGE}")),
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+
+    if paths.is_empty() {
+        return Err(USAGE.into());
+    }
+
+    let mut roms = Vec::new();
+
+    for path in &paths {
+        collect(path, &mut roms).map_err(|e| format!("unable to read {}: {e}", path.display()))?;
+    }
+
+    roms.sort();
+
+    // Crashes are reported in the matrix, so the default hook would only add noise.
+    std::panic::set_hook(Box::new(|_| {}));
+
+    let names: Vec<_> = roms
+        .iter()
+        .map(|rom| {
+            paths
+                .iter()
+                .find_map(|base| rom.strip_prefix(base).ok())
+                .filter(|name| !name.as_os_str().is_empty())
+                .unwrap_or(rom)
+                .display()
+                .to_string()
+        })
+        .collect();
+
+    let width = names.iter().map(String::len).max().unwrap_or_default();
+    let mut passed = 0;
+
+    for (rom, name) in roms.iter().zip(&names) {
+        let report = runner.run_file(rom);
+
+        if report.outcome == Outcome::Pass {
+            passed += 1;
+        }
+
+        println!(
+            "{name:width$}  {:7}  {:>5}  {}",
+            report.outcome.to_string(),
+            report.frames,
+            report.detail()
+        );
+    }
+
+    println!("\n{passed}/{} passed", roms.len());
+
+    Ok(passed == roms.len())
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Runs a ROM and writes an execution trace in the gameboy-doctor format.
+//!
+//! ```text
+//! gbtrace [options] <rom>
+//! ```
+//!
+//! The trace goes to standard output unless `-o` is given. Addresses are hexadecimal.
+
+use gb_asm::{cdl::CodeDataLog, symbols::SymbolFile};
+use gb_hardware::Device;
+use gb_interpreter::{
+    debugger::expression::Expression,
+    trace::{Columns, RollingFile, Tracer},
+    Interpreter,
+};
+use std::{
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+const USAGE: &str = "usage: gbtrace [options] <rom>
+
+options:
+  -n <count>          stop after this many instructions (default 1000000)
+  -x, --extra         add the disassembly, ROM bank, LY and cycle count to each line
+  --range <from-to>   only log instructions between two addresses
+  --bank <bank>       only log instructions in a ROM bank
+  --after <expr>      start logging once a debugger expression is true, such as `PC == $150`
+  -s <file>           label the disassembly and crash reports with a .sym file
+  --cdl <file>        write a code/data log of how ROM was used, and print its summary
+  -o <file>           write the trace to a file
+  --max-size <bytes>  keep the file under this size, moving older lines to <file>.1";
+
+struct Options {
+    rom: PathBuf,
+    count: u64,
+    columns: Columns,
+    range: Option<(u16, u16)>,
+    bank: Option<usize>,
+    after: Option<Expression>,
+    symbols: SymbolFile,
+    coverage: Option<PathBuf>,
+    output: Option<PathBuf>,
+    max_size: Option<u64>,
+}
+
+fn parse_address(value: &str) -> Result<u16, String> {
+    let digits = value.trim_start_matches('$').trim_start_matches("0x");
+    u16::from_str_radix(digits, 16).map_err(|_| format!("invalid address `{value}`"))
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        rom: PathBuf::new(),
+        count: 1_000_000,
+        columns: Columns::default(),
+        range: None,
+        bank: None,
+        after: None,
+        symbols: SymbolFile::new(),
+        coverage: None,
+        output: None,
+        max_size: None,
+    };
+
+    let mut rom = None;
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("{arg} expects a value\n{USAGE}"))
+        };
+
+        match arg.as_str() {
+            "-n" => {
+                let count = value()?;
+                options.count = count
+                    .parse()
+                    .map_err(|_| format!("invalid count `{count}`"))?;
+            }
+            "-x" | "--extra" => options.columns = Columns::all(),
+            "--range" => {
+                let range = value()?;
+                let (from, to) = range
+                    .split_once('-')
+                    .ok_or_else(|| format!("invalid range `{range}`"))?;
+
+                options.range = Some((parse_address(from)?, parse_address(to)?));
+            }
+            "--bank" => {
+                let bank = value()?;
+                options.bank = Some(bank.parse().map_err(|_| format!("invalid bank `{bank}`"))?);
+            }
+            "--after" => {
+                let expression = value()?;
+                options.after = Some(Expression::parse(&expression).map_err(|e| e.to_string())?);
+            }
+            "-s" => {
+                let path = value()?;
+                let text = std::fs::read_to_string(&path)
+                    .map_err(|e| format!("unable to read {path}: {e}"))?;
+
+                options.symbols = text.parse().map_err(|e| format!("{path}: {e}"))?;
+            }
+            "--cdl" => options.coverage = Some(PathBuf::from(value()?)),
+            "-o" => options.output = Some(PathBuf::from(value()?)),
+            "--max-size" => {
+                let size = value()?;
+                options.max_size =
+                    Some(size.parse().map_err(|_| format!("invalid size `{size}`"))?);
+            }
+            "-h" | "--help" => return Err(USAGE.into()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{arg}`\n{USAGE}")),
+            _ => rom = Some(PathBuf::from(arg)),
+        }
+    }
+
+    options.rom = rom.ok_or(USAGE)?;
+
+    if options.max_size.is_some() && options.output.is_none() {
+        return Err("--max-size needs a file from -o".into());
+    }
+
+    Ok(options)
+}
+
+fn trace(options: Options, writer: impl Write) -> Result<(), String> {
+    let mut device = Device::from_file(&options.rom)
+        .map_err(|e| format!("unable to load {}: {e}", options.rom.display()))?;
+    let mut interpreter = Interpreter::default();
+
+    if options.coverage.is_some() {
+        device.enable_coverage();
+    }
+
+    let mut tracer = Tracer::new(writer);
+    tracer.columns(options.columns);
+
+    if let Some((from, to)) = options.range {
+        tracer.range(from..=to);
+    }
+
+    if let Some(bank) = options.bank {
+        tracer.bank(bank);
+    }
+
+    if let Some(after) = options.after {
+        tracer.trigger(after);
+    }
+
+    tracer.symbols(options.symbols.clone());
+
+    let error = |e: io::Error| format!("unable to write the trace: {e}");
+    let mut result = Ok(());
+
+    for _ in 0..options.count {
+        tracer.log(&device).map_err(error)?;
+
+        if let Err(mut report) = interpreter.try_step(&mut device) {
+            report.symbols = options.symbols;
+            result = Err(report.to_string());
+            break;
+        }
+    }
+
+    tracer.flush().map_err(error)?;
+
+    // The log is kept after a crash too, as it shows what ran up to it.
+    if let (Some(path), Some(log)) = (&options.coverage, &device.coverage) {
+        save_coverage(path, log)?;
+    }
+
+    result
+}
+
+/// Writes `log` to `path` and prints how much of each bank was used.
+fn save_coverage(path: &Path, log: &CodeDataLog) -> Result<(), String> {
+    std::fs::write(path, log.as_bytes())
+        .map_err(|e| format!("unable to write {}: {e}", path.display()))?;
+
+    for stats in log.stats() {
+        eprintln!("{stats}");
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<(), String> {
+    let options = parse_options()?;
+
+    match (&options.output, options.max_size) {
+        (Some(path), Some(limit)) => {
+            let file = RollingFile::create(path, limit)
+                .map_err(|e| format!("unable to create {}: {e}", path.display()))?;
+
+            trace(options, file)
+        }
+        (Some(path), None) => {
+            let file = std::fs::File::create(path)
+                .map_err(|e| format!("unable to create {}: {e}", path.display()))?;
+
+            trace(options, BufWriter::new(file))
+        }
+        (None, _) => trace(options, BufWriter::new(io::stdout().lock())),
+    }
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! A bus that sits between the interpreter and the device while it keeps a code/data log, and
+//! notes which bytes of ROM each instruction reads.
+
+use crate::StepInfo;
+use gb_asm::{cdl, symbols::Banks, Info};
+use gb_hardware::{
+    bus::{Bus, CpuState, RegisterAccess},
+    cpu::Cpu,
+    InterruptController,
+};
+use std::cell::RefCell;
+
+pub(crate) struct CoverageBus<'a, S: ?Sized> {
+    pub device: &'a mut S,
+    /// The addresses of the ROM bytes read so far, with their offsets into ROM. Reads only borrow
+    /// the bus, so this needs to be a `RefCell`.
+    pub reads: RefCell<Vec<(u16, usize)>>,
+}
+
+impl<'a, S: Bus + CpuState + ?Sized> CoverageBus<'a, S> {
+    pub fn new(device: &'a mut S) -> Self {
+        Self {
+            device,
+            reads: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Logs the bytes of the instruction that executed, and every other ROM byte it read as data.
+    /// The offsets were looked up as each byte was read, since the instruction may have switched
+    /// banks since.
+    pub fn finish(self, info: &StepInfo) {
+        let length = info.instruction.bytes() as u16;
+
+        for (address, offset) in self.reads.take() {
+            let flags = match address.wrapping_sub(info.address) {
+                0 => cdl::OPCODE,
+                position if position < length => cdl::OPERAND,
+                _ => cdl::DATA,
+            };
+
+            self.device.log_coverage(offset, flags);
+        }
+    }
+}
+
+impl<S: Bus + ?Sized> Bus for CoverageBus<'_, S> {
+    fn read_byte(&self, address: u16) -> u8 {
+        if let Some(offset) = self.device.rom_offset(address) {
+            self.reads.borrow_mut().push((address, offset));
+        }
+
+        self.device.read_byte(address)
+    }
+
+    fn write_byte(&mut self, address: u16, value: u8) {
+        self.device.write_byte(address, value);
+    }
+
+    fn tick(&mut self, cycles: u8) {
+        self.device.tick(cycles);
+    }
+
+    fn interrupts(&self) -> &InterruptController {
+        self.device.interrupts()
+    }
+
+    fn interrupts_mut(&mut self) -> &mut InterruptController {
+        self.device.interrupts_mut()
+    }
+
+    fn register_update(&mut self, address: u16, access: RegisterAccess, cycle: u8) {
+        self.device.register_update(address, access, cycle);
+    }
+
+    fn peek_byte(&self, address: u16) -> u8 {
+        self.device.peek_byte(address)
+    }
+
+    fn banks(&self) -> Option<Banks> {
+        self.device.banks()
+    }
+
+    fn logs_coverage(&self) -> bool {
+        self.device.logs_coverage()
+    }
+
+    fn rom_offset(&self, address: u16) -> Option<usize> {
+        self.device.rom_offset(address)
+    }
+
+    fn log_coverage(&mut self, offset: usize, flags: u8) {
+        self.device.log_coverage(offset, flags);
+    }
+}
+
+impl<S: CpuState + ?Sized> CpuState for CoverageBus<'_, S> {
+    fn cpu(&self) -> &Cpu {
+        self.device.cpu()
+    }
+
+    fn cpu_mut(&mut self) -> &mut Cpu {
+        self.device.cpu_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Interpreter;
+    use gb_asm::cdl::{DATA, DMA, OPCODE, OPERAND};
+    use gb_hardware::Device;
+
+    #[test]
+    fn logs_how_rom_is_used() {
+        let mut rom = vec![0; 0x8000];
+
+        // LD A, [$0300]; SWAP A; LD A, $02; LDH [$46], A
+        let code = [0xFA, 0x00, 0x03, 0xCB, 0x37, 0x3E, 0x02, 0xE0, 0x46];
+        rom[0x100..0x109].copy_from_slice(&code);
+
+        let mut device = Device::from_rom(rom).unwrap();
+        let mut interpreter = Interpreter::default();
+
+        interpreter.step(&mut device).unwrap();
+        assert!(device.coverage.is_none());
+
+        device.cpu.program_counter = 0x100;
+        device.enable_coverage();
+
+        for _ in 0..4 {
+            interpreter.step(&mut device).unwrap();
+        }
+
+        let log = device.coverage.as_ref().unwrap();
+        let flags: Vec<_> = (0x100..0x10A).map(|offset| log.flags(offset)).collect();
+
+        assert_eq!(
+            flags,
+            [OPCODE, OPERAND, OPERAND, OPCODE, OPERAND, OPCODE, OPERAND, OPCODE, OPERAND, 0]
+        );
+        assert_eq!(log.flags(0x300), DATA);
+        assert_eq!(log.flags(0x200), DMA);
+        assert_eq!(log.flags(0x29F), DMA);
+        assert_eq!(log.flags(0x2A0), 0);
+    }
+
+    #[test]
+    fn logs_instructions_that_switch_banks_to_the_bank_they_ran_from() {
+        // An MBC1 cartridge with four banks.
+        let mut rom = vec![0; 0x10000];
+        rom[0x147] = 0x01;
+        rom[0x148] = 0x01;
+
+        // LD A, $02; LD [$2000], A
+        let code = [0x3E, 0x02, 0xEA, 0x00, 0x20];
+        rom[0x4000..0x4005].copy_from_slice(&code);
+
+        let mut device = Device::from_rom(rom).unwrap();
+        let mut interpreter = Interpreter::default();
+
+        device.cpu.program_counter = 0x4000;
+        device.enable_coverage();
+
+        for _ in 0..2 {
+            interpreter.step(&mut device).unwrap();
+        }
+
+        let log = device.coverage.as_ref().unwrap();
+        let bank_1: Vec<_> = (0x4000..0x4006).map(|offset| log.flags(offset)).collect();
+        let bank_2: Vec<_> = (0x8000..0x8006).map(|offset| log.flags(offset)).collect();
+
+        assert_eq!(bank_1, [OPCODE, OPERAND, OPCODE, OPERAND, OPERAND, 0]);
+        assert_eq!(bank_2, [0; 6]);
+    }
+}
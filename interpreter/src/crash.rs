@@ -0,0 +1,169 @@
+//! Crash reports, which capture the state of the emulator when the CPU faults.
+
+use crate::history::{Entry, History};
+use gb_asm::symbols::{bank_of, Banks, SymbolFile};
+use gb_hardware::{cpu::Cpu, io, Device};
+use std::{any::Any, fmt::Display, path::Path};
+
+/// How many bytes of the stack a report includes, from SP up to the end of memory.
+const STACK_BYTES: u16 = 32;
+
+#[derive(Debug, Clone)]
+pub struct CrashReport {
+    /// What went wrong.
+    pub message: String,
+    /// The last instructions executed, the one that faulted last.
+    pub history: Vec<Entry>,
+    pub cpu: Cpu,
+    /// The bytes from SP up.
+    pub stack: Vec<u8>,
+    pub banks: Banks,
+    /// The I/O registers on this model, as address, name and value.
+    pub io_registers: Vec<(u16, &'static str, u8)>,
+    /// Labels for the addresses in the report, which is empty unless set.
+    pub symbols: SymbolFile,
+}
+
+impl CrashReport {
+    pub fn new(message: impl Into<String>, device: &Device, history: &History) -> Self {
+        let stack_pointer = device.cpu.stack_pointer;
+
+        Self {
+            message: message.into(),
+            history: history.entries().cloned().collect(),
+            cpu: device.cpu.clone(),
+            stack: (stack_pointer..=stack_pointer.saturating_add(STACK_BYTES - 1))
+                .map(|address| device.peek(address))
+                .collect(),
+            banks: device.banks(),
+            io_registers: io::REGISTERS
+                .iter()
+                .filter(|register| register.is_available(device.mode))
+                .map(|register| {
+                    let address = register.address as u16;
+                    (address, register.name, device.peek(address))
+                })
+                .collect(),
+            symbols: SymbolFile::new(),
+        }
+    }
+
+    /// Writes the report to `path` as text.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+}
+
+impl Display for CrashReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cpu = &self.cpu;
+
+        writeln!(f, "crash: {}", self.message)?;
+
+        writeln!(f, "\nregisters:")?;
+        writeln!(
+            f,
+            "  A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} \
+             PC:{:04X}",
+            cpu.a,
+            cpu.flags,
+            cpu.b,
+            cpu.c,
+            cpu.d,
+            cpu.e,
+            cpu.h,
+            cpu.l,
+            cpu.stack_pointer,
+            cpu.program_counter,
+        )?;
+
+        let pc = cpu.program_counter;
+
+        if let Some(location) = self.symbols.resolve(bank_of(pc, self.banks), pc) {
+            writeln!(f, "  PC is at {location}")?;
+        }
+
+        writeln!(
+            f,
+            "\nbanks:\n  ROM {:02X}, RAM {:02X}, WRAM {:X}",
+            self.banks.rom, self.banks.sram, self.banks.wram
+        )?;
+
+        writeln!(f, "\nstack:")?;
+
+        for (row, bytes) in self.stack.chunks(8).enumerate() {
+            let address = cpu.stack_pointer.wrapping_add(row as u16 * 8);
+            let bytes: Vec<_> = bytes.iter().map(|byte| format!("{byte:02X}")).collect();
+
+            writeln!(f, "  {address:04X}: {}", bytes.join(" "))?;
+        }
+
+        writeln!(f, "\nhistory (oldest first):")?;
+
+        for entry in &self.history {
+            write!(f, "  ")?;
+            entry.write(f, &self.symbols)?;
+            writeln!(f)?;
+        }
+
+        writeln!(f, "\nI/O registers:")?;
+
+        for (address, name, value) in &self.io_registers {
+            writeln!(f, "  {address:04X} {name:6} {value:02X}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the message of a panic, from the payload `catch_unwind` gives.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<String>()
+        .cloned()
+        .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+        .unwrap_or_else(|| "panicked".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Interpreter;
+    use gb_hardware::Device;
+
+    #[test]
+    fn reports_faults() {
+        let mut rom = vec![0; 0x8000];
+
+        // CALL $0200, where there's a STOP, which isn't supported.
+        rom[0x100..0x103].copy_from_slice(&[0xCD, 0x00, 0x02]);
+        rom[0x200..0x202].copy_from_slice(&[0x10, 0x00]);
+
+        let mut device = Device::from_rom(rom).unwrap();
+        let mut interpreter = Interpreter::default();
+
+        assert!(interpreter.try_step(&mut device).is_ok());
+
+        let report = interpreter.try_step(&mut device).unwrap_err();
+
+        assert_eq!(report.message, "STOP at $0200 is not supported yet");
+        assert_eq!(report.history.last().unwrap().pc, 0x200);
+        assert_eq!(report.stack[..2], [0x03, 0x01]);
+        assert_eq!(report.banks.rom, 1);
+        assert!(report
+            .io_registers
+            .iter()
+            .any(|&(_, name, _)| name == "LCDC"));
+
+        let text = report.to_string();
+        assert!(text.contains("   0100  CD 00 02  CALL $0200"));
+        assert!(text.contains("   0200  10 00     STOP"));
+
+        let mut report = report;
+        report.symbols = "00:0100 Start\n00:0200 Sleep".parse().unwrap();
+
+        let text = report.to_string();
+        assert!(text.contains("  PC is at Sleep\n"));
+        assert!(text.contains("   0100  CD 00 02  CALL Sleep "));
+        assert!(text.contains("  ; Start\n"));
+    }
+}
@@ -0,0 +1,107 @@
+//! A bus that sits between the interpreter and the device, and looks for accesses that hit a
+//! watchpoint.
+
+use super::{WatchHit, WatchKind, Watchpoint};
+use gb_asm::symbols::Banks;
+use gb_hardware::{
+    bus::{Bus, CpuState, RegisterAccess},
+    cpu::Cpu,
+    Device, InterruptController,
+};
+use std::{cell::Cell, collections::BTreeMap};
+
+pub(super) struct WatchedBus<'a> {
+    pub device: &'a mut Device,
+    pub watchpoints: &'a BTreeMap<u32, Watchpoint>,
+    /// The first access that hit a watchpoint. Reads only borrow the bus, so this needs to be a
+    /// `Cell`.
+    pub hit: Cell<Option<WatchHit>>,
+}
+
+impl WatchedBus<'_> {
+    fn check(&self, address: u16, value: u8, write: bool) {
+        if self.hit.get().is_some() {
+            return;
+        }
+
+        let hit = self.watchpoints.iter().find(|(_, watchpoint)| {
+            watchpoint.enabled
+                && watchpoint.range.contains(&address)
+                && match watchpoint.kind {
+                    WatchKind::Read => !write,
+                    WatchKind::Write => write,
+                    WatchKind::Access => true,
+                    WatchKind::Value(expected) => write && value == expected,
+                }
+        });
+
+        if let Some((&id, _)) = hit {
+            self.hit.set(Some(WatchHit {
+                id,
+                address,
+                value,
+                write,
+            }));
+        }
+    }
+}
+
+impl Bus for WatchedBus<'_> {
+    fn read_byte(&self, address: u16) -> u8 {
+        let value = self.device.read_byte(address);
+        self.check(address, value, false);
+
+        value
+    }
+
+    fn write_byte(&mut self, address: u16, value: u8) {
+        self.check(address, value, true);
+        self.device.write_byte(address, value);
+    }
+
+    fn tick(&mut self, cycles: u8) {
+        self.device.tick(cycles);
+    }
+
+    fn interrupts(&self) -> &InterruptController {
+        self.device.interrupts()
+    }
+
+    fn interrupts_mut(&mut self) -> &mut InterruptController {
+        self.device.interrupts_mut()
+    }
+
+    fn register_update(&mut self, address: u16, access: RegisterAccess, cycle: u8) {
+        self.device.register_update(address, access, cycle);
+    }
+
+    fn peek_byte(&self, address: u16) -> u8 {
+        self.device.peek(address)
+    }
+
+    fn banks(&self) -> Option<Banks> {
+        Some(self.device.banks())
+    }
+
+    fn logs_coverage(&self) -> bool {
+        self.device.logs_coverage()
+    }
+
+    fn rom_offset(&self, address: u16) -> Option<usize> {
+        self.device.rom_offset(address)
+    }
+
+    fn log_coverage(&mut self, offset: usize, flags: u8) {
+        self.device.log_coverage(offset, flags);
+    }
+}
+
+impl CpuState for WatchedBus<'_> {
+    fn cpu(&self) -> &Cpu {
+        &self.device.cpu
+    }
+
+    fn cpu_mut(&mut self) -> &mut Cpu {
+        &mut self.device.cpu
+    }
+}
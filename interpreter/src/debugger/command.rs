@@ -0,0 +1,180 @@
+//! Text commands that define breakpoints, tracepoints and watch expressions:
+//!
+//! ```text
+//! break [bank:]address [if condition]
+//! trace [bank:]address [if condition] log expression[, expression...]
+//! watch expression
+//! ```
+//!
+//! Addresses and banks are numbers in the assembler's syntax, such as `$4123` or `2:$4123`. A
+//! label from the symbol file, such as `Main.loop`, stands for its address and, in switchable
+//! memory, its bank.
+
+use super::expression::{Expression, ExpressionError};
+use gb_asm::{
+    assembler::lexer::{tokenize, Token},
+    symbols::{is_switched, SymbolFile},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Break {
+        address: u16,
+        bank: Option<usize>,
+        condition: Option<Expression>,
+    },
+    Trace {
+        address: u16,
+        bank: Option<usize>,
+        condition: Option<Expression>,
+        log: Vec<Expression>,
+    },
+    Watch(Expression),
+}
+
+impl Command {
+    /// Parses a command, looking up labels in `symbols`.
+    pub fn parse(line: &str, symbols: &SymbolFile) -> Result<Self, ExpressionError> {
+        let line = line.trim();
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+
+        match keyword.to_ascii_lowercase().as_str() {
+            "break" => {
+                let (location, condition) = split_keyword(rest, "if");
+                let (address, bank) = parse_location(location, symbols)?;
+
+                Ok(Self::Break {
+                    address,
+                    bank,
+                    condition: condition.map(Expression::parse).transpose()?,
+                })
+            }
+            "trace" => {
+                let (rest, log) = split_keyword(rest, "log");
+                let log = log.ok_or_else(|| syntax("tracepoints need something to log"))?;
+                let (location, condition) = split_keyword(rest, "if");
+                let (address, bank) = parse_location(location, symbols)?;
+
+                Ok(Self::Trace {
+                    address,
+                    bank,
+                    condition: condition.map(Expression::parse).transpose()?,
+                    log: log
+                        .split(',')
+                        .map(Expression::parse)
+                        .collect::<Result<_, _>>()?,
+                })
+            }
+            "watch" => Ok(Self::Watch(Expression::parse(rest)?)),
+            _ => Err(syntax(&format!("unknown command `{keyword}`"))),
+        }
+    }
+}
+
+/// Splits `text` around the first occurrence of `keyword` as a separate word.
+fn split_keyword<'a>(text: &'a str, keyword: &str) -> (&'a str, Option<&'a str>) {
+    let mut offset = 0;
+
+    for word in text.split_whitespace() {
+        // `split_whitespace` doesn't give positions, so find the word after the last one.
+        let start = offset + text[offset..].find(word).unwrap_or_default();
+        offset = start + word.len();
+
+        if word.eq_ignore_ascii_case(keyword) {
+            return (&text[..start], Some(&text[offset..]));
+        }
+    }
+
+    (text, None)
+}
+
+/// Parses `address`, `bank:address` or a label.
+fn parse_location(
+    location: &str,
+    symbols: &SymbolFile,
+) -> Result<(u16, Option<usize>), ExpressionError> {
+    let tokens = tokenize(location).map_err(|e| ExpressionError::Syntax(e.to_string()))?;
+
+    let (bank, address) = match tokens.as_slice() {
+        [Token::Ident(name)] => {
+            let symbol = symbols
+                .find(name)
+                .ok_or_else(|| syntax(&format!("unknown label `{name}`")))?;
+
+            let bank = is_switched(symbol.address).then_some(symbol.bank as usize);
+            return Ok((symbol.address, bank));
+        }
+        [Token::Number(address)] => (None, *address),
+        [Token::Number(bank), Token::Colon, Token::Number(address)] => {
+            let bank = usize::try_from(*bank).map_err(|_| syntax("invalid bank"))?;
+            (Some(bank), *address)
+        }
+        _ => return Err(syntax(&format!("invalid location `{}`", location.trim()))),
+    };
+
+    let address = u16::try_from(address).map_err(|_| syntax("addresses must fit in 16 bits"))?;
+
+    Ok((address, bank))
+}
+
+fn syntax(message: &str) -> ExpressionError {
+    ExpressionError::Syntax(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Command, ExpressionError> {
+        let symbols = "00:0150 Main\n02:4010 Main.loop\n03:a000 sSave"
+            .parse()
+            .unwrap();
+        Command::parse(line, &symbols)
+    }
+
+    #[test]
+    fn parses_commands() {
+        let Command::Break {
+            address,
+            bank,
+            condition,
+        } = parse("break $4123 if A == $10 && [HL] > 3 && hits % 5 == 0").unwrap()
+        else {
+            panic!("expected a breakpoint");
+        };
+
+        assert_eq!((address, bank), (0x4123, None));
+        assert_eq!(
+            condition.unwrap().source(),
+            "A == $10 && [HL] > 3 && hits % 5 == 0"
+        );
+
+        let Command::Trace {
+            address, bank, log, ..
+        } = parse("TRACE 2:$4000 log A, [HL]").unwrap()
+        else {
+            panic!("expected a tracepoint");
+        };
+
+        assert_eq!((address, bank), (0x4000, Some(2)));
+        assert_eq!(log.len(), 2);
+
+        assert!(parse("watch [$C000]").is_ok());
+        assert!(parse("break").is_err());
+        assert!(parse("trace $100").is_err());
+        assert!(parse("break $10000").is_err());
+    }
+
+    #[test]
+    fn resolves_labels() {
+        let location = |line| match parse(line).unwrap() {
+            Command::Break { address, bank, .. } => (address, bank),
+            _ => panic!("expected a breakpoint"),
+        };
+
+        assert_eq!(location("break Main"), (0x0150, None));
+        assert_eq!(location("break Main.loop if A == 0"), (0x4010, Some(2)));
+        assert_eq!(location("break sSave"), (0xA000, Some(3)));
+        assert!(parse("break Missing").is_err());
+    }
+}
@@ -0,0 +1,307 @@
+//! Expressions for conditional breakpoints, tracepoints and watch expressions.
+//!
+//! They use the assembler's syntax for numbers and operators, and can refer to:
+//!
+//! - the registers `A`, `F`, `B`, `C`, `D`, `E`, `H` and `L`, and the pairs `AF`, `BC`, `DE`,
+//!   `HL`, `SP` and `PC`
+//! - the flags `ZF`, `NF`, `HF` and `CF`, which are 0 or 1
+//! - memory, with the address in brackets, as in `[HL]` or `[$C0A0]`
+//! - `ROMBANK` and `RAMBANK`, the cartridge banks currently mapped, and `LY`
+//! - `HITS`, the number of times the breakpoint has been reached, including this time
+//!
+//! Names are case-insensitive, and comparisons and logical operators evaluate to 0 or 1.
+
+use gb_asm::{
+    assembler::{
+        expr::binary_op,
+        lexer::{tokenize, Token},
+    },
+    object::{BinaryOp, ExprError, UnaryOp},
+    Flag, Pair, Register,
+};
+use gb_hardware::Device;
+use std::fmt::Display;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ExpressionError {
+    #[error("syntax error: {0}")]
+    Syntax(String),
+    #[error("{0}")]
+    Evaluation(#[from] ExprError),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Variable {
+    Register(Register),
+    Flags,
+    Pair(Pair),
+    AccumulatorAndFlags,
+    StackPointer,
+    ProgramCounter,
+    Flag(Flag),
+    RomBank,
+    RamBank,
+    Line,
+    Hits,
+}
+
+impl Variable {
+    fn from_name(name: &str) -> Option<Self> {
+        let variable = match name.to_ascii_uppercase().as_str() {
+            "A" => Self::Register(Register::A),
+            "B" => Self::Register(Register::B),
+            "C" => Self::Register(Register::C),
+            "D" => Self::Register(Register::D),
+            "E" => Self::Register(Register::E),
+            "H" => Self::Register(Register::H),
+            "L" => Self::Register(Register::L),
+            "F" => Self::Flags,
+            "AF" => Self::AccumulatorAndFlags,
+            "BC" => Self::Pair(Pair::BC),
+            "DE" => Self::Pair(Pair::DE),
+            "HL" => Self::Pair(Pair::HL),
+            "SP" => Self::StackPointer,
+            "PC" => Self::ProgramCounter,
+            "ZF" => Self::Flag(Flag::Zero),
+            "NF" => Self::Flag(Flag::Subtract),
+            "HF" => Self::Flag(Flag::HalfCarry),
+            "CF" => Self::Flag(Flag::Carry),
+            "ROMBANK" => Self::RomBank,
+            "RAMBANK" => Self::RamBank,
+            "LY" => Self::Line,
+            "HITS" => Self::Hits,
+            _ => return None,
+        };
+
+        Some(variable)
+    }
+
+    fn value(&self, device: &Device, hits: u32) -> i64 {
+        let cpu = &device.cpu;
+
+        match self {
+            Self::Register(r) => cpu.get(r) as i64,
+            Self::Flags => cpu.flags as i64,
+            Self::Pair(p) => cpu.get(p) as i64,
+            Self::AccumulatorAndFlags => (cpu.a as i64) << 8 | cpu.flags as i64,
+            Self::StackPointer => cpu.stack_pointer as i64,
+            Self::ProgramCounter => cpu.program_counter as i64,
+            Self::Flag(f) => cpu.get(f) as i64,
+            Self::RomBank => device.memory.cartridge.rom_bank() as i64,
+            Self::RamBank => device.memory.cartridge.ram_bank() as i64,
+            Self::Line => device.video.current_line as i64,
+            Self::Hits => hits as i64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Number(i64),
+    Variable(Variable),
+    /// The byte at an address.
+    Memory(Box<Node>),
+    Unary(UnaryOp, Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+}
+
+impl Node {
+    fn evaluate(&self, device: &Device, hits: u32) -> Result<i64, ExprError> {
+        let value = match self {
+            Self::Number(n) => *n,
+            Self::Variable(variable) => variable.value(device, hits),
+            Self::Memory(address) => device.peek(address.evaluate(device, hits)? as u16) as i64,
+            Self::Unary(op, inner) => {
+                let inner = inner.evaluate(device, hits)?;
+
+                match op {
+                    UnaryOp::Negate => inner.wrapping_neg(),
+                    UnaryOp::Complement => !inner,
+                    UnaryOp::Not => (inner == 0) as i64,
+                }
+            }
+            // Both sides are always evaluated, which is fine since neither has side effects.
+            Self::Binary(op, lhs, rhs) => {
+                op.apply(lhs.evaluate(device, hits)?, rhs.evaluate(device, hits)?)?
+            }
+        };
+
+        Ok(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expression {
+    source: String,
+    root: Node,
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Self, ExpressionError> {
+        let tokens = tokenize(source).map_err(|e| ExpressionError::Syntax(e.to_string()))?;
+
+        let mut parser = Parser {
+            tokens: &tokens,
+            pos: 0,
+        };
+        let root = parser.parse_binary(0)?;
+
+        if let Some(token) = parser.peek() {
+            return Err(ExpressionError::Syntax(format!(
+                "unexpected {token:?} in expression"
+            )));
+        }
+
+        Ok(Self {
+            source: source.trim().to_string(),
+            root,
+        })
+    }
+
+    /// Evaluates the expression for `device`, with `hits` as the value of `HITS`.
+    pub fn evaluate(&self, device: &Device, hits: u32) -> Result<i64, ExpressionError> {
+        Ok(self.root.evaluate(device, hits)?)
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), ExpressionError> {
+        match self.next() {
+            Some(token) if *token == expected => Ok(()),
+            token => Err(ExpressionError::Syntax(format!(
+                "expected {expected:?}, found {token:?}"
+            ))),
+        }
+    }
+
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<Node, ExpressionError> {
+        let mut lhs = self.parse_unary()?;
+
+        while let Some((op, precedence)) = self.peek().and_then(binary_op) {
+            if precedence < min_precedence {
+                break;
+            }
+
+            self.pos += 1;
+
+            let rhs = self.parse_binary(precedence + 1)?;
+            lhs = Node::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Node, ExpressionError> {
+        let op = match self.peek() {
+            Some(Token::Minus) => UnaryOp::Negate,
+            Some(Token::Tilde) => UnaryOp::Complement,
+            Some(Token::Bang) => UnaryOp::Not,
+            Some(Token::Plus) => {
+                self.pos += 1;
+                return self.parse_unary();
+            }
+            _ => return self.parse_primary(),
+        };
+
+        self.pos += 1;
+
+        Ok(Node::Unary(op, Box::new(self.parse_unary()?)))
+    }
+
+    fn parse_primary(&mut self) -> Result<Node, ExpressionError> {
+        let Some(token) = self.next().cloned() else {
+            return Err(ExpressionError::Syntax("expected an expression".into()));
+        };
+
+        match token {
+            Token::Number(n) => Ok(Node::Number(n)),
+            Token::LeftParen => {
+                let node = self.parse_binary(0)?;
+                self.expect(Token::RightParen)?;
+
+                Ok(node)
+            }
+            Token::LeftBracket => {
+                let address = self.parse_binary(0)?;
+                self.expect(Token::RightBracket)?;
+
+                Ok(Node::Memory(Box::new(address)))
+            }
+            Token::Ident(name) => Variable::from_name(&name)
+                .map(Node::Variable)
+                .ok_or_else(|| ExpressionError::Syntax(format!("unknown name `{name}`"))),
+            token => Err(ExpressionError::Syntax(format!(
+                "unexpected {token:?} in expression"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_against_device() {
+        let mut device = Device::from_rom(vec![0; 0x8000]).unwrap();
+        device.cpu.a = 0x10;
+        device.cpu.h = 0xC0;
+        device.cpu.l = 0xA0;
+        device.cpu.flags = 0x80;
+        device.poke(0xC0A0, 4);
+
+        let evaluate = |source: &str, hits: u32| {
+            Expression::parse(source)
+                .unwrap()
+                .evaluate(&device, hits)
+                .unwrap()
+        };
+
+        assert_eq!(evaluate("A == $10 && [HL] > 3 && hits % 5 == 0", 10), 1);
+        assert_eq!(evaluate("A == $10 && [HL] > 3 && hits % 5 == 0", 11), 0);
+        assert_eq!(evaluate("[$C0A0] + [hl + 1] * 2", 0), 4);
+        assert_eq!(evaluate("ZF << 1 | CF", 0), 2);
+        assert_eq!(evaluate("rombank == 1 && ly == 0", 0), 1);
+
+        assert!(matches!(
+            Expression::parse("A == "),
+            Err(ExpressionError::Syntax(_))
+        ));
+        assert!(matches!(
+            Expression::parse("Q + 1"),
+            Err(ExpressionError::Syntax(_))
+        ));
+        assert_eq!(
+            Expression::parse("1 / (A - $10)")
+                .unwrap()
+                .evaluate(&device, 0),
+            Err(ExpressionError::Evaluation(ExprError::DivisionByZero))
+        );
+    }
+}
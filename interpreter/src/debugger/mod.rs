@@ -0,0 +1,645 @@
+//! An interactive debugger, which runs the interpreter until a breakpoint or watchpoint is hit, or
+//! until a stepping command is done.
+//!
+//! Every command takes a limit on the number of instructions to execute, after which it stops with
+//! [`Stop::Limit`]. Front ends can use it to stay responsive while the program runs.
+//!
+//! Breakpoints can have a condition, written as an [`Expression`], and tracepoints are
+//! breakpoints that log the values of expressions instead of stopping. Watch expressions stop
+//! execution when their value changes.
+
+use crate::{Interpreter, StepError};
+use bus::WatchedBus;
+use command::Command;
+use expression::{Expression, ExpressionError};
+use gb_asm::{
+    instructions::Instruction,
+    symbols::{bank_of, is_switched, SymbolFile},
+    Info,
+};
+use gb_hardware::{cpu::State, Device};
+use gb_parser::parse;
+use std::{cell::Cell, collections::BTreeMap, ops::RangeInclusive};
+
+mod bus;
+pub mod command;
+pub mod expression;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breakpoint {
+    pub address: u16,
+    /// The bank the breakpoint is in. This only matters for addresses in switchable ROM, cartridge
+    /// RAM or WRAM, and breaks in every bank when `None`.
+    pub bank: Option<usize>,
+    pub enabled: bool,
+    /// The number of times execution has reached the breakpoint, whether or not its condition
+    /// held.
+    pub hits: u32,
+    /// An expression that must be non-zero for the breakpoint to trigger.
+    pub condition: Option<Expression>,
+    /// The expressions to log when the breakpoint triggers, which makes it a tracepoint that
+    /// doesn't stop execution.
+    pub log: Option<Vec<Expression>>,
+}
+
+impl Breakpoint {
+    fn new(address: u16, bank: Option<usize>) -> Self {
+        Self {
+            address,
+            bank,
+            enabled: true,
+            hits: 0,
+            condition: None,
+            log: None,
+        }
+    }
+
+    fn matches(&self, device: &Device) -> bool {
+        let pc = device.cpu.program_counter;
+
+        self.enabled && self.address == pc && bank_matches(self.bank, pc, device)
+    }
+
+    /// Counts a hit and returns `true` if the condition holds. A condition that can't be
+    /// evaluated also triggers the breakpoint, so that the problem gets noticed.
+    fn trigger(&mut self, device: &Device) -> bool {
+        self.hits += 1;
+
+        self.condition
+            .as_ref()
+            .is_none_or(|condition| condition.evaluate(device, self.hits) != Ok(0))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    /// Both reads and writes.
+    Access,
+    /// Writes of this value.
+    Value(u8),
+}
+
+/// Stops execution when the CPU accesses an address in `range`. Instruction fetches count as
+/// reads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub range: RangeInclusive<u16>,
+    pub kind: WatchKind,
+    pub enabled: bool,
+}
+
+/// Stops execution when the value of an expression changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchExpression {
+    pub expression: Expression,
+    pub enabled: bool,
+    /// The value when the expression was last evaluated.
+    pub value: Option<Result<i64, ExpressionError>>,
+}
+
+/// A line logged by a tracepoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    /// The ID of the tracepoint.
+    pub id: u32,
+    pub address: u16,
+    pub message: String,
+}
+
+/// An access that hit a watchpoint.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WatchHit {
+    /// The ID of the watchpoint.
+    pub id: u32,
+    pub address: u16,
+    /// The value read or written.
+    pub value: u8,
+    pub write: bool,
+}
+
+/// Why a command stopped.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Stop {
+    /// The command finished, such as when a step-over reaches the next instruction.
+    Done,
+    /// Execution reached the breakpoint with this ID. The instruction there hasn't executed yet.
+    Breakpoint(u32),
+    /// The last instruction hit a watchpoint.
+    Watchpoint(WatchHit),
+    /// The last instruction changed the value of the watch expression with this ID.
+    Expression(u32),
+    /// The instruction limit was reached.
+    Limit,
+    /// The instruction at PC couldn't be executed.
+    Fault(StepError),
+    /// The CPU locked up on the illegal opcode at PC. Execution can continue, but only the rest
+    /// of the system runs.
+    Locked,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Debugger {
+    pub interpreter: Interpreter,
+    breakpoints: BTreeMap<u32, Breakpoint>,
+    watchpoints: BTreeMap<u32, Watchpoint>,
+    expressions: BTreeMap<u32, WatchExpression>,
+    trace: Vec<TraceEntry>,
+    symbols: SymbolFile,
+    next_id: u32,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a breakpoint at `address`, optionally only in bank `bank`, and returns its ID.
+    pub fn add_breakpoint(&mut self, address: u16, bank: Option<usize>) -> u32 {
+        self.insert_breakpoint(Breakpoint::new(address, bank))
+    }
+
+    /// Adds a breakpoint that only triggers when `condition` is non-zero, and returns its ID.
+    pub fn add_conditional_breakpoint(
+        &mut self,
+        address: u16,
+        bank: Option<usize>,
+        condition: Expression,
+    ) -> u32 {
+        self.insert_breakpoint(Breakpoint {
+            condition: Some(condition),
+            ..Breakpoint::new(address, bank)
+        })
+    }
+
+    /// Adds a tracepoint, which logs the values of `log` instead of stopping, and returns its ID.
+    pub fn add_tracepoint(
+        &mut self,
+        address: u16,
+        bank: Option<usize>,
+        condition: Option<Expression>,
+        log: Vec<Expression>,
+    ) -> u32 {
+        self.insert_breakpoint(Breakpoint {
+            condition,
+            log: Some(log),
+            ..Breakpoint::new(address, bank)
+        })
+    }
+
+    pub fn remove_breakpoint(&mut self, id: u32) -> Option<Breakpoint> {
+        self.breakpoints.remove(&id)
+    }
+
+    pub fn breakpoint_mut(&mut self, id: u32) -> Option<&mut Breakpoint> {
+        self.breakpoints.get_mut(&id)
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = (u32, &Breakpoint)> {
+        self.breakpoints.iter().map(|(&id, b)| (id, b))
+    }
+
+    /// Adds a watchpoint on `range` and returns its ID.
+    pub fn add_watchpoint(&mut self, range: RangeInclusive<u16>, kind: WatchKind) -> u32 {
+        let id = self.next_id();
+        self.watchpoints.insert(
+            id,
+            Watchpoint {
+                range,
+                kind,
+                enabled: true,
+            },
+        );
+
+        id
+    }
+
+    pub fn remove_watchpoint(&mut self, id: u32) -> Option<Watchpoint> {
+        self.watchpoints.remove(&id)
+    }
+
+    pub fn watchpoint_mut(&mut self, id: u32) -> Option<&mut Watchpoint> {
+        self.watchpoints.get_mut(&id)
+    }
+
+    pub fn watchpoints(&self) -> impl Iterator<Item = (u32, &Watchpoint)> {
+        self.watchpoints.iter().map(|(&id, w)| (id, w))
+    }
+
+    /// Adds a watch expression and returns its ID.
+    pub fn add_watch_expression(&mut self, expression: Expression) -> u32 {
+        let id = self.next_id();
+        self.expressions.insert(
+            id,
+            WatchExpression {
+                expression,
+                enabled: true,
+                value: None,
+            },
+        );
+
+        id
+    }
+
+    pub fn remove_watch_expression(&mut self, id: u32) -> Option<WatchExpression> {
+        self.expressions.remove(&id)
+    }
+
+    pub fn watch_expressions(&self) -> impl Iterator<Item = (u32, &WatchExpression)> {
+        self.expressions.iter().map(|(&id, e)| (id, e))
+    }
+
+    /// Sets the labels that commands can use in place of addresses.
+    pub fn set_symbols(&mut self, symbols: SymbolFile) {
+        self.symbols = symbols;
+    }
+
+    pub fn symbols(&self) -> &SymbolFile {
+        &self.symbols
+    }
+
+    /// Adds whatever `line` defines, as described in [`command`], and returns its ID.
+    pub fn add_command(&mut self, line: &str) -> Result<u32, ExpressionError> {
+        let id = match Command::parse(line, &self.symbols)? {
+            Command::Break {
+                address,
+                bank,
+                condition: Some(condition),
+            } => self.add_conditional_breakpoint(address, bank, condition),
+            Command::Break { address, bank, .. } => self.add_breakpoint(address, bank),
+            Command::Trace {
+                address,
+                bank,
+                condition,
+                log,
+            } => self.add_tracepoint(address, bank, condition, log),
+            Command::Watch(expression) => self.add_watch_expression(expression),
+        };
+
+        Ok(id)
+    }
+
+    /// Returns the lines logged by tracepoints since the last call.
+    pub fn take_trace(&mut self) -> Vec<TraceEntry> {
+        std::mem::take(&mut self.trace)
+    }
+
+    /// Executes a single instruction.
+    pub fn step_into(&mut self, device: &mut Device) -> Stop {
+        self.run(device, 1, |_, _| true)
+    }
+
+    /// Executes a single instruction, unless it's a `CALL` or `RST`, in which case execution
+    /// continues until the subroutine returns.
+    pub fn step_over(&mut self, device: &mut Device, limit: u64) -> Stop {
+        let pc = device.cpu.program_counter;
+
+        let call @ Instruction::Call(_) = decode(device, pc) else {
+            return self.step_into(device);
+        };
+
+        let return_address = pc.wrapping_add(call.bytes() as u16);
+        let stack_pointer = device.cpu.stack_pointer;
+
+        // A recursive call can come back to the same address with a deeper stack, so the stack
+        // pointer tells them apart.
+        self.run(device, limit, |device, _| {
+            device.cpu.program_counter == return_address
+                && device.cpu.stack_pointer >= stack_pointer
+        })
+    }
+
+    /// Continues until the current subroutine returns.
+    pub fn step_out(&mut self, device: &mut Device, limit: u64) -> Stop {
+        let stack_pointer = device.cpu.stack_pointer;
+
+        self.run(device, limit, |device, executed| {
+            matches!(executed, Instruction::Return(_)) && device.cpu.stack_pointer > stack_pointer
+        })
+    }
+
+    /// Continues until execution reaches `address`, optionally only in bank `bank`.
+    pub fn run_to(
+        &mut self,
+        device: &mut Device,
+        address: u16,
+        bank: Option<usize>,
+        limit: u64,
+    ) -> Stop {
+        self.run(device, limit, |device, _| {
+            let pc = device.cpu.program_counter;
+            pc == address && bank_matches(bank, pc, device)
+        })
+    }
+
+    /// Continues until a breakpoint or watchpoint is hit.
+    pub fn resume(&mut self, device: &mut Device, limit: u64) -> Stop {
+        self.run(device, limit, |_, _| false)
+    }
+
+    /// Continues until `done` returns `true` after an instruction, such as at the end of a frame.
+    /// A breakpoint at the address it stops at is reported instead of [`Stop::Done`], since the
+    /// next command would skip it.
+    pub fn run_until(
+        &mut self,
+        device: &mut Device,
+        limit: u64,
+        mut done: impl FnMut(&Device) -> bool,
+    ) -> Stop {
+        match self.run(device, limit, |device, _| done(device)) {
+            Stop::Done => self
+                .hit_breakpoint(device)
+                .map_or(Stop::Done, Stop::Breakpoint),
+            stop => stop,
+        }
+    }
+
+    /// Executes up to `limit` instructions, stopping early when `done` returns `true` after one
+    /// of them. `done` is given the instruction that was at PC before the step.
+    fn run(
+        &mut self,
+        device: &mut Device,
+        limit: u64,
+        mut done: impl FnMut(&Device, Instruction) -> bool,
+    ) -> Stop {
+        self.update_expressions(device);
+
+        for count in 0..limit {
+            // Execution always moves past the breakpoint it's resumed from.
+            if count > 0 {
+                if let Some(id) = self.hit_breakpoint(device) {
+                    return Stop::Breakpoint(id);
+                }
+            }
+
+            let instruction = decode(device, device.cpu.program_counter);
+            let locked = matches!(device.cpu.state, State::Locked(_));
+
+            match self.step(device) {
+                Ok(Some(hit)) => return Stop::Watchpoint(hit),
+                Ok(None) => (),
+                Err(error) => return Stop::Fault(error),
+            }
+
+            if !locked && matches!(device.cpu.state, State::Locked(_)) {
+                return Stop::Locked;
+            }
+
+            if let Some(id) = self.update_expressions(device) {
+                return Stop::Expression(id);
+            }
+
+            if done(device, instruction) {
+                return Stop::Done;
+            }
+        }
+
+        // The next command skips breakpoints on its first instruction, so one there has to be
+        // reported now for a run split into several commands to stop at it.
+        if limit > 0 {
+            if let Some(id) = self.hit_breakpoint(device) {
+                return Stop::Breakpoint(id);
+            }
+        }
+
+        Stop::Limit
+    }
+
+    /// Executes one instruction, returning the first access that hit a watchpoint.
+    fn step(&mut self, device: &mut Device) -> Result<Option<WatchHit>, StepError> {
+        let mut bus = WatchedBus {
+            device,
+            watchpoints: &self.watchpoints,
+            hit: Cell::new(None),
+        };
+
+        self.interpreter.step(&mut bus)?;
+        Ok(bus.hit.get())
+    }
+
+    /// Triggers the breakpoints at PC, logging tracepoints, and returns the ID of the first one
+    /// that stops execution.
+    fn hit_breakpoint(&mut self, device: &Device) -> Option<u32> {
+        let mut stop = None;
+
+        for (&id, breakpoint) in &mut self.breakpoints {
+            if !breakpoint.matches(device) || !breakpoint.trigger(device) {
+                continue;
+            }
+
+            let Some(log) = &breakpoint.log else {
+                stop = stop.or(Some(id));
+                continue;
+            };
+
+            let message = log
+                .iter()
+                .map(
+                    |expression| match expression.evaluate(device, breakpoint.hits) {
+                        Ok(value) => format!("{expression}=${value:X}"),
+                        Err(e) => format!("{expression}=<{e}>"),
+                    },
+                )
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            self.trace.push(TraceEntry {
+                id,
+                address: breakpoint.address,
+                message,
+            });
+        }
+
+        stop
+    }
+
+    /// Evaluates the watch expressions, returning the ID of the first one whose value changed.
+    fn update_expressions(&mut self, device: &Device) -> Option<u32> {
+        let mut changed = None;
+
+        for (&id, watch) in &mut self.expressions {
+            if !watch.enabled {
+                continue;
+            }
+
+            let value = Some(watch.expression.evaluate(device, 0));
+
+            if watch.value.is_some() && watch.value != value {
+                changed = changed.or(Some(id));
+            }
+
+            watch.value = value;
+        }
+
+        changed
+    }
+
+    fn insert_breakpoint(&mut self, breakpoint: Breakpoint) -> u32 {
+        let id = self.next_id();
+        self.breakpoints.insert(id, breakpoint);
+
+        id
+    }
+
+    fn next_id(&mut self) -> u32 {
+        self.next_id += 1;
+        self.next_id
+    }
+}
+
+/// Returns `true` if `bank` is the bank mapped at `address`, or doesn't matter there.
+fn bank_matches(bank: Option<usize>, address: u16, device: &Device) -> bool {
+    match bank {
+        Some(bank) if is_switched(address) => bank_of(address, device.banks()) == bank as u32,
+        _ => true,
+    }
+}
+
+/// Decodes the instruction at `address` without side effects. Prefixed instructions are never
+/// calls or returns, so they aren't decoded any further.
+fn decode(device: &Device, address: u16) -> Instruction {
+    parse(device.peek(address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A program that calls a subroutine at `$0200`, which stores A to `$C000`, then loops
+    /// forever at `$4000`.
+    fn device() -> Device {
+        let mut rom = vec![0; 0x8000];
+
+        // CALL $0200; JP $4000
+        rom[0x100..0x106].copy_from_slice(&[0xCD, 0x00, 0x02, 0xC3, 0x00, 0x40]);
+        // LD A, $34; LD [$C000], A; RET
+        rom[0x200..0x206].copy_from_slice(&[0x3E, 0x34, 0xEA, 0x00, 0xC0, 0xC9]);
+        // JR -2
+        rom[0x4000..0x4002].copy_from_slice(&[0x18, 0xFE]);
+
+        Device::from_rom(rom).unwrap()
+    }
+
+    #[test]
+    fn step_over_and_out() {
+        let mut device = device();
+        let mut debugger = Debugger::new();
+
+        assert_eq!(debugger.step_over(&mut device, 100), Stop::Done);
+        assert_eq!(device.cpu.program_counter, 0x103);
+        assert_eq!(device.cpu.a, 0x34);
+
+        let mut device = self::device();
+        assert_eq!(debugger.step_into(&mut device), Stop::Done);
+        assert_eq!(device.cpu.program_counter, 0x200);
+
+        assert_eq!(debugger.step_out(&mut device, 100), Stop::Done);
+        assert_eq!(device.cpu.program_counter, 0x103);
+    }
+
+    #[test]
+    fn breakpoints_and_watchpoints() {
+        let mut device = device();
+        let mut debugger = Debugger::new();
+
+        let breakpoint = debugger.add_breakpoint(0x202, None);
+        let watchpoint = debugger.add_watchpoint(0xC000..=0xC0FF, WatchKind::Value(0x34));
+
+        assert_eq!(
+            debugger.resume(&mut device, 100),
+            Stop::Breakpoint(breakpoint)
+        );
+        assert_eq!(device.cpu.program_counter, 0x202);
+
+        assert_eq!(
+            debugger.resume(&mut device, 100),
+            Stop::Watchpoint(WatchHit {
+                id: watchpoint,
+                address: 0xC000,
+                value: 0x34,
+                write: true,
+            })
+        );
+        assert_eq!(device.cpu.program_counter, 0x205);
+
+        // Breakpoints in the switchable bank only break in their bank.
+        debugger.add_breakpoint(0x4000, Some(2));
+        assert_eq!(debugger.run_to(&mut device, 0x4000, None, 100), Stop::Done);
+        assert_eq!(debugger.resume(&mut device, 100), Stop::Limit);
+
+        let breakpoint = debugger.add_breakpoint(0x4000, Some(1));
+        assert_eq!(
+            debugger.resume(&mut device, 100),
+            Stop::Breakpoint(breakpoint)
+        );
+        assert_eq!(debugger.breakpoint_mut(breakpoint).unwrap().hits, 1);
+
+        // Runs split at a breakpoint still stop there.
+        assert_eq!(
+            debugger.run_until(&mut device, 100, |device| device.cpu.program_counter
+                == 0x4000),
+            Stop::Breakpoint(breakpoint)
+        );
+        assert_eq!(
+            debugger.resume(&mut device, 1),
+            Stop::Breakpoint(breakpoint)
+        );
+    }
+
+    #[test]
+    fn conditions_tracepoints_and_watch_expressions() {
+        let mut device = device();
+        let mut debugger = Debugger::new();
+
+        // The loop at $4000 is reached once per instruction after the subroutine returns.
+        let trace = debugger
+            .add_command("trace $4000 if A == $34 log A, hits")
+            .unwrap();
+        let breakpoint = debugger
+            .add_command("break $4000 if hits % 3 == 0")
+            .unwrap();
+
+        assert_eq!(
+            debugger.resume(&mut device, 100),
+            Stop::Breakpoint(breakpoint)
+        );
+
+        let entries = debugger.take_trace();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries[2],
+            TraceEntry {
+                id: trace,
+                address: 0x4000,
+                message: "A=$34 hits=$3".into(),
+            }
+        );
+
+        // A watch expression stops once its value changes.
+        let mut device = self::device();
+        let watch = debugger.add_command("watch [$C000] == $34").unwrap();
+
+        assert_eq!(debugger.resume(&mut device, 100), Stop::Expression(watch));
+        assert_eq!(device.cpu.program_counter, 0x205);
+    }
+
+    #[test]
+    fn reports_lock_ups() {
+        let mut rom = vec![0; 0x8000];
+
+        // CALL $0200, where there's an illegal opcode.
+        rom[0x100..0x103].copy_from_slice(&[0xCD, 0x00, 0x02]);
+        rom[0x200] = 0xFC;
+
+        let mut device = Device::from_rom(rom).unwrap();
+        let mut debugger = Debugger::new();
+
+        assert_eq!(debugger.resume(&mut device, 100), Stop::Locked);
+        assert_eq!(device.cpu.program_counter, 0x200);
+
+        // The CPU stays stuck, but running it doesn't report the lock-up again.
+        assert_eq!(debugger.resume(&mut device, 100), Stop::Limit);
+        assert_eq!(device.cpu.program_counter, 0x200);
+    }
+}
@@ -0,0 +1,181 @@
+//! A ring buffer of the last instructions the interpreter executed, for crash reports.
+
+use crate::trace::disassemble_with_symbols;
+use gb_asm::{
+    instructions::Instruction,
+    symbols::{bank_of, Banks, SymbolFile},
+    Info,
+};
+use gb_hardware::{bus::System, cpu::Cpu};
+use std::{collections::VecDeque, fmt::Display};
+
+/// How many instructions [`History::default`] keeps.
+pub const DEFAULT_CAPACITY: usize = 64;
+
+/// An instruction, and the state of the CPU before it executed.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub pc: u16,
+    /// The banks that were mapped at the time.
+    pub banks: Option<Banks>,
+    /// The bytes at PC, of which the first `length` are the instruction.
+    pub bytes: [u8; 3],
+    pub length: u8,
+    pub cpu: Cpu,
+}
+
+impl Entry {
+    /// Writes the entry like `Display`, with the labels in `symbols`. The label the instruction
+    /// is at goes in a comment at the end of the line.
+    pub(crate) fn write(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        symbols: &SymbolFile,
+    ) -> std::fmt::Result {
+        let banks = self.banks.unwrap_or_default();
+
+        match (self.pc, self.banks) {
+            (0x4000..=0x7FFF, Some(banks)) => write!(f, "{:02X}:{:04X}", banks.rom, self.pc)?,
+            _ => write!(f, "   {:04X}", self.pc)?,
+        }
+
+        let bytes: Vec<_> = self.bytes[..self.length as usize]
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect();
+
+        let [first, second, third] = self.bytes;
+        let cpu = &self.cpu;
+
+        write!(
+            f,
+            "  {:8}  {:16}  A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} \
+             L:{:02X} SP:{:04X}",
+            bytes.join(" "),
+            disassemble_with_symbols(&[first, second, third, 0], symbols, banks),
+            cpu.a,
+            cpu.flags,
+            cpu.b,
+            cpu.c,
+            cpu.d,
+            cpu.e,
+            cpu.h,
+            cpu.l,
+            cpu.stack_pointer,
+        )?;
+
+        match symbols.resolve(bank_of(self.pc, banks), self.pc) {
+            Some(location) => write!(f, "  ; {location}"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write(f, &SymbolFile::new())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct History {
+    entries: VecDeque<Entry>,
+    capacity: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl History {
+    /// Keeps the last `capacity` instructions. A capacity of 0 turns the history off.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The instructions, from the oldest to the most recent.
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Records the instruction at PC, which is about to execute.
+    pub(crate) fn record<S: System>(&mut self, device: &S, instruction: Instruction) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+
+        let pc = device.cpu().program_counter;
+        let length = match instruction {
+            instruction if instruction.is_prefix() => 2,
+            instruction => instruction.bytes(),
+        };
+
+        self.entries.push_back(Entry {
+            pc,
+            banks: device.banks(),
+            bytes: [0, 1, 2].map(|offset| device.peek_byte(pc.wrapping_add(offset))),
+            length,
+            cpu: device.cpu().clone(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Interpreter;
+    use gb_hardware::Device;
+
+    #[test]
+    fn keeps_the_last_instructions() {
+        let mut rom = vec![0; 0x8000];
+
+        // LD A, $34; CB 37 (SWAP A); JR -6
+        rom[0x100..0x106].copy_from_slice(&[0x3E, 0x34, 0xCB, 0x37, 0x18, 0xFA]);
+
+        let mut device = Device::from_rom(rom).unwrap();
+        let mut interpreter = Interpreter::default();
+
+        for _ in 0..DEFAULT_CAPACITY + 1 {
+            interpreter.step(&mut device).unwrap();
+        }
+
+        let entries: Vec<_> = interpreter.history.entries().collect();
+
+        assert_eq!(entries.len(), DEFAULT_CAPACITY);
+        assert_eq!((entries[0].pc, entries[0].length), (0x102, 2));
+        assert_eq!(entries[0].cpu.a, 0x34);
+        assert_eq!(entries[1].pc, 0x104);
+        assert_eq!(
+            entries[0].to_string(),
+            format!(
+                "   0102  CB 37     SWAP A            A:34 F:{:02X} B:{:02X} C:{:02X} D:{:02X} \
+                 E:{:02X} H:{:02X} L:{:02X} SP:FFFE",
+                entries[0].cpu.flags,
+                device.cpu.b,
+                device.cpu.c,
+                device.cpu.d,
+                device.cpu.e,
+                device.cpu.h,
+                device.cpu.l,
+            )
+        );
+    }
+}
@@ -27,8 +27,16 @@ impl Inspector {
 }
 
 pub enum Message {
-    Instruction { pc: u16, instruction: Instruction },
+    Instruction {
+        pc: u16,
+        instruction: Instruction,
+    },
     Step,
+    /// The CPU locked up on an illegal opcode.
+    Locked {
+        pc: u16,
+        opcode: u8,
+    },
 }
 
 impl Interpreter {
@@ -1,16 +1,17 @@
 use crate::{Execute, LoadValue};
 use gb_asm::{instructions::bitwise::and::*, Flag, Info};
-use gb_hardware::Device;
+use gb_hardware::bus::System;
 
 impl Execute for And {
-    fn execute(&self, device: &mut Device) -> u8 {
+    fn execute<S: System>(&self, device: &mut S) -> u8 {
         let rhs = self.source.load_value(device);
-        let result = device.cpu.a & rhs;
+        let result = device.cpu().a & rhs;
+        device.cpu_mut().a = result;
 
-        device.cpu.set(Flag::Zero, result == 0);
-        device.cpu.set(Flag::Subtract, false);
-        device.cpu.set(Flag::HalfCarry, true);
-        device.cpu.set(Flag::Carry, false);
+        device.cpu_mut().set(Flag::Zero, result == 0);
+        device.cpu_mut().set(Flag::Subtract, false);
+        device.cpu_mut().set(Flag::HalfCarry, true);
+        device.cpu_mut().set(Flag::Carry, false);
 
         self.cycles().max()
     }
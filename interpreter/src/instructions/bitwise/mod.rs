@@ -8,3 +8,92 @@ pub mod shift_right;
 pub mod swap;
 pub mod test;
 pub mod xor;
+
+#[cfg(test)]
+mod tests {
+    use crate::Execute;
+    use gb_asm::{Flag, Register};
+    use gb_hardware::bus::FlatBus;
+    use gb_parser::parse_prefixed;
+
+    const REGISTERS: [Option<Register>; 8] = [
+        Some(Register::B),
+        Some(Register::C),
+        Some(Register::D),
+        Some(Register::E),
+        Some(Register::H),
+        Some(Register::L),
+        None,
+        Some(Register::A),
+    ];
+
+    /// Computes the result and the Z, N, H and C flags of a CB-prefixed opcode from the
+    /// documented behavior, with `None` for flags that are left alone.
+    fn expected(opcode: u8, value: u8, carry: bool) -> (u8, [Option<bool>; 4]) {
+        let bit = (opcode >> 3) & 7;
+        let carry_in = carry as u8;
+
+        let (result, carry) = match opcode >> 3 {
+            0x00 => (value.rotate_left(1), value & 0x80 != 0),
+            0x01 => (value.rotate_right(1), value & 1 != 0),
+            0x02 => (value << 1 | carry_in, value & 0x80 != 0),
+            0x03 => (value >> 1 | carry_in << 7, value & 1 != 0),
+            0x04 => (value << 1, value & 0x80 != 0),
+            0x05 => (value >> 1 | value & 0x80, value & 1 != 0),
+            0x06 => (value.rotate_left(4), false),
+            0x07 => (value >> 1, value & 1 != 0),
+            0x08..=0x0F => {
+                let zero = value & (1 << bit) == 0;
+                return (value, [Some(zero), Some(false), Some(true), None]);
+            }
+            0x10..=0x17 => return (value & !(1 << bit), [None; 4]),
+            _ => return (value | 1 << bit, [None; 4]),
+        };
+
+        (
+            result,
+            [Some(result == 0), Some(false), Some(false), Some(carry)],
+        )
+    }
+
+    #[test]
+    fn register_targets() {
+        let mut device = FlatBus::new();
+        let flags = [Flag::Zero, Flag::Subtract, Flag::HalfCarry, Flag::Carry];
+
+        for opcode in 0..=0xFF {
+            let Some(register) = REGISTERS[opcode as usize & 7] else {
+                continue;
+            };
+
+            let instruction = parse_prefixed(opcode);
+
+            for value in [0x00, 0x01, 0x0F, 0x10, 0x80, 0x81, 0xA5, 0xFF] {
+                for carry in [false, true] {
+                    device.cpu.set(register, value);
+
+                    // Start Z, N and H opposite to the carry so that flags which should be left
+                    // alone are checked as well.
+                    let initial = [!carry, !carry, !carry, carry];
+
+                    for (flag, value) in flags.into_iter().zip(initial) {
+                        device.cpu.set(flag, value);
+                    }
+
+                    let (result, expected) = expected(opcode, value, carry);
+                    instruction.execute(&mut device);
+
+                    let name =
+                        format!("CB {opcode:02X} with {register} = {value:02X}, carry {carry}");
+                    assert_eq!(device.cpu.get(register), result, "result of {name}");
+
+                    for ((flag, expected), initial) in flags.into_iter().zip(expected).zip(initial)
+                    {
+                        let expected = expected.unwrap_or(initial);
+                        assert_eq!(device.cpu.get(flag), expected, "{flag} after {name}");
+                    }
+                }
+            }
+        }
+    }
+}
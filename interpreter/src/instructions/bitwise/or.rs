@@ -1,15 +1,17 @@
 use crate::{Execute, LoadValue};
 use gb_asm::{instructions::bitwise::or::*, Flag, Info};
+use gb_hardware::bus::System;
 
 impl Execute for Or {
-    fn execute(&self, device: &mut gb_hardware::Device) -> u8 {
+    fn execute<S: System>(&self, device: &mut S) -> u8 {
         let rhs = self.source.load_value(device);
-        device.cpu.a |= rhs;
+        let cpu = device.cpu_mut();
+        cpu.a |= rhs;
 
-        device.cpu.set(Flag::Zero, device.cpu.a == 0);
-        device.cpu.set(Flag::Subtract, false);
-        device.cpu.set(Flag::HalfCarry, false);
-        device.cpu.set(Flag::Carry, false);
+        cpu.set(Flag::Zero, cpu.a == 0);
+        cpu.set(Flag::Subtract, false);
+        cpu.set(Flag::HalfCarry, false);
+        cpu.set(Flag::Carry, false);
 
         self.cycles().max()
     }
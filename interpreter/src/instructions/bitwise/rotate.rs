@@ -3,12 +3,12 @@ use gb_asm::{
     instructions::bitwise::rotate::{Behavior::*, Direction::*, *},
     Flag, Info, Pair,
 };
-use gb_hardware::Device;
+use gb_hardware::bus::System;
 
 impl Execute for Rotate {
-    fn execute(&self, device: &mut Device) -> u8 {
+    fn execute<S: System>(&self, device: &mut S) -> u8 {
         let value = self.target.load_value(device);
-        let carry = device.cpu.get(Flag::Carry);
+        let carry = device.cpu().get(Flag::Carry);
 
         let (value, carry) = match self.direction {
             Left => match self.behavior {
@@ -29,14 +29,14 @@ impl Execute for Rotate {
 
         self.target.write_value(device, value);
 
-        device.cpu.set(Flag::Subtract, false);
-        device.cpu.set(Flag::HalfCarry, false);
-        device.cpu.set(Flag::Carry, carry);
+        device.cpu_mut().set(Flag::Subtract, false);
+        device.cpu_mut().set(Flag::HalfCarry, false);
+        device.cpu_mut().set(Flag::Carry, carry);
 
         if self.target == Target::Accumulator {
-            device.cpu.set(Flag::Zero, false);
+            device.cpu_mut().set(Flag::Zero, false);
         } else {
-            device.cpu.set(Flag::Zero, value == 0);
+            device.cpu_mut().set(Flag::Zero, value == 0);
         }
 
         self.cycles().max()
@@ -46,11 +46,11 @@ impl Execute for Rotate {
 impl LoadValue for Target {
     type Value = u8;
 
-    fn load_value(&self, device: &Device) -> Self::Value {
+    fn load_value<S: System>(&self, device: &S) -> Self::Value {
         match self {
-            Self::Accumulator => device.cpu.a,
-            Self::Register(r) => device.cpu.get(r),
-            Self::PointerValue => device.read_byte(device.cpu.get(Pair::HL)),
+            Self::Accumulator => device.cpu().a,
+            Self::Register(r) => device.cpu().get(r),
+            Self::PointerValue => device.read_byte(device.cpu().get(Pair::HL)),
         }
     }
 }
@@ -58,11 +58,11 @@ impl LoadValue for Target {
 impl WriteValue for Target {
     type Value = u8;
 
-    fn write_value(&self, device: &mut Device, value: Self::Value) {
+    fn write_value<S: System>(&self, device: &mut S, value: Self::Value) {
         match self {
-            Self::Accumulator => device.cpu.a = value,
-            Self::Register(r) => device.cpu.set(r, value),
-            Self::PointerValue => device.write_byte(device.cpu.get(Pair::HL), value),
+            Self::Accumulator => device.cpu_mut().a = value,
+            Self::Register(r) => device.cpu_mut().set(r, value),
+            Self::PointerValue => device.write_byte(device.cpu().get(Pair::HL), value),
         }
     }
 }
@@ -1,9 +1,9 @@
 use crate::{Execute, LoadValue, WriteValue};
 use gb_asm::{instructions::bitwise::set::*, Info};
-use gb_hardware::Device;
+use gb_hardware::bus::System;
 
 impl Execute for SetBit {
-    fn execute(&self, device: &mut Device) -> u8 {
+    fn execute<S: System>(&self, device: &mut S) -> u8 {
         let mask = 1 << self.position.value();
         let result = self.target.load_value(device) | mask;
         self.target.write_value(device, result);
@@ -1,16 +1,16 @@
-use crate::{Execute, LoadValue, WriteValue};
+use crate::{math::Operand, Execute, LoadValue, WriteValue};
 use gb_asm::{instructions::bitwise::shift_left::*, Flag, Info};
-use gb_hardware::Device;
+use gb_hardware::bus::System;
 
 impl Execute for ShiftLeft {
-    fn execute(&self, device: &mut Device) -> u8 {
-        let (result, carry) = self.target.load_value(device).overflowing_shl(1);
+    fn execute<S: System>(&self, device: &mut S) -> u8 {
+        let (result, carry) = self.target.load_value(device).carrying_shl(1, false);
         self.target.write_value(device, result);
 
-        device.cpu.set(Flag::Zero, result == 0);
-        device.cpu.set(Flag::Subtract, false);
-        device.cpu.set(Flag::HalfCarry, false);
-        device.cpu.set(Flag::Carry, carry);
+        device.cpu_mut().set(Flag::Zero, result == 0);
+        device.cpu_mut().set(Flag::Subtract, false);
+        device.cpu_mut().set(Flag::HalfCarry, false);
+        device.cpu_mut().set(Flag::Carry, carry);
 
         self.cycles().max()
     }
@@ -1,14 +1,14 @@
-use crate::{Execute, LoadValue, WriteValue};
+use crate::{math::Operand, Execute, LoadValue, WriteValue};
 use gb_asm::{instructions::bitwise::shift_right::*, Flag, Info, Pair};
-use gb_hardware::Device;
+use gb_hardware::bus::System;
 
 impl Execute for ShiftRight {
-    fn execute(&self, device: &mut Device) -> u8 {
+    fn execute<S: System>(&self, device: &mut S) -> u8 {
         let value = self.target.load_value(device);
 
         // Store most-significant bit in case we determine this is an arithmetic shift later on.
         let msb = value & (1 << 7);
-        let (mut result, carry) = value.overflowing_shr(1);
+        let (mut result, carry) = value.carrying_shr(1, false);
 
         if matches!(self.behavior, Behavior::Arithmetic) {
             result |= msb;
@@ -16,10 +16,10 @@ impl Execute for ShiftRight {
 
         self.target.write_value(device, result);
 
-        device.cpu.set(Flag::Zero, result == 0);
-        device.cpu.set(Flag::Subtract, false);
-        device.cpu.set(Flag::HalfCarry, false);
-        device.cpu.set(Flag::Carry, carry);
+        device.cpu_mut().set(Flag::Zero, result == 0);
+        device.cpu_mut().set(Flag::Subtract, false);
+        device.cpu_mut().set(Flag::HalfCarry, false);
+        device.cpu_mut().set(Flag::Carry, carry);
 
         self.cycles().max()
     }
@@ -28,10 +28,10 @@ impl Execute for ShiftRight {
 impl LoadValue for Target {
     type Value = u8;
 
-    fn load_value(&self, device: &Device) -> Self::Value {
+    fn load_value<S: System>(&self, device: &S) -> Self::Value {
         match self {
-            Self::Register(r) => device.cpu.get(r),
-            Self::PointerValue => device.read_byte(device.cpu.get(Pair::HL)),
+            Self::Register(r) => device.cpu().get(r),
+            Self::PointerValue => device.read_byte(device.cpu().get(Pair::HL)),
         }
     }
 }
@@ -39,10 +39,10 @@ impl LoadValue for Target {
 impl WriteValue for Target {
     type Value = u8;
 
-    fn write_value(&self, device: &mut Device, value: Self::Value) {
+    fn write_value<S: System>(&self, device: &mut S, value: Self::Value) {
         match self {
-            Self::Register(r) => device.cpu.set(r, value),
-            Self::PointerValue => device.write_byte(device.cpu.get(Pair::HL), value),
+            Self::Register(r) => device.cpu_mut().set(r, value),
+            Self::PointerValue => device.write_byte(device.cpu().get(Pair::HL), value),
         }
     }
 }
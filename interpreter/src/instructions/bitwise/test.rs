@@ -6,16 +6,16 @@ use gb_asm::{
     },
     Flag, Info, Pair,
 };
-use gb_hardware::Device;
+use gb_hardware::bus::System;
 
 impl Execute for Test {
-    fn execute(&self, device: &mut Device) -> u8 {
+    fn execute<S: System>(&self, device: &mut S) -> u8 {
         let mask = 1 << self.position.value();
         let result = self.target.load_value(device) & mask;
 
-        device.cpu.set(Flag::Zero, result == 0);
-        device.cpu.set(Flag::Subtract, false);
-        device.cpu.set(Flag::HalfCarry, true);
+        device.cpu_mut().set(Flag::Zero, result == 0);
+        device.cpu_mut().set(Flag::Subtract, false);
+        device.cpu_mut().set(Flag::HalfCarry, true);
 
         self.cycles().max()
     }
@@ -24,10 +24,10 @@ impl Execute for Test {
 impl LoadValue for Target {
     type Value = u8;
 
-    fn load_value(&self, device: &Device) -> Self::Value {
+    fn load_value<S: System>(&self, device: &S) -> Self::Value {
         match self {
-            Register(r) => device.cpu.get(r),
-            PointerValue => device.read_byte(device.cpu.get(Pair::HL)),
+            Register(r) => device.cpu().get(r),
+            PointerValue => device.read_byte(device.cpu().get(Pair::HL)),
         }
     }
 }
@@ -35,10 +35,10 @@ impl LoadValue for Target {
 impl WriteValue for Target {
     type Value = u8;
 
-    fn write_value(&self, device: &mut Device, value: Self::Value) {
+    fn write_value<S: System>(&self, device: &mut S, value: Self::Value) {
         match self {
-            Register(r) => device.cpu.set(r, value),
-            PointerValue => device.write_byte(device.cpu.get(Pair::HL), value),
+            Register(r) => device.cpu_mut().set(r, value),
+            PointerValue => device.write_byte(device.cpu().get(Pair::HL), value),
         }
     }
 }
@@ -3,18 +3,20 @@ use gb_asm::{
     instructions::jump::{Target::*, *},
     Info, Pair,
 };
-use gb_hardware::Device;
+use gb_hardware::bus::System;
 
 impl Execute for Jump {
-    fn execute(&self, device: &mut Device) -> u8 {
+    fn execute<S: System>(&self, device: &mut S) -> u8 {
+        // The address is read even when the jump isn't taken.
+        let address = self.target.load_value(device);
+
         if let Some(cond) = self.target.get_condition() {
-            if !cond.test(device.cpu.flags) {
+            if !cond.test(device.cpu().flags) {
                 return self.cycles().min();
             }
         }
 
-        let address = self.target.load_value(device);
-        device.cpu.program_counter = address;
+        device.cpu_mut().program_counter = address;
 
         self.cycles().max()
     }
@@ -23,34 +25,35 @@ impl Execute for Jump {
 impl LoadValue for Target {
     type Value = u16;
 
-    fn load_value(&self, device: &Device) -> Self::Value {
+    fn load_value<S: System>(&self, device: &S) -> Self::Value {
         match self {
-            Pointer => device.cpu.get(Pair::HL),
-            ConstantAddress(_) => device.read_word(device.cpu.program_counter),
+            Pointer => device.cpu().get(Pair::HL),
+            ConstantAddress(_) => device.read_word(device.cpu().program_counter),
         }
     }
 }
 
 impl Execute for JumpRelative {
-    fn execute(&self, device: &mut Device) -> u8 {
+    fn execute<S: System>(&self, device: &mut S) -> u8 {
+        // The constant byte used by JR is a two's complement signed value. Since we need to expand
+        // the value to a u16 in order to add it to PC, we need to cast it to an i8 when before we
+        // use it, otherwise it won't saturate properly when expanding to a u16. It's read even
+        // when the jump isn't taken.
+        let offset = device.read_byte(device.cpu().program_counter) as i8;
+
         if let Some(cond) = self.condition {
-            if !cond.test(device.cpu.flags) {
+            if !cond.test(device.cpu().flags) {
                 return self.cycles().min();
             }
         }
 
-        // The constant byte used by JR is a two's complement signed value. Since we need to expand
-        // the value to a u16 in order to add it to PC, we need to cast it to an i8 when before we
-        // use it, otherwise it won't saturate properly when expanding to a u16.
-        let offset = device.read_byte(device.cpu.program_counter) as i8;
-
         // We shift PC one byte forward during execution in order to simplify reading constant
         // values for instructions that need it. In this case, however, JR offsets PC starting at
         // the position _after_ the full instruction (including it's constant value). So, we need
         // to add one before adding our offset to ensure we end up in the correct place.
         // See https://rgbds.gbdev.io/docs/v0.8.0/gbz80.7#JR_n16
-        device.cpu.program_counter = device
-            .cpu
+        device.cpu_mut().program_counter = device
+            .cpu()
             .program_counter
             .wrapping_add(1)
             .wrapping_add(offset as u16);
@@ -1,73 +1,81 @@
-use crate::{Execute, LoadValue};
-use gb_asm::{instructions::load::*, Info, Pair};
-use gb_hardware::{util::word_to_bytes, Device};
+use crate::{math::offset_stack_pointer, Execute, LoadValue};
+use gb_asm::{instructions::load::*, Flag, Info, Pair};
+use gb_hardware::{
+    bus::{RegisterAccess, System},
+    util::word_to_bytes,
+};
 
 impl Execute for Load {
-    fn execute(&self, device: &mut Device) -> u8 {
+    fn execute<S: System>(&self, device: &mut S) -> u8 {
         match self {
             Self::ToRegister(inner) => {
                 let value = inner.source.load_value(device);
-                device.cpu.set(inner.target, value);
+                device.cpu_mut().set(inner.target, value);
             }
             Self::ToAccumulator(inner) => {
                 let value = inner.source.load_value(device);
-                device.cpu.a = value;
+                device.cpu_mut().a = value;
 
                 if let ToAccumulatorSource::HLX(action) = inner.source {
-                    let pointer = device.cpu.get(Pair::HL);
-                    device.cpu.set(Pair::HL, action.apply(pointer));
+                    let pointer = device.cpu().get(Pair::HL);
+                    device.register_update(pointer, RegisterAccess::Read, 1);
+                    device.cpu_mut().set(Pair::HL, action.apply(pointer));
                 }
             }
             Self::ToPair(inner) => {
-                let value = device.read_word(device.cpu.program_counter);
-                device.cpu.set(inner.target, value);
+                let value = device.read_word(device.cpu().program_counter);
+                device.cpu_mut().set(inner.target, value);
             }
             Self::ToPairPointer(inner) => match inner.target {
                 ToPairPointerTarget::HLX(action) => {
-                    let pointer = device.cpu.get(Pair::HL);
-                    device.write_byte(pointer, device.cpu.a);
+                    let pointer = device.cpu().get(Pair::HL);
+                    device.register_update(pointer, RegisterAccess::Write, 1);
+                    device.write_byte(pointer, device.cpu().a);
 
-                    device.cpu.set(Pair::HL, action.apply(pointer));
+                    device.cpu_mut().set(Pair::HL, action.apply(pointer));
                 }
                 ToPairPointerTarget::Pair(p) => {
-                    let pointer = device.cpu.get(p);
-                    device.write_byte(pointer, device.cpu.a);
+                    let pointer = device.cpu().get(p);
+                    device.write_byte(pointer, device.cpu().a);
                 }
             },
             Self::ToHLPointer(inner) => {
                 let value = inner.source.load_value(device);
-                device.write_byte(device.cpu.get(Pair::HL), value);
+                device.write_byte(device.cpu().get(Pair::HL), value);
             }
             Self::ToStackPointer(inner) => {
-                device.cpu.stack_pointer = inner.source.load_value(device);
+                device.cpu_mut().stack_pointer = inner.source.load_value(device);
             }
             Self::ToHighC(_) => {
-                let address = 0xFF00 + device.cpu.c as u16;
-                device.write_byte(address, device.cpu.a);
+                let address = 0xFF00 + device.cpu().c as u16;
+                device.write_byte(address, device.cpu().a);
             }
             Self::ToConstantPointer(inner) => {
-                let pointer = device.read_word(device.cpu.program_counter);
+                let pointer = device.read_word(device.cpu().program_counter);
 
                 match inner.source {
                     ToConstantPointerSource::Accumulator => {
-                        device.write_byte(pointer, device.cpu.a);
+                        device.write_byte(pointer, device.cpu().a);
                     }
                     ToConstantPointerSource::StackPointer => {
-                        let [low, high] = word_to_bytes(device.cpu.stack_pointer);
+                        let [low, high] = word_to_bytes(device.cpu().stack_pointer);
                         device.write_byte(pointer, low);
-                        device.write_byte(pointer + 1, high);
+                        device.write_byte(pointer.wrapping_add(1), high);
                     }
                 };
             }
             Self::ToHighConstantPointer(_) => {
-                let address = 0xFF00 + device.read_byte(device.cpu.program_counter) as u16;
-                device.write_byte(address, device.cpu.a);
+                let address = 0xFF00 + device.read_byte(device.cpu().program_counter) as u16;
+                device.write_byte(address, device.cpu().a);
             }
             Self::ToHL(_) => {
-                let offset = device.read_byte(device.cpu.program_counter) as i8;
-                let value = device.cpu.stack_pointer.wrapping_add(offset as u16);
+                let offset = device.read_byte(device.cpu().program_counter);
+                let result = offset_stack_pointer(device.cpu().stack_pointer, offset);
+                device.cpu_mut().set(Pair::HL, result.value);
 
-                device.cpu.set(Pair::HL, value);
+                result.copy_to_cpu_flags(device.cpu_mut());
+                device.cpu_mut().set(Flag::Zero, false);
+                device.cpu_mut().set(Flag::Subtract, false);
             }
         };
 
@@ -78,19 +86,19 @@ impl Execute for Load {
 impl LoadValue for ToAccumulatorSource {
     type Value = u8;
 
-    fn load_value(&self, device: &Device) -> Self::Value {
+    fn load_value<S: System>(&self, device: &S) -> Self::Value {
         match self {
-            Self::HighC => device.read_byte(0xFF00 + device.cpu.c as u16),
+            Self::HighC => device.read_byte(0xFF00 + device.cpu().c as u16),
             Self::HighConstantPointer => {
-                let value = device.read_byte(device.cpu.program_counter);
+                let value = device.read_byte(device.cpu().program_counter);
                 device.read_byte(0xFF00 + value as u16)
             }
-            Self::HLX(_) => device.read_byte(device.cpu.get(Pair::HL)),
+            Self::HLX(_) => device.read_byte(device.cpu().get(Pair::HL)),
             Self::ConstantPointer => {
-                let address = device.read_word(device.cpu.program_counter);
+                let address = device.read_word(device.cpu().program_counter);
                 device.read_byte(address)
             }
-            Self::PairPointer(p) => device.read_byte(device.cpu.get(p)),
+            Self::PairPointer(p) => device.read_byte(device.cpu().get(p)),
         }
     }
 }
@@ -98,10 +106,10 @@ impl LoadValue for ToAccumulatorSource {
 impl LoadValue for ToHLPointerSource {
     type Value = u8;
 
-    fn load_value(&self, device: &Device) -> Self::Value {
+    fn load_value<S: System>(&self, device: &S) -> Self::Value {
         match self {
-            Self::ConstantByte => device.read_byte(device.cpu.program_counter),
-            Self::Register(r) => device.cpu.get(r),
+            Self::ConstantByte => device.read_byte(device.cpu().program_counter),
+            Self::Register(r) => device.cpu().get(r),
         }
     }
 }
@@ -109,10 +117,31 @@ impl LoadValue for ToHLPointerSource {
 impl LoadValue for ToStackPointerSource {
     type Value = u16;
 
-    fn load_value(&self, device: &Device) -> Self::Value {
+    fn load_value<S: System>(&self, device: &S) -> Self::Value {
         match self {
-            Self::HL => device.cpu.get(Pair::HL),
-            Self::ConstantWord => device.read_word(device.cpu.program_counter),
+            Self::HL => device.cpu().get(Pair::HL),
+            Self::ConstantWord => device.read_word(device.cpu().program_counter),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::Interpreter;
+    use gb_hardware::bus::FlatBus;
+
+    #[test]
+    fn storing_sp_at_the_last_address_wraps() {
+        let mut device = FlatBus::new();
+        device.cpu.program_counter = 0xC000;
+        device.cpu.stack_pointer = 0xABCD;
+
+        // LD [$FFFF], SP
+        device.memory[0xC000..0xC003].copy_from_slice(&[0x08, 0xFF, 0xFF]);
+
+        Interpreter::default().step(&mut device).unwrap();
+
+        assert_eq!(device.memory[0xFFFF], 0xCD);
+        assert_eq!(device.memory[0x0000], 0xAB);
+    }
+}
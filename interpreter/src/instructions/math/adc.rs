@@ -1,17 +1,17 @@
 use crate::{math::GbAdd, Execute, LoadValue};
 use gb_asm::{instructions::math::adc::*, Flag, Info};
-use gb_hardware::Device;
+use gb_hardware::bus::System;
 
 impl Execute for AddPlusCarry {
-    fn execute(&self, device: &mut Device) -> u8 {
+    fn execute<S: System>(&self, device: &mut S) -> u8 {
         let rhs = self.source.load_value(device);
-        let carry = device.cpu.get(Flag::Carry);
+        let carry = device.cpu().get(Flag::Carry);
 
-        let result = device.cpu.a.add_with_carry(rhs, carry);
-        device.cpu.a = result.value;
+        let result = device.cpu().a.add_with_carry(rhs, carry);
+        device.cpu_mut().a = result.value;
 
-        result.copy_to_cpu_flags(&mut device.cpu);
-        device.cpu.set(Flag::Subtract, false);
+        result.copy_to_cpu_flags(device.cpu_mut());
+        device.cpu_mut().set(Flag::Subtract, false);
 
         self.cycles().max()
     }
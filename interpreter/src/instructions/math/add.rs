@@ -1,30 +1,36 @@
-use crate::{math::GbAdd as _, Execute, LoadValue};
+use crate::{
+    math::{offset_stack_pointer, GbAdd as _},
+    Execute, LoadValue,
+};
 use gb_asm::{instructions::math::add::*, Flag, Info, Pair};
-use gb_hardware::Device;
+use gb_hardware::bus::System;
 
 impl Execute for Add {
-    fn execute(&self, device: &mut Device) -> u8 {
+    fn execute<S: System>(&self, device: &mut S) -> u8 {
         match self {
             Self::ToAccumulator(inner) => {
                 let rhs = inner.source.load_value(device);
-                let result = device.cpu.a.add(rhs);
-                result.copy_to_cpu_flags(&mut device.cpu);
+                let result = device.cpu().a.add(rhs);
+                device.cpu_mut().a = result.value;
+                result.copy_to_cpu_flags(device.cpu_mut());
             }
             Self::ToHLPair(inner) => {
                 let rhs = inner.source.load_value(device);
-                let result = device.cpu.get(Pair::HL).add(rhs);
-                result.copy_to_cpu_flags(&mut device.cpu);
+                let result = device.cpu().get(Pair::HL).add(rhs);
+                device.cpu_mut().set(Pair::HL, result.value);
+                result.copy_to_cpu_flags(device.cpu_mut());
             }
             Self::ToStackPointer => {
-                // It's important that we immediately cast the byte to an i8, otherwise the
-                // following cast to a u16 won't saturate during expansion.
-                let rhs = device.read_byte(device.cpu.program_counter) as i8;
-                let result = device.cpu.stack_pointer.add(rhs as u16);
-                result.copy_to_cpu_flags(&mut device.cpu);
+                let offset = device.read_byte(device.cpu().program_counter);
+                let result = offset_stack_pointer(device.cpu().stack_pointer, offset);
+                device.cpu_mut().stack_pointer = result.value;
+
+                result.copy_to_cpu_flags(device.cpu_mut());
+                device.cpu_mut().set(Flag::Zero, false);
             }
         };
 
-        device.cpu.set(Flag::Subtract, false);
+        device.cpu_mut().set(Flag::Subtract, false);
 
         self.cycles().max()
     }
@@ -33,7 +39,9 @@ impl Execute for Add {
 impl LoadValue for ToHLPairSource {
     type Value = u16;
 
-    fn load_value(&self, Device { cpu, .. }: &Device) -> Self::Value {
+    fn load_value<S: System>(&self, device: &S) -> Self::Value {
+        let cpu = device.cpu();
+
         match self {
             Self::Pair(p) => cpu.get(p),
             Self::StackPointer => cpu.stack_pointer,
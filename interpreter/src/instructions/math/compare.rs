@@ -1,14 +1,14 @@
 use crate::{math::GbSub, Execute, LoadValue};
 use gb_asm::{instructions::math::cp::*, Flag, Info};
-use gb_hardware::Device;
+use gb_hardware::bus::System;
 
 impl Execute for Compare {
-    fn execute(&self, device: &mut Device) -> u8 {
+    fn execute<S: System>(&self, device: &mut S) -> u8 {
         let rhs = self.source.load_value(device);
-        let result = device.cpu.a.sub(rhs);
+        let result = device.cpu().a.sub(rhs);
 
-        result.copy_to_cpu_flags(&mut device.cpu);
-        device.cpu.set(Flag::Subtract, true);
+        result.copy_to_cpu_flags(device.cpu_mut());
+        device.cpu_mut().set(Flag::Subtract, true);
 
         self.cycles().max()
     }
@@ -3,35 +3,37 @@ use gb_asm::{
     instructions::math::{dec::Target::*, inc::*},
     Flag, Info, Pair,
 };
-use gb_hardware::Device;
+use gb_hardware::bus::{RegisterAccess, System};
 
 impl Execute for Increment {
-    fn execute(&self, device: &mut Device) -> u8 {
+    fn execute<S: System>(&self, device: &mut S) -> u8 {
         match self.target {
             Register(r) => {
-                let result = device.cpu.get(r).add(1);
-                device.cpu.set(r, result.value);
+                let result = device.cpu().get(r).add(1);
+                device.cpu_mut().set(r, result.value);
 
-                device.cpu.set(Flag::Zero, result.value == 0);
-                device.cpu.set(Flag::Subtract, false);
-                device.cpu.set(Flag::HalfCarry, result.half_carry);
+                device.cpu_mut().set(Flag::Zero, result.value == 0);
+                device.cpu_mut().set(Flag::Subtract, false);
+                device.cpu_mut().set(Flag::HalfCarry, result.half_carry);
             }
             PointerValue => {
-                let address = device.cpu.get(Pair::HL);
+                let address = device.cpu().get(Pair::HL);
                 let result = device.read_byte(address).add(1);
                 device.write_byte(address, result.value);
 
-                device.cpu.set(Flag::Zero, result.value == 0);
-                device.cpu.set(Flag::Subtract, false);
-                device.cpu.set(Flag::HalfCarry, result.half_carry);
+                device.cpu_mut().set(Flag::Zero, result.value == 0);
+                device.cpu_mut().set(Flag::Subtract, false);
+                device.cpu_mut().set(Flag::HalfCarry, result.half_carry);
             }
             Pair(p) => {
-                let result = device.cpu.get(p).add(1);
-                device.cpu.set(p, result.value);
+                device.register_update(device.cpu().get(p), RegisterAccess::Idle, 1);
+                let result = device.cpu().get(p).add(1);
+                device.cpu_mut().set(p, result.value);
             }
             StackPointer => {
-                let result = device.cpu.stack_pointer.add(1);
-                device.cpu.stack_pointer = result.value;
+                device.register_update(device.cpu().stack_pointer, RegisterAccess::Idle, 1);
+                let result = device.cpu().stack_pointer.add(1);
+                device.cpu_mut().stack_pointer = result.value;
             }
         };
 
@@ -1,7 +1,10 @@
 use gb_asm::{instructions::misc::*, Flag, Info};
-use gb_hardware::Device;
+use gb_hardware::{
+    cpu::{Cpu, State},
+    InterruptController,
+};
 
-pub fn complement_carry_flag(ccf: &ComplementCarryFlag, Device { cpu, .. }: &mut Device) -> u8 {
+pub fn complement_carry_flag(ccf: &ComplementCarryFlag, cpu: &mut Cpu) -> u8 {
     cpu.set(Flag::Subtract, false);
     cpu.set(Flag::HalfCarry, false);
     cpu.set(Flag::Carry, !cpu.get(Flag::Carry));
@@ -9,7 +12,7 @@ pub fn complement_carry_flag(ccf: &ComplementCarryFlag, Device { cpu, .. }: &mut
     ccf.cycles().max()
 }
 
-pub fn complement_accumulator(cpl: &ComplementAccumulator, Device { cpu, .. }: &mut Device) -> u8 {
+pub fn complement_accumulator(cpl: &ComplementAccumulator, cpu: &mut Cpu) -> u8 {
     cpu.a = !cpu.a;
 
     cpu.set(Flag::Subtract, true);
@@ -18,27 +21,27 @@ pub fn complement_accumulator(cpl: &ComplementAccumulator, Device { cpu, .. }: &
     cpl.cycles().max()
 }
 
-pub fn decimal_adjust_accumulator(
-    daa: &DecimalAdjustAccumulator,
-    Device { cpu, .. }: &mut Device,
-) -> u8 {
+pub fn decimal_adjust_accumulator(daa: &DecimalAdjustAccumulator, cpu: &mut Cpu) -> u8 {
     // I'm not even going to pretend I know what the DAA instruction is used for, but [WTF is the
     // DAA instruction](https://ehaskins.com/2018-01-30%20Z80%20DAA/) by Eric Haskins has a great
     // write-up on how to emulate the behavior of the instruction. Code below adapted from their
     // article.
 
     let mut correction = 0u8;
+    let subtract = cpu.get(Flag::Subtract);
 
-    if cpu.get(Flag::Carry) || cpu.a > 0x99 {
+    // After a subtraction only the carry flags tell whether a digit needs correcting, since the
+    // result can't have gone out of the BCD range the way an addition can.
+    if cpu.get(Flag::Carry) || (!subtract && cpu.a > 0x99) {
         correction |= 0x60;
         cpu.set(Flag::Carry, true);
     }
 
-    if cpu.get(Flag::HalfCarry) || (cpu.a & 0xF) > 0x09 {
+    if cpu.get(Flag::HalfCarry) || (!subtract && (cpu.a & 0xF) > 0x09) {
         correction |= 0x06;
     }
 
-    cpu.a = if cpu.get(Flag::Subtract) {
+    cpu.a = if subtract {
         cpu.a.wrapping_sub(correction)
     } else {
         cpu.a.wrapping_add(correction)
@@ -50,29 +53,33 @@ pub fn decimal_adjust_accumulator(
     daa.cycles().max()
 }
 
-pub fn disable_interrupts(di: &DisableInterrupts, Device { cpu, .. }: &mut Device) -> u8 {
-    cpu.interrupts_enabled = false;
+pub fn disable_interrupts(di: &DisableInterrupts, interrupts: &mut InterruptController) -> u8 {
+    interrupts.disable();
     di.cycles().max()
 }
 
-pub fn enable_interrupts(ei: &EnableInterrupts, Device { cpu, .. }: &mut Device) -> u8 {
-    cpu.interrupts_enabled = true;
-    ei.cycles().max()
+/// Halts the CPU until an enabled interrupt is requested. If one already is, the CPU carries on
+/// without halting. The hardware bug that makes it read the next byte twice when IME is clear isn't
+/// emulated.
+pub fn halt(halt: &Halt, interrupt_pending: bool, cpu: &mut Cpu) -> u8 {
+    if !interrupt_pending {
+        cpu.state = State::Halted;
+    }
+
+    halt.cycles().max()
 }
 
-pub fn halt(_halt: &Halt, _device: &mut Device) -> u8 {
-    // See https://rgbds.gbdev.io/docs/v0.8.0/gbz80.7#HALT
-    todo!()
+pub fn enable_interrupts(ei: &EnableInterrupts, interrupts: &mut InterruptController) -> u8 {
+    // IME is only set after the next instruction, so `EI` followed by `RET` returns before any
+    // interrupt is handled.
+    interrupts.schedule_enable();
+    ei.cycles().max()
 }
 
-pub fn set_carry_flag(scf: &SetCarryFlag, Device { cpu, .. }: &mut Device) -> u8 {
+pub fn set_carry_flag(scf: &SetCarryFlag, cpu: &mut Cpu) -> u8 {
     cpu.set(Flag::Subtract, false);
     cpu.set(Flag::HalfCarry, false);
     cpu.set(Flag::Carry, true);
 
     scf.cycles().max()
 }
-
-pub fn stop(_stop: &Stop, _device: &mut Device) -> u8 {
-    todo!()
-}
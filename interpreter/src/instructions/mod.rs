@@ -1,6 +1,6 @@
 use crate::Execute;
 use gb_asm::{instructions::Instruction, Info};
-use gb_hardware::Device;
+use gb_hardware::bus::System;
 
 pub mod bitwise;
 pub mod jump;
@@ -11,23 +11,30 @@ pub mod stack;
 pub mod subroutine;
 
 impl Execute for Instruction {
-    fn execute(&self, device: &mut Device) -> u8 {
+    fn execute<S: System>(&self, device: &mut S) -> u8 {
         match self {
             Self::AddPlusCarry(inner) => inner.execute(device),
             Self::Add(inner) => inner.execute(device),
             Self::And(inner) => inner.execute(device),
             Self::Test(inner) => inner.execute(device),
             Self::Call(inner) => inner.execute(device),
-            Self::ComplementCarryFlag(inner) => misc::complement_carry_flag(inner, device),
+            Self::ComplementCarryFlag(inner) => {
+                misc::complement_carry_flag(inner, device.cpu_mut())
+            }
             Self::Compare(inner) => inner.execute(device),
-            Self::ComplementAccumulator(inner) => misc::complement_accumulator(inner, device),
+            Self::ComplementAccumulator(inner) => {
+                misc::complement_accumulator(inner, device.cpu_mut())
+            }
             Self::DecimalAdjustAccumulator(inner) => {
-                misc::decimal_adjust_accumulator(inner, device)
+                misc::decimal_adjust_accumulator(inner, device.cpu_mut())
             }
             Self::Decrement(inner) => inner.execute(device),
-            Self::DisableInterrupts(inner) => misc::disable_interrupts(inner, device),
-            Self::EnableInterrupts(inner) => misc::enable_interrupts(inner, device),
-            Self::Halt(inner) => misc::halt(inner, device),
+            Self::DisableInterrupts(inner) => {
+                misc::disable_interrupts(inner, device.interrupts_mut())
+            }
+            Self::EnableInterrupts(inner) => {
+                misc::enable_interrupts(inner, device.interrupts_mut())
+            }
             Self::Increment(inner) => inner.execute(device),
             Self::Jump(inner) => inner.execute(device),
             Self::JumpRelative(inner) => inner.execute(device),
@@ -40,14 +47,21 @@ impl Execute for Instruction {
             Self::Return(inner) => inner.execute(device),
             Self::Rotate(inner) => inner.execute(device),
             Self::Subtract(inner) => inner.execute(device),
-            Self::SetCarryFlag(inner) => misc::set_carry_flag(inner, device),
+            Self::SetCarryFlag(inner) => misc::set_carry_flag(inner, device.cpu_mut()),
             Self::SetBit(inner) => inner.execute(device),
             Self::ShiftLeft(inner) => inner.execute(device),
             Self::ShiftRight(inner) => inner.execute(device),
-            Self::Stop(inner) => misc::stop(inner, device),
             Self::Swap(inner) => inner.execute(device),
             Self::Xor(inner) => inner.execute(device),
             Self::Prefix(inner) => inner.cycles().max(),
+            Self::Halt(inner) => {
+                let pending = device.interrupts().pending().is_some();
+                misc::halt(inner, pending, device.cpu_mut())
+            }
+            // `Interpreter::step` reports this as unsupported rather than executing it.
+            Self::Stop(inner) => inner.cycles().max(),
+            // `Interpreter::step` locks up the CPU instead.
+            Self::Illegal(inner) => inner.cycles().max(),
         }
     }
 }
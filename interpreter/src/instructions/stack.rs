@@ -7,22 +7,23 @@ use gb_asm::{
     Info,
 };
 use gb_hardware::{
+    bus::System,
     util::{bytes_to_word, word_to_bytes},
-    Device,
 };
 
 impl Execute for Pop {
-    fn execute(&self, device: &mut Device) -> u8 {
-        let value = device.stack_pop();
+    fn execute<S: System>(&self, device: &mut S) -> u8 {
+        let value = device.stack_pop(1);
 
         match self.target {
             AccumulatorAndFlags => {
                 let [low, high] = word_to_bytes(value);
 
-                device.cpu.a = high;
-                device.cpu.flags = low;
+                device.cpu_mut().a = high;
+                // The lower four bits of F don't exist, and always read as zero.
+                device.cpu_mut().flags = low & 0xF0;
             }
-            Pair(p) => device.cpu.set(p, value),
+            Pair(p) => device.cpu_mut().set(p, value),
         };
 
         self.cycles().max()
@@ -30,13 +31,13 @@ impl Execute for Pop {
 }
 
 impl Execute for Push {
-    fn execute(&self, device: &mut Device) -> u8 {
+    fn execute<S: System>(&self, device: &mut S) -> u8 {
         let value = match self.source {
-            AccumulatorAndFlags => bytes_to_word(device.cpu.a, device.cpu.flags),
-            Pair(p) => device.cpu.get(p),
+            AccumulatorAndFlags => bytes_to_word(device.cpu().a, device.cpu().flags),
+            Pair(p) => device.cpu().get(p),
         };
 
-        device.stack_push(value);
+        device.stack_push(value, 1);
 
         self.cycles().max()
     }
@@ -3,30 +3,35 @@ use gb_asm::{
     instructions::subroutine::call::{Call::*, *},
     Info,
 };
-use gb_hardware::Device;
+use gb_hardware::bus::System;
 
 impl Execute for Call {
-    fn execute(&self, device: &mut Device) -> u8 {
+    fn execute<S: System>(&self, device: &mut S) -> u8 {
         // The next PC position is one less than the width of this instruction, since PC is
         // positioned after the opcode when executing instructions.
         let next_pc = device
-            .cpu
+            .cpu()
             .program_counter
             .wrapping_add(self.bytes() as u16 - 1);
 
-        device.stack_push(next_pc);
-
         match self {
             ConstantAddress(cond) => {
+                // The address is read even when the call isn't taken.
+                let address = device.read_word(device.cpu().program_counter);
+
                 if let Some(cond) = cond {
-                    if !cond.test(device.cpu.flags) {
+                    if !cond.test(device.cpu().flags) {
                         return self.cycles().min();
                     }
                 }
 
-                device.cpu.program_counter = device.read_word(device.cpu.program_counter);
+                device.stack_push(next_pc, 3);
+                device.cpu_mut().program_counter = address;
+            }
+            Vector(v) => {
+                device.stack_push(next_pc, 1);
+                device.cpu_mut().program_counter = *v as u16;
             }
-            Vector(v) => device.cpu.program_counter = *v as u16,
         };
 
         self.cycles().max()
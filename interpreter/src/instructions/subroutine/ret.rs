@@ -1,20 +1,26 @@
 use crate::Execute;
 use gb_asm::{instructions::subroutine::ret::*, Info};
-use gb_hardware::Device;
+use gb_hardware::bus::System;
 
 impl Execute for Return {
-    fn execute(&self, device: &mut Device) -> u8 {
+    fn execute<S: System>(&self, device: &mut S) -> u8 {
         if let Self::Normal(Some(cond)) = self {
-            if !cond.test(device.cpu.flags) {
+            if !cond.test(device.cpu().flags) {
                 return self.cycles().min();
             }
         }
 
-        let new_pc = device.stack_pop();
-        device.cpu.program_counter = new_pc;
+        // A conditional return spends an M-cycle checking its condition first.
+        let cycle = match self {
+            Self::Normal(Some(_)) => 2,
+            _ => 1,
+        };
+
+        let new_pc = device.stack_pop(cycle);
+        device.cpu_mut().program_counter = new_pc;
 
         if matches!(self, Self::EnableInterrupts) {
-            device.cpu.interrupts_enabled = true;
+            device.interrupts_mut().enable();
         }
 
         self.cycles().max()
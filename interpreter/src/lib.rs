@@ -1,48 +1,125 @@
-use gb_asm::{sources::ByteSource, Info, Pair};
-use gb_hardware::Device;
+use coverage::CoverageBus;
+use crash::CrashReport;
+use gb_asm::{
+    instructions::{
+        misc::{Halt, Illegal},
+        Instruction,
+    },
+    sources::ByteSource,
+    Info, Pair,
+};
+use gb_hardware::{
+    bus::{RegisterAccess, System},
+    cpu::State,
+    util::word_to_bytes,
+    Device,
+};
 use gb_parser::{parse, parse_prefixed};
+use history::History;
 
+mod coverage;
+pub mod crash;
+pub mod debugger;
+pub mod history;
 pub mod instructions;
 pub mod math;
+pub mod testrom;
+pub mod trace;
 
 #[cfg(feature = "inspect")]
 pub mod inspect;
 
 #[derive(Debug, Clone, Default)]
 pub struct Interpreter {
+    /// The last instructions executed, for crash reports.
+    pub history: History,
     #[cfg(feature = "inspect")]
     pub(crate) inspector: inspect::Inspector,
 }
 
+/// What [`Interpreter::step`] executed.
+#[derive(Debug, Copy, Clone)]
+pub struct StepInfo {
+    /// The address of the instruction.
+    pub address: u16,
+    pub instruction: Instruction,
+    /// The M-cycles taken, including those of an interrupt dispatched before the instruction.
+    pub cycles: u8,
+}
+
+/// Why [`Interpreter::step`] couldn't execute an instruction. PC is left at the instruction, so
+/// the state of the device can be inspected or fixed up before stepping again.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum StepError {
+    #[error("{mnemonic} at ${address:04X} is not supported yet")]
+    Unsupported {
+        mnemonic: &'static str,
+        address: u16,
+    },
+}
+
 impl Interpreter {
-    pub fn step(&mut self, device: &mut Device) {
-        if let Some(interrupt) = device.get_next_interrupt() {
-            if device.is_interrupt_enabled(interrupt) {
-                device.stack_push(device.cpu.program_counter);
-                device.cpu.interrupts_enabled = false;
-                device.cpu.program_counter = interrupt.get_address();
-
-                // According to Pandocs, transitioning to an interrupt handler takes 5 cycles.
-                device.cpu.cycle_counter = device.cpu.cycle_counter.wrapping_add(5);
-                device.video.process(5 * 4);
+    /// Executes one instruction, and logs how it used ROM if the device keeps a code/data log.
+    pub fn step<S: System>(&mut self, device: &mut S) -> Result<StepInfo, StepError> {
+        if !device.logs_coverage() {
+            return self.execute_step(device);
+        }
+
+        let mut bus = CoverageBus::new(device);
+        let info = self.execute_step(&mut bus)?;
+        bus.finish(&info);
+
+        Ok(info)
+    }
+
+    fn execute_step<S: System>(&mut self, device: &mut S) -> Result<StepInfo, StepError> {
+        match device.cpu().state {
+            State::Locked(opcode) => return Ok(idle(device, Illegal(opcode).into(), 0)),
+            State::Halted if device.interrupts().pending().is_none() => {
+                return Ok(idle(device, Halt.into(), 0));
             }
+            State::Halted => device.cpu_mut().state = State::Running,
+            State::Running => (),
+        }
+
+        let mut dispatch_cycles = 0;
+
+        if device.interrupts().should_dispatch() {
+            dispatch_interrupt(device);
+            dispatch_cycles = 5;
         }
 
-        let base_pc = device.cpu.program_counter;
+        let base_pc = device.cpu().program_counter;
         let opcode = device.read_byte(base_pc);
-        let instr = parse(opcode).unwrap_or_else(|| {
-            panic!(
-                "Unimplemented opcode {opcode:#04X} at ${:04X}",
-                device.cpu.program_counter
-            )
-        });
+        let instr = parse(opcode);
+        self.history.record(device, instr);
+
+        if let Instruction::Illegal(_) = instr {
+            // PC is left at the opcode the CPU hung on.
+            device.cpu_mut().state = State::Locked(opcode);
+
+            #[cfg(feature = "inspect")]
+            self.inspector.send(inspect::Message::Locked {
+                pc: base_pc,
+                opcode,
+            });
 
-        device.cpu.program_counter += 1;
+            return Ok(idle(device, instr, dispatch_cycles));
+        }
+
+        if let Instruction::Stop(_) = instr {
+            return Err(StepError::Unsupported {
+                mnemonic: "STOP",
+                address: base_pc,
+            });
+        }
+
+        device.cpu_mut().program_counter = base_pc.wrapping_add(1);
 
         let instr = if instr.is_prefix() {
-            let opcode = device.read_byte(device.cpu.program_counter);
+            let opcode = device.read_byte(device.cpu().program_counter);
             let instr = parse_prefixed(opcode);
-            device.cpu.program_counter += 1;
+            device.cpu_mut().program_counter = base_pc.wrapping_add(2);
 
             instr
         } else {
@@ -59,13 +136,13 @@ impl Interpreter {
         // changes to the PC by an instruction. Some instructions (such as jumps or calls) can
         // modify PC, and if they do, we don't want to change PC again after they're done
         // executing.
-        let pre_exec_pc = device.cpu.program_counter;
+        let pre_exec_pc = device.cpu().program_counter;
 
         let cycles = instr.execute(device);
-        let cycle_counter = &mut device.cpu.cycle_counter;
+        let cycle_counter = &mut device.cpu_mut().cycle_counter;
         *cycle_counter = cycle_counter.wrapping_add(cycles as u16);
 
-        let pc = &mut device.cpu.program_counter;
+        let pc = &mut device.cpu_mut().program_counter;
 
         // As long as PC didn't change during instruction execution, we're safe to move to the
         // next instruction.
@@ -73,35 +150,275 @@ impl Interpreter {
             *pc = base_pc.wrapping_add(instr.bytes() as u16);
         }
 
-        device.process(cycles);
+        device.tick(cycles);
+        device.interrupts_mut().end_instruction();
 
         #[cfg(feature = "inspect")]
         self.inspector.send(inspect::Message::Step);
+
+        Ok(StepInfo {
+            address: base_pc,
+            instruction: instr,
+            cycles: dispatch_cycles + cycles,
+        })
+    }
+
+    /// Executes one instruction like [`Interpreter::step`], but reports an error with a
+    /// [`CrashReport`] of the state that led to it.
+    pub fn try_step(&mut self, device: &mut Device) -> Result<StepInfo, Box<CrashReport>> {
+        self.step(device)
+            .map_err(|error| Box::new(CrashReport::new(error.to_string(), device, &self.history)))
     }
 }
 
+/// Lets one M-cycle pass for the rest of the system while the CPU is halted or locked up by
+/// `instruction`.
+fn idle<S: System>(device: &mut S, instruction: Instruction, dispatch_cycles: u8) -> StepInfo {
+    let cpu = device.cpu_mut();
+    cpu.cycle_counter = cpu.cycle_counter.wrapping_add(1);
+    device.tick(1);
+
+    StepInfo {
+        address: device.cpu().program_counter,
+        instruction,
+        cycles: dispatch_cycles + 1,
+    }
+}
+
+/// Jumps to the handler of the highest priority pending interrupt, which takes 5 M-cycles.
+fn dispatch_interrupt<S: System>(device: &mut S) {
+    device.interrupts_mut().disable();
+
+    let [low, high] = word_to_bytes(device.cpu().program_counter);
+    device.register_update(device.cpu().stack_pointer, RegisterAccess::Idle, 2);
+    let stack_pointer = device.cpu().stack_pointer.wrapping_sub(1);
+    device.register_update(stack_pointer, RegisterAccess::Write, 3);
+    device.write_byte(stack_pointer, high);
+
+    // The interrupt to handle is only chosen once the high byte of PC has been pushed. If that push
+    // overwrote IE, it can pick a different interrupt, or find none at all, in which case the
+    // dispatch is cancelled and execution continues from $0000.
+    let interrupt = device.interrupts().pending();
+
+    let stack_pointer = stack_pointer.wrapping_sub(1);
+    device.write_byte(stack_pointer, low);
+
+    let cpu = device.cpu_mut();
+    cpu.stack_pointer = stack_pointer;
+    cpu.program_counter = interrupt.map_or(0x0000, |i| i.get_address());
+    cpu.cycle_counter = cpu.cycle_counter.wrapping_add(5);
+
+    if let Some(interrupt) = interrupt {
+        device.interrupts_mut().acknowledge(interrupt);
+    }
+
+    device.tick(5);
+}
+
 pub trait Execute {
-    fn execute(&self, device: &mut Device) -> u8;
+    fn execute<S: System>(&self, device: &mut S) -> u8;
 }
 
 pub trait LoadValue {
     type Value;
-    fn load_value(&self, device: &Device) -> Self::Value;
+    fn load_value<S: System>(&self, device: &S) -> Self::Value;
 }
 
 impl LoadValue for ByteSource {
     type Value = u8;
 
-    fn load_value(&self, device: &Device) -> Self::Value {
+    fn load_value<S: System>(&self, device: &S) -> Self::Value {
         match self {
-            Self::Register(r) => device.cpu.get(r),
-            Self::PointerValue => device.read_byte(device.cpu.get(Pair::HL)),
-            Self::ConstantByte => device.read_byte(device.cpu.program_counter),
+            Self::Register(r) => device.cpu().get(r),
+            Self::PointerValue => device.read_byte(device.cpu().get(Pair::HL)),
+            Self::ConstantByte => device.read_byte(device.cpu().program_counter),
         }
     }
 }
 
 pub trait WriteValue {
     type Value;
-    fn write_value(&self, device: &mut Device, value: Self::Value);
+    fn write_value<S: System>(&self, device: &mut S, value: Self::Value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gb_hardware::{
+        bus::Bus,
+        memory::oam_bug::{self, Corruption},
+        video::Mode,
+        Device, Interrupt,
+    };
+
+    /// Returns a device running a ROM of `NOP`s, with `code` at $0100.
+    fn device(code: &[u8]) -> Device {
+        let mut rom = vec![0; 0x8000];
+        rom[0x100..][..code.len()].copy_from_slice(code);
+
+        Device::from_rom(rom).unwrap()
+    }
+
+    #[test]
+    fn interrupts_wait_for_the_instruction_after_ei() {
+        // EI, NOP
+        let mut device = device(&[0xFB, 0x00]);
+        let mut interpreter = Interpreter::default();
+
+        device.write_byte(0xFFFF, Interrupt::Timer.get_mask());
+        device.interrupts.request(Interrupt::Timer);
+
+        interpreter.step(&mut device).unwrap();
+        interpreter.step(&mut device).unwrap();
+        assert_eq!(device.cpu.program_counter, 0x102);
+
+        // The handler's first instruction executes in the same step as the dispatch.
+        let cycles = device.cpu.cycle_counter;
+        let info = interpreter.step(&mut device).unwrap();
+
+        assert_eq!((info.address, info.cycles), (0x50, 6));
+        assert_eq!(device.cpu.program_counter, 0x51);
+        assert_eq!(device.cpu.cycle_counter.wrapping_sub(cycles), 6);
+        assert_eq!(device.read_word(device.cpu.stack_pointer), 0x102);
+        assert_eq!(device.read_byte(0xFF0F) & 0x1F, 0);
+        assert!(!device.interrupts.master_enable);
+    }
+
+    #[test]
+    fn pushing_over_ie_cancels_dispatch() {
+        let mut device = device(&[]);
+        let mut interpreter = Interpreter::default();
+
+        // The high byte of PC ($01) lands in IE, disabling the timer interrupt.
+        device.cpu.stack_pointer = 0x0000;
+        device.interrupts.enabled = Interrupt::Timer.get_mask();
+        device.interrupts.request(Interrupt::Timer);
+        device.interrupts.enable();
+
+        interpreter.step(&mut device).unwrap();
+
+        assert_eq!(device.cpu.program_counter, 0x0001);
+        assert_eq!(device.interrupts.enabled, 0x01);
+        assert_eq!(device.interrupts.pending(), None);
+        assert_ne!(device.read_byte(0xFF0F) & Interrupt::Timer.get_mask(), 0);
+    }
+
+    #[test]
+    fn reports_instructions_it_cannot_execute() {
+        // NOP, STOP
+        let mut device = device(&[0x00, 0x10, 0x00]);
+        let mut interpreter = Interpreter::default();
+
+        interpreter.step(&mut device).unwrap();
+
+        assert_eq!(
+            interpreter.step(&mut device).unwrap_err(),
+            StepError::Unsupported {
+                mnemonic: "STOP",
+                address: 0x101
+            }
+        );
+        assert_eq!(device.cpu.program_counter, 0x101);
+    }
+
+    #[test]
+    fn halt_waits_for_an_interrupt() {
+        // HALT, INC A, HALT
+        let mut device = device(&[0x76, 0x3C, 0x76]);
+        let mut interpreter = Interpreter::default();
+
+        device.write_byte(0xFFFF, Interrupt::Timer.get_mask());
+
+        interpreter.step(&mut device).unwrap();
+        assert_eq!(device.cpu.state, State::Halted);
+
+        for _ in 0..10 {
+            let info = interpreter.step(&mut device).unwrap();
+            assert_eq!((info.address, info.cycles), (0x101, 1));
+        }
+
+        // Without IME, the CPU wakes up and carries on without handling the interrupt.
+        let a = device.cpu.a;
+        device.interrupts.request(Interrupt::Timer);
+        interpreter.step(&mut device).unwrap();
+
+        assert_eq!(device.cpu.state, State::Running);
+        assert_eq!(device.cpu.a, a.wrapping_add(1));
+
+        // HALT doesn't halt while an interrupt is pending.
+        interpreter.step(&mut device).unwrap();
+        assert_eq!(device.cpu.state, State::Running);
+        assert_eq!(device.cpu.program_counter, 0x103);
+
+        // With IME, the interrupt is dispatched as the CPU wakes up.
+        device.interrupts.acknowledge(Interrupt::Timer);
+        device.interrupts.enable();
+        device.cpu.program_counter = 0x102;
+
+        interpreter.step(&mut device).unwrap();
+        assert_eq!(device.cpu.state, State::Halted);
+
+        device.interrupts.request(Interrupt::Timer);
+        let info = interpreter.step(&mut device).unwrap();
+
+        assert_eq!(info.address, 0x50);
+        assert_eq!(device.read_word(device.cpu.stack_pointer), 0x103);
+    }
+
+    #[test]
+    fn illegal_opcodes_lock_up_the_cpu() {
+        // NOP, $DD
+        let mut device = device(&[0x00, 0xDD]);
+        let mut interpreter = Interpreter::default();
+
+        interpreter.step(&mut device).unwrap();
+        interpreter.step(&mut device).unwrap();
+        assert_eq!(device.cpu.state, State::Locked(0xDD));
+
+        // Interrupts don't wake the CPU, but the PPU keeps drawing.
+        device.write_byte(0xFFFF, Interrupt::VerticalBlank.get_mask());
+        device.interrupts.enable();
+
+        for _ in 0..20_000 {
+            let info = interpreter.step(&mut device).unwrap();
+            assert_eq!((info.address, info.cycles), (0x101, 1));
+        }
+
+        assert_eq!(device.cpu.program_counter, 0x101);
+        assert_ne!(
+            device.read_byte(0xFF0F) & Interrupt::VerticalBlank.get_mask(),
+            0
+        );
+    }
+
+    #[test]
+    fn oam_corruption_follows_the_ppu_through_an_instruction() {
+        // CALL $0200
+        let mut device = device(&[0xCD, 0x00, 0x02]);
+        let mut interpreter = Interpreter::default();
+
+        for offset in 0..0xA0 {
+            device.poke(0xFE00 + offset, offset as u8);
+        }
+
+        device.cpu.stack_pointer = 0xFE20;
+
+        while device.video.mode != Mode::HorizontalBlank {
+            device.process(1);
+        }
+
+        while device.video.mode != Mode::OamScan {
+            device.process(1);
+        }
+
+        // SP is decremented in the fourth M-cycle and the fifth, by which time the PPU has moved
+        // on to the fourth and fifth rows.
+        let mut expected = device.memory.oam.clone();
+        oam_bug::corrupt(&mut expected, 3, Corruption::Write);
+        oam_bug::corrupt(&mut expected, 4, Corruption::Write);
+
+        interpreter.step(&mut device).unwrap();
+
+        assert_eq!(device.memory.oam, expected);
+    }
 }
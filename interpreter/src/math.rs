@@ -36,6 +36,19 @@ where
     }
 }
 
+/// Adds the signed `offset` to `stack_pointer`, as `ADD SP, e8` and `LD HL, SP + e8` do. Their
+/// flags come from adding the offset, as an unsigned byte, to the low byte of SP.
+pub fn offset_stack_pointer(stack_pointer: u16, offset: u8) -> MathResult<u16> {
+    let low = (stack_pointer as u8).add(offset);
+
+    MathResult {
+        half_carry: low.half_carry,
+        carry: low.carry,
+        // Casting to an i8 first makes the following cast to a u16 sign-extend the offset.
+        value: stack_pointer.wrapping_add(offset as i8 as u16),
+    }
+}
+
 pub trait GbAdd<Rhs = Self> {
     type Output;
 
@@ -61,11 +74,14 @@ where
     }
 
     fn add_with_carry(self, rhs: Self, carry: bool) -> MathResult<Self::Output> {
-        let result = carry
-            .then(|| self.add(Self::get_carry_value()))
-            .unwrap_or_default();
+        let result = self.add(rhs);
 
-        result.merge(self.add(rhs))
+        if carry {
+            let value = result.value;
+            result.merge(value.add(Self::get_carry_value()))
+        } else {
+            result
+        }
     }
 }
 
@@ -94,11 +110,14 @@ where
     }
 
     fn sub_with_carry(self, rhs: Self, carry: bool) -> MathResult<Self::Output> {
-        let result = carry
-            .then(|| self.sub(Self::get_carry_value()))
-            .unwrap_or_default();
+        let result = self.sub(rhs);
 
-        result.merge(self.sub(rhs))
+        if carry {
+            let value = result.value;
+            result.merge(value.sub(Self::get_carry_value()))
+        } else {
+            result
+        }
     }
 }
 
@@ -120,20 +139,24 @@ where
 macro_rules! carrying_fns {
     () => {
         fn carrying_shl(self, rhs: u32, carry: bool) -> (Self, bool) {
-            let (mut result, new_carry) = self.overflowing_shl(rhs);
+            // The carry out is the last bit shifted out of the top of the value.
+            let new_carry = rhs > 0 && rhs <= Self::BITS && (self >> (Self::BITS - rhs)) & 1 != 0;
+            let mut result = self.checked_shl(rhs).unwrap_or_default();
 
             if carry {
-                result |= 1;
+                result |= 1 << (rhs - 1);
             }
 
             (result, new_carry)
         }
 
         fn carrying_shr(self, rhs: u32, carry: bool) -> (Self, bool) {
-            let (mut result, new_carry) = self.overflowing_shr(rhs);
+            // The carry out is the last bit shifted out of the bottom of the value.
+            let new_carry = rhs > 0 && rhs <= Self::BITS && (self >> (rhs - 1)) & 1 != 0;
+            let mut result = self.checked_shr(rhs).unwrap_or_default();
 
             if carry {
-                result |= 1 << (Self::BITS - 1);
+                result |= 1 << (Self::BITS - rhs);
             }
 
             (result, new_carry)
@@ -160,7 +183,7 @@ impl Operand for u8 {
     }
 
     fn is_half_carry_sub(&self, rhs: Self) -> bool {
-        (self & 0xF) > (rhs & 0xF)
+        (self & 0xF) < (rhs & 0xF)
     }
 
     fn get_carry_value() -> Self {
@@ -188,25 +211,16 @@ impl Operand for u16 {
     }
 
     fn is_half_carry_add(&self, rhs: Self) -> bool {
-        // There's a lot of conflicting information out there about how the Gameboy actually does
-        // math. For my implementation, I'm going off the assertion that the ALU operates in 4-bit
-        // steps (thus the need for a half-carry flag in the first place).
-        //
-        // Since a 16-bit operation would then, naturally, require 4 4-bit operations, in order to
-        // determine a half-carry for a 16-bit operation we would need to examine the third 4-bit
-        // operation. To emulate this, we discard the bottom 8-bits (the first two 4-bit
-        // operators), and then apply our half-carry logic to the now-lowest 4-bits of the value.
-        let lhs = (self >> 8) & 0xF;
-        let rhs = (rhs >> 8) & 0xF;
+        // 16-bit additions are carried out as two 8-bit additions, and the half-carry flag is set
+        // by the second one, so it reflects a carry from bit 11 into bit 12.
+        let lhs = self & 0xFFF;
+        let rhs = rhs & 0xFFF;
 
-        lhs + rhs > 0xF
+        lhs + rhs > 0xFFF
     }
 
     fn is_half_carry_sub(&self, rhs: Self) -> bool {
-        let lhs = (self >> 8) & 0xF;
-        let rhs = (rhs >> 8) & 0xF;
-
-        lhs > rhs
+        (self & 0xFFF) < (rhs & 0xFFF)
     }
 
     fn get_carry_value() -> Self {
@@ -0,0 +1,336 @@
+//! Runs the community test ROMs and decides whether they passed, using whichever reporting
+//! convention each suite uses:
+//!
+//! - Blargg's ROMs print their result over the serial port, and the newer ones also write it to
+//!   cartridge RAM at `$A000`, behind the signature `$DE $B0 $61`.
+//! - Mooneye's ROMs execute `LD B, B` when they're done, with B, C, D, E, H and L holding the
+//!   Fibonacci numbers 3, 5, 8, 13, 21 and 34 on success, or `$42` on failure.
+//! - dmg-acid2 and cgb-acid2 also execute `LD B, B` when done, and are judged by comparing a hash of
+//!   the framebuffer with a reference. The reference is read from a file next to the ROM with the
+//!   extension `.hash`, holding the hash in hexadecimal. Without one, the ROM runs until the frame
+//!   limit, and the hash it reaches is reported so that it can be checked and saved.
+
+use crate::{crash::panic_message, Interpreter};
+use gb_asm::Register;
+use gb_hardware::{bus::Bus, Device};
+use std::{
+    fmt::Display,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+};
+
+/// The opcode of `LD B, B`, used as a software breakpoint by several test suites.
+const BREAKPOINT: u8 = 0x40;
+
+/// The length of a frame in M-cycles, which the frame limit is measured in. Counting cycles rather
+/// than VBlanks keeps the limit working while the LCD is off.
+const FRAME_CYCLES: u64 = 17556;
+
+const FIBONACCI: [u8; 6] = [3, 5, 8, 13, 21, 34];
+const MOONEYE_FAILURE: u8 = 0x42;
+
+const SIGNATURE_ADDRESS: u16 = 0xA001;
+const SIGNATURE: [u8; 3] = [0xDE, 0xB0, 0x61];
+const STATUS_RUNNING: u8 = 0x80;
+const TEXT_ADDRESS: u16 = 0xA004;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Pass,
+    Fail(String),
+    /// The ROM didn't report a result within the frame limit.
+    Timeout,
+    /// The ROM couldn't be loaded or run, or the emulator panicked.
+    Crash(String),
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pass => write!(f, "pass"),
+            Self::Fail(_) => write!(f, "FAIL"),
+            Self::Timeout => write!(f, "timeout"),
+            Self::Crash(_) => write!(f, "CRASH"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub outcome: Outcome,
+    pub frames: u32,
+    /// Everything the ROM printed over the serial port.
+    pub serial: String,
+    /// The hash of the framebuffer when the ROM stopped, which can be saved as the reference for
+    /// ROMs judged by their output.
+    pub framebuffer_hash: u64,
+}
+
+impl Report {
+    /// Returns a one-line explanation of the outcome.
+    pub fn detail(&self) -> String {
+        match &self.outcome {
+            Outcome::Fail(detail) | Outcome::Crash(detail) => detail.clone(),
+            Outcome::Pass | Outcome::Timeout if self.serial.trim().is_empty() => {
+                format!("framebuffer hash {:016x}", self.framebuffer_hash)
+            }
+            Outcome::Pass | Outcome::Timeout => {
+                let serial = self.serial.split_whitespace().collect::<Vec<_>>();
+                serial.join(" ")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Runner {
+    frame_limit: u32,
+}
+
+impl Default for Runner {
+    fn default() -> Self {
+        Self {
+            // Blargg's full cpu_instrs takes close to a minute on hardware.
+            frame_limit: 60 * 120,
+        }
+    }
+}
+
+impl Runner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn frame_limit(&mut self, frames: u32) -> &mut Self {
+        self.frame_limit = frames;
+        self
+    }
+
+    /// Runs the ROM at `path`, reading the reference framebuffer hash from the file next to it if
+    /// there is one.
+    pub fn run_file(&self, path: &Path) -> Report {
+        let reference = std::fs::read_to_string(path.with_extension("hash"))
+            .ok()
+            .and_then(|text| u64::from_str_radix(text.trim(), 16).ok());
+
+        match std::fs::read(path) {
+            Ok(rom) => self.run(rom, reference),
+            Err(e) => Report {
+                outcome: Outcome::Crash(format!("unable to read ROM: {e}")),
+                frames: 0,
+                serial: String::new(),
+                framebuffer_hash: 0,
+            },
+        }
+    }
+
+    /// Runs `rom` until it reports a result or the frame limit is reached. Errors, and panics in
+    /// the emulator, are reported as a [`Outcome::Crash`].
+    pub fn run(&self, rom: Vec<u8>, reference: Option<u64>) -> Report {
+        let mut frames = 0;
+        let mut cycles = 0;
+        let mut serial = Vec::new();
+        let mut hash = 0;
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut device = Device::from_rom(rom).map_err(|e| e.to_string())?;
+            let mut interpreter = Interpreter::default();
+
+            let outcome = loop {
+                if frames >= self.frame_limit {
+                    break Outcome::Timeout;
+                }
+
+                if device.read_byte(device.cpu.program_counter) == BREAKPOINT {
+                    if let Some(outcome) = breakpoint(&device, reference) {
+                        break outcome;
+                    }
+                }
+
+                let counter = device.cpu.cycle_counter;
+
+                if let Err(error) = interpreter.step(&mut device) {
+                    break Outcome::Crash(error.to_string());
+                }
+
+                cycles += device.cpu.cycle_counter.wrapping_sub(counter) as u64;
+
+                if cycles / FRAME_CYCLES > frames as u64 {
+                    frames = (cycles / FRAME_CYCLES) as u32;
+
+                    if let Some(outcome) = blargg(&device) {
+                        break outcome;
+                    }
+                }
+            };
+
+            serial.clone_from(&device.serial.output);
+            hash = framebuffer_hash(&device.video.framebuffer);
+            Ok(outcome)
+        }));
+
+        let outcome = match result {
+            Ok(Ok(outcome)) => outcome,
+            Ok(Err(message)) => Outcome::Crash(message),
+            Err(payload) => Outcome::Crash(panic_message(&*payload)),
+        };
+
+        Report {
+            outcome,
+            frames,
+            serial: String::from_utf8_lossy(&serial).into_owned(),
+            framebuffer_hash: hash,
+        }
+    }
+}
+
+/// Returns the FNV-1a hash of a framebuffer.
+pub fn framebuffer_hash(framebuffer: &[u8]) -> u64 {
+    framebuffer
+        .iter()
+        .fold(0xCBF2_9CE4_8422_2325, |hash, shade| {
+            (hash ^ *shade as u64).wrapping_mul(0x0100_0000_01B3)
+        })
+}
+
+/// Checks the registers when the ROM hits `LD B, B`. Breakpoints that don't carry a result are
+/// ignored.
+fn breakpoint(device: &Device, reference: Option<u64>) -> Option<Outcome> {
+    use Register::*;
+
+    let registers = [B, C, D, E, H, L].map(|r| device.cpu.get(r));
+
+    if registers == FIBONACCI {
+        return Some(Outcome::Pass);
+    }
+
+    if registers.iter().all(|r| *r == MOONEYE_FAILURE) {
+        return Some(Outcome::Fail("registers hold $42".into()));
+    }
+
+    // Other breakpoints only mean something to ROMs judged by their framebuffer.
+    let reference = reference?;
+    let hash = framebuffer_hash(&device.video.framebuffer);
+
+    if hash == reference {
+        Some(Outcome::Pass)
+    } else {
+        Some(Outcome::Fail(format!(
+            "framebuffer hash {hash:016x}, expected {reference:016x}"
+        )))
+    }
+}
+
+/// Checks the serial output and the `$A000` signature for a result from one of Blargg's ROMs.
+fn blargg(device: &Device) -> Option<Outcome> {
+    let serial = String::from_utf8_lossy(&device.serial.output);
+
+    if serial.contains("Passed") {
+        return Some(Outcome::Pass);
+    }
+
+    if serial.contains("Failed") {
+        return Some(Outcome::Fail(
+            serial.split_whitespace().collect::<Vec<_>>().join(" "),
+        ));
+    }
+
+    let signature = [0, 1, 2].map(|i| device.read_byte(SIGNATURE_ADDRESS + i));
+    let status = device.read_byte(SIGNATURE_ADDRESS - 1);
+
+    if signature != SIGNATURE || status == STATUS_RUNNING {
+        return None;
+    }
+
+    let text: Vec<u8> = (TEXT_ADDRESS..0xC000)
+        .map(|address| device.read_byte(address))
+        .take_while(|b| *b != 0)
+        .collect();
+
+    let text = String::from_utf8_lossy(&text);
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    if status == 0 {
+        Some(Outcome::Pass)
+    } else {
+        Some(Outcome::Fail(format!("status ${status:02X}: {text}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gb_asm::{assembler::Assembler, linker::Linker};
+
+    fn assemble(code: &str) -> Vec<u8> {
+        let source = format!(
+            "SECTION \"Entry\", ROM0[$100]\n    jp Main\nSECTION \"Main\", ROM0[$150]\nMain:\n{code}\nSpin:\n    jr Spin\n"
+        );
+
+        let object = Assembler::new()
+            .assemble_source("test.asm", &source)
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        Linker::new().fix_header(true).link(&[object]).unwrap().rom
+    }
+
+    #[test]
+    fn mooneye_breakpoint() {
+        let pass = assemble(
+            "    ld b, 3\n    ld c, 5\n    ld d, 8\n    ld e, 13\n    ld h, 21\n    ld l, 34\n    ld b, b",
+        );
+        let fail = assemble(
+            "    ld a, $42\n    ld b, a\n    ld c, a\n    ld d, a\n    ld e, a\n    ld h, a\n    ld l, a\n    ld b, b",
+        );
+
+        let mut runner = Runner::new();
+        runner.frame_limit(10);
+
+        assert_eq!(runner.run(pass, None).outcome, Outcome::Pass);
+        assert!(matches!(runner.run(fail, None).outcome, Outcome::Fail(_)));
+    }
+
+    #[test]
+    fn serial_output() {
+        let rom = assemble(
+            r#"
+    ld hl, Message
+.next
+    ld a, [hl+]
+    and a
+    jr z, Spin
+    ldh [$01], a
+    ld a, $81
+    ldh [$02], a
+    jr .next
+Message:
+    db "cpu_instrs\n\nPassed\n", 0"#,
+        );
+
+        let report = Runner::new().frame_limit(10).run(rom, None);
+
+        assert_eq!(report.outcome, Outcome::Pass);
+        assert_eq!(report.serial, "cpu_instrs\n\nPassed\n");
+    }
+
+    #[test]
+    fn halt_waits_for_vblank() {
+        let rom = assemble(
+            "    ld a, $01\n    ldh [$FF], a\n    xor a\n    ldh [$0F], a\n    halt\n    nop\n    ld b, 3\n    ld c, 5\n    ld d, 8\n    ld e, 13\n    ld h, 21\n    ld l, 34\n    ld b, b",
+        );
+
+        let report = Runner::new().frame_limit(10).run(rom, None);
+
+        assert_eq!(report.outcome, Outcome::Pass);
+        assert_eq!(report.frames, 0);
+    }
+
+    #[test]
+    fn frame_limit_holds_with_the_lcd_off() {
+        let rom = assemble("    xor a\n    ldh [$40], a");
+        let report = Runner::new().frame_limit(5).run(rom, None);
+
+        assert_eq!(report.outcome, Outcome::Timeout);
+        assert_eq!(report.frames, 5);
+    }
+}
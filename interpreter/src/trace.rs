@@ -0,0 +1,418 @@
+//! An execution trace in the format of [gameboy-doctor], one line per instruction:
+//!
+//! ```text
+//! A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,13,02
+//! ```
+//!
+//! Each line holds the state before the instruction at PC executes. Extra columns can follow it,
+//! with the disassembled instruction, the ROM bank, LY and the number of M-cycles executed, which
+//! gameboy-doctor ignores. Comparing a trace against a reference log finds the first instruction
+//! where the CPU goes wrong. Given a symbol file, the disassembly starts with the label PC is
+//! at and shows addresses with a label by name.
+//!
+//! [gameboy-doctor]: https://github.com/robert/gameboy-doctor
+
+use crate::debugger::expression::Expression;
+use gb_asm::symbols::{bank_of, Banks, SymbolFile};
+use gb_hardware::Device;
+use gb_parser::{parse, parse_prefixed};
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    ops::RangeInclusive,
+    path::PathBuf,
+};
+
+/// The optional columns after the gameboy-doctor ones.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Columns {
+    pub disassembly: bool,
+    pub bank: bool,
+    pub line: bool,
+    pub cycles: bool,
+}
+
+impl Columns {
+    pub fn all() -> Self {
+        Self {
+            disassembly: true,
+            bank: true,
+            line: true,
+            cycles: true,
+        }
+    }
+}
+
+/// Writes a line to `writer` for each instruction that passes the filters.
+#[derive(Debug)]
+pub struct Tracer<W: Write> {
+    writer: W,
+    columns: Columns,
+    range: Option<RangeInclusive<u16>>,
+    bank: Option<usize>,
+    trigger: Option<Expression>,
+    triggered: bool,
+    symbols: SymbolFile,
+    /// The total number of M-cycles, kept from the CPU's wrapping counter.
+    cycles: u64,
+    last_counter: Option<u16>,
+}
+
+impl<W: Write> Tracer<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            columns: Columns::default(),
+            range: None,
+            bank: None,
+            trigger: None,
+            triggered: true,
+            symbols: SymbolFile::new(),
+            cycles: 0,
+            last_counter: None,
+        }
+    }
+
+    pub fn columns(&mut self, columns: Columns) -> &mut Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Only logs instructions in `range`.
+    pub fn range(&mut self, range: RangeInclusive<u16>) -> &mut Self {
+        self.range = Some(range);
+        self
+    }
+
+    /// Only logs instructions in ROM bank `bank`. Bank 0 is `$0000-$3FFF`, and instructions
+    /// outside ROM are never in a bank.
+    pub fn bank(&mut self, bank: usize) -> &mut Self {
+        self.bank = Some(bank);
+        self
+    }
+
+    /// Starts logging the first time `trigger` is true, such as `PC == $150`.
+    pub fn trigger(&mut self, trigger: Expression) -> &mut Self {
+        self.trigger = Some(trigger);
+        self.triggered = false;
+        self
+    }
+
+    /// Labels the disassembly column with `symbols`.
+    pub fn symbols(&mut self, symbols: SymbolFile) -> &mut Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Logs the instruction at PC, if it passes the filters. Call this before each step.
+    pub fn log(&mut self, device: &Device) -> io::Result<()> {
+        let counter = device.cpu.cycle_counter;
+        let delta = self
+            .last_counter
+            .map_or(0, |last| counter.wrapping_sub(last));
+        self.cycles += delta as u64;
+        self.last_counter = Some(counter);
+
+        if !self.triggered {
+            let Some(trigger) = &self.trigger else {
+                return Ok(());
+            };
+
+            if trigger.evaluate(device, 0).unwrap_or_default() == 0 {
+                return Ok(());
+            }
+
+            self.triggered = true;
+        }
+
+        let pc = device.cpu.program_counter;
+
+        if self
+            .range
+            .as_ref()
+            .is_some_and(|range| !range.contains(&pc))
+        {
+            return Ok(());
+        }
+
+        if self.bank.is_some() && code_bank(device, pc) != self.bank {
+            return Ok(());
+        }
+
+        let mut line = format_line(device, self.columns, self.cycles, &self.symbols);
+        line.push('\n');
+
+        self.writer.write_all(line.as_bytes())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Formats the state of `device` as a line of the trace, without the line break.
+pub fn format_line(device: &Device, columns: Columns, cycles: u64, symbols: &SymbolFile) -> String {
+    let cpu = &device.cpu;
+    let pc = cpu.program_counter;
+    let memory = [0, 1, 2, 3].map(|offset| device.peek(pc.wrapping_add(offset)));
+
+    let mut line = format!(
+        "A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} \
+         PC:{:04X} PCMEM:{:02X},{:02X},{:02X},{:02X}",
+        cpu.a,
+        cpu.flags,
+        cpu.b,
+        cpu.c,
+        cpu.d,
+        cpu.e,
+        cpu.h,
+        cpu.l,
+        cpu.stack_pointer,
+        pc,
+        memory[0],
+        memory[1],
+        memory[2],
+        memory[3],
+    );
+
+    if columns.disassembly {
+        let banks = device.banks();
+        line += " | ";
+
+        if let Some(location) = symbols.resolve(bank_of(pc, banks), pc) {
+            line += &format!("{location}: ");
+        }
+
+        line += &disassemble_with_symbols(&memory, symbols, banks);
+    }
+
+    if columns.bank {
+        match code_bank(device, pc) {
+            Some(bank) => line += &format!(" BANK:{bank:02X}"),
+            None => line += " BANK:--",
+        }
+    }
+
+    if columns.line {
+        line += &format!(" LY:{:02X}", device.video.current_line);
+    }
+
+    if columns.cycles {
+        line += &format!(" CY:{cycles}");
+    }
+
+    line
+}
+
+/// Disassembles the instruction in `bytes`, with its operands filled in.
+pub fn disassemble(bytes: &[u8; 4]) -> String {
+    disassemble_with_symbols(bytes, &SymbolFile::new(), Banks::default())
+}
+
+/// Disassembles like [`disassemble`], but shows 16-bit operands that are at a label in `symbols`
+/// as that label. `banks` are the banks that are mapped.
+pub fn disassemble_with_symbols(bytes: &[u8; 4], symbols: &SymbolFile, banks: Banks) -> String {
+    let instruction = match parse(bytes[0]) {
+        instruction if instruction.is_prefix() => parse_prefixed(bytes[1]),
+        instruction => instruction,
+    };
+
+    // Operands follow the opcode, and are shown as placeholders by `Display`.
+    let word = u16::from_le_bytes([bytes[1], bytes[2]]);
+    let word = match symbols.name_at(bank_of(word, banks), word) {
+        Some(name) => name.to_string(),
+        None => format!("${word:04X}"),
+    };
+
+    instruction
+        .to_string()
+        .replace("d16", &word)
+        .replace("d8", &format!("${:02X}", bytes[1]))
+        .replace("s8", &(bytes[1] as i8).to_string())
+}
+
+/// Returns the ROM bank that `address` is in, or `None` outside ROM.
+fn code_bank(device: &Device, address: u16) -> Option<usize> {
+    match address {
+        0x0000..=0x3FFF => Some(0),
+        0x4000..=0x7FFF => Some(device.memory.cartridge.rom_bank()),
+        _ => None,
+    }
+}
+
+/// A log file that's kept to a bounded size. Once a line would take the file past the limit, it's
+/// renamed with `.1` appended, replacing the previous one, and a new file is started. Between
+/// them, the two files hold about twice the limit.
+#[derive(Debug)]
+pub struct RollingFile {
+    path: PathBuf,
+    limit: u64,
+    file: BufWriter<File>,
+    written: u64,
+    /// Files are only rolled over between lines.
+    at_line_start: bool,
+}
+
+impl RollingFile {
+    pub fn create(path: impl Into<PathBuf>, limit: u64) -> io::Result<Self> {
+        let path = path.into();
+
+        Ok(Self {
+            file: BufWriter::new(File::create(&path)?),
+            path,
+            limit,
+            written: 0,
+            at_line_start: true,
+        })
+    }
+
+    /// The path of the previous file, once there is one.
+    pub fn previous_path(&self) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".1");
+
+        path.into()
+    }
+
+    fn roll(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        fs::rename(&self.path, self.previous_path())?;
+
+        self.file = BufWriter::new(File::create(&self.path)?);
+        self.written = 0;
+
+        Ok(())
+    }
+}
+
+impl Write for RollingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.at_line_start && self.written > 0 && self.written + buf.len() as u64 > self.limit {
+            self.roll()?;
+        }
+
+        let written = self.file.write(buf)?;
+        self.written += written as u64;
+
+        if written > 0 {
+            self.at_line_start = buf[written - 1] == b'\n';
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Interpreter;
+
+    fn device() -> Device {
+        let mut rom = vec![0; 0x8000];
+
+        // LD A, $34; LD [$C000], A; JR -2
+        rom[0x100..0x107].copy_from_slice(&[0x3E, 0x34, 0xEA, 0x00, 0xC0, 0x18, 0xFE]);
+
+        Device::from_rom(rom).unwrap()
+    }
+
+    fn trace(tracer: &mut Tracer<Vec<u8>>, steps: usize) -> Vec<String> {
+        let mut device = device();
+        let mut interpreter = Interpreter::default();
+
+        for _ in 0..steps {
+            tracer.log(&device).unwrap();
+            interpreter.step(&mut device).unwrap();
+        }
+
+        String::from_utf8(std::mem::take(&mut tracer.writer))
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn writes_gameboy_doctor_lines() {
+        let mut tracer = Tracer::new(Vec::new());
+        tracer.columns(Columns::all());
+
+        let lines = trace(&mut tracer, 3);
+        let cpu = &device().cpu;
+
+        assert_eq!(
+            lines[0],
+            format!(
+                "A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} \
+                 SP:{:04X} PC:0100 PCMEM:3E,34,EA,00 | LD A, $34 BANK:00 LY:00 CY:0",
+                cpu.a, cpu.flags, cpu.b, cpu.c, cpu.d, cpu.e, cpu.h, cpu.l, cpu.stack_pointer
+            )
+        );
+        assert!(lines[1].starts_with("A:34 "));
+        assert!(lines[1].ends_with("PC:0102 PCMEM:EA,00,C0,18 | LD ($C000), A BANK:00 LY:00 CY:2"));
+        assert!(lines[2].contains("| JR -2 "));
+    }
+
+    #[test]
+    fn filters_instructions() {
+        let mut tracer = Tracer::new(Vec::new());
+        tracer.range(0x102..=0x105);
+        assert_eq!(trace(&mut tracer, 5).len(), 4);
+
+        let mut tracer = Tracer::new(Vec::new());
+        tracer.trigger(Expression::parse("[$C000] == $34").unwrap());
+        let lines = trace(&mut tracer, 5);
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|line| line.contains("PC:0105")));
+
+        let mut tracer = Tracer::new(Vec::new());
+        tracer.bank(1);
+        assert!(trace(&mut tracer, 5).is_empty());
+    }
+
+    #[test]
+    fn labels_the_disassembly() {
+        let mut tracer = Tracer::new(Vec::new());
+        tracer
+            .columns(Columns::all())
+            .symbols("00:0100 Start\n00:c000 wValue".parse().unwrap());
+
+        let lines = trace(&mut tracer, 3);
+
+        assert!(lines[0].contains("| Start: LD A, $34 "));
+        assert!(lines[1].contains("| Start+2: LD (wValue), A "));
+        assert!(lines[2].contains("| Start+5: JR -2 "));
+    }
+
+    #[test]
+    fn rolls_files_over() {
+        let path = std::env::temp_dir().join(format!("gb-trace-{}.log", std::process::id()));
+        let mut file = RollingFile::create(&path, 100).unwrap();
+
+        // The tracer writes whole lines at once.
+        for line in 0..10 {
+            file.write_all(format!("{line:039}\n").as_bytes()).unwrap();
+        }
+
+        file.flush().unwrap();
+
+        let current = fs::read_to_string(&path).unwrap();
+        let previous = fs::read_to_string(file.previous_path()).unwrap();
+
+        assert_eq!(current.lines().count(), 2);
+        assert_eq!(previous.lines().count(), 2);
+        assert!(current.ends_with(&format!("{:039}\n", 9)));
+
+        fs::remove_file(file.previous_path()).unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+}
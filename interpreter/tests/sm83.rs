@@ -0,0 +1,310 @@
+//! Runs SM83 single-step test vectors against the interpreter. Every vector executes a single
+//! instruction on a flat 64 KiB bus, after which the registers, the RAM, the number of M-cycles
+//! taken and the reads and writes made on the bus are compared with the expected state. Mismatches
+//! are reported per opcode.
+//!
+//! `derived_vectors` runs the vectors in `tests/sm83`, which are written for this repository
+//! rather than taken from a test suite. `upstream_vectors` runs a checkout of the
+//! [SingleStepTests/sm83](https://github.com/SingleStepTests/sm83) suite, if the `SM83_TESTS`
+//! environment variable names one.
+
+use gb_hardware::bus::{Bus, FlatBus, MemoryAccess};
+use gb_interpreter::Interpreter;
+use serde::Deserialize;
+use std::{
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+struct Vector {
+    name: String,
+    initial: State,
+    expected: State,
+    cycles: u16,
+    accesses: Vec<MemoryAccess>,
+}
+
+/// A line of a `tests/sm83` file. `bus` lists the reads and writes in order, written as
+/// `r 1234=56` or `w 1234=56`.
+#[derive(Debug, Deserialize)]
+struct DerivedVector {
+    before: State,
+    after: State,
+    m_cycles: u16,
+    bus: Vec<String>,
+}
+
+fn parse_access(text: &str) -> MemoryAccess {
+    let parsed = text.split_once(' ').and_then(|(kind, access)| {
+        let (address, value) = access.split_once('=')?;
+        let address = u16::from_str_radix(address, 16).ok()?;
+        let value = u8::from_str_radix(value, 16).ok()?;
+
+        match kind {
+            "r" => Some(MemoryAccess::Read { address, value }),
+            "w" => Some(MemoryAccess::Write { address, value }),
+            _ => None,
+        }
+    });
+
+    parsed.unwrap_or_else(|| panic!("`{text}` is not a bus access"))
+}
+
+fn load_derived(path: &Path) -> Vec<Vector> {
+    fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .enumerate()
+        .map(|(line, text)| {
+            let vector: DerivedVector = serde_json::from_str(text).unwrap_or_else(|e| {
+                panic!(
+                    "line {} of {} is not a vector: {e}",
+                    line + 1,
+                    path.display()
+                )
+            });
+
+            Vector {
+                name: format!("line {}", line + 1),
+                initial: vector.before,
+                expected: vector.after,
+                cycles: vector.m_cycles,
+                accesses: vector
+                    .bus
+                    .iter()
+                    .map(|access| parse_access(access))
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// A vector of the SingleStepTests suite.
+#[derive(Debug, Deserialize)]
+struct UpstreamVector {
+    name: String,
+    initial: State,
+    #[serde(rename = "final")]
+    expected: State,
+    cycles: Vec<Cycle>,
+}
+
+/// The bus activity of one M-cycle: the address, the value on the data bus, and a `r`, `w` or `-`
+/// for whether memory was read or written. Cycles without activity are `[null, null, "---"]`.
+#[derive(Debug, Deserialize)]
+struct Cycle(Option<u16>, Option<u8>, String);
+
+impl Cycle {
+    fn access(&self) -> Option<MemoryAccess> {
+        let (Some(address), Some(value)) = (self.0, self.1) else {
+            return None;
+        };
+
+        match self.2.as_bytes() {
+            [b'r', ..] => Some(MemoryAccess::Read { address, value }),
+            [_, b'w', ..] => Some(MemoryAccess::Write { address, value }),
+            _ => None,
+        }
+    }
+}
+
+fn load_upstream(path: &Path) -> Vec<Vector> {
+    let text = fs::read_to_string(path).unwrap();
+    let vectors: Vec<UpstreamVector> = serde_json::from_str(&text)
+        .unwrap_or_else(|e| panic!("{} is not a valid vector file: {e}", path.display()));
+
+    vectors
+        .into_iter()
+        .map(|vector| Vector {
+            accesses: vector.cycles.iter().filter_map(Cycle::access).collect(),
+            cycles: vector.cycles.len() as u16,
+            name: vector.name,
+            initial: vector.initial,
+            expected: vector.expected,
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct State {
+    pc: u16,
+    sp: u16,
+    a: u8,
+    b: u8,
+    c: u8,
+    d: u8,
+    e: u8,
+    f: u8,
+    h: u8,
+    l: u8,
+    ime: u8,
+    ie: Option<u8>,
+    ram: Vec<(u16, u8)>,
+}
+
+fn load(state: &State) -> FlatBus {
+    let mut device = FlatBus::new();
+    let cpu = &mut device.cpu;
+
+    cpu.program_counter = state.pc;
+    cpu.stack_pointer = state.sp;
+    cpu.a = state.a;
+    cpu.b = state.b;
+    cpu.c = state.c;
+    cpu.d = state.d;
+    cpu.e = state.e;
+    cpu.flags = state.f;
+    cpu.h = state.h;
+    cpu.l = state.l;
+
+    device.interrupts.master_enable = state.ime != 0;
+    device.write_byte(0xFFFF, state.ie.unwrap_or_default());
+
+    for &(address, value) in &state.ram {
+        device.write_byte(address, value);
+    }
+
+    device.record_accesses();
+    device
+}
+
+fn describe(accesses: &[MemoryAccess]) -> String {
+    let accesses: Vec<_> = accesses
+        .iter()
+        .map(|access| match access {
+            MemoryAccess::Read { address, value } => format!("r {address:04x}={value:02x}"),
+            MemoryAccess::Write { address, value } => format!("w {address:04x}={value:02x}"),
+        })
+        .collect();
+
+    format!("[{}]", accesses.join(", "))
+}
+
+/// Runs a vector, returning a description of every difference from the expected state.
+fn run(vector: &Vector) -> Vec<String> {
+    let mut device = load(&vector.initial);
+    let cycles = device.cpu.cycle_counter;
+
+    if let Err(error) = Interpreter::default().step(&mut device) {
+        return vec![error.to_string()];
+    }
+
+    let accesses = device.take_accesses();
+
+    let cpu = &device.cpu;
+    let expected = &vector.expected;
+    let mut differences = Vec::new();
+
+    let mut compare = |name: &str, actual: u16, expected: u16| {
+        if actual != expected {
+            differences.push(format!("{name} = {actual:#06x}, expected {expected:#06x}"));
+        }
+    };
+
+    compare("PC", cpu.program_counter, expected.pc);
+    compare("SP", cpu.stack_pointer, expected.sp);
+    compare("A", cpu.a.into(), expected.a.into());
+    compare("B", cpu.b.into(), expected.b.into());
+    compare("C", cpu.c.into(), expected.c.into());
+    compare("D", cpu.d.into(), expected.d.into());
+    compare("E", cpu.e.into(), expected.e.into());
+    compare("F", cpu.flags.into(), expected.f.into());
+    compare("H", cpu.h.into(), expected.h.into());
+    compare("L", cpu.l.into(), expected.l.into());
+    compare(
+        "IME",
+        device.interrupts.master_enable.into(),
+        expected.ime.into(),
+    );
+    compare(
+        "M-cycles",
+        cpu.cycle_counter.wrapping_sub(cycles),
+        vector.cycles,
+    );
+
+    for &(address, value) in &expected.ram {
+        compare(
+            &format!("[{address:#06x}]"),
+            device.read_byte(address).into(),
+            value.into(),
+        );
+    }
+
+    // The interpreter ticks once per instruction, so only the order of the cycles that reach
+    // memory can be compared, not where the idle cycles fall between them.
+    if accesses != vector.accesses {
+        differences.push(format!(
+            "bus activity {}, expected {}",
+            describe(&accesses),
+            describe(&vector.accesses)
+        ));
+    }
+
+    differences
+}
+
+/// Runs every `extension` file in `directory`, each holding the vectors of one opcode, and fails
+/// with a report of the opcodes that don't match.
+fn run_directory(directory: &Path, extension: &str, load: fn(&Path) -> Vec<Vector>) {
+    let mut files: Vec<_> = fs::read_dir(directory)
+        .expect("test vector directory is readable")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == extension))
+        .collect();
+
+    files.sort();
+    assert!(!files.is_empty(), "no test vectors found");
+
+    let mut report = String::new();
+    let mut failed_opcodes = 0;
+
+    for path in &files {
+        let vectors = load(path);
+        let failures: Vec<_> = vectors
+            .iter()
+            .map(|vector| (vector, run(vector)))
+            .filter(|(_, differences)| !differences.is_empty())
+            .collect();
+
+        if let Some((vector, differences)) = failures.first() {
+            failed_opcodes += 1;
+
+            let opcode = path.file_stem().unwrap().to_string_lossy();
+            writeln!(
+                report,
+                "{opcode}: {}/{} failed, e.g. `{}`: {}",
+                failures.len(),
+                vectors.len(),
+                vector.name,
+                differences.join(", ")
+            )
+            .unwrap();
+        }
+    }
+
+    assert!(
+        failed_opcodes == 0,
+        "{failed_opcodes} of {} opcodes failed:\n{report}",
+        files.len()
+    );
+}
+
+/// Runs the vectors in `tests/sm83`. See the README there for what they cover.
+#[test]
+fn derived_vectors() {
+    let directory = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/sm83");
+
+    run_directory(&directory, "jsonl", load_derived);
+}
+
+/// Runs the SingleStepTests suite in the directory named by `SM83_TESTS`, such as the `v1`
+/// directory of a checkout. Passes without running anything when it isn't set.
+#[test]
+fn upstream_vectors() {
+    let Some(directory) = std::env::var_os("SM83_TESTS") else {
+        return;
+    };
+
+    run_directory(Path::new(&directory), "json", load_upstream);
+}
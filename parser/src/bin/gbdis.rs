@@ -0,0 +1,112 @@
+//! Disassembles a ROM image into RGBDS source that reassembles into the same ROM.
+//!
+//! ```text
+//! gbdis [-s game.sym] [-c game.cdl] [-o game.asm] [-e bank:address]... game.gb
+//! ```
+
+use gb_asm::{cdl::CodeDataLog, symbols::SymbolFile};
+use gb_parser::disassembler::Disassembler;
+use std::{path::PathBuf, process::ExitCode};
+
+const USAGE: &str =
+    "usage: gbdis [-s symbols.sym] [-c coverage.cdl] [-o out.asm] [-e bank:address]... <rom>";
+
+#[derive(Debug, Default)]
+struct Options {
+    rom: Option<PathBuf>,
+    symbols: Option<PathBuf>,
+    coverage: Option<PathBuf>,
+    output: Option<PathBuf>,
+    entry_points: Vec<(u32, u16)>,
+}
+
+fn parse_entry_point(value: &str) -> Option<(u32, u16)> {
+    let (bank, address) = value.split_once(':')?;
+
+    Some((
+        u32::from_str_radix(bank, 16).ok()?,
+        u16::from_str_radix(address, 16).ok()?,
+    ))
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} expects a value"));
+
+        match arg.as_str() {
+            "-s" | "--sym" => options.symbols = Some(value()?.into()),
+            "-c" | "--cdl" => options.coverage = Some(value()?.into()),
+            "-o" | "--output" => options.output = Some(value()?.into()),
+            "-e" | "--entry" => {
+                let value = value()?;
+                let entry = parse_entry_point(&value).ok_or_else(|| {
+                    format!("invalid entry point `{value}`, expected bank:address")
+                })?;
+
+                options.entry_points.push(entry);
+            }
+            "-h" | "--help" => return Err(USAGE.into()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{arg}`\n{USAGE}")),
+            _ if options.rom.is_none() => options.rom = Some(arg.into()),
+            _ => return Err(USAGE.into()),
+        }
+    }
+
+    Ok(options)
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let Some(path) = options.rom else {
+        return Err(USAGE.into());
+    };
+
+    let rom =
+        std::fs::read(&path).map_err(|e| format!("unable to read {}: {e}", path.display()))?;
+
+    let mut disassembler = Disassembler::new(&rom);
+
+    if let Some(path) = options.symbols {
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| format!("unable to read {}: {e}", path.display()))?;
+        let symbols: SymbolFile = text
+            .parse()
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+
+        disassembler.symbols(symbols);
+    }
+
+    if let Some(path) = options.coverage {
+        let bytes =
+            std::fs::read(&path).map_err(|e| format!("unable to read {}: {e}", path.display()))?;
+
+        disassembler.coverage(&CodeDataLog::from_bytes(bytes));
+    }
+
+    for (bank, address) in options.entry_points {
+        disassembler.entry_point(bank, address);
+    }
+
+    let source = disassembler.disassemble();
+
+    match options.output {
+        Some(output) => std::fs::write(&output, source)
+            .map_err(|e| format!("unable to write {}: {e}", output.display())),
+        None => {
+            print!("{source}");
+            Ok(())
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    match parse_args().and_then(run) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
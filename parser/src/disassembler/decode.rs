@@ -0,0 +1,382 @@
+use crate::{parse, parse_prefixed};
+use gb_asm::{
+    instructions::{
+        bitwise::{
+            rotate::{self, Behavior, Direction},
+            shift_right, test,
+        },
+        jump::Target as JumpTarget,
+        load::{
+            Action, Load, ToAccumulatorSource, ToConstantPointerSource, ToHLPointerSource,
+            ToPairPointerTarget, ToStackPointerSource,
+        },
+        math::{
+            add::{Add, ToHLPairSource},
+            dec::Target as CountTarget,
+        },
+        stack::pop::Source as StackSource,
+        subroutine::{
+            call::{Call, VectorSlot},
+            ret::Return,
+        },
+        Condition, Instruction,
+    },
+    sources::ByteSource,
+    Info, Pair, Register,
+};
+use std::fmt::Write;
+
+/// An instruction decoded from a byte stream, along with its operand bytes.
+#[derive(Debug, Copy, Clone)]
+pub struct Decoded {
+    pub instruction: Instruction,
+    bytes: [u8; 3],
+    length: u8,
+}
+
+/// How execution continues after an instruction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Flow {
+    /// Execution continues with the next instruction.
+    Next,
+    /// Execution may continue at the target, or with the next instruction. Used for conditional
+    /// jumps, and for calls (which eventually return).
+    Branch(u16),
+    /// Execution always continues at the target.
+    Jump(u16),
+    /// Execution does not continue with the next instruction, and where it does continue can't be
+    /// known without running the code (`RET`, `RETI` and `JP HL`).
+    End,
+}
+
+impl Decoded {
+    /// Decodes the instruction at the start of `data`. Returns `None` if the opcode is not a valid
+    /// instruction, or `data` ends before the instruction does.
+    ///
+    /// `STOP` is only decoded if it is followed by `$00`, since that is the only form an assembler
+    /// will produce.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let opcode = *data.first()?;
+
+        let instruction = match parse(opcode) {
+            Instruction::Illegal(_) => return None,
+            i if i.is_prefix() => parse_prefixed(*data.get(1)?),
+            i => i,
+        };
+
+        let length = match instruction {
+            Instruction::Stop(_) if data.get(1) != Some(&0x00) => return None,
+            Instruction::Stop(_) => 2,
+            Instruction::Prefix(_) => unreachable!(),
+            i if opcode == 0xCB => i.bytes().max(2),
+            i => i.bytes(),
+        };
+
+        let mut bytes = [0; 3];
+        bytes[..length as usize].copy_from_slice(data.get(..length as usize)?);
+
+        Some(Self {
+            instruction,
+            bytes,
+            length,
+        })
+    }
+
+    /// The number of bytes the instruction occupies, including the prefix and operands.
+    pub fn len(&self) -> usize {
+        self.length as usize
+    }
+
+    /// Always `false`, since every instruction is at least one byte long.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes[..self.len()]
+    }
+
+    fn byte(&self) -> u8 {
+        self.bytes[1]
+    }
+
+    fn word(&self) -> u16 {
+        u16::from_le_bytes([self.bytes[1], self.bytes[2]])
+    }
+
+    fn relative_target(&self, address: u16) -> u16 {
+        address
+            .wrapping_add(2)
+            .wrapping_add_signed(self.byte() as i8 as i16)
+    }
+
+    /// Returns `true` if the target of a relative jump at `address` is only reached by wrapping
+    /// around the address space, which can't be written as an operand.
+    fn wraps(&self, address: u16) -> bool {
+        let target = address as i32 + 2 + self.byte() as i8 as i32;
+        u16::try_from(target).is_err()
+    }
+
+    /// Describes how execution continues after this instruction, if it is located at `address`.
+    pub fn flow(&self, address: u16) -> Flow {
+        match self.instruction {
+            Instruction::Jump(j) => match j.target {
+                JumpTarget::Pointer => Flow::End,
+                JumpTarget::ConstantAddress(None) => Flow::Jump(self.word()),
+                JumpTarget::ConstantAddress(Some(_)) => Flow::Branch(self.word()),
+            },
+            Instruction::JumpRelative(j) => match j.condition {
+                None => Flow::Jump(self.relative_target(address)),
+                Some(_) => Flow::Branch(self.relative_target(address)),
+            },
+            Instruction::Call(Call::Vector(slot)) => Flow::Branch(vector_address(slot)),
+            Instruction::Call(Call::ConstantAddress(_)) => Flow::Branch(self.word()),
+            Instruction::Return(Return::Normal(Some(_))) => Flow::Next,
+            Instruction::Return(_) => Flow::End,
+            _ => Flow::Next,
+        }
+    }
+
+    /// Returns the address of the memory this instruction refers to through its operand, if any.
+    /// This includes the targets of jumps and calls, as well as addresses that are loaded from,
+    /// stored to, or loaded into a register pair.
+    pub fn reference(&self, address: u16) -> Option<u16> {
+        if let Flow::Branch(target) | Flow::Jump(target) = self.flow(address) {
+            return Some(target);
+        }
+
+        let Instruction::Load(load) = self.instruction else {
+            return None;
+        };
+
+        match load {
+            Load::ToPair(_)
+            | Load::ToConstantPointer(_)
+            | Load::ToStackPointer(gb_asm::instructions::load::ToStackPointer {
+                source: ToStackPointerSource::ConstantWord,
+            }) => Some(self.word()),
+            Load::ToAccumulator(a) => match a.source {
+                ToAccumulatorSource::ConstantPointer => Some(self.word()),
+                ToAccumulatorSource::HighConstantPointer => Some(0xFF00 | self.byte() as u16),
+                _ => None,
+            },
+            Load::ToHighConstantPointer(_) => Some(0xFF00 | self.byte() as u16),
+            _ => None,
+        }
+    }
+
+    /// Renders the instruction as RGBDS source. If `name` is given, it is used in place of the
+    /// address returned by [`Decoded::reference()`].
+    pub fn render(&self, address: u16, name: Option<&str>) -> String {
+        let mut out = String::new();
+        self.write(&mut out, address, name)
+            .expect("writing to a string cannot fail");
+
+        out
+    }
+
+    fn write(&self, f: &mut String, address: u16, name: Option<&str>) -> std::fmt::Result {
+        let word = || match name {
+            Some(name) => name.to_owned(),
+            None => format!("${:04X}", self.word()),
+        };
+        let high = || match name {
+            Some(name) => name.to_owned(),
+            None => format!("${:04X}", 0xFF00 | self.byte() as u16),
+        };
+        let target = || match name {
+            Some(name) => name.to_owned(),
+            None => format!("${:04X}", self.relative_target(address)),
+        };
+        let byte_source = |source: ByteSource| match source {
+            ByteSource::Register(r) => register(r),
+            ByteSource::PointerValue => "[hl]".to_owned(),
+            ByteSource::ConstantByte => format!("${:02X}", self.byte()),
+        };
+
+        match self.instruction {
+            Instruction::Nop(_) => write!(f, "nop"),
+            Instruction::Halt(_) => write!(f, "halt"),
+            Instruction::Stop(_) => write!(f, "stop"),
+            Instruction::DisableInterrupts(_) => write!(f, "di"),
+            Instruction::EnableInterrupts(_) => write!(f, "ei"),
+            Instruction::DecimalAdjustAccumulator(_) => write!(f, "daa"),
+            Instruction::ComplementAccumulator(_) => write!(f, "cpl"),
+            Instruction::ComplementCarryFlag(_) => write!(f, "ccf"),
+            Instruction::SetCarryFlag(_) => write!(f, "scf"),
+            Instruction::Prefix(_) => write!(f, "db $CB"),
+            Instruction::Illegal(i) => write!(f, "db ${:02X}", i.0),
+
+            Instruction::Load(load) => match load {
+                Load::ToRegister(l) => {
+                    write!(f, "ld {}, {}", register(l.target), byte_source(l.source))
+                }
+                Load::ToAccumulator(l) => match l.source {
+                    ToAccumulatorSource::PairPointer(p) => write!(f, "ld a, [{}]", pair(p)),
+                    ToAccumulatorSource::ConstantPointer => write!(f, "ld a, [{}]", word()),
+                    ToAccumulatorSource::HighConstantPointer => write!(f, "ldh a, [{}]", high()),
+                    ToAccumulatorSource::HLX(action) => {
+                        write!(f, "ld a, [hl{}]", action_sign(action))
+                    }
+                    ToAccumulatorSource::HighC => write!(f, "ldh a, [c]"),
+                },
+                Load::ToPair(l) => write!(f, "ld {}, {}", pair(l.target), word()),
+                Load::ToPairPointer(l) => match l.target {
+                    ToPairPointerTarget::Pair(p) => write!(f, "ld [{}], a", pair(p)),
+                    ToPairPointerTarget::HLX(action) => {
+                        write!(f, "ld [hl{}], a", action_sign(action))
+                    }
+                },
+                Load::ToHLPointer(l) => match l.source {
+                    ToHLPointerSource::Register(r) => write!(f, "ld [hl], {}", register(r)),
+                    ToHLPointerSource::ConstantByte => write!(f, "ld [hl], ${:02X}", self.byte()),
+                },
+                Load::ToStackPointer(l) => match l.source {
+                    ToStackPointerSource::HL => write!(f, "ld sp, hl"),
+                    ToStackPointerSource::ConstantWord => write!(f, "ld sp, {}", word()),
+                },
+                Load::ToHighC(_) => write!(f, "ldh [c], a"),
+                Load::ToConstantPointer(l) => match l.source {
+                    ToConstantPointerSource::Accumulator => write!(f, "ld [{}], a", word()),
+                    ToConstantPointerSource::StackPointer => write!(f, "ld [{}], sp", word()),
+                },
+                Load::ToHighConstantPointer(_) => write!(f, "ldh [{}], a", high()),
+                Load::ToHL(_) => {
+                    let offset = self.byte() as i8;
+                    let sign = if offset < 0 { '-' } else { '+' };
+
+                    write!(f, "ld hl, sp {sign} {}", offset.unsigned_abs())
+                }
+            },
+
+            Instruction::Add(add) => match add {
+                Add::ToAccumulator(a) => write!(f, "add a, {}", byte_source(a.source)),
+                Add::ToHLPair(a) => match a.source {
+                    ToHLPairSource::Pair(p) => write!(f, "add hl, {}", pair(p)),
+                    ToHLPairSource::StackPointer => write!(f, "add hl, sp"),
+                },
+                Add::ToStackPointer => write!(f, "add sp, {}", self.byte() as i8),
+            },
+            Instruction::AddPlusCarry(a) => write!(f, "adc a, {}", byte_source(a.source)),
+            Instruction::Subtract(s) if s.with_carry => {
+                write!(f, "sbc a, {}", byte_source(s.source))
+            }
+            Instruction::Subtract(s) => write!(f, "sub a, {}", byte_source(s.source)),
+            Instruction::And(a) => write!(f, "and a, {}", byte_source(a.source)),
+            Instruction::Or(o) => write!(f, "or a, {}", byte_source(o.source)),
+            Instruction::Xor(x) => write!(f, "xor a, {}", byte_source(x.source)),
+            Instruction::Compare(c) => write!(f, "cp a, {}", byte_source(c.source)),
+            Instruction::Increment(i) => write!(f, "inc {}", count_target(i.target)),
+            Instruction::Decrement(d) => write!(f, "dec {}", count_target(d.target)),
+
+            Instruction::Jump(j) => match j.target {
+                JumpTarget::Pointer => write!(f, "jp hl"),
+                JumpTarget::ConstantAddress(c) => write!(f, "jp {}{}", condition(c), word()),
+            },
+            Instruction::JumpRelative(_) if self.wraps(address) => {
+                write!(f, "db ${:02X}, ${:02X}", self.bytes[0], self.bytes[1])
+            }
+            Instruction::JumpRelative(j) => write!(f, "jr {}{}", condition(j.condition), target()),
+            Instruction::Call(Call::Vector(slot)) => write!(f, "rst ${:02X}", vector_address(slot)),
+            Instruction::Call(Call::ConstantAddress(c)) => {
+                write!(f, "call {}{}", condition(c), word())
+            }
+            Instruction::Return(Return::EnableInterrupts) => write!(f, "reti"),
+            Instruction::Return(Return::Normal(None)) => write!(f, "ret"),
+            Instruction::Return(Return::Normal(Some(c))) => write!(f, "ret {}", condition_name(c)),
+            Instruction::Push(p) => write!(f, "push {}", stack_source(p.source)),
+            Instruction::Pop(p) => write!(f, "pop {}", stack_source(p.target)),
+
+            Instruction::Rotate(r) => {
+                let mnemonic = match (r.direction, r.behavior) {
+                    (Direction::Left, Behavior::Cyclic) => "rlc",
+                    (Direction::Left, Behavior::Carrying) => "rl",
+                    (Direction::Right, Behavior::Cyclic) => "rrc",
+                    (Direction::Right, Behavior::Carrying) => "rr",
+                };
+
+                match r.target {
+                    rotate::Target::Accumulator => write!(f, "{mnemonic}a"),
+                    rotate::Target::Register(reg) => write!(f, "{mnemonic} {}", register(reg)),
+                    rotate::Target::PointerValue => write!(f, "{mnemonic} [hl]"),
+                }
+            }
+            Instruction::ShiftLeft(s) => write!(f, "sla {}", shift_target(s.target)),
+            Instruction::ShiftRight(s) => match s.behavior {
+                shift_right::Behavior::Arithmetic => write!(f, "sra {}", shift_target(s.target)),
+                shift_right::Behavior::Logical => write!(f, "srl {}", shift_target(s.target)),
+            },
+            Instruction::Swap(s) => write!(f, "swap {}", bit_target(s.target)),
+            Instruction::Test(t) => {
+                write!(f, "bit {}, {}", t.position.value(), bit_target(t.target))
+            }
+            Instruction::ResetBit(r) => {
+                write!(f, "res {}, {}", r.position.value(), bit_target(r.target))
+            }
+            Instruction::SetBit(s) => {
+                write!(f, "set {}, {}", s.position.value(), bit_target(s.target))
+            }
+        }
+    }
+}
+
+/// Returns the address called by an `RST` instruction.
+pub fn vector_address(slot: VectorSlot) -> u16 {
+    slot as u16
+}
+
+fn register(register: Register) -> String {
+    register.to_string().to_ascii_lowercase()
+}
+
+fn pair(pair: Pair) -> String {
+    pair.to_string().to_ascii_lowercase()
+}
+
+fn condition_name(condition: Condition) -> String {
+    condition.to_string().to_ascii_lowercase()
+}
+
+/// Renders an optional condition, followed by the separating comma.
+fn condition(condition: Option<Condition>) -> String {
+    condition
+        .map(|c| format!("{}, ", condition_name(c)))
+        .unwrap_or_default()
+}
+
+fn action_sign(action: Action) -> char {
+    match action {
+        Action::Increment => '+',
+        Action::Decrement => '-',
+    }
+}
+
+fn count_target(target: CountTarget) -> String {
+    match target {
+        CountTarget::Register(r) => register(r),
+        CountTarget::Pair(p) => pair(p),
+        CountTarget::StackPointer => "sp".to_owned(),
+        CountTarget::PointerValue => "[hl]".to_owned(),
+    }
+}
+
+fn stack_source(source: StackSource) -> String {
+    match source {
+        StackSource::AccumulatorAndFlags => "af".to_owned(),
+        StackSource::Pair(p) => pair(p),
+    }
+}
+
+fn shift_target(target: shift_right::Target) -> String {
+    match target {
+        shift_right::Target::Register(r) => register(r),
+        shift_right::Target::PointerValue => "[hl]".to_owned(),
+    }
+}
+
+fn bit_target(target: test::Target) -> String {
+    match target {
+        test::Target::Register(r) => register(r),
+        test::Target::PointerValue => "[hl]".to_owned(),
+    }
+}
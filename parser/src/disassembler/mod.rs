@@ -0,0 +1,674 @@
+//! A recursive-descent disassembler that turns a ROM image back into RGBDS source.
+//!
+//! Code is found by following every path execution can take, starting from the entry point, the
+//! restart vectors and the interrupt vectors. Everything that is never reached is treated as data.
+//! Jumps and calls into the switchable ROM bank are followed when the bank can be worked out,
+//! which is the case when the code is itself in that bank, when the ROM only has one switchable
+//! bank, or when the bank was just selected with the usual `ld a, n` / `ld [$2000], a` sequence.
+//!
+//! A code/data log from a run of the game adds every instruction that was executed as an entry
+//! point, which finds code that is only reached through pointers.
+//!
+//! The output always reassembles into an identical ROM. Every bank is written as a section with a
+//! fixed address, and operands are only replaced by labels that are defined at exactly the address
+//! they stand for.
+
+use gb_asm::{
+    cdl::{self, CodeDataLog},
+    instructions::{
+        load::{Load, ToConstantPointerSource},
+        Instruction,
+    },
+    symbols::SymbolFile,
+    Register,
+};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Write,
+};
+
+mod decode;
+pub use decode::*;
+
+const BANK_SIZE: usize = 0x4000;
+
+/// Runs of at least this many identical bytes are written with `DS` instead of `DB`.
+const MIN_FILL_RUN: usize = 16;
+
+const ENTRY_POINTS: &[(u16, &str)] = &[
+    (0x0000, "RST_00"),
+    (0x0008, "RST_08"),
+    (0x0010, "RST_10"),
+    (0x0018, "RST_18"),
+    (0x0020, "RST_20"),
+    (0x0028, "RST_28"),
+    (0x0030, "RST_30"),
+    (0x0038, "RST_38"),
+    (0x0040, "VBlankInterrupt"),
+    (0x0048, "LCDCInterrupt"),
+    (0x0050, "TimerOverflowInterrupt"),
+    (0x0058, "SerialTransferCompleteInterrupt"),
+    (0x0060, "JoypadTransitionInterrupt"),
+    (0x0100, "Boot"),
+];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ByteKind {
+    Data,
+    Instruction,
+    Operand,
+}
+
+/// Labels generated for jump targets, in increasing order of priority. Labels provided by a symbol
+/// file always take precedence.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum LabelKind {
+    Relative,
+    Jump,
+    Call,
+}
+
+#[derive(Debug, Clone)]
+struct Traced {
+    decoded: Decoded,
+    /// The ROM offset of the location the instruction refers to, if it is in ROM and its bank is
+    /// known.
+    reference: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Disassembler<'a> {
+    rom: &'a [u8],
+    symbols: SymbolFile,
+    entry_points: Vec<(u32, u16)>,
+}
+
+impl<'a> Disassembler<'a> {
+    pub fn new(rom: &'a [u8]) -> Self {
+        Self {
+            rom,
+            symbols: SymbolFile::new(),
+            entry_points: ENTRY_POINTS.iter().map(|(a, _)| (0, *a)).collect(),
+        }
+    }
+
+    /// Uses the names in a symbol file for labels, and for memory addresses outside of ROM.
+    pub fn symbols(&mut self, symbols: SymbolFile) -> &mut Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Adds a location that is known to contain code, such as a function that is only ever called
+    /// through a pointer table.
+    pub fn entry_point(&mut self, bank: u32, address: u16) -> &mut Self {
+        self.entry_points.push((bank, address));
+        self
+    }
+
+    /// Adds every instruction the log saw executed as an entry point.
+    pub fn coverage(&mut self, log: &CodeDataLog) -> &mut Self {
+        for offset in 0..log.len().min(self.rom.len()) {
+            if log.flags(offset) & cdl::OPCODE != 0 {
+                let (bank, address) = cpu_address(offset);
+                self.entry_points.push((bank as u32, address));
+            }
+        }
+
+        self
+    }
+
+    pub fn disassemble(&self) -> String {
+        let mut analysis = Analysis::new(self.rom);
+
+        for &(bank, address) in &self.entry_points {
+            if let Some(offset) = analysis.resolve(address, bank, None) {
+                analysis.trace(offset, None);
+            }
+        }
+
+        let labels = self.labels(&analysis);
+        let constants = self.constants(&labels);
+
+        let mut out = String::new();
+        writeln!(out, "; Disassembled by gb_parser").unwrap();
+
+        if !constants.is_empty() {
+            writeln!(out).unwrap();
+
+            let mut sorted: Vec<_> = constants.iter().collect();
+            sorted.sort_by_key(|(address, name)| (**address, (*name).clone()));
+
+            for (address, name) in sorted {
+                writeln!(out, "DEF {name} EQU ${address:04X}").unwrap();
+            }
+        }
+
+        for bank in 0..self.rom.len().div_ceil(BANK_SIZE) {
+            writeln!(out).unwrap();
+
+            match bank {
+                0 => writeln!(out, "SECTION \"ROM Bank $000\", ROM0[$0000]"),
+                _ => writeln!(
+                    out,
+                    "SECTION \"ROM Bank ${bank:03X}\", ROMX[$4000], BANK[${bank:X}]"
+                ),
+            }
+            .unwrap();
+
+            let end = ((bank + 1) * BANK_SIZE).min(self.rom.len());
+            self.write_bank(
+                &mut out,
+                &analysis,
+                &labels,
+                &constants,
+                bank * BANK_SIZE..end,
+            );
+        }
+
+        out
+    }
+
+    fn labels(&self, analysis: &Analysis) -> BTreeMap<usize, String> {
+        let mut kinds: BTreeMap<usize, LabelKind> = BTreeMap::new();
+
+        for traced in analysis.instructions.values() {
+            let Some(target) = traced.reference else {
+                continue;
+            };
+
+            let kind = match traced.decoded.instruction {
+                Instruction::Call(_) => LabelKind::Call,
+                Instruction::Jump(_) => LabelKind::Jump,
+                Instruction::JumpRelative(_) => LabelKind::Relative,
+                _ => continue,
+            };
+
+            let entry = kinds.entry(target).or_insert(kind);
+            *entry = (*entry).max(kind);
+        }
+
+        let mut labels: BTreeMap<usize, String> = kinds
+            .into_iter()
+            .map(|(offset, kind)| {
+                let (bank, address) = cpu_address(offset);
+                let prefix = match kind {
+                    LabelKind::Call => "Call",
+                    LabelKind::Jump => "Jump",
+                    LabelKind::Relative => "jr",
+                };
+
+                (offset, format!("{prefix}_{bank:03X}_{address:04X}"))
+            })
+            .collect();
+
+        for (address, name) in ENTRY_POINTS {
+            if analysis.kinds.get(*address as usize) == Some(&ByteKind::Instruction) {
+                labels.insert(*address as usize, (*name).to_owned());
+            }
+        }
+
+        let mut named = HashSet::new();
+
+        for entry in self.symbols.entries() {
+            if entry.address >= 0x8000 || !is_valid_name(&entry.name) {
+                continue;
+            }
+
+            let Some(offset) = analysis.resolve(entry.address, entry.bank, None) else {
+                continue;
+            };
+
+            // Only the first name given to an address is used.
+            if named.insert(offset) {
+                labels.insert(offset, entry.name.clone());
+            }
+        }
+
+        // Labels can't be defined in the middle of an instruction.
+        labels.retain(|offset, _| analysis.kinds.get(*offset) != Some(&ByteKind::Operand));
+
+        labels
+    }
+
+    /// Collects names for memory outside of ROM, which are written as constants.
+    fn constants(&self, labels: &BTreeMap<usize, String>) -> HashMap<u16, String> {
+        let taken: HashSet<&str> = labels.values().map(String::as_str).collect();
+        let mut defined = HashSet::new();
+        let mut constants = HashMap::new();
+
+        for entry in self.symbols.entries() {
+            if entry.address < 0x8000
+                || !is_valid_name(&entry.name)
+                || taken.contains(entry.name.as_str())
+                || !defined.insert(entry.name.as_str())
+            {
+                continue;
+            }
+
+            constants
+                .entry(entry.address)
+                .or_insert_with(|| entry.name.clone());
+        }
+
+        constants
+    }
+
+    fn write_bank(
+        &self,
+        out: &mut String,
+        analysis: &Analysis,
+        labels: &BTreeMap<usize, String>,
+        constants: &HashMap<u16, String>,
+        range: std::ops::Range<usize>,
+    ) {
+        let mut offset = range.start;
+
+        while offset < range.end {
+            if let Some(label) = labels.get(&offset) {
+                writeln!(out).unwrap();
+                writeln!(out, "{label}:").unwrap();
+            }
+
+            if let Some(traced) = analysis.instructions.get(&offset) {
+                let (_, address) = cpu_address(offset);
+                let name = match (traced.reference, traced.decoded.reference(address)) {
+                    (Some(target), _) => labels.get(&target),
+                    (None, Some(target)) => constants.get(&target),
+                    _ => None,
+                };
+
+                writeln!(
+                    out,
+                    "    {}",
+                    traced.decoded.render(address, name.map(String::as_str))
+                )
+                .unwrap();
+
+                offset += traced.decoded.len();
+                continue;
+            }
+
+            // Data runs until the next label or instruction.
+            let end = (offset + 1..range.end)
+                .find(|o| labels.contains_key(o) || analysis.instructions.contains_key(o))
+                .unwrap_or(range.end);
+
+            write_data(out, &self.rom[offset..end]);
+            offset = end;
+        }
+    }
+}
+
+fn write_data(out: &mut String, data: &[u8]) {
+    let mut pending: Vec<u8> = Vec::new();
+    let mut i = 0;
+
+    let flush = |out: &mut String, pending: &mut Vec<u8>| {
+        for chunk in pending.chunks(8) {
+            let bytes: Vec<String> = chunk.iter().map(|b| format!("${b:02X}")).collect();
+            writeln!(out, "    db {}", bytes.join(", ")).unwrap();
+        }
+
+        pending.clear();
+    };
+
+    while i < data.len() {
+        let run = data[i..].iter().take_while(|b| **b == data[i]).count();
+
+        if run >= MIN_FILL_RUN {
+            flush(out, &mut pending);
+            writeln!(out, "    ds {run}, ${:02X}", data[i]).unwrap();
+            i += run;
+        } else {
+            pending.push(data[i]);
+            i += 1;
+        }
+    }
+
+    flush(out, &mut pending);
+}
+
+/// Converts a ROM offset into a bank number and the address the byte is mapped to.
+fn cpu_address(offset: usize) -> (usize, u16) {
+    let bank = offset / BANK_SIZE;
+
+    match bank {
+        0 => (0, offset as u16),
+        _ => (bank, (BANK_SIZE + offset % BANK_SIZE) as u16),
+    }
+}
+
+/// Returns `true` if `name` can be used as a label without changing the meaning of the source.
+fn is_valid_name(name: &str) -> bool {
+    const RESERVED: &[&str] = &[
+        "a", "b", "c", "d", "e", "h", "l", "af", "bc", "de", "hl", "sp", "hli", "hld", "z", "nz",
+        "nc",
+    ];
+
+    let mut chars = name.chars();
+    let valid_start = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+
+    valid_start
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '#'))
+        && !RESERVED.contains(&name.to_ascii_lowercase().as_str())
+}
+
+struct Analysis<'a> {
+    rom: &'a [u8],
+    kinds: Vec<ByteKind>,
+    instructions: BTreeMap<usize, Traced>,
+}
+
+impl<'a> Analysis<'a> {
+    fn new(rom: &'a [u8]) -> Self {
+        Self {
+            rom,
+            kinds: vec![ByteKind::Data; rom.len()],
+            instructions: BTreeMap::new(),
+        }
+    }
+
+    fn banks(&self) -> usize {
+        self.rom.len().div_ceil(BANK_SIZE)
+    }
+
+    /// Converts an address into a ROM offset. `bank` is the bank of the code referring to the
+    /// address, and `romx` the bank known to be mapped into `$4000-$7FFF` (if any).
+    fn resolve(&self, address: u16, bank: u32, romx: Option<u32>) -> Option<usize> {
+        let offset = match address {
+            0x0000..=0x3FFF => address as usize,
+            0x4000..=0x7FFF => {
+                let bank = match (bank, romx) {
+                    (0, Some(romx)) => romx,
+                    (0, None) if self.banks() == 2 => 1,
+                    (0, None) => return None,
+                    (bank, _) => bank,
+                };
+
+                bank as usize * BANK_SIZE + (address as usize - BANK_SIZE)
+            }
+            _ => return None,
+        };
+
+        (offset < self.rom.len()).then_some(offset)
+    }
+
+    /// Follows execution from `start`, marking every instruction reached.
+    fn trace(&mut self, start: usize, romx: Option<u32>) {
+        let mut queue = vec![(start, romx)];
+
+        while let Some((mut offset, mut romx)) = queue.pop() {
+            // The value of A, if it was set by a constant load. Used to spot bank switches.
+            let mut accumulator: Option<u8> = None;
+
+            let bank = offset / BANK_SIZE;
+            let bank_end = ((bank + 1) * BANK_SIZE).min(self.rom.len());
+
+            while offset < bank_end && self.kinds[offset] == ByteKind::Data {
+                let Some(decoded) = Decoded::decode(&self.rom[offset..bank_end]) else {
+                    break;
+                };
+
+                let end = offset + decoded.len();
+
+                if self.kinds[offset..end].iter().any(|k| *k != ByteKind::Data) {
+                    break;
+                }
+
+                self.kinds[offset] = ByteKind::Instruction;
+                self.kinds[offset + 1..end].fill(ByteKind::Operand);
+
+                let (_, address) = cpu_address(offset);
+
+                if let Some(selected) = bank_switch(&decoded, accumulator) {
+                    romx = Some(selected);
+                }
+
+                accumulator = match decoded.instruction {
+                    Instruction::Load(Load::ToRegister(l)) if l.target == Register::A => {
+                        match l.source {
+                            gb_asm::sources::ByteSource::ConstantByte => Some(decoded.bytes()[1]),
+                            _ => None,
+                        }
+                    }
+                    i if preserves_accumulator(&i) => accumulator,
+                    _ => None,
+                };
+
+                let reference = decoded
+                    .reference(address)
+                    .and_then(|target| self.resolve(target, bank as u32, romx));
+
+                self.instructions
+                    .insert(offset, Traced { decoded, reference });
+
+                match decoded.flow(address) {
+                    Flow::Next => (),
+                    Flow::Branch(_) => {
+                        if let Some(target) = reference {
+                            queue.push((target, romx));
+                        }
+                    }
+                    Flow::Jump(_) => {
+                        if let Some(target) = reference {
+                            queue.push((target, romx));
+                        }
+
+                        break;
+                    }
+                    Flow::End => break,
+                }
+
+                offset = end;
+            }
+        }
+    }
+}
+
+/// Returns the ROM bank selected by an instruction, if it writes a known value to the MBC's ROM
+/// bank register.
+fn bank_switch(decoded: &Decoded, accumulator: Option<u8>) -> Option<u32> {
+    let Instruction::Load(Load::ToConstantPointer(l)) = decoded.instruction else {
+        return None;
+    };
+
+    let address = u16::from_le_bytes([decoded.bytes()[1], decoded.bytes()[2]]);
+
+    match (l.source, address) {
+        (ToConstantPointerSource::Accumulator, 0x2000..=0x3FFF) => {
+            // Most MBCs treat bank 0 as bank 1.
+            accumulator.map(|bank| (bank as u32).max(1))
+        }
+        _ => None,
+    }
+}
+
+/// Returns `true` for the instructions that can appear between `ld a, n` and the write to the
+/// bank register without changing A.
+fn preserves_accumulator(instruction: &Instruction) -> bool {
+    match instruction {
+        Instruction::Load(Load::ToRegister(l)) => l.target != Register::A,
+        Instruction::Load(Load::ToAccumulator(_)) => false,
+        Instruction::Load(_)
+        | Instruction::Nop(_)
+        | Instruction::Push(_)
+        | Instruction::DisableInterrupts(_)
+        | Instruction::EnableInterrupts(_) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gb_asm::{assembler::Assembler, linker::Linker, table};
+
+    fn reassemble(source: &str) -> Vec<u8> {
+        let object = Assembler::new()
+            .assemble_source("disassembly.asm", source)
+            .unwrap_or_else(|e| panic!("{e}\n{source}"));
+
+        Linker::new().link(&[object]).unwrap().rom
+    }
+
+    #[test]
+    fn every_opcode_reassembles() {
+        let mut opcodes: Vec<Vec<u8>> = (0..=0xFF)
+            .filter(|op| *op != 0xCB)
+            .map(|op| vec![op, 0x34, 0x12])
+            .collect();
+        opcodes.extend((0..=0xFF).map(|op| vec![0xCB, op]));
+
+        for bytes in opcodes {
+            let Some(decoded) = Decoded::decode(&bytes) else {
+                continue;
+            };
+
+            let text = decoded.render(0x1000, None);
+            let source = format!("SECTION \"Test\", ROM0[$1000]\n    {text}\n");
+            let rom = reassemble(&source);
+
+            assert_eq!(
+                &rom[0x1000..0x1000 + decoded.len()],
+                decoded.bytes(),
+                "`{text}` did not reassemble into {:02X?}",
+                decoded.bytes()
+            );
+        }
+    }
+
+    /// Writes a mnemonic from the opcode matrix the way it's rendered at $1000, with `$34, $12` as
+    /// the operand bytes.
+    fn render_mnemonic(mnemonic: &str) -> String {
+        let relative = if mnemonic.starts_with("JR") {
+            "$1036"
+        } else {
+            "52"
+        };
+
+        mnemonic
+            .split_inclusive(|c: char| !c.is_ascii_alphanumeric())
+            .map(|word| {
+                let operand = word.trim_end_matches(|c: char| !c.is_ascii_alphanumeric());
+                let replacement = match operand {
+                    "n16" | "a16" => "$1234",
+                    "n8" => "$34",
+                    "a8" => "$FF34",
+                    "e8" => relative,
+                    _ => return word.to_ascii_lowercase(),
+                };
+
+                word.replacen(operand, replacement, 1)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_opcode_renders_as_in_the_matrix() {
+        let unprefixed = table::UNPREFIXED.iter().enumerate().map(|(opcode, entry)| {
+            // `STOP` is only decoded with a $00 operand, which isn't written out.
+            match entry.mnemonic {
+                "STOP n8" => ([opcode as u8, 0x00, 0x00], entry),
+                _ => ([opcode as u8, 0x34, 0x12], entry),
+            }
+        });
+        let prefixed = table::PREFIXED
+            .iter()
+            .enumerate()
+            .map(|(opcode, entry)| ([0xCB, opcode as u8, 0x00], entry));
+
+        for (bytes, entry) in unprefixed.chain(prefixed) {
+            let name = format!("{:02X?} {}", bytes, entry.mnemonic);
+
+            match (Decoded::decode(&bytes), entry.mnemonic) {
+                (None, mnemonic) => assert!(mnemonic.starts_with("ILLEGAL_"), "decoding {name}"),
+                (Some(decoded), "STOP n8") => assert_eq!(decoded.render(0x1000, None), "stop"),
+                (Some(_), "PREFIX") => (),
+                (Some(decoded), mnemonic) => assert_eq!(
+                    decoded.render(0x1000, None).to_ascii_lowercase(),
+                    render_mnemonic(mnemonic).to_ascii_lowercase(),
+                    "mnemonic of {name}"
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn relative_jumps_that_wrap_stay_bytes() {
+        // JR C, -7 at $0000 jumps to $FFFB, which `jr` can't be written to reach.
+        let mut rom = vec![0; 0x8000];
+        rom[..2].copy_from_slice(&[0x38, 0xF9]);
+
+        let output = Disassembler::new(&rom).disassemble();
+
+        assert!(output.contains("    db $38, $F9\n"));
+        assert_eq!(reassemble(&output), rom);
+    }
+
+    #[test]
+    fn follows_control_flow_and_bank_switches() {
+        let source = r#"
+            SECTION "Entry", ROM0[$100]
+                nop
+                jp Main
+            SECTION "Main", ROM0[$150]
+            Main:
+                ld a, 2
+                ld [$2000], a
+                call Far
+            .loop
+                halt
+                jr .loop
+                db "not code"
+            SECTION "Bank 1", ROMX[$4000], BANK[1]
+                db $DD, $DD
+            SECTION "Bank 2", ROMX[$4000], BANK[2]
+            Far:
+                ld hl, wValue
+                ld [hl], a
+                ret
+            SECTION "Variables", WRAM0[$C000]
+            wValue: ds 1
+        "#;
+
+        let rom = reassemble(source);
+        let mut symbols = SymbolFile::new();
+        symbols.insert(2, 0x4000, "FarFunction");
+        symbols.insert(0, 0xC000, "wValue");
+
+        let output = Disassembler::new(&rom).symbols(symbols).disassemble();
+
+        assert!(output.contains("DEF wValue EQU $C000"));
+        assert!(output.contains("Boot:\n    nop\n    jp Jump_000_0150\n"));
+        assert!(output.contains("    call FarFunction\n"));
+        assert!(output.contains("FarFunction:\n    ld hl, wValue\n"));
+        assert!(output.contains("jr_000_0158:\n    halt\n    jr jr_000_0158\n"));
+        assert!(output.contains("    db $6E, $6F, $74, $20"));
+        assert_eq!(reassemble(&output), rom);
+    }
+
+    #[test]
+    fn starts_from_logged_code() {
+        let source = r#"
+            SECTION "Entry", ROM0[$100]
+                ret
+            SECTION "Handler", ROM0[$200]
+                ld a, 1
+                ret
+        "#;
+
+        let rom = reassemble(source);
+        assert!(!Disassembler::new(&rom).disassemble().contains("ld a"));
+
+        let mut log = CodeDataLog::new(rom.len());
+        log.mark(0x200, cdl::OPCODE);
+        log.mark(0x201, cdl::OPERAND);
+
+        let output = Disassembler::new(&rom).coverage(&log).disassemble();
+
+        assert!(output.contains("    ld a, $01\n    ret\n"));
+        assert_eq!(reassemble(&output), rom);
+    }
+}
@@ -0,0 +1,50 @@
+//! Loads a ROM and waits for a JSON-RPC client to connect.
+//!
+//! ```text
+//! gbrpc <rom> [port]
+//! ```
+//!
+//! The port defaults to 4370. The emulator stays paused until the client resumes it.
+
+use gb_hardware::Device;
+use gb_rpc::session::Session;
+use std::{net::TcpListener, path::PathBuf, process::ExitCode};
+
+const USAGE: &str = "usage: gbrpc <rom> [port]";
+
+fn run() -> Result<(), String> {
+    let mut args = std::env::args().skip(1);
+
+    let rom = match args.next() {
+        Some(arg) if arg == "-h" || arg == "--help" => return Err(USAGE.into()),
+        Some(arg) => PathBuf::from(arg),
+        None => return Err(USAGE.into()),
+    };
+
+    let port: u16 = match args.next() {
+        Some(port) => port
+            .parse()
+            .map_err(|_| format!("invalid port `{port}`\n{USAGE}"))?,
+        None => 4370,
+    };
+
+    let device =
+        Device::from_file(&rom).map_err(|e| format!("unable to load {}: {e}", rom.display()))?;
+
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .map_err(|e| format!("unable to listen on port {port}: {e}"))?;
+
+    println!("waiting for a client on port {port}");
+
+    gb_rpc::serve(&listener, &mut Session::new(device)).map_err(|e| format!("{e}"))
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
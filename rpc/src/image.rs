@@ -0,0 +1,67 @@
+//! Encodes what's on screen as a PNG.
+
+use gb_hardware::{
+    sgb::{FRAME_HEIGHT, FRAME_WIDTH},
+    video::{SCREEN_HEIGHT, SCREEN_WIDTH},
+    Device,
+};
+use png::{BitDepth, ColorType, Encoder, EncodingError};
+
+/// The grey for each shade, from white to black.
+const SHADES: [u8; 4] = [0xFF, 0xAA, 0x55, 0x00];
+
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub png: Vec<u8>,
+}
+
+/// Encodes the last frame. On a Super Game Boy, that's the whole frame with its border and
+/// colours. Otherwise it's the screen in shades of grey.
+pub fn screenshot(device: &Device) -> Result<Image, EncodingError> {
+    match &device.sgb {
+        Some(sgb) => {
+            let pixels: Vec<u8> = sgb.frame.iter().flat_map(|&c| rgb(c)).collect();
+            encode(FRAME_WIDTH, FRAME_HEIGHT, ColorType::Rgb, &pixels)
+        }
+        None => {
+            let pixels: Vec<u8> = device
+                .video
+                .framebuffer
+                .iter()
+                .map(|&shade| SHADES[shade as usize & 3])
+                .collect();
+
+            encode(SCREEN_WIDTH, SCREEN_HEIGHT, ColorType::Grayscale, &pixels)
+        }
+    }
+}
+
+/// Expands a 15-bit colour, with red in the lowest bits, to 24 bits.
+fn rgb(color: u16) -> [u8; 3] {
+    let channel = |shift: u16| {
+        let value = (color >> shift & 0x1F) as u8;
+        value << 3 | value >> 2
+    };
+
+    [channel(0), channel(5), channel(10)]
+}
+
+fn encode(
+    width: usize,
+    height: usize,
+    color: ColorType,
+    pixels: &[u8],
+) -> Result<Image, EncodingError> {
+    let mut png = Vec::new();
+
+    let mut encoder = Encoder::new(&mut png, width as u32, height as u32);
+    encoder.set_color(color);
+    encoder.set_depth(BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(pixels)?;
+    writer.finish()?;
+
+    Ok(Image { width, height, png })
+}
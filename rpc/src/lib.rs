@@ -0,0 +1,256 @@
+//! A JSON-RPC 2.0 server, so test scripts in any language can drive the emulator over TCP.
+//!
+//! Requests, responses and notifications are each one line of JSON. The emulator starts paused,
+//! and runs frame by frame between requests after `resume`. The methods are:
+//!
+//! - `pause` and `resume`
+//! - `step`, with a `count` and a `unit` of `"instruction"` or `"frame"`
+//! - `readMemory` with an `address` and `length`, and `writeMemory` with an `address` and
+//!   `bytes`, which access memory the way debuggers do
+//! - `readRegisters`, and `writeRegisters` with any of `a`, `f`, `b`, `c`, `d`, `e`, `h`, `l`,
+//!   `sp` and `pc`
+//! - `screenshot`, which returns the last frame as a base64-encoded PNG
+//! - `setButton`, with a `button` such as `"start"` and whether it's `pressed`
+//! - `saveState` and `loadState`, with a `slot` name. States are kept in memory.
+//! - `addBreakpoint`, with an `address`, an optional `bank` and an optional `condition`, and
+//!   `removeBreakpoint` with its `id`
+//! - `subscribe` and `unsubscribe`, with a list of `events`: `"breakpoint"`, `"frame"` and
+//!   `"serial"`. Each event is sent as a notification with the same name.
+
+mod image;
+pub mod protocol;
+pub mod session;
+
+use protocol::{Error, Notification, Request, Response, INVALID_REQUEST, PARSE_ERROR};
+use serde_json::Value;
+use session::Session;
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::mpsc::{self, TryRecvError},
+    thread,
+};
+
+/// Accepts one client on `listener` and serves it until it disconnects.
+pub fn serve(listener: &TcpListener, session: &mut Session) -> io::Result<()> {
+    let (stream, _) = listener.accept()?;
+    let mut writer = stream.try_clone()?;
+
+    // Lines are read on another thread, so the emulator can keep running while none arrive.
+    let (sender, lines) = mpsc::channel();
+
+    thread::spawn(move || {
+        for line in BufReader::new(stream).lines() {
+            if line.as_ref().is_err() || sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    loop {
+        let line = if session.running {
+            match lines.try_recv() {
+                Ok(line) => Some(line),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => break,
+            }
+        } else {
+            match lines.recv() {
+                Ok(line) => Some(line),
+                Err(_) => break,
+            }
+        };
+
+        let response = match line {
+            Some(line) => respond(session, &line?),
+            None => {
+                session.run_frame();
+                None
+            }
+        };
+
+        // Events from a request happened before it finished, so they're sent before its response.
+        for (method, params) in session.take_events() {
+            send(&mut writer, &Notification::new(method, params))?;
+        }
+
+        if let Some(response) = response {
+            send(&mut writer, &response)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Handles one line from the client, returning the response unless it was a notification.
+fn respond(session: &mut Session, line: &str) -> Option<Response> {
+    let request = match serde_json::from_str::<Value>(line) {
+        Ok(request) => request,
+        Err(e) => {
+            return Some(Response::new(
+                Value::Null,
+                Err(Error::new(PARSE_ERROR, e.to_string())),
+            ))
+        }
+    };
+
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+
+    let request: Request = match serde_json::from_value(request) {
+        Ok(request) => request,
+        Err(e) => {
+            return Some(Response::new(
+                id,
+                Err(Error::new(INVALID_REQUEST, e.to_string())),
+            ))
+        }
+    };
+
+    if request.jsonrpc != "2.0" {
+        let error = Error::new(INVALID_REQUEST, "only JSON-RPC 2.0 is supported");
+        return Some(Response::new(id, Err(error)));
+    }
+
+    let result = session.handle(&request.method, request.params);
+
+    request.id.map(|id| Response::new(id, result))
+}
+
+fn send(writer: &mut TcpStream, message: &impl serde::Serialize) -> io::Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+
+    writer.write_all(&line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gb_hardware::Device;
+    use serde_json::json;
+    use std::io::{BufRead, BufReader};
+
+    struct Client {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+        next_id: u64,
+    }
+
+    impl Client {
+        /// Calls `method`, returning its result and the notifications that came before it.
+        fn call(&mut self, method: &str, params: Value) -> (Value, Vec<Value>) {
+            self.next_id += 1;
+
+            let request = json!({
+                "jsonrpc": "2.0",
+                "id": self.next_id,
+                "method": method,
+                "params": params,
+            });
+            send(&mut self.writer, &request).unwrap();
+
+            let mut notifications = Vec::new();
+
+            loop {
+                let message = self.read_message();
+
+                if message["id"] == json!(self.next_id) {
+                    assert!(message.get("error").is_none(), "{message}");
+                    return (message["result"].clone(), notifications);
+                }
+
+                notifications.push(message);
+            }
+        }
+
+        fn read_message(&mut self) -> Value {
+            let mut line = String::new();
+            self.reader.read_line(&mut line).unwrap();
+
+            serde_json::from_str(&line).unwrap()
+        }
+    }
+
+    #[test]
+    fn serves_a_scripted_client() {
+        let mut rom = vec![0; 0x8000];
+
+        // LD A, $41; LD [$FF01], A; LD A, $81; LD [$FF02], A; JR -2
+        rom[0x100..0x10C].copy_from_slice(&[
+            0x3E, 0x41, 0xE0, 0x01, 0x3E, 0x81, 0xE0, 0x02, 0x18, 0xFE, 0x00, 0x00,
+        ]);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            let mut session = Session::new(Device::from_rom(rom).unwrap());
+            serve(&listener, &mut session).unwrap();
+        });
+
+        let stream = TcpStream::connect(address).unwrap();
+        let mut client = Client {
+            reader: BufReader::new(stream.try_clone().unwrap()),
+            writer: stream,
+            next_id: 0,
+        };
+
+        client.call(
+            "subscribe",
+            json!({ "events": ["serial", "frame", "breakpoint"] }),
+        );
+        client.call("saveState", json!({ "slot": "start" }));
+
+        let (result, _) = client.call("step", json!({ "count": 2 }));
+        assert_eq!(result["pc"], 0x104);
+        assert_eq!(client.call("readRegisters", Value::Null).0["a"], 0x41);
+
+        let (result, events) = client.call("step", json!({ "count": 2 }));
+        assert_eq!(result["pc"], 0x108);
+        assert_eq!(
+            events,
+            [json!({ "jsonrpc": "2.0", "method": "serial", "params": { "byte": 0x41 } })]
+        );
+
+        let (_, events) = client.call("step", json!({ "count": 1, "unit": "frame" }));
+        assert_eq!(events[0]["method"], "frame");
+
+        client.call(
+            "writeMemory",
+            json!({ "address": 0xC000, "bytes": [1, 2, 3] }),
+        );
+        let (result, _) = client.call("readMemory", json!({ "address": 0xC001, "length": 2 }));
+        assert_eq!(result["bytes"], json!([2, 3]));
+
+        client.call("loadState", json!({ "slot": "start" }));
+        let (result, _) = client.call("writeRegisters", json!({ "b": 7 }));
+        assert_eq!(
+            (result["pc"].clone(), result["b"].clone()),
+            (json!(0x100), json!(7))
+        );
+
+        let (result, _) = client.call("addBreakpoint", json!({ "address": 0x108 }));
+        let id = result["id"].clone();
+
+        // Once resumed, the emulator runs until the breakpoint stops it.
+        client.call("resume", Value::Null);
+
+        let breakpoint = loop {
+            let message = client.read_message();
+
+            if message["method"] == "breakpoint" {
+                break message;
+            }
+        };
+
+        assert_eq!(breakpoint["params"]["id"], id);
+        assert_eq!(breakpoint["params"]["pc"], 0x108);
+
+        let (result, _) = client.call("screenshot", Value::Null);
+        assert_eq!(
+            (result["width"].clone(), result["height"].clone()),
+            (json!(160), json!(144))
+        );
+        assert!(result["png"].as_str().unwrap().starts_with("iVBORw0KGgo"));
+    }
+}
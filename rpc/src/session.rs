@@ -0,0 +1,464 @@
+//! The methods of the server, applied to a device and its debugger.
+
+use crate::{
+    image,
+    protocol::{Error, FAILED, INVALID_PARAMS, METHOD_NOT_FOUND},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use gb_hardware::{joypad::Button, Device};
+use gb_interpreter::debugger::{expression::Expression, Debugger, Stop};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+
+/// The most instructions a frame can take, since each one takes at least one M-cycle. Frames with
+/// the LCD off never reach VBlank, so they end after this many instead.
+pub const FRAME_INSTRUCTIONS: u64 = 17556;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Event {
+    /// Execution stopped at a breakpoint, watchpoint or watch expression, on an instruction that
+    /// couldn't execute, or because the CPU locked up.
+    Breakpoint,
+    /// The PPU reached VBlank.
+    Frame,
+    /// The game sent a byte over the serial port.
+    Serial,
+}
+
+impl Event {
+    fn method(&self) -> &'static str {
+        match self {
+            Self::Breakpoint => "breakpoint",
+            Self::Frame => "frame",
+            Self::Serial => "serial",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum StepUnit {
+    Instruction,
+    Frame,
+}
+
+#[derive(Deserialize)]
+struct StepParams {
+    #[serde(default = "one")]
+    count: u64,
+    #[serde(default = "instruction")]
+    unit: StepUnit,
+}
+
+fn one() -> u64 {
+    1
+}
+
+fn instruction() -> StepUnit {
+    StepUnit::Instruction
+}
+
+#[derive(Deserialize)]
+struct ReadMemoryParams {
+    address: u16,
+    length: u32,
+}
+
+#[derive(Deserialize)]
+struct WriteMemoryParams {
+    address: u16,
+    bytes: Vec<u8>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Registers {
+    a: Option<u8>,
+    f: Option<u8>,
+    b: Option<u8>,
+    c: Option<u8>,
+    d: Option<u8>,
+    e: Option<u8>,
+    h: Option<u8>,
+    l: Option<u8>,
+    sp: Option<u16>,
+    pc: Option<u16>,
+}
+
+#[derive(Deserialize)]
+struct ButtonParams {
+    button: ButtonName,
+    pressed: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum ButtonName {
+    Right,
+    Left,
+    Up,
+    Down,
+    A,
+    B,
+    Select,
+    Start,
+}
+
+impl From<ButtonName> for Button {
+    fn from(name: ButtonName) -> Self {
+        match name {
+            ButtonName::Right => Self::Right,
+            ButtonName::Left => Self::Left,
+            ButtonName::Up => Self::Up,
+            ButtonName::Down => Self::Down,
+            ButtonName::A => Self::A,
+            ButtonName::B => Self::B,
+            ButtonName::Select => Self::Select,
+            ButtonName::Start => Self::Start,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct StateParams {
+    #[serde(default)]
+    slot: String,
+}
+
+#[derive(Deserialize)]
+struct SubscribeParams {
+    events: Vec<Event>,
+}
+
+#[derive(Deserialize)]
+struct BreakpointParams {
+    address: u16,
+    bank: Option<usize>,
+    condition: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct IdParams {
+    id: u32,
+}
+
+pub struct Session {
+    pub device: Device,
+    pub debugger: Debugger,
+    /// Whether the emulator runs between requests.
+    pub running: bool,
+    subscriptions: HashSet<Event>,
+    /// Save states, by slot name.
+    states: HashMap<String, Device>,
+    /// The number of frames completed so far.
+    frames: u64,
+    /// How much of the serial output has been reported.
+    serial_reported: usize,
+    /// Notifications for subscribed events, waiting to be sent.
+    events: Vec<(Event, Value)>,
+}
+
+impl Session {
+    /// Starts a session for `device`, paused.
+    pub fn new(device: Device) -> Self {
+        Self {
+            serial_reported: device.serial.output.len(),
+            device,
+            debugger: Debugger::new(),
+            running: false,
+            subscriptions: HashSet::new(),
+            states: HashMap::new(),
+            frames: 0,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn handle(&mut self, method: &str, params: Value) -> Result<Value, Error> {
+        match method {
+            "pause" => {
+                self.running = false;
+                Ok(json!({ "pc": self.device.cpu.program_counter }))
+            }
+            "resume" => {
+                self.running = true;
+                Ok(Value::Null)
+            }
+            "step" => {
+                let StepParams { count, unit } = parse(params)?;
+                self.running = false;
+
+                let stop = match unit {
+                    StepUnit::Instruction => self.advance(count, u64::MAX),
+                    StepUnit::Frame => {
+                        self.advance(count.saturating_mul(FRAME_INSTRUCTIONS), count)
+                    }
+                };
+
+                Ok(json!({
+                    "stop": describe(stop),
+                    "pc": self.device.cpu.program_counter,
+                }))
+            }
+            "readMemory" => {
+                let ReadMemoryParams { address, length } = parse(params)?;
+
+                if address as u32 + length > 0x10000 {
+                    return Err(Error::new(INVALID_PARAMS, "range extends past $FFFF"));
+                }
+
+                let bytes: Vec<u8> = (0..length)
+                    .map(|offset| self.device.peek(address + offset as u16))
+                    .collect();
+
+                Ok(json!({ "bytes": bytes }))
+            }
+            "writeMemory" => {
+                let WriteMemoryParams { address, bytes } = parse(params)?;
+
+                if address as usize + bytes.len() > 0x10000 {
+                    return Err(Error::new(INVALID_PARAMS, "range extends past $FFFF"));
+                }
+
+                for (offset, byte) in bytes.into_iter().enumerate() {
+                    self.device.poke(address + offset as u16, byte);
+                }
+
+                Ok(Value::Null)
+            }
+            "readRegisters" => Ok(json!(self.registers())),
+            "writeRegisters" => {
+                self.set_registers(parse(params)?);
+                Ok(json!(self.registers()))
+            }
+            "screenshot" => {
+                let image = image::screenshot(&self.device)
+                    .map_err(|e| Error::new(FAILED, format!("unable to encode the frame: {e}")))?;
+
+                Ok(json!({
+                    "width": image.width,
+                    "height": image.height,
+                    "png": STANDARD.encode(image.png),
+                }))
+            }
+            "setButton" => {
+                let ButtonParams { button, pressed } = parse(params)?;
+                self.device.set_button(button.into(), pressed);
+                Ok(Value::Null)
+            }
+            "saveState" => {
+                let StateParams { slot } = parse(params)?;
+                self.states.insert(slot, self.device.clone());
+                Ok(Value::Null)
+            }
+            "loadState" => {
+                let StateParams { slot } = parse(params)?;
+                let state = self.states.get(&slot).ok_or_else(|| {
+                    Error::new(FAILED, format!("no state saved in slot `{slot}`"))
+                })?;
+
+                self.device = state.clone();
+                self.serial_reported = self.device.serial.output.len();
+                Ok(Value::Null)
+            }
+            "subscribe" => {
+                let SubscribeParams { events } = parse(params)?;
+                self.subscriptions.extend(events);
+                Ok(Value::Null)
+            }
+            "unsubscribe" => {
+                let SubscribeParams { events } = parse(params)?;
+
+                for event in events {
+                    self.subscriptions.remove(&event);
+                }
+
+                Ok(Value::Null)
+            }
+            "addBreakpoint" => {
+                let BreakpointParams {
+                    address,
+                    bank,
+                    condition,
+                } = parse(params)?;
+
+                let id = match condition {
+                    Some(condition) => {
+                        let condition = Expression::parse(&condition)
+                            .map_err(|e| Error::new(INVALID_PARAMS, e.to_string()))?;
+
+                        self.debugger
+                            .add_conditional_breakpoint(address, bank, condition)
+                    }
+                    None => self.debugger.add_breakpoint(address, bank),
+                };
+
+                Ok(json!({ "id": id }))
+            }
+            "removeBreakpoint" => {
+                let IdParams { id } = parse(params)?;
+
+                match self.debugger.remove_breakpoint(id) {
+                    Some(_) => Ok(Value::Null),
+                    None => Err(Error::new(FAILED, format!("no breakpoint with ID {id}"))),
+                }
+            }
+            _ => Err(Error::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method `{method}`"),
+            )),
+        }
+    }
+
+    /// Runs one frame while the session is running, pausing if something stops execution.
+    pub fn run_frame(&mut self) {
+        if !self.running {
+            return;
+        }
+
+        if !matches!(
+            self.advance(FRAME_INSTRUCTIONS, 1),
+            Stop::Done | Stop::Limit
+        ) {
+            self.running = false;
+        }
+    }
+
+    /// Returns the notifications for the events since the last call, as method and parameters.
+    pub fn take_events(&mut self) -> Vec<(&'static str, Value)> {
+        self.events
+            .drain(..)
+            .map(|(event, params)| (event.method(), params))
+            .collect()
+    }
+
+    /// Runs up to `instructions` instructions, or until `frames` frames have finished, reporting
+    /// events along the way.
+    fn advance(&mut self, mut instructions: u64, mut frames: u64) -> Stop {
+        loop {
+            let limit = instructions;
+            let mut executed = 0;
+
+            let stop = self
+                .debugger
+                .run_until(&mut self.device, instructions, |device| {
+                    executed += 1;
+                    device.video.has_vblank_interrupt
+                });
+
+            instructions = instructions.saturating_sub(executed);
+            self.report_serial();
+
+            // The flag stays set until the next instruction, so it only counts if one ran.
+            if limit > 0 && self.device.video.has_vblank_interrupt {
+                self.frames += 1;
+                frames -= 1;
+                self.emit(Event::Frame, json!({ "frame": self.frames }));
+            }
+
+            match stop {
+                Stop::Done if instructions > 0 && frames > 0 => continue,
+                Stop::Done | Stop::Limit => return stop,
+                stop => {
+                    let mut description = describe(stop);
+                    description["pc"] = json!(self.device.cpu.program_counter);
+                    self.emit(Event::Breakpoint, description);
+
+                    return stop;
+                }
+            }
+        }
+    }
+
+    fn report_serial(&mut self) {
+        let output = &self.device.serial.output;
+
+        let bytes: Vec<_> = output[self.serial_reported.min(output.len())..].to_vec();
+        self.serial_reported = output.len();
+
+        for byte in bytes {
+            self.emit(Event::Serial, json!({ "byte": byte }));
+        }
+    }
+
+    fn emit(&mut self, event: Event, params: Value) {
+        if self.subscriptions.contains(&event) {
+            self.events.push((event, params));
+        }
+    }
+
+    fn registers(&self) -> Registers {
+        let cpu = &self.device.cpu;
+
+        Registers {
+            a: Some(cpu.a),
+            f: Some(cpu.flags),
+            b: Some(cpu.b),
+            c: Some(cpu.c),
+            d: Some(cpu.d),
+            e: Some(cpu.e),
+            h: Some(cpu.h),
+            l: Some(cpu.l),
+            sp: Some(cpu.stack_pointer),
+            pc: Some(cpu.program_counter),
+        }
+    }
+
+    fn set_registers(&mut self, registers: Registers) {
+        let cpu = &mut self.device.cpu;
+
+        let bytes = [
+            (registers.a, &mut cpu.a),
+            (registers.b, &mut cpu.b),
+            (registers.c, &mut cpu.c),
+            (registers.d, &mut cpu.d),
+            (registers.e, &mut cpu.e),
+            (registers.h, &mut cpu.h),
+            (registers.l, &mut cpu.l),
+        ];
+
+        for (value, register) in bytes {
+            if let Some(value) = value {
+                *register = value;
+            }
+        }
+
+        // The lower four bits of F don't exist.
+        if let Some(flags) = registers.f {
+            cpu.flags = flags & 0xF0;
+        }
+
+        if let Some(sp) = registers.sp {
+            cpu.stack_pointer = sp;
+        }
+
+        if let Some(pc) = registers.pc {
+            cpu.program_counter = pc;
+        }
+    }
+}
+
+fn parse<T: DeserializeOwned>(params: Value) -> Result<T, Error> {
+    // Methods without parameters can be called with none at all.
+    let params = if params.is_null() { json!({}) } else { params };
+
+    serde_json::from_value(params).map_err(|e| Error::new(INVALID_PARAMS, e.to_string()))
+}
+
+/// Describes why a run stopped, for responses and breakpoint events.
+fn describe(stop: Stop) -> Value {
+    match stop {
+        Stop::Done | Stop::Limit => json!({ "reason": "done" }),
+        Stop::Breakpoint(id) => json!({ "reason": "breakpoint", "id": id }),
+        Stop::Watchpoint(hit) => json!({
+            "reason": "watchpoint",
+            "id": hit.id,
+            "address": hit.address,
+            "value": hit.value,
+            "write": hit.write,
+        }),
+        Stop::Expression(id) => json!({ "reason": "expression", "id": id }),
+        Stop::Fault(error) => json!({ "reason": "fault", "message": error.to_string() }),
+        Stop::Locked => json!({ "reason": "locked" }),
+    }
+}